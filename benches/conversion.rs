@@ -0,0 +1,85 @@
+//! Throughput of the conversion for text-heavy and number-heavy documents.
+//! Run it with `cargo bench` before and after changes to the conversion.
+
+use roxmltree_to_serde::{xml_str_to_json, Config};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+fn document(value: impl Fn(usize) -> String) -> String {
+    let mut xml = String::from("<root>");
+    for i in 0..20_000 {
+        xml.push_str(&format!(
+            "<item id=\"{}\"><a>{}</a><b>{}</b></item>",
+            value(i),
+            value(i + 1),
+            value(i + 2)
+        ));
+    }
+    xml.push_str("</root>");
+    xml
+}
+
+fn bench(name: &str, xml: &str) {
+    bench_with_config(name, xml, &Config::new_with_defaults());
+}
+
+fn bench_with_config(name: &str, xml: &str, config: &Config) {
+    // warm up, then take the best of several runs
+    black_box(xml_str_to_json(xml, config).unwrap());
+    let mut best = Duration::MAX;
+    for _ in 0..10 {
+        let start = Instant::now();
+        black_box(xml_str_to_json(black_box(xml), config).unwrap());
+        best = best.min(start.elapsed());
+    }
+    let mb_per_s = xml.len() as f64 / best.as_secs_f64() / 1_000_000.0;
+    println!("{:<9} {:>8.2?} {:>8.1} MB/s", name, best, mb_per_s);
+}
+
+fn main() {
+    bench("text", &document(|i| format!("some text {}", i)));
+    bench("numbers", &document(|i| format!("{}.5", i)));
+    bench(
+        "mixed",
+        &document(|i| match i % 3 {
+            0 => i.to_string(),
+            1 => "true".to_owned(),
+            _ => format!("name-{}", i),
+        }),
+    );
+
+    // hundreds of absolute path overrides, none of them matching the document
+    #[cfg(feature = "json_types")]
+    {
+        use roxmltree_to_serde::{JsonArray, JsonType};
+        let config = (0..500).fold(Config::new_with_defaults(), |config, i| {
+            config.add_json_type_override(
+                format!("/root/item/other{}", i).as_str(),
+                JsonArray::Infer(JsonType::AlwaysString),
+            )
+        });
+        bench_with_config("overrides", &document(|i| i.to_string()), &config);
+    }
+
+    // convert, serialize and drop, with the output allocated from an arena that is reset in between
+    #[cfg(feature = "bumpalo")]
+    {
+        use roxmltree_to_serde::xml_str_to_arena_json;
+        let xml = document(|i| format!("some text {}", i));
+        let config = Config::new_with_defaults();
+        let mut bump = bumpalo::Bump::new();
+        let mut out = Vec::new();
+        let mut best = Duration::MAX;
+        for _ in 0..10 {
+            let start = Instant::now();
+            let json = xml_str_to_arena_json(black_box(&xml), &config, &bump).unwrap();
+            serde_json::to_writer(&mut out, &json).unwrap();
+            drop(json);
+            bump.reset();
+            out.clear();
+            best = best.min(start.elapsed());
+        }
+        let mb_per_s = xml.len() as f64 / best.as_secs_f64() / 1_000_000.0;
+        println!("{:<9} {:>8.2?} {:>8.1} MB/s", "arena", best, mb_per_s);
+    }
+}
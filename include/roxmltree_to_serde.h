@@ -0,0 +1,51 @@
+/*
+ * C bindings of roxmltree_to_serde, built with the `capi` feature.
+ *
+ * All strings are NUL-terminated UTF-8. Strings returned by the library are owned by the caller
+ * and must be released with rxts_string_free, configs with rxts_config_free.
+ */
+
+#ifndef ROXMLTREE_TO_SERDE_H
+#define ROXMLTREE_TO_SERDE_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The conversion succeeded. */
+#define RXTS_OK 0
+/* A pointer argument is null or a string is not valid UTF-8. */
+#define RXTS_INVALID_ARGUMENT 1
+/* The document or the config is invalid, or the conversion failed. */
+#define RXTS_CONVERSION_ERROR 2
+
+/* An opaque conversion config. */
+typedef struct RxtsConfig RxtsConfig;
+
+/* Returns a new config with the default values. */
+RxtsConfig *rxts_config_new(void);
+
+/*
+ * Returns a new config from JSON with the names of the Config fields, e.g.
+ * {"xml_attr_prefix": "", "json_type_overrides": {"/a/@id": "string"}}, or NULL if it's invalid.
+ * The reason is stored in *error if error is not NULL.
+ */
+RxtsConfig *rxts_config_from_json(const char *json, char **error);
+
+/* Releases a config. NULL is ignored. */
+void rxts_config_free(RxtsConfig *config);
+
+/*
+ * Converts an XML document into a JSON string, using the default config if config is NULL.
+ * Returns RXTS_OK and stores the JSON in *out, or an error code and stores the error message in *out.
+ */
+int rxts_xml_to_json(const char *xml, const RxtsConfig *config, char **out);
+
+/* Releases a string returned by the library. NULL is ignored. */
+void rxts_string_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* ROXMLTREE_TO_SERDE_H */
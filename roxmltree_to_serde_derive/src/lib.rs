@@ -0,0 +1,441 @@
+//! # roxmltree_to_serde_derive
+//! The `#[xml]` attribute macro of [roxmltree_to_serde](https://github.com/marcomq/roxmltree_to_serde),
+//! re-exported there with the `derive` feature. It maps XML attributes, texts and nested elements onto
+//! the fields of a struct that derives `serde::Deserialize`, with the keys of the JSON output of
+//! `roxmltree_to_serde`, so they don't have to be spelled out with `#[serde(rename = "@id")]`.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::punctuated::Punctuated;
+use syn::{
+    parse_macro_input, Attribute, Error, Fields, FieldsNamed, Generics, Ident, Item, ItemEnum,
+    LitStr, Path, Token, Type,
+};
+
+/// Maps the fields of a struct onto the XML it's deserialized from, with the JSON keys of `roxmltree_to_serde`.
+/// Put it before `#[derive(Deserialize)]`. The fields take these options:
+/// * `#[xml(attr = "id")]` - the attribute `id`, i.e. the key `@id`
+/// * `#[xml(text)]` - the text of an element with attributes, i.e. the key `#text`
+/// * `#[xml(path = "meta/author")]` - the element `author` in the child element `meta`. An `@` at the start
+///   of a name stands for an attribute, e.g. `price/@currency`. `Option` fields are `None` and `Vec` fields
+///   are empty if an element on the path is missing.
+///
+/// `Vec` fields are empty if the element is missing, without `#[serde(default)]`. Fields without an
+/// option are deserialized like any serde field. If the `Config` of the conversion has
+/// another attribute prefix or text property name, pass them to the macro, e.g.
+/// `#[xml(attr_prefix = "", text = "value")]`.
+///
+/// Fields with a `path` are the only ones with their first element name, so two paths can't start with
+/// the same element.
+///
+/// The options work for the fields of enum variants as well. On an enum with `#[serde(untagged)]`, `#[xml]`
+/// implements `Deserialize` itself and tries the variants in order on a copy of the element, with the same
+/// rules as `XmlRecords::deserialize`, e.g. numbers for `String` fields. serde's own untagged enums
+/// buffer the element in a way that loses these rules.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml, Config, XmlRecords};
+/// use serde_derive::Deserialize;
+///
+/// #[xml]
+/// #[derive(Deserialize)]
+/// struct Book {
+///     #[xml(attr = "isbn")]
+///     isbn: String,
+///     title: String,
+///     #[xml(path = "meta/author")]
+///     author: String,
+///     #[xml(path = "price/@currency")]
+///     currency: Option<String>,
+/// }
+///
+/// let xml = r#"<books><book isbn="123"><title>XML</title><meta><author>Ann</author></meta></book></books>"#;
+/// let config = Config::new_with_defaults();
+/// let book = XmlRecords::new(xml.as_bytes(), "/books/book", &config)
+///     .deserialize::<Book>()
+///     .next()
+///     .unwrap()
+///     .unwrap();
+/// assert_eq!((book.isbn.as_str(), book.author.as_str(), book.currency), ("123", "Ann", None));
+/// ```
+#[proc_macro_attribute]
+pub fn xml(args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut names = Names {
+        attr_prefix: "@".to_owned(),
+        text: "#text".to_owned(),
+    };
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("attr_prefix") {
+            names.attr_prefix = meta.value()?.parse::<LitStr>()?.value();
+        } else if meta.path.is_ident("text") {
+            names.text = meta.value()?.parse::<LitStr>()?.value();
+        } else {
+            return Err(meta.error("expected `attr_prefix` or `text`"));
+        }
+        Ok(())
+    });
+    parse_macro_input!(args with parser);
+    let item = parse_macro_input!(input as Item);
+    match expand(item, &names) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// The attribute prefix and the text property name of the `Config`.
+struct Names {
+    attr_prefix: String,
+    text: String,
+}
+
+impl Names {
+    /// Returns the JSON key of an element or attribute name of a path.
+    fn key(&self, name: &str) -> String {
+        match name.strip_prefix('@') {
+            Some(attr) => [self.attr_prefix.as_str(), attr].concat(),
+            None => name.to_owned(),
+        }
+    }
+}
+
+/// The option of a field.
+enum Mapping {
+    Attr(String),
+    Text,
+    Path(Vec<String>),
+}
+
+fn expand(item: Item, names: &Names) -> Result<proc_macro2::TokenStream, Error> {
+    match item {
+        Item::Struct(mut item) => {
+            let Fields::Named(fields) = &mut item.fields else {
+                return Err(Error::new_spanned(
+                    &item,
+                    "#[xml] only supports structs with named fields",
+                ));
+            };
+            let path_fns = map_fields(fields, &item.ident, &item.generics, names)?;
+            Ok(quote! {
+                #item
+                #(#path_fns)*
+            })
+        }
+        Item::Enum(mut item) => {
+            if is_untagged(&item.attrs)? {
+                return expand_untagged(item, names);
+            }
+            let mut path_fns = Vec::new();
+            for variant in item.variants.iter_mut() {
+                if let Fields::Named(fields) = &mut variant.fields {
+                    let owner = format_ident!("{}_{}", item.ident, variant.ident);
+                    path_fns.extend(map_fields(fields, &owner, &item.generics, names)?);
+                }
+            }
+            Ok(quote! {
+                #item
+                #(#path_fns)*
+            })
+        }
+        item => Err(Error::new_spanned(
+            item,
+            "#[xml] only supports structs and enums",
+        )),
+    }
+}
+
+/// Replaces the `#[xml(..)]` attributes of `fields` with serde attributes and returns the functions
+/// that deserialize the fields with a `path`. The functions are named after `owner`.
+fn map_fields(
+    fields: &mut FieldsNamed,
+    owner: &Ident,
+    generics: &Generics,
+    names: &Names,
+) -> Result<Vec<proc_macro2::TokenStream>, Error> {
+    let mut path_fns = Vec::new();
+    let mut first_names: Vec<(String, Span)> = Vec::new();
+    for field in fields.named.iter_mut() {
+        let mapping = take_mapping(&mut field.attrs)?;
+        if is_named(&field.ty, "Vec")
+            && !matches!(mapping, Some(Mapping::Path(_)))
+            && !has_serde_default(&field.attrs)?
+        {
+            // a missing element is an empty sequence
+            field.attrs.push(syn::parse_quote!(#[serde(default)]));
+        }
+        let Some(mapping) = mapping else {
+            continue;
+        };
+        let ident = field.ident.as_ref().expect("named field");
+        let attr = match mapping {
+            Mapping::Attr(name) => {
+                let key = [names.attr_prefix.as_str(), &name].concat();
+                quote!(#[serde(rename = #key)])
+            }
+            Mapping::Text => {
+                let key = &names.text;
+                quote!(#[serde(rename = #key)])
+            }
+            Mapping::Path(path) => {
+                let keys: Vec<String> = path.iter().map(|name| names.key(name)).collect();
+                let first = &keys[0];
+                if let Some((_, span)) = first_names.iter().find(|(name, _)| name == first) {
+                    let mut e = Error::new_spanned(
+                        &field.ident,
+                        format!("another path already starts with `{}`", first),
+                    );
+                    e.combine(Error::new(*span, "first path with this element"));
+                    return Err(e);
+                }
+                first_names.push((first.clone(), ident.span()));
+                let default = (is_named(&field.ty, "Option") || is_named(&field.ty, "Vec"))
+                    .then(|| quote!(default,));
+                if keys.len() == 1 {
+                    quote!(#[serde(rename = #first, #default)])
+                } else {
+                    let fn_name = format_ident!("__xml_path_{}_{}", owner, ident);
+                    let fn_path = fn_name.to_string();
+                    let rest = &keys[1..];
+                    let ty = &field.ty;
+                    let params = generics.params.iter();
+                    let predicates = generics
+                        .where_clause
+                        .iter()
+                        .flat_map(|w| w.predicates.iter());
+                    path_fns.push(quote! {
+                        #[doc(hidden)]
+                        #[allow(non_snake_case)]
+                        fn #fn_name<'de, #(#params,)* __D>(deserializer: __D) -> ::std::result::Result<#ty, __D::Error>
+                        where
+                            __D: ::serde::Deserializer<'de>,
+                            #ty: ::serde::Deserialize<'de>,
+                            #(#predicates,)*
+                        {
+                            ::roxmltree_to_serde::__private::deserialize_path(deserializer, &[#(#rest),*])
+                        }
+                    });
+                    quote!(#[serde(rename = #first, deserialize_with = #fn_path, #default)])
+                }
+            }
+        };
+        field.attrs.push(syn::parse_quote!(#attr));
+    }
+    Ok(path_fns)
+}
+
+/// Implements `Deserialize` for an untagged enum instead of serde, which tries the variants in order
+/// on a buffered copy of the value with the rules of `roxmltree_to_serde`.
+fn expand_untagged(mut item: ItemEnum, names: &Names) -> Result<proc_macro2::TokenStream, Error> {
+    if !item.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &item.generics,
+            "#[xml] doesn't support generic untagged enums",
+        ));
+    }
+    let derive = take_derive_deserialize(&mut item.attrs)?.ok_or_else(|| {
+        Error::new_spanned(
+            &item.ident,
+            "#[xml] must come before #[derive(Deserialize)] of an untagged enum",
+        )
+    })?;
+    let ident = &item.ident;
+    let mut helpers = Vec::new();
+    let mut attempts = Vec::new();
+    for variant in item.variants.iter_mut() {
+        let name = &variant.ident;
+        let attempt = match &mut variant.fields {
+            Fields::Unit => quote! {
+                if let Some(()) = untagged.deserialize::<()>() {
+                    return Ok(#ident::#name);
+                }
+            },
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                let ty = &fields.unnamed[0].ty;
+                quote! {
+                    if let Some(value) = untagged.deserialize::<#ty>() {
+                        return Ok(#ident::#name(value));
+                    }
+                }
+            }
+            Fields::Unnamed(fields) => {
+                let types = fields.unnamed.iter().map(|field| &field.ty);
+                let index = (0..fields.unnamed.len()).map(syn::Index::from);
+                quote! {
+                    if let Some(value) = untagged.deserialize::<(#(#types,)*)>() {
+                        return Ok(#ident::#name(#(value.#index),*));
+                    }
+                }
+            }
+            Fields::Named(fields) => {
+                let helper = format_ident!("__Xml{}{}", ident, name);
+                let path_fns = map_fields(fields, &helper, &item.generics, names)?;
+                let field_defs = fields.named.iter();
+                let field_names: Vec<_> = fields.named.iter().map(|f| &f.ident).collect();
+                helpers.push(quote! {
+                    #[derive(#derive)]
+                    struct #helper {
+                        #(#field_defs,)*
+                    }
+                    #(#path_fns)*
+                });
+                quote! {
+                    if let Some(value) = untagged.deserialize::<#helper>() {
+                        return Ok(#ident::#name { #(#field_names: value.#field_names),* });
+                    }
+                }
+            }
+        };
+        attempts.push(attempt);
+    }
+    if !derives(&item.attrs, "Serialize") {
+        // without a serde derive, the attributes of serde are unknown
+        item.attrs.retain(|attr| !attr.path().is_ident("serde"));
+        for variant in item.variants.iter_mut() {
+            variant.attrs.retain(|attr| !attr.path().is_ident("serde"));
+            for field in variant.fields.iter_mut() {
+                field.attrs.retain(|attr| !attr.path().is_ident("serde"));
+            }
+        }
+    }
+    let attr_prefix = &names.attr_prefix;
+    let text = &names.text;
+    let message = format!("data did not match any variant of untagged enum {}", ident);
+    Ok(quote! {
+        #item
+        const _: () = {
+            #(#helpers)*
+
+            impl<'de> ::serde::Deserialize<'de> for #ident {
+                fn deserialize<__D>(deserializer: __D) -> ::std::result::Result<Self, __D::Error>
+                where
+                    __D: ::serde::Deserializer<'de>,
+                {
+                    let untagged = ::roxmltree_to_serde::__private::Untagged::new(deserializer, #attr_prefix, #text)?;
+                    #(#attempts)*
+                    Err(::serde::de::Error::custom(#message))
+                }
+            }
+        };
+    })
+}
+
+/// Returns `true` if the enum has `#[serde(untagged)]`.
+fn is_untagged(attrs: &[Attribute]) -> Result<bool, Error> {
+    let mut untagged = false;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("untagged") {
+                untagged = true;
+            } else if meta.input.peek(Token![=]) {
+                // skip the value of options like `rename_all = ".."`
+                meta.value()?.parse::<syn::Expr>()?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(untagged)
+}
+
+/// Removes `Deserialize` from the derives and returns its path.
+fn take_derive_deserialize(attrs: &mut [Attribute]) -> Result<Option<Path>, Error> {
+    let mut deserialize = None;
+    for attr in attrs.iter_mut() {
+        if !attr.path().is_ident("derive") {
+            continue;
+        }
+        let derives = attr.parse_args_with(Punctuated::<Path, Token![,]>::parse_terminated)?;
+        let (found, rest): (Vec<Path>, Vec<Path>) = derives.into_iter().partition(|path| {
+            path.segments
+                .last()
+                .is_some_and(|segment| segment.ident == "Deserialize")
+        });
+        if let Some(path) = found.into_iter().next() {
+            deserialize = Some(path);
+            *attr = syn::parse_quote!(#[derive(#(#rest),*)]);
+        }
+    }
+    Ok(deserialize)
+}
+
+/// Removes the `#[xml(..)]` attribute of a field and returns its option.
+fn take_mapping(attrs: &mut Vec<Attribute>) -> Result<Option<Mapping>, Error> {
+    let Some(index) = attrs.iter().position(|attr| attr.path().is_ident("xml")) else {
+        return Ok(None);
+    };
+    let attr = attrs.remove(index);
+    let mut mapping = None;
+    attr.parse_nested_meta(|meta| {
+        if mapping.is_some() {
+            return Err(meta.error("expected only one of `attr`, `text` and `path`"));
+        }
+        if meta.path.is_ident("attr") {
+            mapping = Some(Mapping::Attr(meta.value()?.parse::<LitStr>()?.value()));
+        } else if meta.path.is_ident("text") {
+            mapping = Some(Mapping::Text);
+        } else if meta.path.is_ident("path") {
+            let lit = meta.value()?.parse::<LitStr>()?;
+            let path: Vec<String> = lit.value().split('/').map(str::to_owned).collect();
+            if path.iter().any(String::is_empty) {
+                return Err(Error::new_spanned(
+                    lit,
+                    "expected a relative path like `a/b`",
+                ));
+            }
+            mapping = Some(Mapping::Path(path));
+        } else {
+            return Err(meta.error("expected `attr`, `text` or `path`"));
+        }
+        Ok(())
+    })?;
+    match mapping {
+        Some(mapping) => Ok(Some(mapping)),
+        None => Err(Error::new_spanned(
+            attr,
+            "expected one of `attr`, `text` and `path`",
+        )),
+    }
+}
+
+fn derives(attrs: &[Attribute], name: &str) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("derive"))
+        .filter_map(|attr| {
+            attr.parse_args_with(Punctuated::<Path, Token![,]>::parse_terminated)
+                .ok()
+        })
+        .flatten()
+        .any(|path| {
+            path.segments
+                .last()
+                .is_some_and(|segment| segment.ident == name)
+        })
+}
+
+/// Returns `true` if `ty` is a path to a type with the name `name`, e.g. `Option<T>` for `Option`.
+fn is_named(ty: &Type, name: &str) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == name),
+        _ => false,
+    }
+}
+
+fn has_serde_default(attrs: &[Attribute]) -> Result<bool, Error> {
+    let mut default = false;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("default") {
+                default = true;
+            }
+            if meta.input.peek(Token![=]) {
+                meta.value()?.parse::<syn::Expr>()?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(default)
+}
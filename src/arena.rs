@@ -0,0 +1,478 @@
+//! An output value type with all keys and strings allocated from a bump arena.
+
+use bumpalo::collections::Vec as BumpVec;
+use bumpalo::Bump;
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use serde_json::{Map, Number, Value};
+
+#[cfg(feature = "pseudonymize")]
+use crate::pseudonymize::is_pseudonymized;
+use crate::units::is_unit_path;
+use crate::xml_base;
+use crate::{
+    add_location, add_namespace_declarations, collapses_to_attribute, convert_value, element_key,
+    finish_conversion, get_json_type, has_text_attributes, is_inner_text, keep_element,
+    keep_text_attribute, key_name, parse_document, parse_scalar, prepare_input, start_conversion,
+    text_content, text_prop_name, truncate_child, truncate_string, Config, Context, DiagnosticKind,
+    Error, JsonType, NullValue, Truncated, TruncationMarker,
+};
+#[cfg(feature = "json_types")]
+use crate::{is_coercion_failure, is_nil};
+
+/// A JSON value like `serde_json::Value`, but with all keys, strings, arrays and objects allocated from
+/// a `bumpalo::Bump` arena, so dropping the arena frees the whole output at once.
+///
+/// Objects are sorted by key, like `serde_json::Map`, so both serialize into the same JSON.
+#[derive(Debug, PartialEq)]
+pub enum ArenaValue<'b> {
+    Null,
+    Bool(bool),
+    Number(Number),
+    String(&'b str),
+    Array(BumpVec<'b, ArenaValue<'b>>),
+    Object(BumpVec<'b, (&'b str, ArenaValue<'b>)>),
+}
+
+impl<'b> ArenaValue<'b> {
+    /// Converts the value into a `serde_json::Value` by copying all strings out of the arena.
+    pub fn to_value(&self) -> Value {
+        match self {
+            ArenaValue::Null => Value::Null,
+            ArenaValue::Bool(b) => Value::Bool(*b),
+            ArenaValue::Number(n) => Value::Number(n.clone()),
+            ArenaValue::String(s) => Value::String((*s).to_owned()),
+            ArenaValue::Array(items) => Value::Array(items.iter().map(Self::to_value).collect()),
+            ArenaValue::Object(entries) => Value::Object(
+                entries
+                    .iter()
+                    .map(|(k, v)| ((*k).to_owned(), v.to_value()))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Copies a `serde_json::Value` into the arena.
+    fn from_value(bump: &'b Bump, value: Value) -> Self {
+        match value {
+            Value::Null => ArenaValue::Null,
+            Value::Bool(b) => ArenaValue::Bool(b),
+            Value::Number(n) => ArenaValue::Number(n),
+            Value::String(s) => ArenaValue::String(bump.alloc_str(&s)),
+            Value::Array(items) => ArenaValue::Array(BumpVec::from_iter_in(
+                items.into_iter().map(|v| ArenaValue::from_value(bump, v)),
+                bump,
+            )),
+            Value::Object(map) => {
+                let mut data = Object::new_in(bump);
+                for (k, v) in map {
+                    insert(
+                        &mut data,
+                        bump.alloc_str(&k),
+                        ArenaValue::from_value(bump, v),
+                    );
+                }
+                ArenaValue::Object(data)
+            }
+        }
+    }
+}
+
+impl Serialize for ArenaValue<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ArenaValue::Null => serializer.serialize_unit(),
+            ArenaValue::Bool(b) => serializer.serialize_bool(*b),
+            ArenaValue::Number(n) => n.serialize(serializer),
+            ArenaValue::String(s) => serializer.serialize_str(s),
+            ArenaValue::Array(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            ArenaValue::Object(entries) => {
+                let mut ser = serializer.serialize_map(Some(entries.len()))?;
+                for (k, v) in entries {
+                    ser.serialize_entry(k, v)?;
+                }
+                ser.end()
+            }
+        }
+    }
+}
+
+type Object<'b> = BumpVec<'b, (&'b str, ArenaValue<'b>)>;
+
+/// Returns the value of the key in the object, which is sorted by key.
+fn get_mut<'o, 'b>(data: &'o mut Object<'b>, key: &str) -> Option<&'o mut ArenaValue<'b>> {
+    match data.binary_search_by(|(k, _)| (*k).cmp(key)) {
+        Ok(i) => Some(&mut data[i].1),
+        Err(_) => None,
+    }
+}
+
+/// Inserts the entry into the object, which is sorted by key, replacing the value of an existing key.
+fn insert<'b>(data: &mut Object<'b>, key: &'b str, value: ArenaValue<'b>) {
+    match data.binary_search_by(|(k, _)| (*k).cmp(key)) {
+        Ok(i) => data[i].1 = value,
+        Err(i) => data.insert(i, (key, value)),
+    }
+}
+
+fn contains_key(data: &Object, key: &str) -> bool {
+    data.binary_search_by(|(k, _)| (*k).cmp(key)).is_ok()
+}
+
+/// Converts the given XML string into an `ArenaValue` with the same rules and the same result as
+/// `xml_str_to_json`, but allocates all keys, strings, arrays and objects of the output from `bump`.
+/// Use it for workloads that convert a document, serialize it and drop it: resetting the arena for
+/// the next document replaces freeing every single allocation. The parsed document is dropped
+/// before returning, so the output only borrows the arena.
+///
+/// Requires the `bumpalo` feature.
+/// # Example
+/// ```
+/// use bumpalo::Bump;
+/// use roxmltree_to_serde::{xml_str_to_arena_json, Config};
+///
+/// let mut bump = Bump::new();
+/// let config = Config::new_with_defaults();
+/// for xml in [r#"<a x="1"><b>text</b></a>"#, "<c/>"] {
+///     let json = xml_str_to_arena_json(xml, &config, &bump).unwrap();
+///     println!("{}", serde_json::to_string(&json).unwrap());
+///     drop(json);
+///     bump.reset();
+/// }
+/// ```
+pub fn xml_str_to_arena_json<'b>(
+    xml: &str,
+    config: &Config,
+    bump: &'b Bump,
+) -> Result<ArenaValue<'b>, Error> {
+    let xml = prepare_input(xml, config)?;
+    let doc = parse_document(&xml, config)?;
+    let ctx = &mut Context::new(config, None);
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("convert").entered();
+    start_conversion(ctx, &doc)?;
+    let e = doc.root_element();
+    let mut value = convert_element(ctx, bump, e, &mut String::new())?;
+    // the declarations come as an object with the `xmlns` attribute to merge into the root
+    if let Some(Value::Object(declarations)) = add_namespace_declarations(ctx, &doc, None)? {
+        let mut data = match value {
+            Some(ArenaValue::Object(data)) => data,
+            None | Some(ArenaValue::Null) => Object::new_in(bump),
+            Some(text) => {
+                let path = ["/", &element_key(config, &e)].concat();
+                let mut data = Object::new_in(bump);
+                data.push((bump.alloc_str(text_prop_name(config, &path)), text));
+                data
+            }
+        };
+        for (key, value) in declarations {
+            insert(
+                &mut data,
+                bump.alloc_str(&key),
+                ArenaValue::from_value(bump, value),
+            );
+        }
+        value = Some(ArenaValue::Object(data));
+    }
+    let mut data = Object::new_in(bump);
+    data.push((
+        bump.alloc_str(&element_key(config, &e)),
+        value.unwrap_or(ArenaValue::Null),
+    ));
+    finish_conversion(ctx);
+    Ok(ArenaValue::Object(data))
+}
+
+/// Converts a text value like `crate::convert_value`, but copies strings into the arena.
+fn convert_arena_value<'b>(
+    ctx: &mut Context,
+    bump: &'b Bump,
+    path: &str,
+    text: &str,
+    json_type: &JsonType,
+) -> Result<ArenaValue<'b>, Error> {
+    #[cfg(feature = "json_types")]
+    let coercion_failure = is_coercion_failure(text.trim(), json_type);
+    #[cfg(not(feature = "json_types"))]
+    let coercion_failure = false;
+    #[cfg(feature = "pseudonymize")]
+    let pseudonymized = is_pseudonymized(ctx.config, path);
+    #[cfg(not(feature = "pseudonymize"))]
+    let pseudonymized = false;
+    if coercion_failure
+        || pseudonymized
+        || is_unit_path(ctx.config, path)
+        || ctx.config.raw_values.applies_to(path)
+    {
+        let value = convert_value(ctx, path, text, json_type)?;
+        return Ok(ArenaValue::from_value(bump, value));
+    }
+
+    Ok(
+        match parse_scalar(text, ctx.config.leading_zero_as_string, json_type) {
+            Some(value) => ArenaValue::from_value(bump, value),
+            None => ArenaValue::String(bump.alloc_str(&truncate_string(ctx.config, text.trim()))),
+        },
+    )
+}
+
+fn add_arena_location<'b>(
+    ctx: &Context,
+    bump: &'b Bump,
+    el: &roxmltree::Node,
+    data: &mut Object<'b>,
+) {
+    if ctx.config.location_prop_name.is_some() {
+        let mut location = Map::new();
+        add_location(ctx, el, &mut location);
+        for (key, value) in location {
+            insert(
+                data,
+                bump.alloc_str(&key),
+                ArenaValue::from_value(bump, value),
+            );
+        }
+    }
+}
+
+fn convert_attributes<'b>(
+    ctx: &mut Context,
+    bump: &'b Bump,
+    el: roxmltree::Node,
+    path: &mut String,
+    data: &mut Object<'b>,
+    with_text: bool,
+) -> Result<(), Error> {
+    let prefix = ctx.config.xml_attr_prefix.as_str();
+    let path_len = path.len();
+    for attr in el.attributes() {
+        let name = key_name(ctx.config, attr.namespace(), attr.name());
+        if with_text && !keep_text_attribute(ctx.config, &name) {
+            continue;
+        }
+        path.push_str("/@");
+        path.push_str(&name);
+        ctx.report_namespace(path, attr.namespace())?;
+        let (_, json_type_value) = get_json_type(ctx.config, path);
+        let text = xml_base::resolve_attribute(ctx.config, &el, path, attr.value());
+        let value = convert_arena_value(ctx, bump, path, &text, json_type_value)?;
+        path.truncate(path_len);
+        let key: &str = bumpalo::format!(in bump, "{}{}", prefix, name).into_bump_str();
+        if contains_key(data, key) {
+            ctx.report(path, || DiagnosticKind::KeyCollision(key.to_owned()))?;
+        }
+        insert(data, key, value);
+    }
+    Ok(())
+}
+
+fn convert_text<'b>(
+    ctx: &mut Context,
+    bump: &'b Bump,
+    el: roxmltree::Node,
+    path: &mut String,
+    text: &str,
+    json_type_value: &JsonType,
+) -> Result<Option<ArenaValue<'b>>, Error> {
+    for child in el.children().skip(1) {
+        ctx.report_dropped(path, &child)?;
+    }
+
+    if !has_text_attributes(ctx.config, &el) {
+        return Ok(Some(convert_arena_value(
+            ctx,
+            bump,
+            path,
+            text,
+            json_type_value,
+        )?));
+    }
+    let mut data = Object::new_in(bump);
+    add_arena_location(ctx, bump, &el, &mut data);
+    convert_attributes(ctx, bump, el, path, &mut data, true)?;
+    let key = text_prop_name(ctx.config, path);
+    if contains_key(&data, key) {
+        ctx.report(path, || DiagnosticKind::KeyCollision(key.to_owned()))?;
+    }
+    let value = convert_arena_value(ctx, bump, path, text, json_type_value)?;
+    insert(&mut data, bump.alloc_str(key), value);
+    Ok(Some(ArenaValue::Object(data)))
+}
+
+fn convert_no_text<'b>(
+    ctx: &mut Context,
+    bump: &'b Bump,
+    el: roxmltree::Node,
+    path: &mut String,
+) -> Result<Option<ArenaValue<'b>>, Error> {
+    let mut data = Object::new_in(bump);
+    convert_attributes(ctx, bump, el, path, &mut data, false)?;
+    let mut truncated = Truncated::new();
+
+    for child in el.children() {
+        if !child.is_element() {
+            ctx.report_dropped(path, &child)?;
+            continue;
+        }
+
+        if !ctx.config.array_limits.is_empty() {
+            let key = element_key(ctx.config, &child);
+            let items = match get_mut(&mut data, &key) {
+                Some(ArenaValue::Array(items)) => items.len(),
+                Some(_) => 1,
+                None => 0,
+            };
+            if truncate_child(ctx.config, path, &key, items, &mut truncated) {
+                continue;
+            }
+        }
+
+        if let Some(val) = convert_element(ctx, bump, child, path)? {
+            let key = element_key(ctx.config, &child);
+            let name = key.as_ref();
+            let path_len = path.len();
+            path.push('/');
+            path.push_str(name);
+            let (json_type_array, _) = get_json_type(ctx.config, path);
+            path.truncate(path_len);
+
+            if json_type_array || contains_key(&data, name) || ctx.repeated_names.contains(name) {
+                if el.attributes().any(|attr| {
+                    name.strip_prefix(ctx.config.xml_attr_prefix.as_str())
+                        == Some(&key_name(ctx.config, attr.namespace(), attr.name()))
+                }) && !el
+                    .children()
+                    .take_while(|c| c != &child)
+                    .any(|c| c.is_element() && element_key(ctx.config, &c) == name)
+                {
+                    ctx.report(path, || DiagnosticKind::KeyCollision(name.to_owned()))?;
+                }
+
+                match get_mut(&mut data, name) {
+                    Some(ArenaValue::Array(existing)) => existing.push(val),
+                    Some(existing) => {
+                        let temp = std::mem::replace(existing, ArenaValue::Null);
+                        let mut items = BumpVec::with_capacity_in(2, bump);
+                        items.push(temp);
+                        items.push(val);
+                        *existing = ArenaValue::Array(items);
+                    }
+                    None => {
+                        let mut items = BumpVec::new_in(bump);
+                        items.push(val);
+                        insert(&mut data, bump.alloc_str(name), ArenaValue::Array(items));
+                    }
+                }
+            } else {
+                insert(&mut data, bump.alloc_str(name), val);
+            }
+        }
+    }
+
+    for (key, limit, dropped) in truncated {
+        let marker = match &limit.marker {
+            TruncationMarker::None => None,
+            TruncationMarker::Count => Some(ArenaValue::Number(dropped.into())),
+            TruncationMarker::Text(text) => Some(ArenaValue::String(bump.alloc_str(text))),
+        };
+        match get_mut(&mut data, &key) {
+            Some(ArenaValue::Array(items)) => items.extend(marker),
+            Some(existing) => {
+                let item = std::mem::replace(existing, ArenaValue::Null);
+                let mut items = BumpVec::with_capacity_in(2, bump);
+                items.push(item);
+                items.extend(marker);
+                *existing = ArenaValue::Array(items);
+            }
+            None => {
+                let mut items = BumpVec::new_in(bump);
+                items.extend(marker);
+                insert(&mut data, bump.alloc_str(&key), ArenaValue::Array(items));
+            }
+        }
+    }
+
+    if collapses_to_attribute(ctx.config, &el) {
+        return Ok(data.pop().map(|(_, value)| value));
+    }
+
+    if !data.is_empty() {
+        add_arena_location(ctx, bump, &el, &mut data);
+        return Ok(Some(ArenaValue::Object(data)));
+    }
+
+    Ok(match ctx.config.empty_element_handling {
+        NullValue::Null => Some(ArenaValue::Null),
+        NullValue::EmptyObject => {
+            add_arena_location(ctx, bump, &el, &mut data);
+            Some(ArenaValue::Object(data))
+        }
+        NullValue::Ignore => None,
+    })
+}
+
+/// Converts an element like `crate::convert_element`, with the path of its parent in `path`.
+fn convert_element<'b>(
+    ctx: &mut Context,
+    bump: &'b Bump,
+    el: roxmltree::Node,
+    path: &mut String,
+) -> Result<Option<ArenaValue<'b>>, Error> {
+    let path_len = path.len();
+    path.push('/');
+    path.push_str(&element_key(ctx.config, &el));
+    let value = convert_element_at_path(ctx, bump, el, path);
+    path.truncate(path_len);
+    value
+}
+
+fn convert_element_at_path<'b>(
+    ctx: &mut Context,
+    bump: &'b Bump,
+    el: roxmltree::Node,
+    path: &mut String,
+) -> Result<Option<ArenaValue<'b>>, Error> {
+    if !keep_element(ctx.config, &el) {
+        return Ok(None);
+    }
+    ctx.report_element_namespace(path, &el)?;
+
+    ctx.tick()?;
+    ctx.charge(&el)?;
+
+    #[cfg(feature = "tracing")]
+    let _span =
+        (ctx.depth == 1).then(|| tracing::trace_span!("convert_subtree", path = %path).entered());
+
+    if ctx.depth >= ctx.config.max_depth {
+        return Err(Error::DepthLimitExceeded(ctx.config.max_depth));
+    }
+
+    if is_inner_text(ctx.config, path) {
+        return Ok(Some(ArenaValue::String(
+            bump.alloc_str(&text_content(ctx.config, &el, path)?),
+        )));
+    }
+
+    let (_, json_type_value) = get_json_type(ctx.config, path);
+
+    #[cfg(feature = "json_types")]
+    if is_nil(ctx.config, &el, path) {
+        return Ok(Some(ArenaValue::Null));
+    }
+
+    ctx.depth += 1;
+    let value = match el.text() {
+        Some(text) if !text.trim().is_empty() => {
+            convert_text(ctx, bump, el, path, text.trim(), json_type_value)
+        }
+        _ => convert_no_text(ctx, bump, el, path),
+    };
+    ctx.depth -= 1;
+    value
+}
@@ -0,0 +1,195 @@
+//! Conversion of XML records into Arrow record batches, available with the `arrow` feature.
+
+use arrow_array::types::{
+    ArrowPrimitiveType, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type,
+    UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+};
+use arrow_array::{
+    ArrayRef, BooleanArray, LargeStringArray, PrimitiveArray, RecordBatch, StringArray,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::{xml_records_to_json, Config, Error};
+
+/// Converts every element at the absolute `record_path`, e.g. `/feed/item`, into a row of a `RecordBatch`.
+///
+/// The child elements and attributes of the records become the columns. Pass a `schema` to select and
+/// type the columns: values are coerced into the column types, so numeric strings fit number columns.
+/// Attributes are looked up with and without `Config.xml_attr_prefix`. Without a schema, the columns are
+/// inferred from all records: `Boolean`, `Int64` or `Float64` if all values fit, `Utf8` otherwise.
+/// Nested elements and repeated elements are stored as JSON text in `Utf8` columns, missing values are null.
+///
+/// Supported column types are `Boolean`, integers, floats, `Utf8` and `LargeUtf8`.
+/// Values that don't fit their column fail with `Error::Arrow`.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_records_to_arrow, Config};
+///
+/// let xml = r#"<feed><item id="1"><name>a</name></item><item id="2"/></feed>"#;
+/// let batch = xml_records_to_arrow(xml, "/feed/item", None, &Config::new_with_defaults()).unwrap();
+/// assert_eq!(batch.num_rows(), 2);
+/// assert_eq!(batch.schema().field(0).name(), "@id");
+/// ```
+pub fn xml_records_to_arrow(
+    xml: &str,
+    record_path: &str,
+    schema: Option<SchemaRef>,
+    config: &Config,
+) -> Result<RecordBatch, Error> {
+    let records = xml_records_to_json(xml, record_path, config)?;
+    let schema = match schema {
+        Some(schema) => schema,
+        None => infer_schema(&records),
+    };
+
+    let mut columns = Vec::with_capacity(schema.fields().len());
+    for field in schema.fields() {
+        let attr_name = [config.xml_attr_prefix.as_str(), field.name()].concat();
+        let values: Vec<Option<&Value>> = records
+            .iter()
+            .map(|record| {
+                record
+                    .get(field.name())
+                    .or_else(|| record.get(&attr_name))
+                    .filter(|v| !v.is_null())
+            })
+            .collect();
+        columns.push(column(field, &values)?);
+    }
+
+    RecordBatch::try_new(schema, columns).map_err(|e| Error::Arrow(e.to_string()))
+}
+
+/// Writes the record batch into a Parquet file. Available with the `parquet` feature.
+#[cfg(feature = "parquet")]
+pub fn record_batch_to_parquet<W>(batch: &RecordBatch, writer: W) -> Result<(), Error>
+where
+    W: std::io::Write + Send,
+{
+    let to_error = |e: parquet::errors::ParquetError| Error::Arrow(e.to_string());
+    let mut writer =
+        parquet::arrow::ArrowWriter::try_new(writer, batch.schema(), None).map_err(to_error)?;
+    writer.write(batch).map_err(to_error)?;
+    writer.close().map_err(to_error)?;
+    Ok(())
+}
+
+/// Returns a schema with one nullable column per property found in any of the records.
+fn infer_schema(records: &[Value]) -> SchemaRef {
+    let mut names = Vec::new();
+    let mut seen = HashSet::new();
+    for record in records.iter().filter_map(Value::as_object) {
+        for key in record.keys() {
+            if seen.insert(key.as_str()) {
+                names.push(key.as_str());
+            }
+        }
+    }
+
+    let fields: Vec<Field> = names
+        .into_iter()
+        .map(|name| {
+            let mut values = records
+                .iter()
+                .filter_map(|r| r.get(name))
+                .filter(|v| !v.is_null())
+                .peekable();
+            let data_type = if values.peek().is_none() {
+                DataType::Utf8
+            } else if values.clone().all(Value::is_boolean) {
+                DataType::Boolean
+            } else if values.clone().all(Value::is_i64) {
+                DataType::Int64
+            } else if values.all(Value::is_number) {
+                DataType::Float64
+            } else {
+                DataType::Utf8
+            };
+            Field::new(name, data_type, true)
+        })
+        .collect();
+    Arc::new(Schema::new(fields))
+}
+
+/// Returns the text of a value, nested values as JSON text.
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn mismatch(field: &Field, value: &Value) -> Error {
+    Error::Arrow(format!(
+        "{}: {} doesn't fit {}",
+        field.name(),
+        value,
+        field.data_type()
+    ))
+}
+
+fn primitive<T>(field: &Field, values: &[Option<&Value>]) -> Result<ArrayRef, Error>
+where
+    T: ArrowPrimitiveType,
+    T::Native: FromStr,
+{
+    let parsed = values
+        .iter()
+        .map(|v| match v {
+            Some(v) => match text(v).trim().parse::<T::Native>() {
+                Ok(n) => Ok(Some(n)),
+                Err(_) => Err(mismatch(field, v)),
+            },
+            None => Ok(None),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Arc::new(parsed.into_iter().collect::<PrimitiveArray<T>>()))
+}
+
+/// Builds the column of the field from the values of all records.
+fn column(field: &Field, values: &[Option<&Value>]) -> Result<ArrayRef, Error> {
+    Ok(match field.data_type() {
+        DataType::Boolean => {
+            let parsed = values
+                .iter()
+                .map(|v| match v {
+                    Some(Value::Bool(b)) => Ok(Some(*b)),
+                    Some(v) => match text(v).trim() {
+                        "true" | "1" => Ok(Some(true)),
+                        "false" | "0" => Ok(Some(false)),
+                        _ => Err(mismatch(field, v)),
+                    },
+                    None => Ok(None),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Arc::new(BooleanArray::from(parsed))
+        }
+        DataType::Int8 => primitive::<Int8Type>(field, values)?,
+        DataType::Int16 => primitive::<Int16Type>(field, values)?,
+        DataType::Int32 => primitive::<Int32Type>(field, values)?,
+        DataType::Int64 => primitive::<Int64Type>(field, values)?,
+        DataType::UInt8 => primitive::<UInt8Type>(field, values)?,
+        DataType::UInt16 => primitive::<UInt16Type>(field, values)?,
+        DataType::UInt32 => primitive::<UInt32Type>(field, values)?,
+        DataType::UInt64 => primitive::<UInt64Type>(field, values)?,
+        DataType::Float32 => primitive::<Float32Type>(field, values)?,
+        DataType::Float64 => primitive::<Float64Type>(field, values)?,
+        DataType::Utf8 => Arc::new(StringArray::from(
+            values.iter().map(|v| v.map(text)).collect::<Vec<_>>(),
+        )),
+        DataType::LargeUtf8 => Arc::new(LargeStringArray::from(
+            values.iter().map(|v| v.map(text)).collect::<Vec<_>>(),
+        )),
+        data_type => {
+            return Err(Error::Arrow(format!(
+                "{}: unsupported column type {}",
+                field.name(),
+                data_type
+            )))
+        }
+    })
+}
@@ -0,0 +1,63 @@
+//! Conversion of XML from async readers and byte streams, available with the `tokio` feature.
+
+use std::future::poll_fn;
+use std::io;
+use std::pin::pin;
+
+use futures_core::Stream;
+use serde_json::Value;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::{xml_str_to_json, Config, Error};
+
+/// Reads the whole XML document from an async reader, e.g. a `tokio::fs::File` or a socket,
+/// and converts it with the same rules as `xml_str_to_json`.
+///
+/// Reading doesn't block the runtime. The document has to be parsed as a whole, so it's buffered
+/// in memory and converted on the current task once it's complete.
+/// Read errors and invalid UTF-8 are returned as `Error::Io`.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_async_read_to_json, Config};
+/// use serde_json::json;
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let reader: &[u8] = b"<a><b>1</b></a>";
+/// let json = xml_async_read_to_json(reader, &Config::new_with_defaults()).await.unwrap();
+/// assert_eq!(json, json!({"a": {"b": 1}}));
+/// # });
+/// ```
+pub async fn xml_async_read_to_json<R: AsyncRead>(
+    reader: R,
+    config: &Config,
+) -> Result<Value, Error> {
+    let mut buffer = Vec::new();
+    pin!(reader).read_to_end(&mut buffer).await?;
+    convert_buffer(buffer, config)
+}
+
+/// Collects the chunks of a byte stream, e.g. the body of an HTTP request, and converts the XML document
+/// with the same rules as `xml_str_to_json`. A document may be split anywhere, even inside of
+/// a multi-byte character.
+///
+/// Streams of plain `Bytes` can be adapted with `.map(Ok::<_, std::convert::Infallible>)`.
+/// Errors of the stream and invalid UTF-8 are returned as `Error::Io`.
+pub async fn xml_stream_to_json<S, B, E>(stream: S, config: &Config) -> Result<Value, Error>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: AsRef<[u8]>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let mut stream = pin!(stream);
+    let mut buffer = Vec::new();
+    while let Some(chunk) = poll_fn(|cx| stream.as_mut().poll_next(cx)).await {
+        buffer.extend_from_slice(chunk.map_err(io::Error::other)?.as_ref());
+    }
+    convert_buffer(buffer, config)
+}
+
+fn convert_buffer(buffer: Vec<u8>, config: &Config) -> Result<Value, Error> {
+    let xml =
+        String::from_utf8(buffer).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    xml_str_to_json(&xml, config)
+}
@@ -0,0 +1,244 @@
+//! Conversion of XML records into Avro values, available with the `avro` feature.
+
+use avro_rs::types::Value as AvroValue;
+use avro_rs::Schema;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::{xml_records_to_json, Config, Error};
+
+/// Converts every element at the absolute `record_path`, e.g. `/feed/item`, into an Avro value of `schema`,
+/// ready to be appended to an `avro_rs::Writer`.
+///
+/// The elements are converted with the same rules as `xml_str_to_json` first, and the values are then coerced
+/// into the types of the schema, so numbers fit `string` fields and numeric strings fit `long` fields:
+/// * record fields are looked up by name, and with `Config.xml_attr_prefix` for attributes
+/// * missing fields get their default value, `null` for nullable unions or an empty array
+/// * a single element is wrapped into an array for `array` fields
+/// * `date` fields accept `YYYY-MM-DD` strings, other logical types accept numbers only
+///
+/// Values that don't fit the schema fail the conversion with `Error::Avro`. `fixed`, `decimal`, `uuid`
+/// and `duration` are not supported.
+/// # Example
+/// ```
+/// use avro_rs::{types::Value, Schema};
+/// use roxmltree_to_serde::{xml_records_to_avro, Config};
+///
+/// let schema = Schema::parse_str(r#"{"type": "record", "name": "item", "fields": [
+///     {"name": "id", "type": "string"}, {"name": "qty", "type": "long"}
+/// ]}"#).unwrap();
+/// let xml = r#"<feed><item id="A7"><qty>3</qty></item></feed>"#;
+/// let records = xml_records_to_avro(xml, "/feed/item", &schema, &Config::new_with_defaults()).unwrap();
+/// assert_eq!(records[0], Value::Record(vec![
+///     ("id".to_owned(), Value::String("A7".to_owned())),
+///     ("qty".to_owned(), Value::Long(3)),
+/// ]));
+/// ```
+pub fn xml_records_to_avro(
+    xml: &str,
+    record_path: &str,
+    schema: &Schema,
+    config: &Config,
+) -> Result<Vec<AvroValue>, Error> {
+    xml_records_to_json(xml, record_path, config)?
+        .into_iter()
+        .map(|record| json_to_avro(record, schema, config, record_path))
+        .collect()
+}
+
+fn mismatch(path: &str, schema: &Schema, value: &Value) -> Error {
+    Error::Avro(format!(
+        "{}: {} doesn't fit {}",
+        path,
+        value,
+        schema.canonical_form()
+    ))
+}
+
+/// Returns the text of a scalar value, e.g. to parse it into a number.
+fn scalar_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(_) | Value::Bool(_) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// Coerces the JSON value at `path` into the Avro type of `schema`.
+fn json_to_avro(
+    value: Value,
+    schema: &Schema,
+    config: &Config,
+    path: &str,
+) -> Result<AvroValue, Error> {
+    let parse = |value: &Value| scalar_text(value).map(|s| s.trim().to_owned());
+    Ok(match schema {
+        Schema::Null if value.is_null() => AvroValue::Null,
+        Schema::Boolean => match &value {
+            Value::Bool(b) => AvroValue::Boolean(*b),
+            _ => match parse(&value).as_deref() {
+                Some("true") | Some("1") => AvroValue::Boolean(true),
+                Some("false") | Some("0") => AvroValue::Boolean(false),
+                _ => return Err(mismatch(path, schema, &value)),
+            },
+        },
+        Schema::Int => match parse(&value).and_then(|s| s.parse().ok()) {
+            Some(i) => AvroValue::Int(i),
+            None => return Err(mismatch(path, schema, &value)),
+        },
+        Schema::Long => match parse(&value).and_then(|s| s.parse().ok()) {
+            Some(i) => AvroValue::Long(i),
+            None => return Err(mismatch(path, schema, &value)),
+        },
+        Schema::Float => match parse(&value).and_then(|s| s.parse().ok()) {
+            Some(f) => AvroValue::Float(f),
+            None => return Err(mismatch(path, schema, &value)),
+        },
+        Schema::Double => match parse(&value).and_then(|s| s.parse().ok()) {
+            Some(f) => AvroValue::Double(f),
+            None => return Err(mismatch(path, schema, &value)),
+        },
+        Schema::String => match scalar_text(&value) {
+            Some(s) => AvroValue::String(s),
+            None => return Err(mismatch(path, schema, &value)),
+        },
+        Schema::Bytes => match scalar_text(&value) {
+            Some(s) => AvroValue::Bytes(s.into_bytes()),
+            None => return Err(mismatch(path, schema, &value)),
+        },
+        Schema::Enum { symbols, .. } => {
+            let symbol = scalar_text(&value);
+            match symbols.iter().position(|s| Some(s) == symbol.as_ref()) {
+                Some(index) => AvroValue::Enum(index as i32, symbols[index].clone()),
+                None => return Err(mismatch(path, schema, &value)),
+            }
+        }
+        Schema::Array(items) => {
+            let values = match value {
+                Value::Array(values) => values,
+                Value::Null => Vec::new(),
+                value => vec![value],
+            };
+            AvroValue::Array(
+                values
+                    .into_iter()
+                    .map(|v| json_to_avro(v, items, config, path))
+                    .collect::<Result<_, _>>()?,
+            )
+        }
+        Schema::Map(values) => match value {
+            Value::Object(map) => {
+                let mut avro_map = HashMap::with_capacity(map.len());
+                for (key, v) in map {
+                    let key_path = [path, "/", &key].concat();
+                    avro_map.insert(key, json_to_avro(v, values, config, &key_path)?);
+                }
+                AvroValue::Map(avro_map)
+            }
+            value => return Err(mismatch(path, schema, &value)),
+        },
+        Schema::Union(union) => {
+            // the first variant that fits wins
+            for variant in union.variants() {
+                if let Ok(v) = json_to_avro(value.clone(), variant, config, path) {
+                    return Ok(AvroValue::Union(Box::new(v)));
+                }
+            }
+            return Err(mismatch(path, schema, &value));
+        }
+        Schema::Record { fields, .. } => {
+            let mut map = match value {
+                Value::Object(map) => map,
+                value => return Err(mismatch(path, schema, &value)),
+            };
+            let mut record = Vec::with_capacity(fields.len());
+            for field in fields {
+                let field_path = [path, "/", &field.name].concat();
+                let attr_name = [config.xml_attr_prefix.as_str(), &field.name].concat();
+                let value = match map.remove(&field.name).or_else(|| map.remove(&attr_name)) {
+                    Some(value) => json_to_avro(value, &field.schema, config, &field_path)?,
+                    None => missing_field(field, config, &field_path)?,
+                };
+                record.push((field.name.clone(), value));
+            }
+            AvroValue::Record(record)
+        }
+        Schema::Date => match &value {
+            Value::String(s) => match parse_date(s.trim()) {
+                Some(days) => AvroValue::Date(days),
+                None => return Err(mismatch(path, schema, &value)),
+            },
+            _ => match value.as_i64().and_then(|i| i32::try_from(i).ok()) {
+                Some(days) => AvroValue::Date(days),
+                None => return Err(mismatch(path, schema, &value)),
+            },
+        },
+        Schema::TimeMillis => match value.as_i64().and_then(|i| i32::try_from(i).ok()) {
+            Some(t) => AvroValue::TimeMillis(t),
+            None => return Err(mismatch(path, schema, &value)),
+        },
+        Schema::TimeMicros => match value.as_i64() {
+            Some(t) => AvroValue::TimeMicros(t),
+            None => return Err(mismatch(path, schema, &value)),
+        },
+        Schema::TimestampMillis => match value.as_i64() {
+            Some(t) => AvroValue::TimestampMillis(t),
+            None => return Err(mismatch(path, schema, &value)),
+        },
+        Schema::TimestampMicros => match value.as_i64() {
+            Some(t) => AvroValue::TimestampMicros(t),
+            None => return Err(mismatch(path, schema, &value)),
+        },
+        _ => return Err(mismatch(path, schema, &value)),
+    })
+}
+
+/// Returns the value of a field that has no element or attribute.
+fn missing_field(
+    field: &avro_rs::schema::RecordField,
+    config: &Config,
+    path: &str,
+) -> Result<AvroValue, Error> {
+    if let Some(default) = &field.default {
+        // the default of a union belongs to its first variant
+        return match &field.schema {
+            Schema::Union(union) => match union.variants().first() {
+                Some(first) => Ok(AvroValue::Union(Box::new(json_to_avro(
+                    default.clone(),
+                    first,
+                    config,
+                    path,
+                )?))),
+                None => Err(mismatch(path, &field.schema, default)),
+            },
+            schema => json_to_avro(default.clone(), schema, config, path),
+        };
+    }
+    match &field.schema {
+        Schema::Union(union) if union.is_nullable() => {
+            Ok(AvroValue::Union(Box::new(AvroValue::Null)))
+        }
+        Schema::Null => Ok(AvroValue::Null),
+        Schema::Array(_) => Ok(AvroValue::Array(Vec::new())),
+        _ => Err(Error::Avro(format!("{}: missing required field", path))),
+    }
+}
+
+/// Returns the number of days since 1970-01-01 for a `YYYY-MM-DD` date.
+fn parse_date(s: &str) -> Option<i32> {
+    let mut parts = s.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next().filter(|m| m.len() == 2)?.parse().ok()?;
+    let day: i64 = parts.next().filter(|d| d.len() == 2)?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    // days from civil, see http://howardhinnant.github.io/date_algorithms.html
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    i32::try_from(era * 146_097 + doe - 719_468).ok()
+}
@@ -0,0 +1,53 @@
+//! Conversion of many documents with one shared `Config`.
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use serde_json::Value;
+
+use crate::{xml_str_to_json, Config, Error};
+
+/// Converts every document with `xml_str_to_json` and returns the results in the order of the inputs.
+/// A malformed document doesn't stop the batch, its error is returned at its position instead.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_batch_to_json, Config};
+/// use serde_json::json;
+///
+/// let results = xml_batch_to_json(["<a>1</a>", "<a>", "<b>x</b>"], &Config::new_with_defaults());
+/// assert_eq!(results[0].as_ref().unwrap(), &json!({"a": 1}));
+/// assert!(results[1].is_err());
+/// assert_eq!(results[2].as_ref().unwrap(), &json!({"b": "x"}));
+/// ```
+pub fn xml_batch_to_json<'a>(
+    inputs: impl IntoIterator<Item = &'a str>,
+    config: &Config,
+) -> Vec<Result<Value, Error>> {
+    inputs
+        .into_iter()
+        .map(|xml| xml_str_to_json(xml, config))
+        .collect()
+}
+
+/// Converts the documents like `xml_batch_to_json`, but in parallel on the global rayon thread pool.
+/// All threads share the same `Config`. Available with the `rayon` feature.
+///
+/// Use `rayon::ThreadPool::install` to run it on a different pool. Progress callbacks are invoked
+/// concurrently from different threads, and a cancellation flag stops all remaining conversions.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_batch_to_json_parallel, Config};
+///
+/// let docs: Vec<String> = (0..100).map(|i| format!("<a>{}</a>", i)).collect();
+/// let results = xml_batch_to_json_parallel(&docs, &Config::new_with_defaults());
+/// assert_eq!(results[42].as_ref().unwrap()["a"], 42);
+/// ```
+#[cfg(feature = "rayon")]
+pub fn xml_batch_to_json_parallel<S>(inputs: &[S], config: &Config) -> Vec<Result<Value, Error>>
+where
+    S: AsRef<str> + Sync,
+{
+    inputs
+        .par_iter()
+        .map(|xml| xml_str_to_json(xml.as_ref(), config))
+        .collect()
+}
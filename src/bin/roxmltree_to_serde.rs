@@ -0,0 +1,493 @@
+//! Command line converter from XML to JSON, available with the `cli` feature.
+//!
+//! Reads XML documents from files or stdin and writes JSON or newline-delimited JSON to stdout.
+//! Run `roxmltree_to_serde --help` for all options.
+
+use std::fs;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use clap::{Parser, ValueEnum};
+use roxmltree_to_serde::{
+    xml_bytes_to_str, xml_query, xml_split_records_to_files, xml_str_to_json, ArrayInference,
+    CoercionFailurePolicy, Config, DefaultNamespace, EntityCatalog, IdRef, JsonArray, NullValue,
+    OverrideUsage, PruneEmpty, RawValues, SingleKeyObjects, SortedKeys, SplitConfig,
+    TextAttributes, TruncationMarker, UnitTable, Utf8Policy, XmlRecords,
+};
+use serde_json::Value;
+
+const RULES_HELP: &str = "\
+Type rules, one per line in a rules file or one per --type flag, map an absolute path to a JSON type:
+
+    /catalog/book/@id   string
+    /catalog/book/price number
+    /catalog/book/tag   string[]
+    /catalog/book/sold  bool:yes,Y
+
+The types are `string`, `number`, `bool` (true for `true`) or `bool:<values>` with a comma-separated list of
+true values, and `infer`. A `[]` suffix always converts the nodes into an array. Empty lines and lines starting
+with `#` are ignored.";
+
+/// Converts XML documents into JSON.
+#[derive(Parser)]
+#[command(version, after_long_help = RULES_HELP)]
+struct Args {
+    /// XML files to convert, or `-` for stdin. Reads stdin if there are none.
+    files: Vec<String>,
+    /// Write one compact JSON value per line: one per document, or one per match of --query.
+    #[arg(long)]
+    ndjson: bool,
+    /// Pretty-print the JSON output.
+    #[arg(long, conflicts_with = "ndjson")]
+    pretty: bool,
+    /// Sort the keys of objects by name, with attributes before child elements of the same name.
+    #[arg(long)]
+    sort_keys: bool,
+    /// Output only the values selected by a JSONPath query, e.g. `$.feed.item[*]`.
+    #[arg(long, short)]
+    query: Option<String>,
+    /// Stream the elements at an absolute path, e.g. `/feed/item`, in constant memory instead of
+    /// reading the whole document.
+    #[arg(long, value_name = "PATH", conflicts_with = "query")]
+    records: Option<String>,
+    /// Write every record of --records to its own file instead of stdout, e.g. `out/item-{n}.json`,
+    /// with `{n}` replaced by the counter or `{n:6}` by the counter padded to 6 digits.
+    #[arg(long, value_name = "PATTERN", requires = "records")]
+    split: Option<String>,
+    /// Stop after N values of --query or records of --records per document.
+    #[arg(long, value_name = "N")]
+    limit: Option<usize>,
+    /// Prefix of attribute names.
+    #[arg(long, default_value = "@")]
+    attr_prefix: String,
+    /// Property name of the text of elements with attributes or children.
+    #[arg(long, default_value = "#text")]
+    text_prop: String,
+    /// Property name of the text of the element at PATH, e.g. `/station/measurement=value`.
+    #[arg(long = "path-text-prop", value_name = "PATH=NAME")]
+    path_text_props: Vec<String>,
+    /// Split the values at PATH into magnitude and unit, e.g. `/sensor/@weight` for `10.5kg`.
+    #[arg(long = "unit", value_name = "PATH")]
+    unit_paths: Vec<String>,
+    /// Split the durations at PATH into magnitude and unit and convert them into milliseconds,
+    /// e.g. `/job/@timeout` for `1.5s`.
+    #[arg(long = "duration", value_name = "PATH")]
+    duration_paths: Vec<String>,
+    /// Convert elements with a single attribute and nothing else into the value of the attribute.
+    #[arg(long)]
+    collapse_single_attributes: bool,
+    /// Drop the attributes of elements with text, so they become plain values.
+    #[arg(long)]
+    drop_text_attributes: bool,
+    /// Keep the attribute NAME of elements with text and drop all others, can be repeated.
+    #[arg(
+        long = "keep-text-attribute",
+        value_name = "NAME",
+        conflicts_with = "drop_text_attributes"
+    )]
+    keep_text_attributes: Vec<String>,
+    /// Conversion of empty elements.
+    #[arg(long, value_enum, default_value_t = Empty::Object)]
+    empty: Empty,
+    /// Keep numbers with leading zeros, like `007`, as strings.
+    #[arg(long)]
+    leading_zero_as_string: bool,
+    /// When elements without a type rule are converted into arrays.
+    #[arg(long, value_enum, default_value_t = Arrays::PerParent)]
+    arrays: Arrays,
+    /// Handling of values that don't fit the JSON type of their rule.
+    #[arg(long, value_enum, default_value_t = Coercion::Fallback)]
+    coercion_failure: Coercion,
+    /// Fail instead of silently dropping mixed content, duplicate keys and processing instructions.
+    #[arg(long)]
+    strict: bool,
+    /// Maximum nesting depth of elements.
+    #[arg(long, default_value_t = 128)]
+    max_depth: usize,
+    /// Maximum approximate size of the output of a document in bytes.
+    #[arg(long)]
+    memory_budget: Option<usize>,
+    /// Keep the original text of all values next to the converted values.
+    #[arg(long)]
+    raw_values: bool,
+    /// Annotate objects with the source location of their element under this property name.
+    #[arg(long)]
+    locations: Option<String>,
+    /// Emit the namespace declarations of the document on the root element.
+    #[arg(long)]
+    namespace_declarations: bool,
+    /// Give names in a namespace a fixed prefix in the JSON keys, e.g. `urn:vendor=v`.
+    #[arg(long = "namespace-prefix", value_name = "URI=PREFIX")]
+    namespace_prefixes: Vec<String>,
+    /// Emit names in a namespace as `{URI}localname`.
+    #[arg(long)]
+    clark_notation: bool,
+    /// Handling of elements in a default namespace.
+    #[arg(long, value_enum, default_value_t = DefaultNs::Ignore)]
+    default_namespace: DefaultNs,
+    /// Resolve the URIs of the attribute at PATH against xml:base, e.g. `/feed/link/@href`.
+    #[arg(long = "xml-base", value_name = "PATH")]
+    xml_base_paths: Vec<String>,
+    /// Convert the element at PATH into the text of all its descendants, e.g. `/feed/item/description`.
+    #[arg(long = "inner-text", value_name = "PATH")]
+    inner_text_paths: Vec<String>,
+    /// Remove empty values of this kind from the output, can be repeated.
+    #[arg(long, value_enum, value_name = "KIND")]
+    prune_empty: Vec<Prune>,
+    /// Shorten string values to N characters, ending with `…`.
+    #[arg(long, value_name = "N")]
+    max_string_len: Option<usize>,
+    /// Keep only the first N elements at PATH, followed by the number of dropped ones,
+    /// e.g. `/feed/item=100`.
+    #[arg(long = "array-limit", value_name = "PATH=N")]
+    array_limits: Vec<String>,
+    /// Collapse objects with a single property: `join` their keys or `hoist` their values.
+    #[arg(long, value_enum, default_value_t = SingleKeys::Keep)]
+    single_keys: SingleKeys,
+    /// The separator of keys joined by `--single-keys join`.
+    #[arg(long, default_value = ".")]
+    key_separator: String,
+    /// Resolve the IDREFs of the attribute at PATH, with MODE `inline`, `pointer`, `inline-list`
+    /// or `pointer-list`, e.g. `/shop/order/@items=inline-list`.
+    #[arg(long = "idref", value_name = "PATH=MODE")]
+    idrefs: Vec<String>,
+    /// An OASIS XML catalog with the local files of external entities and DTDs. Without it,
+    /// documents with a DTD are rejected.
+    #[arg(long)]
+    catalog: Option<String>,
+    /// Skip byte order marks, whitespace and garbage before the XML declaration.
+    #[arg(long)]
+    skip_leading_junk: bool,
+    /// Replace bytes that are not valid UTF-8 with U+FFFD instead of failing.
+    #[arg(long)]
+    lossy_utf8: bool,
+    /// The URI of the document that xml:base is resolved against.
+    #[arg(long)]
+    base_uri: Option<String>,
+    /// A type rule `PATH=TYPE`, e.g. `/a/b/@id=string`. See --help for the types.
+    #[arg(long = "type", value_name = "PATH=TYPE")]
+    types: Vec<String>,
+    /// A file with one type rule `PATH TYPE` per line. See --help for the format.
+    #[arg(long)]
+    rules: Vec<String>,
+    /// Print the type rules that didn't match any node of the converted documents to stderr.
+    #[arg(long)]
+    report_unused_rules: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Empty {
+    Object,
+    Null,
+    Ignore,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Arrays {
+    PerParent,
+    DocumentWide,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DefaultNs {
+    Ignore,
+    IncludeUri,
+    Error,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Prune {
+    Nulls,
+    Objects,
+    Arrays,
+    Strings,
+    All,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SingleKeys {
+    Keep,
+    Join,
+    Hoist,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Coercion {
+    Fallback,
+    KeepString,
+    Null,
+    Error,
+}
+
+fn main() -> ExitCode {
+    match run(Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("roxmltree_to_serde: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let config = config(&args)?;
+    for warning in config.validate() {
+        eprintln!("roxmltree_to_serde: warning: {}", warning);
+    }
+    let files = if args.files.is_empty() {
+        vec!["-".to_owned()]
+    } else {
+        args.files.clone()
+    };
+
+    let mut values = Vec::new();
+    let mut out = BufWriter::new(io::stdout().lock());
+    // the files of all documents are counted together
+    let mut split_count = 0;
+    for file in &files {
+        if let Some(record_path) = &args.records {
+            let reader = open(file).map_err(|e| format!("{}: {}", file, e))?;
+            if let Some(pattern) = &args.split {
+                let split = SplitConfig::new(pattern).with_first_index(split_count);
+                let split = if args.pretty {
+                    split.with_pretty()
+                } else {
+                    split
+                };
+                split_count += xml_split_records_to_files(reader, record_path, &config, &split)
+                    .map_err(|e| format!("{}: {}", file, e))?;
+                continue;
+            }
+            for record in XmlRecords::new(reader, record_path, &config) {
+                let record = record.map_err(|e| format!("{}: {}", file, e))?;
+                if args.ndjson {
+                    write_value(&mut out, &record, &config, false)?;
+                } else {
+                    values.push(record);
+                }
+            }
+            continue;
+        }
+        let xml = read(file, &config).map_err(|e| format!("{}: {}", file, e))?;
+        let converted = match &args.query {
+            Some(query) => xml_query(&xml, query, &config),
+            None => xml_str_to_json(&xml, &config).map(|value| vec![value]),
+        };
+        let converted = converted.map_err(|e| format!("{}: {}", file, e))?;
+        if args.ndjson {
+            for value in converted {
+                write_value(&mut out, &value, &config, false)?;
+            }
+        } else {
+            values.extend(converted);
+        }
+    }
+
+    if let Some(usage) = &config.override_usage {
+        for rule in usage.report(&config).unmatched {
+            eprintln!("roxmltree_to_serde: unused type rule {}", rule);
+        }
+    }
+    if args.split.is_some() {
+        return Ok(());
+    }
+    if !args.ndjson {
+        // a single document without a query is written as it is, everything else as an array
+        let value = if files.len() == 1 && args.query.is_none() && args.records.is_none() {
+            values.pop().unwrap_or(Value::Null)
+        } else {
+            Value::Array(values)
+        };
+        write_value(&mut out, &value, &config, args.pretty)?;
+    }
+    out.flush().map_err(|e| e.to_string())
+}
+
+fn open(file: &str) -> io::Result<Box<dyn Read>> {
+    if file == "-" {
+        Ok(Box::new(io::stdin().lock()))
+    } else {
+        Ok(Box::new(BufReader::new(fs::File::open(file)?)))
+    }
+}
+
+fn read(file: &str, config: &Config) -> io::Result<String> {
+    let bytes = if file == "-" {
+        let mut bytes = Vec::new();
+        io::stdin().read_to_end(&mut bytes)?;
+        bytes
+    } else {
+        fs::read(file)?
+    };
+    // documents in legacy encodings are transcoded with the `encoding` feature
+    xml_bytes_to_str(&bytes, config)
+        .map(|xml| xml.into_owned())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+}
+
+fn write_value<W: Write>(
+    out: &mut W,
+    value: &Value,
+    config: &Config,
+    pretty: bool,
+) -> Result<(), String> {
+    let sorted = SortedKeys::new(value, config);
+    match (config.sort_keys, pretty) {
+        (true, true) => serde_json::to_writer_pretty(&mut *out, &sorted),
+        (true, false) => serde_json::to_writer(&mut *out, &sorted),
+        (false, true) => serde_json::to_writer_pretty(&mut *out, value),
+        (false, false) => serde_json::to_writer(&mut *out, value),
+    }
+    .map_err(|e| e.to_string())?;
+    out.write_all(b"\n").map_err(|e| e.to_string())
+}
+
+fn config(args: &Args) -> Result<Config, String> {
+    let empty = match args.empty {
+        Empty::Object => NullValue::EmptyObject,
+        Empty::Null => NullValue::Null,
+        Empty::Ignore => NullValue::Ignore,
+    };
+    let mut config = Config::new_with_custom_values(
+        args.leading_zero_as_string,
+        &args.attr_prefix,
+        &args.text_prop,
+        empty,
+    );
+    config.array_inference = match args.arrays {
+        Arrays::PerParent => ArrayInference::PerParent,
+        Arrays::DocumentWide => ArrayInference::DocumentWide,
+    };
+    config.coercion_failure_policy = match args.coercion_failure {
+        Coercion::Fallback => CoercionFailurePolicy::Fallback,
+        Coercion::KeepString => CoercionFailurePolicy::KeepString,
+        Coercion::Null => CoercionFailurePolicy::Null,
+        Coercion::Error => CoercionFailurePolicy::Error,
+    };
+    config.strict = args.strict;
+    config.max_depth = args.max_depth;
+    config.memory_budget = args.memory_budget;
+    config.match_limit = args.limit;
+    config.emit_namespace_declarations = args.namespace_declarations;
+    config.clark_notation = args.clark_notation;
+    config.default_namespace = match args.default_namespace {
+        DefaultNs::Ignore => DefaultNamespace::Ignore,
+        DefaultNs::IncludeUri => DefaultNamespace::IncludeUri,
+        DefaultNs::Error => DefaultNamespace::Error,
+    };
+    for mapping in &args.namespace_prefixes {
+        let (uri, prefix) = mapping
+            .rsplit_once('=')
+            .ok_or_else(|| format!("--namespace-prefix {}: expected `URI=PREFIX`", mapping))?;
+        config = config.with_namespace_prefix(uri, prefix);
+    }
+    for path in &args.xml_base_paths {
+        config = config.add_xml_base_path(path);
+    }
+    for mapping in &args.path_text_props {
+        let (path, name) = mapping
+            .rsplit_once('=')
+            .ok_or_else(|| format!("--path-text-prop {}: expected `PATH=NAME`", mapping))?;
+        config = config.add_text_prop_name(path, name);
+    }
+    for path in &args.unit_paths {
+        config = config.add_unit_path(path, UnitTable::new());
+    }
+    for path in &args.duration_paths {
+        config = config.add_unit_path(path, UnitTable::durations());
+    }
+    config.collapse_single_attributes = args.collapse_single_attributes;
+    if args.drop_text_attributes {
+        config.text_attributes = TextAttributes::Drop;
+    } else if !args.keep_text_attributes.is_empty() {
+        let names = args.keep_text_attributes.iter().cloned().collect();
+        config.text_attributes = TextAttributes::KeepOnly(names);
+    }
+    for path in &args.inner_text_paths {
+        config = config.add_inner_text_path(path);
+    }
+    config.max_string_len = args.max_string_len;
+    for limit in &args.array_limits {
+        let (path, max_items) = limit
+            .rsplit_once('=')
+            .and_then(|(path, n)| Some((path, n.parse().ok()?)))
+            .ok_or_else(|| format!("--array-limit {}: expected `PATH=N`", limit))?;
+        config = config.add_array_limit(path, max_items, TruncationMarker::Count);
+    }
+    for prune in &args.prune_empty {
+        match prune {
+            Prune::Nulls => config.prune_empty.nulls = true,
+            Prune::Objects => config.prune_empty.objects = true,
+            Prune::Arrays => config.prune_empty.arrays = true,
+            Prune::Strings => config.prune_empty.strings = true,
+            Prune::All => config.prune_empty = PruneEmpty::ALL,
+        }
+    }
+    config.single_key_objects = match args.single_keys {
+        SingleKeys::Keep => SingleKeyObjects::Keep,
+        SingleKeys::Join => SingleKeyObjects::Join(args.key_separator.clone()),
+        SingleKeys::Hoist => SingleKeyObjects::Hoist,
+    };
+    config.sort_keys = args.sort_keys;
+    config.base_uri = args.base_uri.clone();
+    config.skip_leading_junk = args.skip_leading_junk;
+    if args.lossy_utf8 {
+        config = config.with_utf8_policy(Utf8Policy::Lossy);
+    }
+    if let Some(catalog) = &args.catalog {
+        let catalog = EntityCatalog::from_file(catalog).map_err(|e| e.to_string())?;
+        config = config.with_entity_catalog(catalog);
+    }
+    for idref in &args.idrefs {
+        let (path, mode) = idref
+            .rsplit_once('=')
+            .ok_or_else(|| format!("--idref {}: expected `PATH=MODE`", idref))?;
+        let mode = match mode {
+            "inline" => IdRef::Inline,
+            "pointer" => IdRef::Pointer,
+            "inline-list" => IdRef::InlineList,
+            "pointer-list" => IdRef::PointerList,
+            _ => return Err(format!("--idref {}: unknown mode `{}`", idref, mode)),
+        };
+        config = config.add_idref_path(path, mode);
+    }
+    if args.raw_values {
+        config = config.with_raw_values(RawValues::Always);
+    }
+    if let Some(prop_name) = &args.locations {
+        config = config.with_source_locations(prop_name);
+    }
+
+    let mut rules = Vec::new();
+    for file in &args.rules {
+        let text = fs::read_to_string(file).map_err(|e| format!("{}: {}", file, e))?;
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (path, json_type) = line
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| format!("{}:{}: expected `PATH TYPE`", file, i + 1))?;
+            rules.push((path.to_owned(), json_type.to_owned()));
+        }
+    }
+    for rule in &args.types {
+        let (path, json_type) = rule
+            .split_once('=')
+            .ok_or_else(|| format!("--type {}: expected `PATH=TYPE`", rule))?;
+        rules.push((path.to_owned(), json_type.to_owned()));
+    }
+    for (path, json_type) in rules {
+        let json_type: JsonArray = json_type
+            .trim()
+            .parse()
+            .map_err(|e| format!("{}: {}", path, e))?;
+        config = config.add_json_type_override(path.as_str(), json_type);
+    }
+    if args.report_unused_rules {
+        config = config.with_override_usage(Arc::new(OverrideUsage::default()));
+    }
+    Ok(config)
+}
@@ -0,0 +1,521 @@
+//! An output value type with strings borrowed from the parsed document instead of owned copies.
+
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use serde_json::{Map, Number, Value};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+
+#[cfg(feature = "pseudonymize")]
+use crate::pseudonymize::is_pseudonymized;
+use crate::units::is_unit_path;
+use crate::xml_base;
+use crate::{
+    add_location, add_namespace_declarations, collapses_to_attribute, convert_value, element_key,
+    finish_conversion, get_json_type, has_text_attributes, is_inner_text, keep_element,
+    keep_text_attribute, key_name, parse_document, parse_scalar, prepare_input, start_conversion,
+    text_content, text_prop_name, truncate_child, truncate_string, Config, Context, DiagnosticKind,
+    Diagnostics, Error, JsonType, NullValue, Truncated, TruncationMarker,
+};
+#[cfg(feature = "json_types")]
+use crate::{is_coercion_failure, is_nil};
+
+/// A JSON value like `serde_json::Value`, but with names and texts borrowed from the parsed document
+/// where possible. Only values that are not taken over as they are, e.g. numbers in `RawValues`, and
+/// attribute keys with `Config.xml_attr_prefix` are owned, unless the keys come from a `KeyCache`.
+///
+/// Objects are sorted by key, like `serde_json::Map`, so both serialize into the same JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BorrowedValue<'a> {
+    Null,
+    Bool(bool),
+    Number(Number),
+    String(Cow<'a, str>),
+    Array(Vec<BorrowedValue<'a>>),
+    Object(BTreeMap<Cow<'a, str>, BorrowedValue<'a>>),
+}
+
+impl BorrowedValue<'_> {
+    /// Converts the value into a `serde_json::Value` by copying all borrowed strings.
+    pub fn into_owned(self) -> Value {
+        match self {
+            BorrowedValue::Null => Value::Null,
+            BorrowedValue::Bool(b) => Value::Bool(b),
+            BorrowedValue::Number(n) => Value::Number(n),
+            BorrowedValue::String(s) => Value::String(s.into_owned()),
+            BorrowedValue::Array(items) => {
+                Value::Array(items.into_iter().map(BorrowedValue::into_owned).collect())
+            }
+            BorrowedValue::Object(map) => Value::Object(
+                map.into_iter()
+                    .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl From<Value> for BorrowedValue<'_> {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => BorrowedValue::Null,
+            Value::Bool(b) => BorrowedValue::Bool(b),
+            Value::Number(n) => BorrowedValue::Number(n),
+            Value::String(s) => BorrowedValue::String(Cow::Owned(s)),
+            Value::Array(items) => {
+                BorrowedValue::Array(items.into_iter().map(BorrowedValue::from).collect())
+            }
+            Value::Object(map) => BorrowedValue::Object(
+                map.into_iter()
+                    .map(|(k, v)| (Cow::Owned(k), BorrowedValue::from(v)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl Serialize for BorrowedValue<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            BorrowedValue::Null => serializer.serialize_unit(),
+            BorrowedValue::Bool(b) => serializer.serialize_bool(*b),
+            BorrowedValue::Number(n) => n.serialize(serializer),
+            BorrowedValue::String(s) => serializer.serialize_str(s),
+            BorrowedValue::Array(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            BorrowedValue::Object(map) => {
+                let mut ser = serializer.serialize_map(Some(map.len()))?;
+                for (k, v) in map {
+                    ser.serialize_entry(k, v)?;
+                }
+                ser.end()
+            }
+        }
+    }
+}
+
+type Object<'a> = BTreeMap<Cow<'a, str>, BorrowedValue<'a>>;
+
+/// The JSON keys of all attributes of a document, e.g. `@id` for `id`, created once and shared by
+/// every element with the same attribute instead of allocating a new key for each of them.
+/// Use it with `xml_document_to_borrowed_json_with_cache`.
+#[derive(Debug, Clone, Default)]
+pub struct KeyCache {
+    keys: HashMap<String, String>,
+}
+
+impl KeyCache {
+    /// Collects the keys of all attributes in the document with `Config.xml_attr_prefix`.
+    pub fn new(doc: &roxmltree::Document, config: &Config) -> Self {
+        let mut keys = HashMap::new();
+        if !config.xml_attr_prefix.is_empty() {
+            for attr in doc.descendants().flat_map(|n| n.attributes()) {
+                if !keys.contains_key(attr.name()) {
+                    let key = [config.xml_attr_prefix.as_str(), attr.name()].concat();
+                    keys.insert(attr.name().to_owned(), key);
+                }
+            }
+        }
+        KeyCache { keys }
+    }
+
+    /// Returns the number of distinct attribute keys.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+/// Converts a parsed document into a `BorrowedValue` with the same rules and the same result as
+/// `xml_str_to_json`, but without copying element names, attribute names and texts.
+/// Use it for pipelines that serialize the output right away, where allocating a `String` for
+/// every key and text would dominate the conversion.
+///
+/// The document is parsed by the caller, so the output can borrow from it. The nesting depth is only
+/// checked during the conversion, after parsing.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_document_to_borrowed_json, BorrowedValue, Config};
+///
+/// let doc = roxmltree::Document::parse(r#"<a x="1"><b>text</b></a>"#).unwrap();
+/// let config = Config::new_with_defaults();
+/// let json = xml_document_to_borrowed_json(&doc, &config).unwrap();
+/// assert_eq!(serde_json::to_string(&json).unwrap(), r#"{"a":{"@x":1,"b":"text"}}"#);
+/// ```
+pub fn xml_document_to_borrowed_json<'a>(
+    doc: &'a roxmltree::Document,
+    config: &'a Config,
+) -> Result<BorrowedValue<'a>, Error> {
+    convert_document(&mut Context::new(config, None), doc, None)
+}
+
+/// Converts an XML document with the same rules as `xml_str_to_json` and serializes the output
+/// with any serde `Serializer`, e.g. of serde_json, rmp-serde, ciborium or a custom format.
+/// The serializer is driven from a `BorrowedValue`, so no `serde_json::Value` is built and names
+/// and texts are not copied. Errors of the serializer are returned as `Error::Serialize`.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_to_serializer, Config};
+///
+/// let mut out = Vec::new();
+/// let mut serializer = serde_json::Serializer::new(&mut out);
+/// xml_to_serializer(r#"<a x="1"><b>text</b></a>"#, &Config::new_with_defaults(), &mut serializer).unwrap();
+/// assert_eq!(String::from_utf8(out).unwrap(), r#"{"a":{"@x":1,"b":"text"}}"#);
+/// ```
+pub fn xml_to_serializer<S: Serializer>(
+    xml: &str,
+    config: &Config,
+    serializer: S,
+) -> Result<S::Ok, Error> {
+    let xml = prepare_input(xml, config)?;
+    let doc = parse_document(&xml, config)?;
+    let value = convert_document(&mut Context::new(config, None), &doc, None)?;
+    value
+        .serialize(serializer)
+        .map_err(|e| Error::Serialize(e.to_string()))
+}
+
+/// Converts a parsed document into a `BorrowedValue` just like `xml_document_to_borrowed_json`, but
+/// with attribute keys borrowed from `keys`, so all `@id` keys share the same string.
+/// The cache must be created for the same document and `Config.xml_attr_prefix`.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_document_to_borrowed_json_with_cache, Config, KeyCache};
+///
+/// let doc = roxmltree::Document::parse(r#"<a><b id="1"/><b id="2"/></a>"#).unwrap();
+/// let config = Config::new_with_defaults();
+/// let keys = KeyCache::new(&doc, &config);
+/// let json = xml_document_to_borrowed_json_with_cache(&doc, &config, &keys).unwrap();
+/// assert_eq!(serde_json::to_string(&json).unwrap(), r#"{"a":{"b":[{"@id":1},{"@id":2}]}}"#);
+/// ```
+pub fn xml_document_to_borrowed_json_with_cache<'a>(
+    doc: &'a roxmltree::Document,
+    config: &'a Config,
+    keys: &'a KeyCache,
+) -> Result<BorrowedValue<'a>, Error> {
+    convert_document(&mut Context::new(config, None), doc, Some(keys))
+}
+
+/// Converts a parsed document into a `BorrowedValue` just like `xml_document_to_borrowed_json`
+/// and records every lossy event in `diagnostics`, see `xml_str_to_json_with_diagnostics`.
+pub fn xml_document_to_borrowed_json_with_diagnostics<'a>(
+    doc: &'a roxmltree::Document,
+    config: &'a Config,
+    diagnostics: &mut Diagnostics,
+) -> Result<BorrowedValue<'a>, Error> {
+    convert_document(&mut Context::new(config, Some(diagnostics)), doc, None)
+}
+
+fn convert_document<'a>(
+    ctx: &mut Context<'a, '_>,
+    doc: &'a roxmltree::Document,
+    keys: Option<&'a KeyCache>,
+) -> Result<BorrowedValue<'a>, Error> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("convert").entered();
+    start_conversion(ctx, doc)?;
+    let e = doc.root_element();
+    let mut value = convert_element(ctx, keys, e, &mut String::new())?;
+    // the declarations come as an object with the `xmlns` attribute to merge into the root
+    if let Some(Value::Object(declarations)) = add_namespace_declarations(ctx, doc, None)? {
+        let mut data = match value {
+            Some(BorrowedValue::Object(data)) => data,
+            None | Some(BorrowedValue::Null) => Object::new(),
+            Some(text) => {
+                let path = ["/", &element_key(ctx.config, &e)].concat();
+                let mut data = Object::new();
+                data.insert(Cow::Borrowed(text_prop_name(ctx.config, &path)), text);
+                data
+            }
+        };
+        for (key, value) in declarations {
+            data.insert(Cow::Owned(key), value.into());
+        }
+        value = Some(BorrowedValue::Object(data));
+    }
+    let mut data = Object::new();
+    data.insert(
+        element_key(ctx.config, &e),
+        value.unwrap_or(BorrowedValue::Null),
+    );
+    finish_conversion(ctx);
+    Ok(BorrowedValue::Object(data))
+}
+
+/// Converts a text value like `crate::convert_value`, but borrows strings that are taken over as they are.
+fn convert_borrowed_value<'a>(
+    ctx: &mut Context<'a, '_>,
+    path: &str,
+    text: &'a str,
+    json_type: &JsonType,
+) -> Result<BorrowedValue<'a>, Error> {
+    #[cfg(feature = "json_types")]
+    let coercion_failure = is_coercion_failure(text.trim(), json_type);
+    #[cfg(not(feature = "json_types"))]
+    let coercion_failure = false;
+    #[cfg(feature = "pseudonymize")]
+    let pseudonymized = is_pseudonymized(ctx.config, path);
+    #[cfg(not(feature = "pseudonymize"))]
+    let pseudonymized = false;
+    if coercion_failure
+        || pseudonymized
+        || is_unit_path(ctx.config, path)
+        || ctx.config.raw_values.applies_to(path)
+    {
+        return Ok(convert_value(ctx, path, text, json_type)?.into());
+    }
+
+    Ok(
+        match parse_scalar(text, ctx.config.leading_zero_as_string, json_type) {
+            Some(value) => value.into(),
+            None => BorrowedValue::String(truncate_string(ctx.config, text.trim())),
+        },
+    )
+}
+
+fn add_borrowed_location<'a>(ctx: &Context<'a, '_>, el: &roxmltree::Node, data: &mut Object<'a>) {
+    if ctx.config.location_prop_name.is_some() {
+        let mut location = Map::new();
+        add_location(ctx, el, &mut location);
+        for (key, value) in location {
+            data.insert(Cow::Owned(key), value.into());
+        }
+    }
+}
+
+fn convert_attributes<'a>(
+    ctx: &mut Context<'a, '_>,
+    keys: Option<&'a KeyCache>,
+    el: roxmltree::Node<'a, '_>,
+    path: &mut String,
+    data: &mut Object<'a>,
+    with_text: bool,
+) -> Result<(), Error> {
+    let prefix = ctx.config.xml_attr_prefix.as_str();
+    let path_len = path.len();
+    for attr in el.attributes() {
+        let name = key_name(ctx.config, attr.namespace(), attr.name());
+        if with_text && !keep_text_attribute(ctx.config, &name) {
+            continue;
+        }
+        path.push_str("/@");
+        path.push_str(&name);
+        ctx.report_namespace(path, attr.namespace())?;
+        let (_, json_type_value) = get_json_type(ctx.config, path);
+        let value = match xml_base::resolve_attribute(ctx.config, &el, path, attr.value()) {
+            Cow::Borrowed(text) => convert_borrowed_value(ctx, path, text, json_type_value)?,
+            Cow::Owned(text) => convert_value(ctx, path, &text, json_type_value)?.into(),
+        };
+        path.truncate(path_len);
+        // the cache only has the keys of attributes that `key_name` doesn't rename
+        let key = match (&name, keys.and_then(|keys| keys.keys.get(attr.name()))) {
+            (Cow::Borrowed(_), Some(key)) => Cow::Borrowed(key.as_str()),
+            _ if prefix.is_empty() => name,
+            _ => Cow::Owned([prefix, &name].concat()),
+        };
+        if data.contains_key(&key) {
+            ctx.report(path, || DiagnosticKind::KeyCollision(key.to_string()))?;
+        }
+        data.insert(key, value);
+    }
+    Ok(())
+}
+
+fn convert_text<'a>(
+    ctx: &mut Context<'a, '_>,
+    keys: Option<&'a KeyCache>,
+    el: roxmltree::Node<'a, '_>,
+    path: &mut String,
+    text: &'a str,
+    json_type_value: &JsonType,
+) -> Result<Option<BorrowedValue<'a>>, Error> {
+    for child in el.children().skip(1) {
+        ctx.report_dropped(path, &child)?;
+    }
+
+    if !has_text_attributes(ctx.config, &el) {
+        return Ok(Some(convert_borrowed_value(
+            ctx,
+            path,
+            text,
+            json_type_value,
+        )?));
+    }
+    let mut data = Object::new();
+    add_borrowed_location(ctx, &el, &mut data);
+    convert_attributes(ctx, keys, el, path, &mut data, true)?;
+    let config: &'a Config = ctx.config;
+    let key = text_prop_name(config, path);
+    if data.contains_key(key) {
+        ctx.report(path, || DiagnosticKind::KeyCollision(key.to_owned()))?;
+    }
+    let value = convert_borrowed_value(ctx, path, text, json_type_value)?;
+    data.insert(Cow::Borrowed(key), value);
+    Ok(Some(BorrowedValue::Object(data)))
+}
+
+fn convert_no_text<'a>(
+    ctx: &mut Context<'a, '_>,
+    keys: Option<&'a KeyCache>,
+    el: roxmltree::Node<'a, '_>,
+    path: &mut String,
+) -> Result<Option<BorrowedValue<'a>>, Error> {
+    let mut data = Object::new();
+    convert_attributes(ctx, keys, el, path, &mut data, false)?;
+    let mut truncated = Truncated::new();
+
+    for child in el.children() {
+        if !child.is_element() {
+            ctx.report_dropped(path, &child)?;
+            continue;
+        }
+
+        if !ctx.config.array_limits.is_empty() {
+            let key = element_key(ctx.config, &child);
+            let items = match data.get(key.as_ref()) {
+                Some(BorrowedValue::Array(items)) => items.len(),
+                Some(_) => 1,
+                None => 0,
+            };
+            if truncate_child(ctx.config, path, &key, items, &mut truncated) {
+                continue;
+            }
+        }
+
+        if let Some(val) = convert_element(ctx, keys, child, path)? {
+            let key = element_key(ctx.config, &child);
+            let name = key.as_ref();
+            let path_len = path.len();
+            path.push('/');
+            path.push_str(name);
+            let (json_type_array, _) = get_json_type(ctx.config, path);
+            path.truncate(path_len);
+
+            if json_type_array || data.contains_key(name) || ctx.repeated_names.contains(name) {
+                if el.attributes().any(|attr| {
+                    name.strip_prefix(ctx.config.xml_attr_prefix.as_str())
+                        == Some(&key_name(ctx.config, attr.namespace(), attr.name()))
+                }) && !el
+                    .children()
+                    .take_while(|c| c != &child)
+                    .any(|c| c.is_element() && element_key(ctx.config, &c) == name)
+                {
+                    ctx.report(path, || DiagnosticKind::KeyCollision(name.to_owned()))?;
+                }
+
+                if let Some(BorrowedValue::Array(existing)) = data.get_mut(name) {
+                    existing.push(val);
+                } else {
+                    let new_val = match data.remove(name) {
+                        None => vec![val],
+                        Some(temp) => vec![temp, val],
+                    };
+                    data.insert(key.clone(), BorrowedValue::Array(new_val));
+                }
+            } else {
+                data.insert(key.clone(), val);
+            }
+        }
+    }
+
+    for (key, limit, dropped) in truncated {
+        let items = match data.remove(key.as_str()) {
+            Some(BorrowedValue::Array(items)) => items,
+            Some(item) => vec![item],
+            None => Vec::new(),
+        };
+        let marker = match &limit.marker {
+            TruncationMarker::None => None,
+            TruncationMarker::Count => Some(BorrowedValue::Number(dropped.into())),
+            TruncationMarker::Text(text) => Some(BorrowedValue::String(Cow::Owned(text.clone()))),
+        };
+        let items = items.into_iter().chain(marker).collect();
+        data.insert(Cow::Owned(key), BorrowedValue::Array(items));
+    }
+
+    if collapses_to_attribute(ctx.config, &el) {
+        return Ok(data.into_values().next());
+    }
+
+    if !data.is_empty() {
+        add_borrowed_location(ctx, &el, &mut data);
+        return Ok(Some(BorrowedValue::Object(data)));
+    }
+
+    Ok(match ctx.config.empty_element_handling {
+        NullValue::Null => Some(BorrowedValue::Null),
+        NullValue::EmptyObject => {
+            add_borrowed_location(ctx, &el, &mut data);
+            Some(BorrowedValue::Object(data))
+        }
+        NullValue::Ignore => None,
+    })
+}
+
+/// Converts an element like `crate::convert_element`, with the path of its parent in `path`.
+pub(crate) fn convert_element<'a>(
+    ctx: &mut Context<'a, '_>,
+    keys: Option<&'a KeyCache>,
+    el: roxmltree::Node<'a, '_>,
+    path: &mut String,
+) -> Result<Option<BorrowedValue<'a>>, Error> {
+    let path_len = path.len();
+    path.push('/');
+    path.push_str(&element_key(ctx.config, &el));
+    let value = convert_element_at_path(ctx, keys, el, path);
+    path.truncate(path_len);
+    value
+}
+
+fn convert_element_at_path<'a>(
+    ctx: &mut Context<'a, '_>,
+    keys: Option<&'a KeyCache>,
+    el: roxmltree::Node<'a, '_>,
+    path: &mut String,
+) -> Result<Option<BorrowedValue<'a>>, Error> {
+    if !keep_element(ctx.config, &el) {
+        return Ok(None);
+    }
+    ctx.report_element_namespace(path, &el)?;
+
+    ctx.tick()?;
+    ctx.charge(&el)?;
+
+    #[cfg(feature = "tracing")]
+    let _span =
+        (ctx.depth == 1).then(|| tracing::trace_span!("convert_subtree", path = %path).entered());
+
+    if ctx.depth >= ctx.config.max_depth {
+        return Err(Error::DepthLimitExceeded(ctx.config.max_depth));
+    }
+
+    if is_inner_text(ctx.config, path) {
+        return Ok(Some(BorrowedValue::String(Cow::Owned(text_content(
+            ctx.config, &el, path,
+        )?))));
+    }
+
+    let (_, json_type_value) = get_json_type(ctx.config, path);
+
+    #[cfg(feature = "json_types")]
+    if is_nil(ctx.config, &el, path) {
+        return Ok(Some(BorrowedValue::Null));
+    }
+
+    ctx.depth += 1;
+    let value = match el.text() {
+        Some(text) if !text.trim().is_empty() => {
+            convert_text(ctx, keys, el, path, text.trim(), json_type_value)
+        }
+        _ => convert_no_text(ctx, keys, el, path),
+    };
+    ctx.depth -= 1;
+    value
+}
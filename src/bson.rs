@@ -0,0 +1,110 @@
+//! Conversion of XML into BSON documents, available with the `bson` feature.
+
+use bson::{Bson, DateTime, Document};
+use serde_json::Value;
+
+use crate::{parse_document, prepare_input, xml_to_map, Config, Context, Error};
+
+/// Defines how values are mapped onto BSON types.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BsonConfig {
+    /// Convert strings in RFC 3339 format like `2024-01-31T12:00:00Z`, or dates like `2024-01-31`,
+    /// into BSON dates. Dates without a time become midnight UTC.
+    /// Defaults to `true`.
+    pub parse_dates: bool,
+}
+
+impl BsonConfig {
+    pub fn new_with_defaults() -> Self {
+        BsonConfig { parse_dates: true }
+    }
+}
+
+impl Default for BsonConfig {
+    fn default() -> Self {
+        BsonConfig::new_with_defaults()
+    }
+}
+
+/// Converts an XML document into a BSON document with the same rules as `xml_str_to_json`,
+/// e.g. to load it into MongoDB.
+/// Integers become `Int32` if they fit and `Int64` otherwise, so no precision is lost for 64-bit
+/// values, including negative ones that `xml_str_to_json` converts into floats. Integers above
+/// `i64::MAX` don't fit into any BSON integer and are converted into strings.
+/// # Example
+/// ```
+/// use bson::Bson;
+/// use roxmltree_to_serde::{xml_str_to_bson, BsonConfig, Config};
+///
+/// let xml = "<a><id>9007199254740993</id><dx>-42</dx><at>2024-01-31T12:00:00Z</at></a>";
+/// let doc = xml_str_to_bson(xml, &Config::new_with_defaults(), &BsonConfig::default()).unwrap();
+/// let a = doc.get_document("a").unwrap();
+/// assert_eq!(a.get("id"), Some(&Bson::Int64(9007199254740993)));
+/// assert_eq!(a.get("dx"), Some(&Bson::Int32(-42)));
+/// assert!(matches!(a.get("at"), Some(Bson::DateTime(_))));
+/// ```
+pub fn xml_str_to_bson(
+    xml: &str,
+    config: &Config,
+    bson_config: &BsonConfig,
+) -> Result<Document, Error> {
+    let xml = prepare_input(xml, config)?;
+    let doc = parse_document(&xml, config)?;
+    let mut ctx = Context::new(config, None);
+    ctx.signed_integers = true;
+    let json = xml_to_map(&mut ctx, &doc)?;
+    // the root is always an object with the root element
+    match json_to_bson(json, bson_config) {
+        Bson::Document(doc) => Ok(doc),
+        _ => Ok(Document::new()),
+    }
+}
+
+fn json_to_bson(value: Value, bson_config: &BsonConfig) -> Bson {
+    match value {
+        Value::Null => Bson::Null,
+        Value::Bool(b) => Bson::Boolean(b),
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                match i32::try_from(i) {
+                    Ok(i) => Bson::Int32(i),
+                    Err(_) => Bson::Int64(i),
+                }
+            } else if n.is_u64() {
+                Bson::String(n.to_string())
+            } else {
+                Bson::Double(n.as_f64().unwrap_or(f64::NAN))
+            }
+        }
+        Value::String(s) => match parse_date(&s, bson_config) {
+            Some(date) => Bson::DateTime(date),
+            None => Bson::String(s),
+        },
+        Value::Array(items) => Bson::Array(
+            items
+                .into_iter()
+                .map(|item| json_to_bson(item, bson_config))
+                .collect(),
+        ),
+        Value::Object(map) => {
+            let mut doc = Document::new();
+            for (key, value) in map {
+                doc.insert(key, json_to_bson(value, bson_config));
+            }
+            Bson::Document(doc)
+        }
+    }
+}
+
+fn parse_date(s: &str, bson_config: &BsonConfig) -> Option<DateTime> {
+    // cheap check to skip the parser for most strings
+    let b = s.as_bytes();
+    if !bson_config.parse_dates || b.len() < 10 || b[4] != b'-' || b[7] != b'-' {
+        return None;
+    }
+    if b.len() == 10 {
+        DateTime::parse_rfc3339_str([s, "T00:00:00Z"].concat()).ok()
+    } else {
+        DateTime::parse_rfc3339_str(s).ok()
+    }
+}
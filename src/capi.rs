@@ -0,0 +1,128 @@
+//! C bindings, available with the `capi` feature. The declarations are in `include/roxmltree_to_serde.h`.
+//!
+//! All strings are NUL-terminated UTF-8. Strings returned by the library are owned by the caller
+//! and must be released with `rxts_string_free`, configs with `rxts_config_free`.
+
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::ptr;
+
+use crate::{xml_str_to_json, Config, Error};
+
+/// The conversion succeeded.
+pub const RXTS_OK: c_int = 0;
+/// A pointer argument is null or a string is not valid UTF-8.
+pub const RXTS_INVALID_ARGUMENT: c_int = 1;
+/// The document or the config is invalid, or the conversion failed.
+pub const RXTS_CONVERSION_ERROR: c_int = 2;
+
+/// An opaque conversion config.
+pub struct RxtsConfig(Config);
+
+/// Returns a new config with the defaults of `Config::new_with_defaults`.
+#[no_mangle]
+pub extern "C" fn rxts_config_new() -> *mut RxtsConfig {
+    Box::into_raw(Box::new(RxtsConfig(Config::new_with_defaults())))
+}
+
+/// Returns a new config from JSON in the format of `Config::from_json`, or null if it's invalid.
+/// The reason is stored in `*error` if `error` is not null.
+///
+/// # Safety
+/// `json` must be null or a NUL-terminated string and `error` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn rxts_config_from_json(
+    json: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut RxtsConfig {
+    match str_arg(json)
+        .and_then(|json| Config::from_json(json).map_err(|e| (RXTS_CONVERSION_ERROR, e)))
+    {
+        Ok(config) => Box::into_raw(Box::new(RxtsConfig(config))),
+        Err((_, e)) => {
+            set_string(error, &e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Releases a config. Null is ignored.
+///
+/// # Safety
+/// `config` must be null or returned by `rxts_config_new` or `rxts_config_from_json`, and not released before.
+#[no_mangle]
+pub unsafe extern "C" fn rxts_config_free(config: *mut RxtsConfig) {
+    if !config.is_null() {
+        drop(Box::from_raw(config));
+    }
+}
+
+/// Converts an XML document into a JSON string with the same rules as `xml_str_to_json`, using the defaults
+/// if `config` is null. Returns `RXTS_OK` and stores the JSON in `*out`, or an error code and stores
+/// the error message in `*out`.
+///
+/// # Safety
+/// `xml` must be null or a NUL-terminated string, `config` must be null or a valid config and `out` must be
+/// valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn rxts_xml_to_json(
+    xml: *const c_char,
+    config: *const RxtsConfig,
+    out: *mut *mut c_char,
+) -> c_int {
+    if out.is_null() {
+        return RXTS_INVALID_ARGUMENT;
+    }
+    let defaults;
+    let config = match config.as_ref() {
+        Some(config) => &config.0,
+        None => {
+            defaults = Config::new_with_defaults();
+            &defaults
+        }
+    };
+    let result = str_arg(xml)
+        .and_then(|xml| xml_str_to_json(xml, config).map_err(|e| (RXTS_CONVERSION_ERROR, e)));
+    match result {
+        Ok(json) => {
+            set_string(out, &json.to_string());
+            RXTS_OK
+        }
+        Err((code, e)) => {
+            set_string(out, &e.to_string());
+            code
+        }
+    }
+}
+
+/// Releases a string returned by the library. Null is ignored.
+///
+/// # Safety
+/// `s` must be null or a string returned by the library, and not released before.
+#[no_mangle]
+pub unsafe extern "C" fn rxts_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Borrows a string argument, failing for null pointers and invalid UTF-8.
+unsafe fn str_arg<'a>(s: *const c_char) -> Result<&'a str, (c_int, Error)> {
+    let invalid = |reason: &str| {
+        let e = std::io::Error::new(std::io::ErrorKind::InvalidInput, reason);
+        (RXTS_INVALID_ARGUMENT, Error::Io(e))
+    };
+    if s.is_null() {
+        return Err(invalid("null pointer"));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| invalid("invalid UTF-8"))
+}
+
+/// Stores a copy of `s` in `*target` if `target` is not null. NUL characters can't be represented and are dropped.
+unsafe fn set_string(target: *mut *mut c_char, s: &str) {
+    if let Some(target) = target.as_mut() {
+        let s = CString::new(s.replace('\0', "")).unwrap_or_default();
+        *target = s.into_raw();
+    }
+}
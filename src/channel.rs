@@ -0,0 +1,53 @@
+//! Conversion of records on a background thread that sends them over a bounded channel,
+//! see `xml_records_channel`.
+
+use std::io::Read;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+
+use serde_json::Value;
+
+use crate::{Config, Error, XmlRecords};
+
+/// Converts the elements at the absolute `record_path` of the document in `reader` like `XmlRecords`
+/// on a new thread and returns the receiving end of a channel with the results, in document order.
+///
+/// The channel holds at most `bound` records, so the thread stops reading while the receiver lags behind
+/// and memory use stays bounded. The thread ends after the last record, or as soon as the receiver
+/// is dropped. Errors are sent like records; like in `XmlRecords`, only read errors and truncated
+/// documents end the iteration. The `Config` is shared with the thread, so it's taken as an `Arc`,
+/// or by value.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_records_channel, Config};
+/// use serde_json::json;
+///
+/// let xml = "<feed><item>1</item><item>2</item><item>3</item></feed>";
+/// let records = xml_records_channel(xml.as_bytes(), "/feed/item", Config::new_with_defaults(), 16);
+/// let sum: i64 = records.iter().map(|record| record.unwrap().as_i64().unwrap()).sum();
+/// assert_eq!(sum, 6);
+/// ```
+pub fn xml_records_channel<R, C>(
+    reader: R,
+    record_path: &str,
+    config: C,
+    bound: usize,
+) -> Receiver<Result<Value, Error>>
+where
+    R: Read + Send + 'static,
+    C: Into<Arc<Config>>,
+{
+    let (sender, receiver) = mpsc::sync_channel(bound);
+    let record_path = record_path.to_owned();
+    let config = config.into();
+    thread::spawn(move || {
+        for record in XmlRecords::new(reader, &record_path, &config) {
+            if sender.send(record).is_err() {
+                // the receiver is gone, nobody is interested in the rest
+                break;
+            }
+        }
+    });
+    receiver
+}
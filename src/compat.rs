@@ -0,0 +1,718 @@
+//! Conversions that match the output of popular XML to JSON libraries of other languages,
+//! to migrate services to Rust without changing their JSON.
+
+use std::collections::HashSet;
+
+use serde_json::{Map, Value};
+
+use crate::{parse_document, prepare_input, Config, Error};
+
+/// Options of `xml2js_to_json`, named like the options of node-xml2js.
+/// The defaults are the defaults of xml2js.
+#[derive(Debug)]
+pub struct Xml2jsOptions {
+    /// The key of the object with the attributes of an element. Defaults to `$`.
+    pub attr_key: String,
+    /// The key of the text of elements with attributes or child elements. Defaults to `_`.
+    pub char_key: String,
+    /// Put every child element and, with `merge_attrs`, every attribute into an array,
+    /// also if it's not repeated. Defaults to `true`.
+    pub explicit_array: bool,
+    /// Return `{"root": ..}` instead of only the value of the root element. Defaults to `true`.
+    pub explicit_root: bool,
+    /// Add the attributes to the object of the element like child elements, instead of
+    /// under `attr_key`. Defaults to `false`.
+    pub merge_attrs: bool,
+    /// Trim the whitespace around texts. Defaults to `false`.
+    pub trim: bool,
+}
+
+impl Xml2jsOptions {
+    /// The default options of xml2js.
+    pub fn new_with_defaults() -> Self {
+        Xml2jsOptions {
+            attr_key: "$".to_owned(),
+            char_key: "_".to_owned(),
+            explicit_array: true,
+            explicit_root: true,
+            merge_attrs: false,
+            trim: false,
+        }
+    }
+}
+
+impl Default for Xml2jsOptions {
+    fn default() -> Self {
+        Xml2jsOptions::new_with_defaults()
+    }
+}
+
+/// Converts the XML like `parseString` of node-xml2js with the given options. All values are strings,
+/// because xml2js doesn't parse numbers or booleans by default. Names keep their namespace prefixes,
+/// e.g. `soap:Body`, and namespace declarations are attributes, like in xml2js.
+/// Empty elements become `""`, texts of elements without attributes and child elements become strings,
+/// and whitespace-only texts are dropped.
+///
+/// The values match xml2js, but the objects are sorted by key like all objects of `serde_json`,
+/// unless its `preserve_order` feature is enabled. Only `Config.max_depth` applies to this conversion.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml2js_to_json, Config, Xml2jsOptions};
+/// use serde_json::json;
+///
+/// let xml = r#"<order id="7"><item>Pen</item><note lang="en">Fragile</note><gift/></order>"#;
+/// let json = xml2js_to_json(xml, &Config::new_with_defaults(), &Xml2jsOptions::default()).unwrap();
+/// assert_eq!(json, json!({"order": {"$": {"id": "7"}, "item": ["Pen"],
+///     "note": [{"$": {"lang": "en"}, "_": "Fragile"}], "gift": [""]}}));
+/// ```
+pub fn xml2js_to_json(xml: &str, config: &Config, options: &Xml2jsOptions) -> Result<Value, Error> {
+    let xml = prepare_input(xml, config)?;
+    let doc = parse_document(&xml, config)?;
+    let root = doc.root_element();
+    let value = xml2js_element(root, options);
+    if !options.explicit_root {
+        return Ok(value);
+    }
+    let mut data = Map::with_capacity(1);
+    data.insert(element_name(root), value);
+    Ok(Value::Object(data))
+}
+
+fn xml2js_element(el: roxmltree::Node, options: &Xml2jsOptions) -> Value {
+    let text: String = el
+        .children()
+        .filter(|c| c.is_text())
+        .filter_map(|c| c.text())
+        .collect();
+    let mut data = Map::new();
+    if !text.trim().is_empty() {
+        let text = if options.trim { text.trim() } else { &text };
+        data.insert(options.char_key.clone(), Value::from(text));
+    }
+
+    let attributes = attributes(el);
+    if options.merge_attrs {
+        for (name, value) in attributes {
+            assign_or_push(&mut data, name, value, options.explicit_array);
+        }
+    } else if !attributes.is_empty() {
+        data.insert(options.attr_key.clone(), Value::Object(attributes));
+    }
+
+    for child in el.children().filter(|c| c.is_element()) {
+        let value = xml2js_element(child, options);
+        assign_or_push(
+            &mut data,
+            element_name(child),
+            value,
+            options.explicit_array,
+        );
+    }
+
+    // elements without content become their whitespace or an empty string
+    if data.is_empty() {
+        return Value::String(text);
+    }
+    if data.len() == 1 {
+        if let Some(text) = data.remove(&options.char_key) {
+            return text;
+        }
+    }
+    Value::Object(data)
+}
+
+/// Options of `xmltodict_to_json`, named like the arguments of `xmltodict.parse` in Python.
+/// The defaults are the defaults of xmltodict.
+#[derive(Debug)]
+pub struct XmltodictOptions {
+    /// The prefix of attribute names. Defaults to `@`.
+    pub attr_prefix: String,
+    /// The key of the text of elements with attributes or child elements. Defaults to `#text`.
+    pub cdata_key: String,
+    /// Put the text of elements without attributes and child elements under `cdata_key` too. Defaults to `false`.
+    pub force_cdata: bool,
+    /// Names of elements that are always lists, also if they are not repeated,
+    /// like a tuple of names for `force_list`. Defaults to none.
+    pub force_list: HashSet<String>,
+    /// Trim the whitespace around texts and drop whitespace-only texts. Defaults to `true`.
+    pub strip_whitespace: bool,
+}
+
+impl XmltodictOptions {
+    /// The default options of xmltodict.
+    pub fn new_with_defaults() -> Self {
+        XmltodictOptions {
+            attr_prefix: "@".to_owned(),
+            cdata_key: "#text".to_owned(),
+            force_cdata: false,
+            force_list: HashSet::new(),
+            strip_whitespace: true,
+        }
+    }
+
+    /// Adds element names to `force_list`.
+    pub fn with_force_list<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.force_list.extend(names.into_iter().map(Into::into));
+        self
+    }
+}
+
+impl Default for XmltodictOptions {
+    fn default() -> Self {
+        XmltodictOptions::new_with_defaults()
+    }
+}
+
+/// Converts the XML like `xmltodict.parse` of Python with the given options. All values are strings,
+/// empty elements become `null` like `None`, and repeated elements become lists. Names keep their
+/// namespace prefixes, e.g. `soap:Body`, and namespace declarations are attributes like `@xmlns:soap`,
+/// as without `process_namespaces`.
+///
+/// xmltodict returns ordered dicts, while the objects of `serde_json` are sorted by key, unless its
+/// `preserve_order` feature is enabled. The values are inserted in the order of xmltodict,
+/// so they compare equal either way. Only `Config.max_depth` applies to this conversion.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xmltodict_to_json, Config, XmltodictOptions};
+/// use serde_json::json;
+///
+/// let xml = r#"<order id="7"><item>Pen</item><note lang="en">Fragile</note><gift/></order>"#;
+/// let options = XmltodictOptions::default().with_force_list(["item"]);
+/// let json = xmltodict_to_json(xml, &Config::new_with_defaults(), &options).unwrap();
+/// assert_eq!(json, json!({"order": {"@id": "7", "item": ["Pen"],
+///     "note": {"@lang": "en", "#text": "Fragile"}, "gift": null}}));
+/// ```
+pub fn xmltodict_to_json(
+    xml: &str,
+    config: &Config,
+    options: &XmltodictOptions,
+) -> Result<Value, Error> {
+    let xml = prepare_input(xml, config)?;
+    let doc = parse_document(&xml, config)?;
+    let root = doc.root_element();
+    let mut data = Map::with_capacity(1);
+    let name = element_name(root);
+    let explicit_array = options.force_list.contains(&name);
+    assign_or_push(
+        &mut data,
+        name,
+        xmltodict_element(root, options),
+        explicit_array,
+    );
+    Ok(Value::Object(data))
+}
+
+fn xmltodict_element(el: roxmltree::Node, options: &XmltodictOptions) -> Value {
+    let mut text = None;
+    for child in el.children().filter(|c| c.is_text()) {
+        let part = child.text().unwrap_or_default();
+        text.get_or_insert_with(String::new).push_str(part);
+    }
+    let text = match text {
+        Some(text) if options.strip_whitespace => Some(text.trim().to_owned()),
+        text => text,
+    }
+    .filter(|text| !text.is_empty());
+
+    let mut data = Map::new();
+    for (name, value) in attributes(el) {
+        data.insert([options.attr_prefix.as_str(), &name].concat(), value);
+    }
+    for child in el.children().filter(|c| c.is_element()) {
+        let name = element_name(child);
+        let explicit_array = options.force_list.contains(&name);
+        assign_or_push(
+            &mut data,
+            name,
+            xmltodict_element(child, options),
+            explicit_array,
+        );
+    }
+
+    match text {
+        Some(text) if !data.is_empty() || options.force_cdata => {
+            data.insert(options.cdata_key.clone(), Value::String(text));
+            Value::Object(data)
+        }
+        Some(text) => Value::String(text),
+        None if data.is_empty() => Value::Null,
+        None => Value::Object(data),
+    }
+}
+
+/// The namespace of the `json:Array` attribute of Json.NET.
+const NEWTONSOFT_JSON_NAMESPACE: &str = "http://james.newtonking.com/projects/json";
+
+/// Options of `newtonsoft_to_json`, named like the arguments of `JsonConvert.SerializeXmlNode` of Json.NET.
+#[derive(Debug)]
+pub struct NewtonsoftOptions {
+    /// Return only the value of the root element, without the XML declaration.
+    /// Defaults to `false`.
+    pub omit_root_object: bool,
+}
+
+impl NewtonsoftOptions {
+    /// The default options of Json.NET.
+    pub fn new_with_defaults() -> Self {
+        NewtonsoftOptions {
+            omit_root_object: false,
+        }
+    }
+}
+
+impl Default for NewtonsoftOptions {
+    fn default() -> Self {
+        NewtonsoftOptions::new_with_defaults()
+    }
+}
+
+/// Converts the XML like `JsonConvert.SerializeXmlNode` of Json.NET (Newtonsoft) for an `XmlDocument`.
+/// All values are strings. The XML declaration becomes `"?xml": {"@version": "1.0", ..}`, texts next to
+/// attributes or child elements become `#text`, CDATA sections `#cdata-section` and processing instructions
+/// `?target`. `<a/>` becomes `null` and `<a></a>` becomes `""`. Repeated elements and elements with
+/// `json:Array="true"` become arrays. Names keep their namespace prefixes and namespace declarations
+/// are attributes like `@xmlns:soap`. Whitespace-only texts are dropped, like `XmlDocument` does by default.
+///
+/// The objects are sorted by key like all objects of `serde_json`, unless its `preserve_order` feature
+/// is enabled. Only `Config.max_depth` applies to this conversion.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{newtonsoft_to_json, Config, NewtonsoftOptions};
+/// use serde_json::json;
+///
+/// let xml = r#"<?xml version="1.0"?><order id="7"><item>Pen</item><note><![CDATA[<b>]]></note><gift/></order>"#;
+/// let json = newtonsoft_to_json(xml, &Config::new_with_defaults(), &NewtonsoftOptions::default()).unwrap();
+/// assert_eq!(json, json!({"?xml": {"@version": "1.0"}, "order": {"@id": "7", "item": "Pen",
+///     "note": {"#cdata-section": "<b>"}, "gift": null}}));
+/// ```
+pub fn newtonsoft_to_json(
+    xml: &str,
+    config: &Config,
+    options: &NewtonsoftOptions,
+) -> Result<Value, Error> {
+    let xml = prepare_input(xml, config)?;
+    let doc = parse_document(&xml, config)?;
+    if options.omit_root_object {
+        return Ok(newtonsoft_element(doc.root_element()));
+    }
+    let mut data = Map::new();
+    if let Some(declaration) = xml_declaration(doc.input_text()) {
+        data.insert("?xml".to_owned(), declaration);
+    }
+    newtonsoft_children(doc.root(), &mut data);
+    Ok(Value::Object(data))
+}
+
+fn newtonsoft_element(el: roxmltree::Node) -> Value {
+    let json_prefix = el.lookup_prefix(NEWTONSOFT_JSON_NAMESPACE);
+    let mut attributes = attributes(el);
+    attributes.retain(|name, value| match json_prefix {
+        Some(prefix) => {
+            *value != NEWTONSOFT_JSON_NAMESPACE
+                && !name
+                    .strip_prefix(prefix)
+                    .is_some_and(|name| name.starts_with(':'))
+        }
+        None => true,
+    });
+
+    let mut children = el
+        .children()
+        .filter(|c| !c.is_text() || !newtonsoft_blank(*c));
+    if attributes.is_empty() {
+        match (children.next(), children.next()) {
+            (None, _) => {
+                let source = &el.document().input_text()[el.range()];
+                return match source.ends_with("/>") {
+                    true => Value::Null,
+                    false => Value::from(""),
+                };
+            }
+            (Some(child), None) if child.is_text() => {
+                if let [Segment::Text(text)] = text_segments(child).as_slice() {
+                    return Value::from(text.as_str());
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let mut data: Map<String, Value> = attributes
+        .into_iter()
+        .map(|(name, value)| (["@", &name].concat(), value))
+        .collect();
+    newtonsoft_children(el, &mut data);
+    Value::Object(data)
+}
+
+/// Adds the child nodes to `data`, with arrays for repeated names and for elements with `json:Array="true"`.
+fn newtonsoft_children(parent: roxmltree::Node, data: &mut Map<String, Value>) {
+    let mut add = |name: String, value: Value, force_array: bool| {
+        assign_or_push(data, name, value, force_array)
+    };
+    for child in parent.children() {
+        match child.node_type() {
+            roxmltree::NodeType::Element => {
+                let force_array =
+                    child.attribute((NEWTONSOFT_JSON_NAMESPACE, "Array")) == Some("true");
+                add(element_name(child), newtonsoft_element(child), force_array);
+            }
+            roxmltree::NodeType::Text => {
+                for segment in text_segments(child) {
+                    match segment {
+                        Segment::Text(text) if text.trim().is_empty() => (),
+                        Segment::Text(text) => add("#text".to_owned(), Value::from(text), false),
+                        Segment::Cdata(text) => {
+                            add("#cdata-section".to_owned(), Value::from(text), false)
+                        }
+                    }
+                }
+            }
+            roxmltree::NodeType::PI => {
+                if let Some(pi) = child.pi() {
+                    let value = Value::from(pi.value.unwrap_or_default());
+                    add(["?", pi.target].concat(), value, false);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Returns `true` if the text node only has whitespace outside of CDATA sections.
+fn newtonsoft_blank(node: roxmltree::Node) -> bool {
+    text_segments(node)
+        .iter()
+        .all(|segment| matches!(segment, Segment::Text(text) if text.trim().is_empty()))
+}
+
+/// Returns the pseudo-attributes of the XML declaration, e.g. `{"@version": "1.0", "@encoding": "utf-8"}`.
+/// roxmltree doesn't keep the declaration as a node.
+fn xml_declaration(input: &str) -> Option<Value> {
+    let declaration = input
+        .strip_prefix('\u{feff}')
+        .unwrap_or(input)
+        .strip_prefix("<?xml")?;
+    if !declaration.starts_with(|c: char| c.is_ascii_whitespace()) {
+        return None;
+    }
+    let mut rest = &declaration[..declaration.find("?>")?];
+    let mut data = Map::new();
+    while let Some((name, value)) = rest.split_once('=') {
+        let value = value.trim_start();
+        let quote = value.chars().next()?;
+        let end = value[1..].find(quote)?;
+        data.insert(["@", name.trim()].concat(), Value::from(&value[1..end + 1]));
+        rest = &value[end + 2..];
+    }
+    Some(Value::Object(data))
+}
+
+/// A part of a text node, which roxmltree merges from the adjacent texts and CDATA sections.
+enum Segment {
+    Text(String),
+    Cdata(String),
+}
+
+/// Splits the text node into its texts and CDATA sections, from the source of the document.
+fn text_segments(node: roxmltree::Node) -> Vec<Segment> {
+    let range = node.range();
+    let source = &node.document().input_text()[range.start..];
+    if !source.starts_with("<![CDATA[") && !source[range.len()..].starts_with("<![CDATA[") {
+        return vec![Segment::Text(node.text().unwrap_or_default().to_owned())];
+    }
+    let mut segments = Vec::new();
+    let mut rest = source;
+    loop {
+        if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+            let end = cdata.find("]]>").unwrap_or(cdata.len());
+            segments.push(Segment::Cdata(normalize_line_ends(&cdata[..end])));
+            rest = cdata.get(end + 3..).unwrap_or_default();
+        } else if rest.is_empty() || rest.starts_with('<') {
+            return segments;
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            segments.push(Segment::Text(unescape(&normalize_line_ends(&rest[..end]))));
+            rest = &rest[end..];
+        }
+    }
+}
+
+fn normalize_line_ends(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Replaces the predefined entities and character references. Documents with a DTD are rejected,
+/// so there are no other entities.
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let character = match &rest[1..end] {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            reference => match reference.strip_prefix("#x") {
+                Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                None => reference.strip_prefix('#').and_then(|dec| dec.parse().ok()),
+            }
+            .and_then(char::from_u32),
+        };
+        match character {
+            Some(character) => unescaped.push(character),
+            None => unescaped.push_str(&rest[..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// How x2js gives access to child elements as arrays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum X2jsArrayAccessForm {
+    /// Only repeated elements and the paths of `array_access_form_paths` are arrays.
+    None,
+    /// Every child element also gets a `<name>_asArray` property with an array of its values.
+    Property,
+}
+
+/// What x2js converts elements without attributes, texts and child elements into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum X2jsEmptyNodeForm {
+    /// An empty string.
+    Text,
+    /// An empty object.
+    Object,
+}
+
+/// Options of `x2js_to_json`, named like the options of the x2js browser library.
+/// The defaults are the defaults of x2js.
+#[derive(Debug)]
+pub struct X2jsOptions {
+    /// The prefix of attribute names. Defaults to `_`.
+    pub attribute_prefix: String,
+    /// Defaults to `X2jsArrayAccessForm::None`.
+    pub array_access_form: X2jsArrayAccessForm,
+    /// Paths of elements that are always arrays, also if they are not repeated.
+    /// The paths are the names from the root element joined with `.`, e.g. `orders.order`.
+    /// Defaults to none.
+    pub array_access_form_paths: Vec<String>,
+    /// Defaults to `X2jsEmptyNodeForm::Text`.
+    pub empty_node_form: X2jsEmptyNodeForm,
+    /// Replace XML escapes like `&lt;` that are still in the texts after parsing,
+    /// e.g. of `&amp;lt;`. Defaults to `true`.
+    pub escape_mode: bool,
+    /// Trim the whitespace around texts. Defaults to `true`.
+    pub strip_whitespaces: bool,
+    /// Drop whitespace-only texts of elements with attributes or child elements. Defaults to `true`.
+    pub skip_empty_text_nodes_for_obj: bool,
+}
+
+impl X2jsOptions {
+    /// The default options of x2js.
+    pub fn new_with_defaults() -> Self {
+        X2jsOptions {
+            attribute_prefix: "_".to_owned(),
+            array_access_form: X2jsArrayAccessForm::None,
+            array_access_form_paths: Vec::new(),
+            empty_node_form: X2jsEmptyNodeForm::Text,
+            escape_mode: true,
+            strip_whitespaces: true,
+            skip_empty_text_nodes_for_obj: true,
+        }
+    }
+}
+
+impl Default for X2jsOptions {
+    fn default() -> Self {
+        X2jsOptions::new_with_defaults()
+    }
+}
+
+/// Converts the XML like `xml_str2json` of the x2js browser library with the given options.
+/// All values are strings. Texts next to attributes or child elements become `__text`, joined with
+/// line breaks if there are several, and CDATA sections become `__cdata`. Names are local names, the
+/// namespace prefix of an element becomes `__prefix`, and namespace declarations are attributes like `_xmlns:soap`.
+///
+/// The `toString` functions of x2js are not part of JSON, and the objects are sorted by key like all
+/// objects of `serde_json`, unless its `preserve_order` feature is enabled.
+/// Only `Config.max_depth` applies to this conversion.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{x2js_to_json, Config, X2jsOptions};
+/// use serde_json::json;
+///
+/// let xml = r#"<order id="7"><item>Pen</item><note><![CDATA[<b>]]></note><gift/></order>"#;
+/// let options = X2jsOptions { array_access_form_paths: vec!["order.item".to_owned()], ..X2jsOptions::default() };
+/// let json = x2js_to_json(xml, &Config::new_with_defaults(), &options).unwrap();
+/// assert_eq!(json, json!({"order": {"_id": "7", "item": ["Pen"], "note": {"__cdata": "<b>"}, "gift": ""}}));
+/// ```
+pub fn x2js_to_json(xml: &str, config: &Config, options: &X2jsOptions) -> Result<Value, Error> {
+    let xml = prepare_input(xml, config)?;
+    let doc = parse_document(&xml, config)?;
+    let root = doc.root_element();
+    let name = root.tag_name().name();
+    let mut data = Map::with_capacity(1);
+    data.insert(name.to_owned(), x2js_element(root, name, options));
+    Ok(Value::Object(data))
+}
+
+fn x2js_element(el: roxmltree::Node, path: &str, options: &X2jsOptions) -> Value {
+    let mut data = Map::new();
+    // the number of texts, CDATA sections, child elements, attributes and prefixes
+    let mut count = 0;
+    let mut texts = Vec::new();
+    let mut cdata = Vec::new();
+    let mut names = Vec::new();
+    for child in el.children() {
+        match child.node_type() {
+            roxmltree::NodeType::Element => {
+                count += 1;
+                let name = child.tag_name().name();
+                let child_path = [path, ".", name].concat();
+                let value = x2js_element(child, &child_path, options);
+                match data.get_mut(name) {
+                    Some(Value::Array(items)) => items.push(value),
+                    Some(existing) => *existing = Value::Array(vec![existing.take(), value]),
+                    None => {
+                        let force_array = options.array_access_form_paths.contains(&child_path);
+                        assign_or_push(&mut data, name.to_owned(), value, force_array);
+                        names.push(name);
+                    }
+                }
+            }
+            roxmltree::NodeType::Text => {
+                for segment in text_segments(child) {
+                    count += 1;
+                    match segment {
+                        Segment::Text(text) => texts.push(text),
+                        Segment::Cdata(text) => cdata.push(Value::String(text)),
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    if options.array_access_form == X2jsArrayAccessForm::Property {
+        for name in names {
+            let items = match &data[name] {
+                Value::Array(items) => items.clone(),
+                value => vec![value.clone()],
+            };
+            data.insert([name, "_asArray"].concat(), Value::Array(items));
+        }
+    }
+    for (name, value) in attributes(el) {
+        count += 1;
+        data.insert([options.attribute_prefix.as_str(), &name].concat(), value);
+    }
+    let prefix = el
+        .tag_name()
+        .namespace()
+        .and_then(|ns| el.lookup_prefix(ns));
+    if let Some(prefix) = prefix.filter(|prefix| !prefix.is_empty()) {
+        count += 1;
+        data.insert("__prefix".to_owned(), Value::from(prefix));
+    }
+    if cdata.len() == 1 {
+        data.insert("__cdata".to_owned(), cdata.remove(0));
+    } else if !cdata.is_empty() {
+        data.insert("__cdata".to_owned(), Value::Array(cdata));
+    }
+
+    let text = (!texts.is_empty()).then(|| {
+        let text = texts.join("\n");
+        let text = match options.escape_mode {
+            true => unescape_x2js(&text),
+            false => text,
+        };
+        match options.strip_whitespaces {
+            true => text.trim().to_owned(),
+            false => text,
+        }
+    });
+    match text {
+        Some(text) if count == 1 => return Value::String(text),
+        Some(text) if options.skip_empty_text_nodes_for_obj && text.trim().is_empty() => (),
+        Some(text) => {
+            data.insert("__text".to_owned(), Value::String(text));
+        }
+        None if count == 0 => {
+            return match options.empty_node_form {
+                X2jsEmptyNodeForm::Text => Value::from(""),
+                X2jsEmptyNodeForm::Object => Value::Object(data),
+            }
+        }
+        None => (),
+    }
+    Value::Object(data)
+}
+
+/// Replaces the escapes like `unescapeXmlChars` of x2js.
+fn unescape_x2js(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#x27;", "'")
+        .replace("&#x2F;", "/")
+        .replace("&amp;", "&")
+}
+
+/// Adds the value to the property, which becomes an array when the name repeats or if `explicit_array` is set.
+fn assign_or_push(data: &mut Map<String, Value>, name: String, value: Value, explicit_array: bool) {
+    match data.get_mut(&name) {
+        Some(Value::Array(items)) => items.push(value),
+        Some(existing) => *existing = Value::Array(vec![existing.take(), value]),
+        None if explicit_array => {
+            data.insert(name, Value::Array(vec![value]));
+        }
+        None => {
+            data.insert(name, value);
+        }
+    }
+}
+
+/// Returns the name of the element with its namespace prefix, as it's written in the XML.
+fn element_name(el: roxmltree::Node) -> String {
+    let tag_name = el.tag_name();
+    match tag_name.namespace().and_then(|ns| el.lookup_prefix(ns)) {
+        Some(prefix) => [prefix, ":", tag_name.name()].concat(),
+        None => tag_name.name().to_owned(),
+    }
+}
+
+/// Returns the namespace declarations of the element and its attributes, with their prefixes, as strings.
+fn attributes(el: roxmltree::Node) -> Map<String, Value> {
+    let mut attributes = Map::new();
+    let inherited = el.parent_element();
+    for ns in el.namespaces() {
+        let declared_by_parent = inherited.is_some_and(|parent| {
+            parent
+                .namespaces()
+                .any(|p| p.name() == ns.name() && p.uri() == ns.uri())
+        });
+        if !declared_by_parent {
+            let name = match ns.name() {
+                Some(prefix) => ["xmlns:", prefix].concat(),
+                None => "xmlns".to_owned(),
+            };
+            attributes.insert(name, Value::from(ns.uri()));
+        }
+    }
+    for attr in el.attributes() {
+        let name = match attr.namespace().and_then(|ns| el.lookup_prefix(ns)) {
+            Some(prefix) => [prefix, ":", attr.name()].concat(),
+            None => attr.name().to_owned(),
+        };
+        attributes.insert(name, Value::from(attr.value()));
+    }
+    attributes
+}
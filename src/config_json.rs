@@ -0,0 +1,457 @@
+//! `Config` from JSON text, for bindings to other languages and configuration files.
+
+use std::collections::HashSet;
+#[cfg(feature = "json_types")]
+use std::str::FromStr;
+#[cfg(feature = "json_types")]
+use std::sync::{Mutex, OnceLock};
+
+use serde_json::{Map, Value};
+
+use crate::{
+    ArrayInference, ArrayLimit, CoercionFailurePolicy, Config, DefaultNamespace, Error, IdRef,
+    NullValue, PruneEmpty, RawValues, SingleKeyObjects, TextAttributes, TruncationMarker,
+    UnitTable, Utf8Policy,
+};
+#[cfg(feature = "json_types")]
+use crate::{JsonArray, JsonType};
+
+/// Parses the type of an override rule, as used by `Config::from_json` and the command line converter:
+/// `string`, `number`, `bool` (`true` for `true` only), `bool:<values>` with a comma-separated list
+/// of values that are `true`, or `infer`. A `[]` suffix always converts the nodes into an array,
+/// e.g. `number[]` is `JsonArray::Always(JsonType::AlwaysNumber)`.
+#[cfg(feature = "json_types")]
+impl FromStr for JsonArray {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Error> {
+        let (json_type, always_array) = match text.strip_suffix("[]") {
+            Some(json_type) => (json_type, true),
+            None => (text, false),
+        };
+        let json_type = match json_type {
+            "string" => JsonType::AlwaysString,
+            "number" => JsonType::AlwaysNumber,
+            "bool" => JsonType::Bool(vec!["true"]),
+            "infer" => JsonType::Infer,
+            json_type => match json_type.strip_prefix("bool:") {
+                Some(values) => JsonType::Bool(values.split(',').map(intern).collect()),
+                None => return Err(invalid(&format!("unknown JSON type `{}`", text))),
+            },
+        };
+        Ok(if always_array {
+            JsonArray::Always(json_type)
+        } else {
+            JsonArray::Infer(json_type)
+        })
+    }
+}
+
+/// Returns a static copy of the string. `JsonType::Bool` only takes static strings, and each
+/// distinct value is only copied once, so parsing configs over and over doesn't leak memory.
+#[cfg(feature = "json_types")]
+fn intern(value: &str) -> &'static str {
+    static VALUES: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+    let mut values = VALUES
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    match values.get(value) {
+        Some(value) => value,
+        None => {
+            let value: &'static str = Box::leak(value.into());
+            values.insert(value);
+            value
+        }
+    }
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidConfig(reason.to_owned())
+}
+
+impl Config {
+    /// Creates a `Config` from a JSON object with the names of the `Config` fields, starting from
+    /// `Config::new_with_defaults` for the missing ones, e.g.
+    /// `{"xml_attr_prefix": "", "empty_element_handling": "null", "json_type_overrides": {"/a/@id": "string"}}`.
+    /// Use it where a `Config` can't be built in Rust, e.g. in bindings for other languages.
+    ///
+    /// * `leading_zero_as_string`, `strict`, `emit_namespace_declarations`, `clark_notation`, `skip_leading_junk`, `sort_keys`, `collapse_single_attributes` and `nillable_everywhere` are booleans, `xml_attr_prefix`, `xml_text_node_prop_name`,
+    ///   `location_prop_name`, `base_uri` and `string_ellipsis` strings and `max_depth`, `memory_budget`, `match_limit` and `max_string_len` numbers.
+    /// * `empty_element_handling` is `"empty_object"`, `"null"` or `"ignore"`.
+    /// * `array_inference` is `"per_parent"` or `"document_wide"`.
+    /// * `coercion_failure_policy` is `"fallback"`, `"keep_string"`, `"null"` or `"error"`.
+    /// * `default_namespace` is `"ignore"`, `"include_uri"` or `"error"`.
+    /// * `utf8_policy` is `"strict"` or `"lossy"`.
+    /// * `raw_values` is `true`, `false` or an array of paths.
+    /// * `namespace_prefixes` maps namespace URIs to prefixes.
+    /// * `xml_base_paths` is an array of paths.
+    /// * `inner_text_paths` is an array of paths.
+    /// * `text_prop_names` maps paths to property names.
+    /// * `unit_paths` maps paths to `"durations"` or to objects of units and `[base_unit, factor]` pairs,
+    ///   e.g. `{"g": ["kg", 0.001]}`, see `UnitTable`.
+    /// * `text_attributes` is `"keep"`, `"drop"` or an array of the attribute names to keep.
+    /// * `array_limits` maps paths to objects with a `max_items` number and an optional `marker`, which is
+    ///   `"none"`, `"count"` or `{"text": text}`.
+    /// * `prune_empty` is `true`, `false` or an array of the kinds `"nulls"`, `"objects"`, `"arrays"` and `"strings"`.
+    /// * `single_key_objects` is `"keep"`, `"hoist"` or `{"join": separator}`.
+    /// * `idref_paths` maps paths to `"inline"`, `"pointer"`, `"inline_list"` or `"pointer_list"`.
+    /// * `json_type_overrides` maps paths to types like `"number"` or `"string[]"`, see the `FromStr`
+    ///   implementation of `JsonArray`, and `json_regex_type_overrides` is an array of `[regex, type]` pairs.
+    ///   They require the `json_types` and `regex_path` features.
+    /// * `pseudonymized_paths` is an array of paths and `pseudonymization_key` a string, with the
+    ///   `pseudonymize` feature.
+    ///
+    /// Unknown fields and invalid values fail with `Error::InvalidConfig`.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{xml_str_to_json, Config};
+    /// use serde_json::json;
+    ///
+    /// let config = Config::from_json(r#"{"xml_attr_prefix": "_", "empty_element_handling": "null"}"#).unwrap();
+    /// let json = xml_str_to_json(r#"<a id="1"><b/></a>"#, &config).unwrap();
+    /// assert_eq!(json, json!({"a": {"_id": 1, "b": null}}));
+    /// ```
+    pub fn from_json(json: &str) -> Result<Config, Error> {
+        let fields: Map<String, Value> =
+            serde_json::from_str(json).map_err(|e| invalid(&e.to_string()))?;
+        Config::from_json_fields(&fields)
+    }
+
+    /// Creates a `Config` from the fields of an already parsed JSON object, see `Config::from_json`.
+    pub(crate) fn from_json_fields(fields: &Map<String, Value>) -> Result<Config, Error> {
+        let mut config = Config::new_with_defaults();
+        for (name, value) in fields {
+            let expected = |kind: &str| invalid(&format!("`{}` must be {}", name, kind));
+            let string = || value.as_str().ok_or_else(|| expected("a string"));
+            let number = || {
+                value
+                    .as_u64()
+                    .and_then(|n| usize::try_from(n).ok())
+                    .ok_or_else(|| expected("a positive integer"))
+            };
+            let boolean = || value.as_bool().ok_or_else(|| expected("a boolean"));
+            match name.as_str() {
+                "leading_zero_as_string" => config.leading_zero_as_string = boolean()?,
+                "sort_keys" => config.sort_keys = boolean()?,
+                "collapse_single_attributes" => config.collapse_single_attributes = boolean()?,
+                "xml_attr_prefix" => config.xml_attr_prefix = string()?.to_owned(),
+                "xml_text_node_prop_name" => config.xml_text_node_prop_name = string()?.to_owned(),
+                "empty_element_handling" => {
+                    config.empty_element_handling = match string()? {
+                        "empty_object" => NullValue::EmptyObject,
+                        "null" => NullValue::Null,
+                        "ignore" => NullValue::Ignore,
+                        _ => return Err(expected("`empty_object`, `null` or `ignore`")),
+                    }
+                }
+                "array_inference" => {
+                    config.array_inference = match string()? {
+                        "per_parent" => ArrayInference::PerParent,
+                        "document_wide" => ArrayInference::DocumentWide,
+                        _ => return Err(expected("`per_parent` or `document_wide`")),
+                    }
+                }
+                "coercion_failure_policy" => {
+                    config.coercion_failure_policy = match string()? {
+                        "fallback" => CoercionFailurePolicy::Fallback,
+                        "keep_string" => CoercionFailurePolicy::KeepString,
+                        "null" => CoercionFailurePolicy::Null,
+                        "error" => CoercionFailurePolicy::Error,
+                        _ => return Err(expected("`fallback`, `keep_string`, `null` or `error`")),
+                    }
+                }
+                "strict" => config.strict = boolean()?,
+                "max_depth" => config.max_depth = number()?,
+                "memory_budget" => {
+                    config.memory_budget = if value.is_null() {
+                        None
+                    } else {
+                        Some(number()?)
+                    }
+                }
+                "max_string_len" => {
+                    config.max_string_len = if value.is_null() {
+                        None
+                    } else {
+                        Some(number()?)
+                    }
+                }
+                "string_ellipsis" => config.string_ellipsis = string()?.to_owned(),
+                "match_limit" => {
+                    config.match_limit = if value.is_null() {
+                        None
+                    } else {
+                        Some(number()?)
+                    }
+                }
+                "raw_values" => {
+                    config.raw_values = match value {
+                        Value::Bool(true) => RawValues::Always,
+                        Value::Bool(false) => RawValues::Never,
+                        Value::Array(paths) => RawValues::Paths(
+                            paths
+                                .iter()
+                                .map(|path| path.as_str().map(str::to_owned))
+                                .collect::<Option<HashSet<_>>>()
+                                .ok_or_else(|| expected("a boolean or an array of paths"))?,
+                        ),
+                        _ => return Err(expected("a boolean or an array of paths")),
+                    }
+                }
+                "location_prop_name" => {
+                    config.location_prop_name = if value.is_null() {
+                        None
+                    } else {
+                        Some(string()?.to_owned())
+                    }
+                }
+                "emit_namespace_declarations" => config.emit_namespace_declarations = boolean()?,
+                "clark_notation" => config.clark_notation = boolean()?,
+                "skip_leading_junk" => config.skip_leading_junk = boolean()?,
+                "xml_base_paths" => {
+                    config.xml_base_paths = value
+                        .as_array()
+                        .and_then(|paths| {
+                            paths
+                                .iter()
+                                .map(|path| path.as_str().map(str::to_owned))
+                                .collect::<Option<HashSet<_>>>()
+                        })
+                        .ok_or_else(|| expected("an array of paths"))?
+                }
+                "inner_text_paths" => {
+                    config.inner_text_paths = value
+                        .as_array()
+                        .and_then(|paths| {
+                            paths
+                                .iter()
+                                .map(|path| path.as_str().map(str::to_owned))
+                                .collect::<Option<HashSet<_>>>()
+                        })
+                        .ok_or_else(|| expected("an array of paths"))?
+                }
+                "prune_empty" => {
+                    let kinds = expected(
+                        "a boolean or an array of `nulls`, `objects`, `arrays` and `strings`",
+                    );
+                    config.prune_empty = match value {
+                        Value::Bool(true) => PruneEmpty::ALL,
+                        Value::Bool(false) => PruneEmpty::NONE,
+                        Value::Array(names) => {
+                            let mut prune = PruneEmpty::NONE;
+                            for name in names {
+                                match name.as_str() {
+                                    Some("nulls") => prune.nulls = true,
+                                    Some("objects") => prune.objects = true,
+                                    Some("arrays") => prune.arrays = true,
+                                    Some("strings") => prune.strings = true,
+                                    _ => return Err(kinds),
+                                }
+                            }
+                            prune
+                        }
+                        _ => return Err(kinds),
+                    }
+                }
+                "single_key_objects" => {
+                    config.single_key_objects = match value {
+                        Value::String(mode) if mode == "keep" => SingleKeyObjects::Keep,
+                        Value::String(mode) if mode == "hoist" => SingleKeyObjects::Hoist,
+                        Value::Object(join) if join.len() == 1 => match join.get("join") {
+                            Some(Value::String(separator)) => {
+                                SingleKeyObjects::Join(separator.clone())
+                            }
+                            _ => {
+                                return Err(expected("`keep`, `hoist` or `{\"join\": separator}`"))
+                            }
+                        },
+                        _ => return Err(expected("`keep`, `hoist` or `{\"join\": separator}`")),
+                    }
+                }
+                "array_limits" => {
+                    let limits = || expected("an object of paths and limits");
+                    let paths = value.as_object().ok_or_else(limits)?;
+                    for (path, limit) in paths {
+                        let max_items = limit
+                            .get("max_items")
+                            .and_then(Value::as_u64)
+                            .and_then(|n| usize::try_from(n).ok())
+                            .ok_or_else(limits)?;
+                        let marker = match limit.get("marker") {
+                            None => TruncationMarker::None,
+                            Some(Value::String(marker)) if marker == "none" => {
+                                TruncationMarker::None
+                            }
+                            Some(Value::String(marker)) if marker == "count" => {
+                                TruncationMarker::Count
+                            }
+                            Some(Value::Object(marker)) => match marker.get("text") {
+                                Some(Value::String(text)) => TruncationMarker::Text(text.clone()),
+                                _ => return Err(limits()),
+                            },
+                            _ => return Err(limits()),
+                        };
+                        config
+                            .array_limits
+                            .insert(path.clone(), ArrayLimit { max_items, marker });
+                    }
+                }
+                "idref_paths" => {
+                    let paths = value
+                        .as_object()
+                        .ok_or_else(|| expected("an object of paths and resolutions"))?;
+                    for (path, idref) in paths {
+                        let idref = match idref.as_str() {
+                            Some("inline") => IdRef::Inline,
+                            Some("pointer") => IdRef::Pointer,
+                            Some("inline_list") => IdRef::InlineList,
+                            Some("pointer_list") => IdRef::PointerList,
+                            _ => {
+                                return Err(expected(
+                                    "`inline`, `pointer`, `inline_list` or `pointer_list`",
+                                ))
+                            }
+                        };
+                        config = config.add_idref_path(path, idref);
+                    }
+                }
+                "base_uri" => {
+                    config.base_uri = if value.is_null() {
+                        None
+                    } else {
+                        Some(string()?.to_owned())
+                    }
+                }
+                "utf8_policy" => {
+                    config.utf8_policy = match string()? {
+                        "strict" => Utf8Policy::Strict,
+                        "lossy" => Utf8Policy::Lossy,
+                        _ => return Err(expected("`strict` or `lossy`")),
+                    }
+                }
+                "default_namespace" => {
+                    config.default_namespace = match string()? {
+                        "ignore" => DefaultNamespace::Ignore,
+                        "include_uri" => DefaultNamespace::IncludeUri,
+                        "error" => DefaultNamespace::Error,
+                        _ => return Err(expected("`ignore`, `include_uri` or `error`")),
+                    }
+                }
+                "text_attributes" => {
+                    let modes = || expected("`keep`, `drop` or an array of attribute names");
+                    config.text_attributes = match value {
+                        Value::String(mode) if mode == "keep" => TextAttributes::Keep,
+                        Value::String(mode) if mode == "drop" => TextAttributes::Drop,
+                        Value::Array(names) => TextAttributes::KeepOnly(
+                            names
+                                .iter()
+                                .map(|name| name.as_str().map(str::to_owned))
+                                .collect::<Option<HashSet<_>>>()
+                                .ok_or_else(modes)?,
+                        ),
+                        _ => return Err(modes()),
+                    }
+                }
+                "unit_paths" => {
+                    let tables = || expected("an object of paths and unit tables");
+                    let paths = value.as_object().ok_or_else(tables)?;
+                    for (path, units) in paths {
+                        let table = match units {
+                            Value::String(name) if name == "durations" => UnitTable::durations(),
+                            Value::Object(units) => {
+                                let mut table = UnitTable::new();
+                                for (unit, conversion) in units {
+                                    let (base_unit, factor) = match conversion.as_array() {
+                                        Some(pair) if pair.len() == 2 => {
+                                            (pair[0].as_str(), pair[1].as_f64())
+                                        }
+                                        _ => (None, None),
+                                    };
+                                    let (Some(base_unit), Some(factor)) = (base_unit, factor)
+                                    else {
+                                        return Err(tables());
+                                    };
+                                    table = table.with_unit(unit, base_unit, factor);
+                                }
+                                table
+                            }
+                            _ => return Err(tables()),
+                        };
+                        config = config.add_unit_path(path, table);
+                    }
+                }
+                "text_prop_names" => {
+                    let names = value
+                        .as_object()
+                        .ok_or_else(|| expected("an object of paths and property names"))?;
+                    for (path, name) in names {
+                        let name = name
+                            .as_str()
+                            .ok_or_else(|| expected("an object of paths and property names"))?;
+                        config = config.add_text_prop_name(path, name);
+                    }
+                }
+                "namespace_prefixes" => {
+                    let prefixes = value
+                        .as_object()
+                        .ok_or_else(|| expected("an object of namespace URIs and prefixes"))?;
+                    for (uri, prefix) in prefixes {
+                        let prefix = prefix
+                            .as_str()
+                            .ok_or_else(|| expected("an object of namespace URIs and prefixes"))?;
+                        config = config.with_namespace_prefix(uri, prefix);
+                    }
+                }
+                #[cfg(feature = "pseudonymize")]
+                "pseudonymized_paths" => {
+                    config.pseudonymized_paths = value
+                        .as_array()
+                        .and_then(|paths| {
+                            paths
+                                .iter()
+                                .map(|path| path.as_str().map(str::to_owned))
+                                .collect::<Option<HashSet<_>>>()
+                        })
+                        .ok_or_else(|| expected("an array of paths"))?
+                }
+                #[cfg(feature = "pseudonymize")]
+                "pseudonymization_key" => {
+                    config = config.with_pseudonymization_key(string()?.as_bytes())
+                }
+                #[cfg(feature = "json_types")]
+                "nillable_everywhere" => config.nillable_everywhere = boolean()?,
+                #[cfg(feature = "json_types")]
+                "json_type_overrides" => {
+                    let overrides = value
+                        .as_object()
+                        .ok_or_else(|| expected("an object of paths and types"))?;
+                    for (path, json_type) in overrides {
+                        let json_type = json_type
+                            .as_str()
+                            .ok_or_else(|| expected("an object of paths and types"))?;
+                        config = config.add_json_type_override(path.as_str(), json_type.parse()?);
+                    }
+                }
+                #[cfg(feature = "regex_path")]
+                "json_regex_type_overrides" => {
+                    let pairs = value
+                        .as_array()
+                        .ok_or_else(|| expected("an array of regex and type pairs"))?;
+                    for pair in pairs {
+                        let Some([Value::String(regex), Value::String(json_type)]) =
+                            pair.as_array().map(Vec::as_slice)
+                        else {
+                            return Err(expected("an array of regex and type pairs"));
+                        };
+                        let regex = regex::Regex::new(regex)
+                            .map_err(|e| invalid(&format!("`{}`: {}", name, e)))?;
+                        config = config.add_json_type_override(regex, json_type.parse()?);
+                    }
+                }
+                _ => return Err(invalid(&format!("unknown field `{}`", name))),
+            }
+        }
+        Ok(config)
+    }
+}
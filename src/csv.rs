@@ -0,0 +1,88 @@
+//! Conversion of XML records into CSV rows.
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use crate::flatten::{flatten_into, text};
+use crate::{split_path, xml_records_to_json, Config, Error, FlattenConfig};
+
+/// Converts every element at the absolute `record_path`, e.g. `/feed/item`, into a CSV row and writes
+/// the rows with a header line to `writer`.
+///
+/// The records are converted with the same rules as `xml_str_to_json` and flattened with `flatten_config`,
+/// so `<item id="1"><price><net>5</net></price></item>` becomes the columns `@id` and `price.net`.
+/// The columns are all keys found in any record, in the order of their first appearance, and missing
+/// values are left empty. Fields are quoted if they contain a comma, a quote or a line break.
+/// All records are held in memory to collect the columns before the first row is written.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_records_to_csv, Config, FlattenConfig};
+///
+/// let xml = r#"<feed><item id="1"><name>a, b</name></item><item id="2"><tag>x</tag></item></feed>"#;
+/// let mut csv = Vec::new();
+/// xml_records_to_csv(xml, "/feed/item", &mut csv, &FlattenConfig::default(), &Config::new_with_defaults()).unwrap();
+/// assert_eq!(String::from_utf8(csv).unwrap(), "@id,name,tag\n1,\"a, b\",\n2,,x\n");
+/// ```
+pub fn xml_records_to_csv<W: Write>(
+    xml: &str,
+    record_path: &str,
+    mut writer: W,
+    flatten_config: &FlattenConfig,
+    config: &Config,
+) -> Result<(), Error> {
+    // scalar and empty records get a column named after the record element
+    let record_name = split_path(record_path).pop().unwrap_or_default();
+    let mut columns: Vec<String> = Vec::new();
+    let mut column_index = HashMap::new();
+    let mut rows = Vec::new();
+    for record in xml_records_to_json(xml, record_path, config)? {
+        let mut fields = Vec::new();
+        let key = if record.as_object().is_some_and(|map| !map.is_empty()) {
+            String::new()
+        } else {
+            record_name.to_owned()
+        };
+        flatten_into(record, key, flatten_config, &mut fields);
+        let mut row = Vec::with_capacity(fields.len());
+        for (key, value) in fields {
+            let index = *column_index.entry(key.clone()).or_insert_with(|| {
+                columns.push(key);
+                columns.len() - 1
+            });
+            row.push((index, value));
+        }
+        rows.push(row);
+    }
+
+    write_line(&mut writer, columns.iter().map(String::as_str))?;
+    let mut line = vec![String::new(); columns.len()];
+    for row in rows {
+        line.iter_mut().for_each(String::clear);
+        for (index, value) in row {
+            line[index] = text(&value);
+        }
+        write_line(&mut writer, line.iter().map(String::as_str))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_line<'a, W: Write>(
+    writer: &mut W,
+    fields: impl Iterator<Item = &'a str>,
+) -> Result<(), Error> {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            writer.write_all(b",")?;
+        }
+        if field.contains([',', '"', '\n', '\r']) {
+            writer.write_all(b"\"")?;
+            writer.write_all(field.replace('"', "\"\"").as_bytes())?;
+            writer.write_all(b"\"")?;
+        } else {
+            writer.write_all(field.as_bytes())?;
+        }
+    }
+    writer.write_all(b"\n")?;
+    Ok(())
+}
@@ -0,0 +1,621 @@
+//! Deserialization of typed values from a `BorrowedValue`, see `XmlRecords::deserialize`.
+
+use serde::de::{
+    self, DeserializeSeed, Deserializer, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor,
+};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::vec;
+
+use crate::{BorrowedValue, Config, Error};
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Deserialize(msg.to_string())
+    }
+}
+
+/// The keys of attributes and texts in the converted values.
+#[derive(Clone, Copy)]
+pub(crate) struct Keys<'c> {
+    attr_prefix: &'c str,
+    text: &'c str,
+}
+
+impl<'c> Keys<'c> {
+    pub(crate) fn new(config: &'c Config) -> Self {
+        Keys {
+            attr_prefix: &config.xml_attr_prefix,
+            text: &config.xml_text_node_prop_name,
+        }
+    }
+
+    fn is_attribute(&self, key: &str) -> bool {
+        !self.attr_prefix.is_empty() && key.starts_with(self.attr_prefix)
+    }
+}
+
+type Map<'a> = BTreeMap<Cow<'a, str>, BorrowedValue<'a>>;
+
+fn unexpected<'a>(value: &'a BorrowedValue) -> de::Unexpected<'a> {
+    match value {
+        BorrowedValue::Null => de::Unexpected::Unit,
+        BorrowedValue::Bool(b) => de::Unexpected::Bool(*b),
+        BorrowedValue::Number(n) => match n.as_f64() {
+            Some(f) => de::Unexpected::Float(f),
+            None => de::Unexpected::Other("number"),
+        },
+        BorrowedValue::String(s) => de::Unexpected::Str(s),
+        BorrowedValue::Array(_) => de::Unexpected::Seq,
+        BorrowedValue::Object(_) => de::Unexpected::Map,
+    }
+}
+
+/// Deserializes a converted value like `serde_json::from_value` does with a `Value`, with texts and names
+/// that are borrowed from the document handed over as borrowed strings.
+///
+/// The keys of attributes and texts are the ones of the `Value` output, e.g. `#[serde(rename = "@id")]`.
+/// Elements with only a text, e.g. `<price>9.99</price>`, are converted into a scalar without the text
+/// property, so they are also accepted for structs with a field for the text property, like
+/// `<price currency="EUR">9.99</price>`, and for maps and structs with `#[serde(flatten)]` fields as an
+/// object with only the text property. Empty elements are accepted for structs and maps without required
+/// fields, also with `NullValue::Null`.
+///
+/// XML doesn't distinguish numbers, booleans and strings, so numbers and booleans are accepted for
+/// string fields, e.g. `<id>42</id>` for `id: String`. Empty elements, `{}` by default, are accepted
+/// for unit values. A single element is accepted for sequences like `Vec<T>` as a sequence with one
+/// element, and `null` as an empty sequence.
+pub(crate) struct ValueDeserializer<'de, 'c> {
+    value: BorrowedValue<'de>,
+    keys: Keys<'c>,
+}
+
+impl<'de, 'c> ValueDeserializer<'de, 'c> {
+    pub(crate) fn new(value: BorrowedValue<'de>, keys: Keys<'c>) -> Self {
+        ValueDeserializer { value, keys }
+    }
+
+    fn visit_number<V: Visitor<'de>>(
+        n: &serde_json::Number,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        if let Some(n) = n.as_u64() {
+            visitor.visit_u64(n)
+        } else if let Some(n) = n.as_i64() {
+            visitor.visit_i64(n)
+        } else {
+            visitor.visit_f64(n.as_f64().unwrap_or(f64::NAN))
+        }
+    }
+
+    fn visit_object<V: Visitor<'de>>(
+        map: Map<'de>,
+        keys: Keys,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        // attributes come first, so e.g. the tag of an adjacently tagged enum is known before its content
+        let (mut entries, children): (Vec<_>, Vec<_>) =
+            map.into_iter().partition(|(key, _)| keys.is_attribute(key));
+        entries.extend(children);
+        visitor.visit_map(Object {
+            entries: entries.into_iter(),
+            value: None,
+            keys,
+        })
+    }
+}
+
+impl<'de> Deserializer<'de> for ValueDeserializer<'de, '_> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            BorrowedValue::Null => visitor.visit_unit(),
+            BorrowedValue::Bool(b) => visitor.visit_bool(b),
+            BorrowedValue::Number(n) => Self::visit_number(&n, visitor),
+            BorrowedValue::String(Cow::Borrowed(s)) => visitor.visit_borrowed_str(s),
+            BorrowedValue::String(Cow::Owned(s)) => visitor.visit_string(s),
+            BorrowedValue::Array(items) => {
+                let len = items.len();
+                let mut seq = Seq {
+                    items: items.into_iter(),
+                    keys: self.keys,
+                };
+                let value = visitor.visit_seq(&mut seq)?;
+                match seq.items.len() {
+                    0 => Ok(value),
+                    _ => Err(de::Error::invalid_length(len, &"fewer elements in array")),
+                }
+            }
+            BorrowedValue::Object(map) => Self::visit_object(map, self.keys, visitor),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            BorrowedValue::Bool(b) => visitor.visit_string(b.to_string()),
+            BorrowedValue::Number(n) => visitor.visit_string(n.to_string()),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            BorrowedValue::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        // a single element isn't converted into an array, like with `JsonArray::Infer`
+        let items = match self.value {
+            BorrowedValue::Array(_) => return self.deserialize_any(visitor),
+            BorrowedValue::Null => Vec::new(),
+            value => vec![value],
+        };
+        visitor.visit_seq(Seq {
+            items: items.into_iter(),
+            keys: self.keys,
+        })
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match &self.value {
+            BorrowedValue::Null => visitor.visit_unit(),
+            BorrowedValue::Object(map) if map.is_empty() => visitor.visit_unit(),
+            value => Err(de::Error::invalid_type(unexpected(value), &visitor)),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        if fields.contains(&self.keys.text) {
+            self.deserialize_map(visitor)
+        } else if let BorrowedValue::Null = self.value {
+            Self::visit_object(Map::new(), self.keys, visitor)
+        } else {
+            self.deserialize_any(visitor)
+        }
+    }
+
+    /// Also used for structs with `#[serde(flatten)]` fields, e.g. for the attributes next to a field
+    /// for the text property.
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            BorrowedValue::Null => Self::visit_object(Map::new(), self.keys, visitor),
+            BorrowedValue::Bool(_) | BorrowedValue::Number(_) | BorrowedValue::String(_) => {
+                // the text of an element without attributes isn't wrapped into an object
+                let mut map = Map::new();
+                map.insert(Cow::Owned(self.keys.text.to_owned()), self.value);
+                Self::visit_object(map, self.keys, visitor)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.value {
+            BorrowedValue::String(variant) => visitor.visit_enum(Enum {
+                variant,
+                value: None,
+                keys: self.keys,
+            }),
+            BorrowedValue::Object(map) if map.len() == 1 => {
+                let (variant, value) = map.into_iter().next().expect("one entry");
+                visitor.visit_enum(Enum {
+                    variant,
+                    value: Some(value),
+                    keys: self.keys,
+                })
+            }
+            value => Err(de::Error::invalid_type(
+                unexpected(&value),
+                &"a string or an object with a single key",
+            )),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char bytes byte_buf
+        tuple tuple_struct identifier ignored_any
+    }
+}
+
+struct Seq<'de, 'c> {
+    items: vec::IntoIter<BorrowedValue<'de>>,
+    keys: Keys<'c>,
+}
+
+impl<'de> SeqAccess<'de> for Seq<'de, '_> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        self.items
+            .next()
+            .map(|item| seed.deserialize(ValueDeserializer::new(item, self.keys)))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+struct Object<'de, 'c> {
+    entries: vec::IntoIter<(Cow<'de, str>, BorrowedValue<'de>)>,
+    /// The value of the key that was returned last.
+    value: Option<BorrowedValue<'de>>,
+    keys: Keys<'c>,
+}
+
+impl<'de> MapAccess<'de> for Object<'de, '_> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                let key = ValueDeserializer::new(BorrowedValue::String(key), self.keys);
+                seed.deserialize(key).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        match self.value.take() {
+            Some(value) => seed.deserialize(ValueDeserializer::new(value, self.keys)),
+            None => Err(de::Error::custom("value is missing")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+/// An enum variant, as a string for unit variants or as an object with the variant name as key.
+struct Enum<'de, 'c> {
+    variant: Cow<'de, str>,
+    value: Option<BorrowedValue<'de>>,
+    keys: Keys<'c>,
+}
+
+impl<'de, 'c> EnumAccess<'de> for Enum<'de, 'c> {
+    type Error = Error;
+    type Variant = Variant<'de, 'c>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Error> {
+        let variant = ValueDeserializer::new(BorrowedValue::String(self.variant), self.keys);
+        let variant = seed.deserialize(variant)?;
+        Ok((
+            variant,
+            Variant {
+                value: self.value,
+                keys: self.keys,
+            },
+        ))
+    }
+}
+
+/// The value of an enum variant, `None` for unit variants given as a string.
+struct Variant<'de, 'c> {
+    value: Option<BorrowedValue<'de>>,
+    keys: Keys<'c>,
+}
+
+impl<'de> VariantAccess<'de> for Variant<'de, '_> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.value {
+            None => Ok(()),
+            Some(value) => de::Deserialize::deserialize(ValueDeserializer::new(value, self.keys)),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        match self.value {
+            Some(value) => seed.deserialize(ValueDeserializer::new(value, self.keys)),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"newtype variant",
+            )),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Some(value @ BorrowedValue::Array(_)) => {
+                ValueDeserializer::new(value, self.keys).deserialize_any(visitor)
+            }
+            Some(value) => Err(de::Error::invalid_type(
+                unexpected(&value),
+                &"tuple variant",
+            )),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"tuple variant",
+            )),
+        }
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.value {
+            Some(value) => {
+                ValueDeserializer::new(value, self.keys).deserialize_struct("", fields, visitor)
+            }
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"struct variant",
+            )),
+        }
+    }
+}
+
+/// Deserializes a `BorrowedValue` from any self-describing format, with the strings that the deserializer
+/// lends out borrowed. Used to buffer a value that is deserialized more than once, see `Untagged`.
+impl<'de> de::Deserialize<'de> for BorrowedValue<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(BorrowedValueVisitor)
+    }
+}
+
+struct BorrowedValueVisitor;
+
+impl<'de> Visitor<'de> for BorrowedValueVisitor {
+    type Value = BorrowedValue<'de>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "any value")
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<Self::Value, E> {
+        Ok(BorrowedValue::Bool(b))
+    }
+
+    fn visit_i64<E>(self, n: i64) -> Result<Self::Value, E> {
+        Ok(BorrowedValue::Number(n.into()))
+    }
+
+    fn visit_u64<E>(self, n: u64) -> Result<Self::Value, E> {
+        Ok(BorrowedValue::Number(n.into()))
+    }
+
+    fn visit_f64<E>(self, n: f64) -> Result<Self::Value, E> {
+        Ok(serde_json::Number::from_f64(n).map_or(BorrowedValue::Null, BorrowedValue::Number))
+    }
+
+    fn visit_borrowed_str<E>(self, s: &'de str) -> Result<Self::Value, E> {
+        Ok(BorrowedValue::String(Cow::Borrowed(s)))
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<Self::Value, E> {
+        Ok(BorrowedValue::String(Cow::Owned(s.to_owned())))
+    }
+
+    fn visit_string<E>(self, s: String) -> Result<Self::Value, E> {
+        Ok(BorrowedValue::String(Cow::Owned(s)))
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E> {
+        Ok(BorrowedValue::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E> {
+        Ok(BorrowedValue::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        de::Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(BorrowedValue::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut entries = Map::new();
+        while let Some(key) = map.next_key::<BorrowedValue>()? {
+            let key = match key {
+                BorrowedValue::String(key) => key,
+                key => return Err(de::Error::invalid_type(unexpected(&key), &"a string key")),
+            };
+            entries.insert(key, map.next_value()?);
+        }
+        Ok(BorrowedValue::Object(entries))
+    }
+}
+
+/// A buffered value that the code of `#[xml]` tries to deserialize into every variant of an untagged enum,
+/// with the rules of `ValueDeserializer`. serde's own untagged enums buffer values in a way that loses
+/// them, e.g. numbers for `String` fields.
+#[cfg(feature = "derive")]
+pub struct Untagged<'de> {
+    value: BorrowedValue<'de>,
+    attr_prefix: &'static str,
+    text: &'static str,
+}
+
+#[cfg(feature = "derive")]
+impl<'de> Untagged<'de> {
+    pub fn new<D: Deserializer<'de>>(
+        deserializer: D,
+        attr_prefix: &'static str,
+        text: &'static str,
+    ) -> Result<Self, D::Error> {
+        Ok(Untagged {
+            value: de::Deserialize::deserialize(deserializer)?,
+            attr_prefix,
+            text,
+        })
+    }
+
+    /// Deserializes a copy of the value into a `T`, or returns `None` if it doesn't fit.
+    pub fn deserialize<T: de::Deserialize<'de>>(&self) -> Option<T> {
+        let keys = Keys {
+            attr_prefix: self.attr_prefix,
+            text: self.text,
+        };
+        T::deserialize(ValueDeserializer::new(self.value.clone(), keys)).ok()
+    }
+}
+
+/// Deserializes the value at `path` in the map of `deserializer`, for fields with `#[xml(path = "..")]`.
+/// Values of missing keys are deserialized like missing fields, so `Option` fields are `None` and sequences
+/// are empty.
+#[cfg(feature = "derive")]
+pub fn deserialize_path<'de, D, T>(
+    deserializer: D,
+    path: &'static [&'static str],
+) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: de::Deserialize<'de>,
+{
+    match path.split_first() {
+        None => T::deserialize(deserializer),
+        Some((key, rest)) => deserializer.deserialize_map(PathVisitor {
+            key,
+            rest,
+            marker: std::marker::PhantomData,
+        }),
+    }
+}
+
+#[cfg(feature = "derive")]
+struct PathVisitor<T> {
+    key: &'static str,
+    rest: &'static [&'static str],
+    marker: std::marker::PhantomData<T>,
+}
+
+#[cfg(feature = "derive")]
+impl<T> PathVisitor<T> {
+    fn missing<'de, E: de::Error>(&self) -> Result<T, E>
+    where
+        T: de::Deserialize<'de>,
+    {
+        T::deserialize(Missing(std::marker::PhantomData))
+            .map_err(|_: E| de::Error::missing_field(self.key))
+    }
+}
+
+#[cfg(feature = "derive")]
+impl<'de, T: de::Deserialize<'de>> Visitor<'de> for PathVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an object with the key {}", self.key)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<T, A::Error> {
+        let mut value = None;
+        while let Some(key) = map.next_key::<Cow<str>>()? {
+            if key == self.key && value.is_none() {
+                value = Some(map.next_value_seed(PathSeed {
+                    path: self.rest,
+                    marker: std::marker::PhantomData,
+                })?);
+            } else {
+                map.next_value::<de::IgnoredAny>()?;
+            }
+        }
+        match value {
+            Some(value) => Ok(value),
+            None => self.missing(),
+        }
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<T, E> {
+        self.missing()
+    }
+}
+
+/// The value of a missing element: `None` for options, an empty sequence for sequences and `()` otherwise.
+#[cfg(feature = "derive")]
+struct Missing<E>(std::marker::PhantomData<E>);
+
+#[cfg(feature = "derive")]
+impl<'de, E: de::Error> Deserializer<'de> for Missing<E> {
+    type Error = E;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        visitor.visit_none()
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+        visitor.visit_seq(de::value::SeqDeserializer::new(std::iter::empty::<()>()))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        unit unit_struct newtype_struct tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+#[cfg(feature = "derive")]
+struct PathSeed<T> {
+    path: &'static [&'static str],
+    marker: std::marker::PhantomData<T>,
+}
+
+#[cfg(feature = "derive")]
+impl<'de, T: de::Deserialize<'de>> DeserializeSeed<'de> for PathSeed<T> {
+    type Value = T;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<T, D::Error> {
+        deserialize_path(deserializer, self.path)
+    }
+}
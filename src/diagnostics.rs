@@ -0,0 +1,198 @@
+//! Collection of non-fatal issues encountered during a conversion.
+//!
+//! The converter only fails because some information could not be represented in JSON if
+//! [`Config.strict`](crate::Config::strict) is set or
+//! [`Config.coercion_failure_policy`](crate::Config::coercion_failure_policy) is
+//! [`CoercionFailurePolicy::Error`](crate::CoercionFailurePolicy::Error).
+//! Pass a `Diagnostics` sink to `xml_str_to_json_with_diagnostics` to get a report of
+//! everything that was dropped, merged or coerced on the way.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::JsonType;
+
+/// Describes a single lossy event that happened during the conversion.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiagnosticKind {
+    /// A text node mixed with child elements was discarded, e.g. `more` in `<a><b/>more</a>`.
+    DroppedText(String),
+    /// A child element was discarded because its parent was converted as a text node,
+    /// e.g. `<b/>` in `<a>text<b/></a>`.
+    DroppedElement(String),
+    /// Two XML nodes mapped onto the same JSON key. Attributes overwrite each other, while an
+    /// attribute and a child element with the same name are merged into an array.
+    KeyCollision(String),
+    /// The value could not be represented by the enforced JSON type and was coerced anyway,
+    /// e.g. `maybe` becoming `false` for a `JsonType::Bool` override.
+    CoercionFailure { value: String, json_type: JsonType },
+    /// Text before the markup of the document was skipped because of `Config.skip_leading_junk`,
+    /// e.g. `HTTP/1.1 200 OK` in front of the XML declaration.
+    SkippedPrefix(String),
+    /// A processing instruction like `<?target data?>` was discarded.
+    DroppedProcessingInstruction(String),
+    /// The namespace of an element or attribute was dropped from the JSON key.
+    /// Reported once per namespace URI.
+    IgnoredNamespace(String),
+    /// An IDREF at one of the paths of `Config.idref_paths` doesn't match any `xml:id` in the output
+    /// and is kept as it is.
+    UnresolvedReference(String),
+    /// A comment was discarded. Only reported by `verify_roundtrip`.
+    DroppedComment(String),
+    /// A value comes back different after converting the JSON back into XML, e.g. `007` as `7`,
+    /// or doesn't come back at all. Only reported by `verify_roundtrip`.
+    ChangedValue {
+        original: String,
+        restored: Option<String>,
+    },
+    /// The element comes back in a different position among its siblings, because the JSON
+    /// groups elements by name. Only reported by `verify_roundtrip`.
+    ReorderedElement(String),
+    /// The document doesn't conform to the XML Schema set with `Config::with_xsd_validation`.
+    #[cfg(feature = "xsd")]
+    SchemaViolation(String),
+}
+
+impl DiagnosticKind {
+    /// Returns `true` if some content of the document is missing from the output.
+    /// These are the events that fail the conversion if `Config.strict` is set.
+    pub fn is_data_loss(&self) -> bool {
+        match self {
+            DiagnosticKind::DroppedText(_)
+            | DiagnosticKind::DroppedElement(_)
+            | DiagnosticKind::DroppedProcessingInstruction(_)
+            | DiagnosticKind::SkippedPrefix(_)
+            | DiagnosticKind::KeyCollision(_) => true,
+            DiagnosticKind::CoercionFailure { .. }
+            | DiagnosticKind::IgnoredNamespace(_)
+            | DiagnosticKind::UnresolvedReference(_)
+            | DiagnosticKind::DroppedComment(_)
+            | DiagnosticKind::ChangedValue { .. }
+            | DiagnosticKind::ReorderedElement(_) => false,
+            #[cfg(feature = "xsd")]
+            DiagnosticKind::SchemaViolation(_) => false,
+        }
+    }
+}
+
+/// A lossy event together with the XML path of the node it happened at.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// XML path of the affected node in the same notation as `json_type_overrides`, e.g. `/a/b/@c`.
+    pub path: String,
+    /// What exactly happened.
+    pub kind: DiagnosticKind,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            DiagnosticKind::DroppedText(text) => {
+                write!(f, "{}: dropped mixed text content {:?}", self.path, text)
+            }
+            DiagnosticKind::DroppedElement(name) => {
+                write!(f, "{}: dropped child element <{}>", self.path, name)
+            }
+            DiagnosticKind::DroppedProcessingInstruction(target) => {
+                write!(
+                    f,
+                    "{}: dropped processing instruction <?{}?>",
+                    self.path, target
+                )
+            }
+            DiagnosticKind::SkippedPrefix(text) => {
+                write!(f, "{}: skipped {:?} before the document", self.path, text)
+            }
+            DiagnosticKind::KeyCollision(key) => {
+                write!(f, "{}: multiple nodes map onto key {:?}", self.path, key)
+            }
+            DiagnosticKind::CoercionFailure { value, json_type } => write!(
+                f,
+                "{}: value {:?} does not fit {:?}",
+                self.path, value, json_type
+            ),
+            DiagnosticKind::IgnoredNamespace(uri) => {
+                write!(f, "{}: ignored namespace {}", self.path, uri)
+            }
+            DiagnosticKind::UnresolvedReference(id) => {
+                write!(f, "{}: no element with the ID {:?}", self.path, id)
+            }
+            DiagnosticKind::DroppedComment(text) => {
+                write!(f, "{}: dropped comment {:?}", self.path, text)
+            }
+            DiagnosticKind::ChangedValue {
+                original,
+                restored: Some(restored),
+            } => write!(
+                f,
+                "{}: value {:?} comes back as {:?}",
+                self.path, original, restored
+            ),
+            DiagnosticKind::ChangedValue {
+                original,
+                restored: None,
+            } => write!(f, "{}: value {:?} is lost", self.path, original),
+            DiagnosticKind::ReorderedElement(name) => {
+                write!(f, "{}: element <{}> changes its position", self.path, name)
+            }
+            #[cfg(feature = "xsd")]
+            DiagnosticKind::SchemaViolation(message) => {
+                write!(f, "{}: {}", self.path, message)
+            }
+        }
+    }
+}
+
+/// A sink for diagnostics collected during one or more conversions.
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    entries: Vec<Diagnostic>,
+    seen_namespaces: HashSet<String>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Diagnostics::default()
+    }
+
+    /// Adds a diagnostic to the list. Namespaces that were already reported are skipped.
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        if let DiagnosticKind::IgnoredNamespace(uri) = &diagnostic.kind {
+            if !self.seen_namespaces.insert(uri.clone()) {
+                return;
+            }
+        }
+        self.entries.push(diagnostic);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Diagnostic> {
+        self.entries.iter()
+    }
+
+    /// Removes all collected diagnostics so the sink can be reused for the next document.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.seen_namespaces.clear();
+    }
+
+    pub fn into_vec(self) -> Vec<Diagnostic> {
+        self.entries
+    }
+}
+
+impl<'a> IntoIterator for &'a Diagnostics {
+    type Item = &'a Diagnostic;
+    type IntoIter = std::slice::Iter<'a, Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
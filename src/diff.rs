@@ -0,0 +1,138 @@
+//! Structured comparison of two documents in their converted form.
+
+use std::sync::Arc;
+
+use serde_json::{json, Map, Value};
+
+use crate::ids::push_escaped;
+use crate::{
+    parse_document, prepare_input, repeated_names, xml_to_map, ArrayInference, Config, Context,
+    Error,
+};
+
+/// Converts both documents with `config` and returns the differences between the results as
+/// `{"added":{..},"removed":{..},"changed":{..}}`. Each of them maps the JSON Pointer of a value
+/// to the value, or to `{"old":..,"new":..}` for changed values.
+///
+/// Arrays are compared regardless of the order of their items: equal items are matched first and
+/// the remaining ones are compared in order. Pointers into arrays use the index of the item in the
+/// document it is from, i.e. `xml_b` for added items and `xml_a` otherwise. With
+/// `ArrayInference::DocumentWide`, elements that repeat in either document are converted into
+/// arrays in both, so an element that occurs once in one of them is compared as an array, too.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_diff, Config};
+/// use serde_json::json;
+///
+/// let a = r#"<a id="1"><b>x</b><b>y</b><c>1</c></a>"#;
+/// let b = r#"<a id="2"><b>y</b><b>x</b><d>true</d></a>"#;
+/// let diff = xml_diff(a, b, &Config::new_with_defaults()).unwrap();
+/// assert_eq!(
+///     diff,
+///     json!({
+///         "added": {"/a/d": true},
+///         "removed": {"/a/c": 1},
+///         "changed": {"/a/@id": {"old": 1, "new": 2}}
+///     })
+/// );
+/// ```
+pub fn xml_diff(xml_a: &str, xml_b: &str, config: &Config) -> Result<Value, Error> {
+    let xml_a = prepare_input(xml_a, config)?;
+    let xml_b = prepare_input(xml_b, config)?;
+    let doc_a = parse_document(&xml_a, config)?;
+    let doc_b = parse_document(&xml_b, config)?;
+    let mut ctx_a = Context::new(config, None);
+    let mut ctx_b = Context::new(config, None);
+    if config.array_inference == ArrayInference::DocumentWide {
+        let mut names = Arc::unwrap_or_clone(repeated_names(&doc_a, config));
+        names.extend(Arc::unwrap_or_clone(repeated_names(&doc_b, config)));
+        let names = Arc::new(names);
+        ctx_a.repeated_names = names.clone();
+        ctx_b.repeated_names = names;
+    }
+    let a = xml_to_map(&mut ctx_a, &doc_a)?;
+    let b = xml_to_map(&mut ctx_b, &doc_b)?;
+    let mut diff = Diff::default();
+    diff.compare(&a, &b, &mut String::new());
+    Ok(json!({
+        "added": diff.added,
+        "removed": diff.removed,
+        "changed": diff.changed,
+    }))
+}
+
+#[derive(Default)]
+struct Diff {
+    added: Map<String, Value>,
+    removed: Map<String, Value>,
+    changed: Map<String, Value>,
+}
+
+impl Diff {
+    /// Records the differences between `a` and `b`, which are both at `pointer`.
+    fn compare(&mut self, a: &Value, b: &Value, pointer: &mut String) {
+        match (a, b) {
+            (Value::Object(a), Value::Object(b)) => {
+                for (key, value) in a {
+                    let len = pointer.len();
+                    pointer.push('/');
+                    push_escaped(pointer, key);
+                    match b.get(key) {
+                        Some(other) => self.compare(value, other, pointer),
+                        None => {
+                            self.removed.insert(pointer.clone(), value.clone());
+                        }
+                    }
+                    pointer.truncate(len);
+                }
+                for (key, value) in b.iter().filter(|(key, _)| !a.contains_key(*key)) {
+                    let mut pointer = pointer.clone();
+                    pointer.push('/');
+                    push_escaped(&mut pointer, key);
+                    self.added.insert(pointer, value.clone());
+                }
+            }
+            (Value::Array(a), Value::Array(b)) => self.compare_arrays(a, b, pointer),
+            (a, b) if a != b => {
+                self.changed
+                    .insert(pointer.clone(), json!({"old": a, "new": b}));
+            }
+            _ => {}
+        }
+    }
+
+    /// Matches equal items of both arrays and compares the remaining ones in order.
+    fn compare_arrays(&mut self, a: &[Value], b: &[Value], pointer: &mut String) {
+        let mut unmatched_b: Vec<Option<&Value>> = b.iter().map(Some).collect();
+        let mut unmatched_a = Vec::new();
+        for (i, item) in a.iter().enumerate() {
+            match unmatched_b.iter_mut().find(|other| *other == &Some(item)) {
+                Some(other) => *other = None,
+                None => unmatched_a.push(i),
+            }
+        }
+        let mut unmatched_b = unmatched_b
+            .iter()
+            .enumerate()
+            .filter_map(|(i, item)| item.map(|item| (i, item)));
+
+        let len = pointer.len();
+        for i in unmatched_a {
+            pointer.push('/');
+            pointer.push_str(&i.to_string());
+            match unmatched_b.next() {
+                Some((_, other)) => self.compare(&a[i], other, pointer),
+                None => {
+                    self.removed.insert(pointer.clone(), a[i].clone());
+                }
+            }
+            pointer.truncate(len);
+        }
+        for (i, item) in unmatched_b {
+            self.added.insert(
+                [pointer.as_str(), "/", &i.to_string()].concat(),
+                item.clone(),
+            );
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! Transcoding of documents in legacy encodings into UTF-8 with encoding_rs.
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use std::borrow::Cow;
+
+use crate::{Error, Utf8Policy};
+
+/// The declaration is expected within the first bytes of the document.
+const MAX_DECLARATION_LEN: usize = 1024;
+
+/// Decodes the bytes of an XML document into a string. The encoding is taken from the byte order mark,
+/// the `encoding` of the XML declaration, e.g. `<?xml version="1.0" encoding="ISO-8859-1"?>`, or is UTF-8
+/// if there is neither. All encodings of the WHATWG Encoding Standard are supported, e.g. Windows-1252,
+/// Shift_JIS or UTF-16. UTF-8 input is borrowed, without the byte order mark.
+/// Unknown encodings and bytes that are not valid in the encoding fail with `Error::Encoding`.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::decode_xml;
+///
+/// let xml = b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><a>Gr\xfc\xdfe</a>";
+/// assert_eq!(decode_xml(xml).unwrap(), "<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><a>Grüße</a>");
+/// ```
+pub fn decode_xml(bytes: &[u8]) -> Result<Cow<'_, str>, Error> {
+    decode(bytes, Utf8Policy::Strict)
+}
+
+/// Decodes like `decode_xml`, with invalid bytes replaced by U+FFFD under `Utf8Policy::Lossy`.
+pub(crate) fn decode(bytes: &[u8], policy: Utf8Policy) -> Result<Cow<'_, str>, Error> {
+    let (encoding, bom_len) = match Encoding::for_bom(bytes) {
+        Some(detected) => detected,
+        None => (declared_encoding(bytes)?, 0),
+    };
+    let bytes = &bytes[bom_len..];
+    if policy == Utf8Policy::Lossy {
+        return Ok(encoding.decode_without_bom_handling(bytes).0);
+    }
+    if encoding == UTF_8 {
+        return std::str::from_utf8(bytes)
+            .map(Cow::Borrowed)
+            .map_err(|e| Error::Encoding(format!("invalid UTF-8: {}", e)));
+    }
+    encoding
+        .decode_without_bom_handling_and_without_replacement(bytes)
+        .ok_or_else(|| Error::Encoding(format!("invalid {} input", encoding.name())))
+}
+
+/// Returns the encoding of a document without a byte order mark.
+fn declared_encoding(bytes: &[u8]) -> Result<&'static Encoding, Error> {
+    // `<?` of the declaration in UTF-16
+    match bytes {
+        [b'<', 0, b'?', 0, ..] => return Ok(UTF_16LE),
+        [0, b'<', 0, b'?', ..] => return Ok(UTF_16BE),
+        _ => (),
+    }
+    let Some(declaration) = bytes.strip_prefix(b"<?xml") else {
+        return Ok(UTF_8);
+    };
+    let declaration = &declaration[..declaration.len().min(MAX_DECLARATION_LEN)];
+    let declaration = declaration
+        .windows(2)
+        .position(|w| w == b"?>")
+        .map_or(declaration, |end| &declaration[..end]);
+    let Some(label) = pseudo_attribute(declaration, b"encoding") else {
+        return Ok(UTF_8);
+    };
+    Encoding::for_label(label).ok_or_else(|| {
+        Error::Encoding(format!(
+            "unsupported encoding {}",
+            String::from_utf8_lossy(label)
+        ))
+    })
+}
+
+/// Returns the value of the pseudo-attribute `name` of the XML declaration.
+fn pseudo_attribute<'d>(declaration: &'d [u8], name: &[u8]) -> Option<&'d [u8]> {
+    let start = declaration.windows(name.len()).position(|w| w == name)?;
+    let rest = declaration[start + name.len()..].trim_ascii_start();
+    let rest = rest.strip_prefix(b"=")?.trim_ascii_start();
+    let (&quote, rest) = rest.split_first()?;
+    if quote != b'"' && quote != b'\'' {
+        return None;
+    }
+    let end = rest.iter().position(|&b| b == quote)?;
+    Some(&rest[..end])
+}
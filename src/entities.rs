@@ -0,0 +1,438 @@
+//! Resolution of external entities and DTD subsets, see `Config.entity_resolver`.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{parse_document, Config, Error};
+
+/// A callback that supplies the content of external entities and DTD subsets by their public and
+/// system IDs, e.g. `-//OASIS//DTD DocBook XML V4.5//EN` and `http://www.oasis-open.org/docbook/xml/4.5/docbookx.dtd`.
+/// Returning `None` refuses the entity and fails the conversion with `Error::ExternalEntity`.
+/// Nothing is ever fetched by the converter itself, so network access is up to the callback.
+/// Set it with `Config::with_entity_resolver` or `Config::with_entity_catalog`.
+pub struct EntityResolver {
+    resolve: Box<ResolveFn>,
+}
+
+type ResolveFn = dyn Fn(Option<&str>, &str) -> Option<String> + Send + Sync;
+
+impl EntityResolver {
+    pub fn new<F>(resolve: F) -> Self
+    where
+        F: Fn(Option<&str>, &str) -> Option<String> + Send + Sync + 'static,
+    {
+        EntityResolver {
+            resolve: Box::new(resolve),
+        }
+    }
+}
+
+impl fmt::Debug for EntityResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EntityResolver").finish_non_exhaustive()
+    }
+}
+
+/// A map of public and system IDs to local files, like an OASIS XML catalog. System IDs are looked up
+/// first. IDs that are not in the catalog are refused, so it never reaches out to the network.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{Config, EntityCatalog};
+///
+/// let catalog = EntityCatalog::new()
+///     .add_public("-//Example//DTD Book//EN", "dtd/book.dtd")
+///     .add_system("http://example.com/chapters/one.xml", "chapters/one.xml");
+/// let conf = Config::new_with_defaults().with_entity_catalog(catalog);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EntityCatalog {
+    public: HashMap<String, PathBuf>,
+    system: HashMap<String, PathBuf>,
+}
+
+impl EntityCatalog {
+    pub fn new() -> Self {
+        EntityCatalog::default()
+    }
+
+    /// Maps the public ID `public_id` to the file at `path`.
+    pub fn add_public(self, public_id: &str, path: impl Into<PathBuf>) -> Self {
+        let mut catalog = self;
+        catalog.public.insert(public_id.to_owned(), path.into());
+        catalog
+    }
+
+    /// Maps the system ID `system_id` to the file at `path`.
+    pub fn add_system(self, system_id: &str, path: impl Into<PathBuf>) -> Self {
+        let mut catalog = self;
+        catalog.system.insert(system_id.to_owned(), path.into());
+        catalog
+    }
+
+    /// Reads the `public` and `system` entries of an OASIS XML catalog file, e.g.
+    /// `<catalog><system systemId="http://example.com/a.dtd" uri="dtd/a.dtd"/></catalog>`.
+    /// Relative and `file:` URIs are resolved against the directory of the catalog, other URIs are
+    /// skipped. Other entries, like `rewriteSystem` or `nextCatalog`, are not supported.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let xml = fs::read_to_string(path).map_err(|e| {
+            Error::InvalidConfig(format!("can't read the catalog {}: {}", path.display(), e))
+        })?;
+        let doc = parse_document(&xml, &Config::new_with_defaults())?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let mut catalog = EntityCatalog::new();
+        for entry in doc.descendants().filter(|n| n.is_element()) {
+            let (map, id) = match entry.tag_name().name() {
+                "public" => (&mut catalog.public, entry.attribute("publicId")),
+                "system" => (&mut catalog.system, entry.attribute("systemId")),
+                _ => continue,
+            };
+            let (Some(id), Some(uri)) = (id, entry.attribute("uri")) else {
+                continue;
+            };
+            let file = uri.strip_prefix("file://").unwrap_or(uri);
+            if file.contains("://") {
+                continue;
+            }
+            map.insert(id.to_owned(), dir.join(file));
+        }
+        Ok(catalog)
+    }
+
+    fn path(&self, public_id: Option<&str>, system_id: &str) -> Option<&Path> {
+        self.system
+            .get(system_id)
+            .or_else(|| public_id.and_then(|id| self.public.get(id)))
+            .map(PathBuf::as_path)
+    }
+}
+
+impl From<EntityCatalog> for EntityResolver {
+    fn from(catalog: EntityCatalog) -> Self {
+        EntityResolver::new(move |public_id, system_id| {
+            fs::read_to_string(catalog.path(public_id, system_id)?).ok()
+        })
+    }
+}
+
+/// Returns the document with the external DTD subset and the external entities declared in the DOCTYPE
+/// replaced by their content from `Config.entity_resolver`, so roxmltree can expand them. The document is
+/// returned as it is without a resolver, or without a DOCTYPE.
+pub(crate) fn resolve_external<'x>(xml: &'x str, config: &Config) -> Result<Cow<'x, str>, Error> {
+    let Some(resolver) = &config.entity_resolver else {
+        return Ok(Cow::Borrowed(xml));
+    };
+    let Some(start) = doctype_start(xml) else {
+        return Ok(Cow::Borrowed(xml));
+    };
+    let mut s = Scanner {
+        text: xml,
+        pos: start + "<!DOCTYPE".len(),
+    };
+    // malformed declarations are left to the parser
+    let Some(name) = s.name() else {
+        return Ok(Cow::Borrowed(xml));
+    };
+    let external_id = s.external_id();
+    s.skip_whitespace();
+    let internal_subset = if s.eat("[") {
+        let subset_start = s.pos;
+        s.skip_declarations();
+        let subset = &xml[subset_start..s.pos];
+        if !s.eat("]") {
+            return Ok(Cow::Borrowed(xml));
+        }
+        s.skip_whitespace();
+        subset
+    } else {
+        ""
+    };
+    if !s.eat(">") {
+        return Ok(Cow::Borrowed(xml));
+    }
+    let end = s.pos;
+
+    // the declarations of the internal subset take precedence, so they come first
+    let mut subset = internal_subset.to_owned();
+    if let Some((public_id, system_id)) = external_id {
+        subset.push_str(&fetch(resolver, public_id, system_id)?);
+    }
+    let subset = resolve_entity_declarations(resolver, &subset)?;
+    Ok(Cow::Owned(
+        [
+            &xml[..start],
+            "<!DOCTYPE ",
+            name,
+            " [",
+            &subset,
+            "]>",
+            &xml[end..],
+        ]
+        .concat(),
+    ))
+}
+
+/// Returns the position of `<!DOCTYPE` in the prolog of the document.
+fn doctype_start(xml: &str) -> Option<usize> {
+    let mut s = Scanner { text: xml, pos: 0 };
+    s.eat("\u{feff}");
+    loop {
+        s.skip_whitespace();
+        if s.rest().starts_with("<!DOCTYPE") {
+            return Some(s.pos);
+        } else if s.eat("<?") {
+            s.skip_past("?>");
+        } else if s.eat("<!--") {
+            s.skip_past("-->");
+        } else {
+            return None;
+        }
+    }
+}
+
+/// Replaces the declarations of external parsed general entities with internal ones.
+/// Unparsed entities with `NDATA` and parameter entities are kept as they are.
+fn resolve_entity_declarations(resolver: &EntityResolver, subset: &str) -> Result<String, Error> {
+    let mut resolved = String::with_capacity(subset.len());
+    let mut s = Scanner {
+        text: subset,
+        pos: 0,
+    };
+    let mut copied = 0;
+    while s.pos < subset.len() {
+        let declaration_start = s.pos;
+        if s.eat("<!--") {
+            s.skip_past("-->");
+        } else if s.eat("<!ENTITY") {
+            s.skip_whitespace();
+            if s.rest().starts_with('%') {
+                s.skip_declaration();
+                continue;
+            }
+            let Some(name) = s.name() else {
+                s.skip_declaration();
+                continue;
+            };
+            let Some((public_id, system_id)) = s.external_id() else {
+                s.skip_declaration();
+                continue;
+            };
+            s.skip_whitespace();
+            if !s.eat(">") {
+                // `NDATA`
+                s.skip_declaration();
+                continue;
+            }
+            let content = fetch(resolver, public_id, system_id)?;
+            resolved.push_str(&subset[copied..declaration_start]);
+            resolved.push_str("<!ENTITY ");
+            resolved.push_str(name);
+            resolved.push(' ');
+            push_entity_value(&mut resolved, &content, system_id)?;
+            resolved.push('>');
+            copied = s.pos;
+        } else if s.eat("<") {
+            s.skip_declaration();
+        } else {
+            s.pos += s.rest().chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    resolved.push_str(&subset[copied..]);
+    Ok(resolved)
+}
+
+fn fetch(
+    resolver: &EntityResolver,
+    public_id: Option<&str>,
+    system_id: &str,
+) -> Result<String, Error> {
+    let content = (resolver.resolve)(public_id, system_id)
+        .ok_or_else(|| Error::ExternalEntity(system_id.to_owned()))?;
+    // the text declaration of an external entity is not part of its content
+    let content = content.strip_prefix('\u{feff}').unwrap_or(&content);
+    Ok(match content.strip_prefix("<?xml") {
+        Some(rest) if rest.starts_with(|c: char| c.is_ascii_whitespace()) => rest
+            .split_once("?>")
+            .map_or(content, |(_, content)| content)
+            .to_owned(),
+        _ => content.to_owned(),
+    })
+}
+
+/// Appends `content` as a quoted entity value. roxmltree parses the value as it is when the entity
+/// is referenced, so the quotes can't be escaped in the literal itself. If the content has both kinds
+/// of quotes, it's put in single quotes and its single quotes are rewritten into equivalent markup:
+/// character references in text and attribute values, double quotes around attribute values and split
+/// CDATA sections. Comments and processing instructions have no such markup, single quotes in them fail
+/// with `Error::ExternalEntity`.
+fn push_entity_value(value: &mut String, content: &str, system_id: &str) -> Result<(), Error> {
+    if !content.contains('"') {
+        value.push('"');
+        value.push_str(content);
+        value.push('"');
+        return Ok(());
+    }
+
+    value.push('\'');
+    let mut rest = content;
+    while let Some(pos) = rest.find(['<', '\'']) {
+        value.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        if let Some(quote) = rest.strip_prefix('\'') {
+            value.push_str("&#39;");
+            rest = quote;
+            continue;
+        }
+        if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+            let end = cdata.find("]]>").map_or(cdata.len(), |end| end + 3);
+            value.push_str("<![CDATA[");
+            value.push_str(&cdata[..end].replace('\'', "]]>&#39;<![CDATA["));
+            rest = &cdata[end..];
+        } else if rest.starts_with("<!--") || rest.starts_with("<?") {
+            let terminator = if rest.starts_with("<?") { "?>" } else { "-->" };
+            let end = rest[2..]
+                .find(terminator)
+                .map_or(rest.len(), |end| end + 2 + terminator.len());
+            if rest[..end].contains('\'') {
+                return Err(Error::ExternalEntity(system_id.to_owned()));
+            }
+            value.push_str(&rest[..end]);
+            rest = &rest[end..];
+        } else {
+            // a tag, with attribute values in either kind of quotes
+            let mut chars = rest.char_indices();
+            let mut end = rest.len();
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '>' => {
+                        value.push('>');
+                        end = i + 1;
+                        break;
+                    }
+                    '"' | '\'' => {
+                        value.push('"');
+                        for (_, v) in chars.by_ref() {
+                            match v {
+                                _ if v == c => break,
+                                '"' => value.push_str("&quot;"),
+                                '\'' => value.push_str("&#39;"),
+                                v => value.push(v),
+                            }
+                        }
+                        value.push('"');
+                    }
+                    c => value.push(c),
+                }
+            }
+            rest = &rest[end..];
+        }
+    }
+    value.push_str(rest);
+    value.push('\'');
+    Ok(())
+}
+
+struct Scanner<'t> {
+    text: &'t str,
+    pos: usize,
+}
+
+impl<'t> Scanner<'t> {
+    fn rest(&self) -> &'t str {
+        &self.text[self.pos..]
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let found = self.rest().starts_with(token);
+        if found {
+            self.pos += token.len();
+        }
+        found
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn skip_past(&mut self, token: &str) {
+        self.pos = self
+            .rest()
+            .find(token)
+            .map_or(self.text.len(), |i| self.pos + i + token.len());
+    }
+
+    fn name(&mut self) -> Option<&'t str> {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '[' | '>' | '"' | '\''))
+            .unwrap_or(rest.len());
+        self.pos += len;
+        (len > 0).then(|| &rest[..len])
+    }
+
+    fn quoted(&mut self) -> Option<&'t str> {
+        self.skip_whitespace();
+        let quote = self
+            .rest()
+            .chars()
+            .next()
+            .filter(|c| matches!(c, '"' | '\''))?;
+        let rest = &self.rest()[1..];
+        let len = rest.find(quote)?;
+        self.pos += len + 2;
+        Some(&rest[..len])
+    }
+
+    /// Reads `SYSTEM "system"` or `PUBLIC "public" "system"`.
+    fn external_id(&mut self) -> Option<(Option<&'t str>, &'t str)> {
+        self.skip_whitespace();
+        if self.eat("SYSTEM") {
+            Some((None, self.quoted()?))
+        } else if self.eat("PUBLIC") {
+            let public_id = self.quoted()?;
+            Some((Some(public_id), self.quoted()?))
+        } else {
+            None
+        }
+    }
+
+    /// Skips to the end of the current markup declaration, past quoted strings that may contain `>`.
+    fn skip_declaration(&mut self) {
+        while let Some(c) = self.rest().chars().next() {
+            match c {
+                '"' | '\'' => {
+                    if self.quoted().is_none() {
+                        self.pos = self.text.len();
+                    }
+                }
+                '>' => {
+                    self.pos += 1;
+                    return;
+                }
+                c => self.pos += c.len_utf8(),
+            }
+        }
+    }
+
+    /// Skips the declarations, comments and processing instructions of an internal subset up to its `]`.
+    fn skip_declarations(&mut self) {
+        while let Some(c) = self.rest().chars().next() {
+            if c == ']' {
+                return;
+            } else if self.eat("<!--") {
+                self.skip_past("-->");
+            } else if self.eat("<?") {
+                self.skip_past("?>");
+            } else if c == '<' {
+                self.skip_declaration();
+            } else {
+                self.pos += c.len_utf8();
+            }
+        }
+    }
+}
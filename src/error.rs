@@ -0,0 +1,139 @@
+use std::fmt;
+
+#[cfg(feature = "json_types")]
+use crate::presets::SoapFault;
+use crate::Diagnostic;
+
+/// Errors returned by the conversion functions. New variants may be added with new options or
+/// features, so matches need a wildcard arm.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The input is not a well-formed XML document.
+    Xml(roxmltree::Error),
+    /// `Config.strict` is set and the conversion would have lost some information.
+    Lossy(Diagnostic),
+    /// `Config.coercion_failure_policy` is set to `Error` and a value doesn't fit its enforced JSON type.
+    Coercion(Diagnostic),
+    /// The document is nested deeper than `Config.max_depth`.
+    DepthLimitExceeded(usize),
+    /// The XML Schema is not valid or not supported.
+    #[cfg(feature = "xsd")]
+    Xsd(String),
+    /// The document doesn't conform to the schema set with `Config::with_xsd_validation`.
+    #[cfg(feature = "xsd")]
+    Invalid(Vec<Diagnostic>),
+    /// The conversion was aborted with `Config.cancellation_flag`.
+    Cancelled,
+    /// The output would be larger than `Config.memory_budget` bytes.
+    MemoryBudgetExceeded(usize),
+    /// The output could not be serialized into YAML.
+    #[cfg(feature = "yaml")]
+    Yaml(serde_yaml::Error),
+    /// The output can't be represented in TOML with the given `TomlConfig`.
+    #[cfg(feature = "toml")]
+    Toml(String),
+    /// A record doesn't fit the Avro schema.
+    #[cfg(feature = "avro")]
+    Avro(String),
+    /// A record doesn't fit the Arrow schema, or writing Parquet failed.
+    #[cfg(feature = "arrow")]
+    Arrow(String),
+    /// Writing the output failed.
+    Io(std::io::Error),
+    /// The serializer of `xml_to_serializer` failed.
+    Serialize(String),
+    /// A record can't be deserialized into the requested type.
+    Deserialize(String),
+    /// The JSONPath query of `xml_query` is malformed or uses unsupported syntax.
+    InvalidQuery(String),
+    /// The JSON of `Config::from_json` is not a valid configuration.
+    InvalidConfig(String),
+    /// The input bytes are not valid in their encoding, or the declared encoding is not supported.
+    Encoding(String),
+    /// `Config.entity_resolver` refused the external entity or DTD subset with this system ID, or the
+    /// entity has single quotes in a comment or processing instruction and double quotes elsewhere,
+    /// which can't be declared as an entity value.
+    ExternalEntity(String),
+    /// The document doesn't have the structure of the expected XML format, e.g. of a SOAP envelope.
+    UnexpectedXml(String),
+    /// The SOAP message of `presets::soap_body` is a fault.
+    #[cfg(feature = "json_types")]
+    SoapFault(SoapFault),
+    /// The JSON value can't be converted back into XML because it doesn't have the expected structure.
+    UnexpectedJson(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Xml(e) => write!(f, "malformed XML: {}", e),
+            Error::Lossy(d) => write!(f, "lossy conversion in strict mode: {}", d),
+            Error::Coercion(d) => write!(f, "type coercion failed: {}", d),
+            Error::DepthLimitExceeded(limit) => {
+                write!(f, "document is nested deeper than {} elements", limit)
+            }
+            #[cfg(feature = "xsd")]
+            Error::Xsd(e) => write!(f, "invalid XML Schema: {}", e),
+            #[cfg(feature = "xsd")]
+            Error::Invalid(findings) => {
+                write!(f, "document doesn't conform to the schema")?;
+                for finding in findings {
+                    write!(f, "\n{}", finding)?;
+                }
+                Ok(())
+            }
+            Error::Cancelled => write!(f, "conversion was cancelled"),
+            Error::MemoryBudgetExceeded(budget) => {
+                write!(
+                    f,
+                    "output is larger than the memory budget of {} bytes",
+                    budget
+                )
+            }
+            #[cfg(feature = "yaml")]
+            Error::Yaml(e) => write!(f, "failed to serialize YAML: {}", e),
+            #[cfg(feature = "toml")]
+            Error::Toml(e) => write!(f, "failed to convert into TOML: {}", e),
+            #[cfg(feature = "avro")]
+            Error::Avro(e) => write!(f, "failed to convert into Avro: {}", e),
+            #[cfg(feature = "arrow")]
+            Error::Arrow(e) => write!(f, "failed to convert into Arrow: {}", e),
+            Error::Io(e) => write!(f, "failed to write the output: {}", e),
+            Error::Serialize(e) => write!(f, "failed to serialize the output: {}", e),
+            Error::Deserialize(e) => write!(f, "failed to deserialize the record: {}", e),
+            Error::InvalidQuery(e) => write!(f, "invalid JSONPath query: {}", e),
+            Error::InvalidConfig(e) => write!(f, "invalid configuration: {}", e),
+            Error::Encoding(e) => write!(f, "undecodable input: {}", e),
+            Error::ExternalEntity(id) => write!(f, "external entity {} was not resolved", id),
+            Error::UnexpectedXml(e) => write!(f, "unexpected XML structure: {}", e),
+            #[cfg(feature = "json_types")]
+            Error::SoapFault(fault) => write!(f, "SOAP fault {}", fault),
+            Error::UnexpectedJson(e) => write!(f, "unexpected JSON structure: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Xml(e) => Some(e),
+            #[cfg(feature = "yaml")]
+            Error::Yaml(e) => Some(e),
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<roxmltree::Error> for Error {
+    fn from(e: roxmltree::Error) -> Self {
+        Error::Xml(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
@@ -0,0 +1,170 @@
+//! Flattening of nested JSON values into a single level of path keys.
+
+use serde_json::{Map, Value};
+
+use crate::{xml_str_to_json, Config, Error, SingleKeyObjects};
+
+/// How arrays are flattened.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FlattenArrays {
+    /// Every item gets its own key with the index in brackets, e.g. `tags[0]` and `tags[1]`.
+    Index,
+    /// The items are joined into a single string with the given separator, e.g. `a;b` for `;`.
+    /// Arrays with nested objects or arrays are stored as JSON text.
+    Join(String),
+    /// The array is stored as JSON text, e.g. `["a","b"]`.
+    Json,
+}
+
+/// Options to flatten nested values into path keys, e.g. `{"a": {"b": 1}}` into `{"a.b": 1}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlattenConfig {
+    /// Separator between the names of nested properties. Defaults to `.`.
+    pub separator: String,
+    /// How arrays are flattened. Defaults to `FlattenArrays::Index`.
+    pub arrays: FlattenArrays,
+}
+
+impl FlattenConfig {
+    pub fn new_with_defaults() -> Self {
+        FlattenConfig {
+            separator: ".".to_owned(),
+            arrays: FlattenArrays::Index,
+        }
+    }
+
+    /// Joins the names of nested properties with the given separator, e.g. `_` or `/`.
+    pub fn with_separator(self, separator: &str) -> Self {
+        FlattenConfig {
+            separator: separator.to_owned(),
+            ..self
+        }
+    }
+
+    /// Sets how arrays are flattened.
+    pub fn with_arrays(self, arrays: FlattenArrays) -> Self {
+        FlattenConfig { arrays, ..self }
+    }
+}
+
+impl Default for FlattenConfig {
+    fn default() -> Self {
+        FlattenConfig::new_with_defaults()
+    }
+}
+
+/// Converts the given XML string like `xml_str_to_json`, but returns a single flat object with path keys
+/// instead of nested structures, e.g. `{"order.items[0].price": 9.99}` for
+/// `<order><items><price>9.99</price></items><items>..</items></order>`.
+///
+/// The keys start with the name of the root element. Empty elements keep their key with a `null` value.
+/// Like all objects of `serde_json`, the map is sorted by key, so `items[10]` comes before `items[2]`.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_str_to_flat_json, Config, FlattenConfig};
+/// use serde_json::json;
+///
+/// let xml = r#"<order id="7"><item><price>9.99</price></item><item><price>5</price></item></order>"#;
+/// let flat = xml_str_to_flat_json(xml, &Config::new_with_defaults(), &FlattenConfig::default()).unwrap();
+/// assert_eq!(flat["order.item[0].price"], json!(9.99));
+/// assert_eq!(flat["order.@id"], json!(7));
+/// ```
+pub fn xml_str_to_flat_json(
+    xml: &str,
+    config: &Config,
+    flatten_config: &FlattenConfig,
+) -> Result<Map<String, Value>, Error> {
+    let mut fields = Vec::new();
+    flatten_into(
+        xml_str_to_json(xml, config)?,
+        String::new(),
+        flatten_config,
+        &mut fields,
+    );
+    Ok(fields.into_iter().collect())
+}
+
+/// Appends the scalar values of `value` to `out`, with keys relative to `key`.
+/// Empty objects and arrays keep their key with a `null` value, so the key doesn't disappear.
+pub(crate) fn flatten_into(
+    value: Value,
+    key: String,
+    config: &FlattenConfig,
+    out: &mut Vec<(String, Value)>,
+) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (name, value) in map {
+                let child_key = if key.is_empty() {
+                    name
+                } else {
+                    [key.as_str(), &config.separator, &name].concat()
+                };
+                flatten_into(value, child_key, config, out);
+            }
+        }
+        Value::Array(items) if !items.is_empty() => match &config.arrays {
+            FlattenArrays::Index => {
+                for (i, item) in items.into_iter().enumerate() {
+                    flatten_into(item, format!("{}[{}]", key, i), config, out);
+                }
+            }
+            FlattenArrays::Join(separator) => {
+                let joined = if items.iter().any(|v| v.is_object() || v.is_array()) {
+                    Value::Array(items).to_string()
+                } else {
+                    items.iter().map(text).collect::<Vec<_>>().join(separator)
+                };
+                out.push((key, Value::String(joined)));
+            }
+            FlattenArrays::Json => out.push((key, Value::String(Value::Array(items).to_string()))),
+        },
+        Value::Object(_) | Value::Array(_) => out.push((key, Value::Null)),
+        scalar => out.push((key, scalar)),
+    }
+}
+
+/// Returns the text of a scalar value, empty for `null`.
+pub(crate) fn text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Collapses the objects with a single property below `value`, see `Config.single_key_objects`.
+pub(crate) fn collapse_single_key_objects(value: &mut Value, mode: &SingleKeyObjects) {
+    match value {
+        Value::Object(map) => {
+            for (mut key, mut child) in std::mem::take(map) {
+                while let Value::Object(inner) = &mut child {
+                    let collapse = inner.len() == 1
+                        && match mode {
+                            SingleKeyObjects::Keep => false,
+                            SingleKeyObjects::Join(_) => true,
+                            SingleKeyObjects::Hoist => {
+                                inner.values().all(|v| v.is_object() || v.is_array())
+                            }
+                        };
+                    if !collapse {
+                        break;
+                    }
+                    let (name, inner_value) = std::mem::take(inner).into_iter().next().unwrap();
+                    if let SingleKeyObjects::Join(separator) = mode {
+                        key = [key.as_str(), separator, &name].concat();
+                    }
+                    child = inner_value;
+                }
+                collapse_single_key_objects(&mut child, mode);
+                map.insert(key, child);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collapse_single_key_objects(item, mode);
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,98 @@
+//! Hashes of the converted output for deduplication of documents.
+
+use std::io::{self, Write};
+
+use crate::{parse_document, prepare_input, xml_document_to_borrowed_json, Config, Error};
+
+/// The hash function of `xml_content_hash`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HashAlgo {
+    /// 64-bit FNV-1a, fast and without dependencies, but too short to rule out collisions in large corpora.
+    Fnv1a64,
+    /// SHA-256, requires the `sha2` feature.
+    #[cfg(feature = "sha2")]
+    Sha256,
+    /// BLAKE3 with a 256-bit output, requires the `blake3` feature.
+    #[cfg(feature = "blake3")]
+    Blake3,
+}
+
+/// Converts the document with `config` and returns the hash of the result as lowercase hex digits.
+/// Documents that convert into the same JSON get the same hash, e.g. if they only differ in the order
+/// of attributes, in whitespace or in comments. The hash is computed from the compact JSON with keys in
+/// sorted order, which is streamed into the hash function, so no `serde_json::Value` or JSON string is built.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_content_hash, Config, HashAlgo};
+///
+/// let config = Config::new_with_defaults();
+/// let a = xml_content_hash(r#"<a x="1" y="2"><b>text</b></a>"#, &config, HashAlgo::Fnv1a64).unwrap();
+/// let b = xml_content_hash("<a y='2' x='1'>\n  <b>text</b><!-- note -->\n</a>", &config, HashAlgo::Fnv1a64).unwrap();
+/// assert_eq!(a, b);
+/// assert_eq!(a.len(), 16);
+/// ```
+pub fn xml_content_hash(xml: &str, config: &Config, algo: HashAlgo) -> Result<String, Error> {
+    let xml = prepare_input(xml, config)?;
+    let doc = parse_document(&xml, config)?;
+    let value = xml_document_to_borrowed_json(&doc, config)?;
+    let mut hasher = Hasher::new(algo);
+    serde_json::to_writer(&mut hasher, &value).map_err(|e| Error::Serialize(e.to_string()))?;
+    Ok(hasher.finish())
+}
+
+enum Hasher {
+    Fnv1a64(u64),
+    #[cfg(feature = "sha2")]
+    Sha256(sha2::Sha256),
+    #[cfg(feature = "blake3")]
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    fn new(algo: HashAlgo) -> Self {
+        match algo {
+            HashAlgo::Fnv1a64 => Hasher::Fnv1a64(0xcbf29ce484222325),
+            #[cfg(feature = "sha2")]
+            HashAlgo::Sha256 => Hasher::Sha256(<sha2::Sha256 as sha2::Digest>::new()),
+            #[cfg(feature = "blake3")]
+            HashAlgo::Blake3 => Hasher::Blake3(Box::default()),
+        }
+    }
+
+    /// Returns the hash as lowercase hex digits.
+    fn finish(self) -> String {
+        match self {
+            Hasher::Fnv1a64(hash) => format!("{:016x}", hash),
+            #[cfg(feature = "sha2")]
+            Hasher::Sha256(hasher) => sha2::Digest::finalize(hasher)
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect(),
+            #[cfg(feature = "blake3")]
+            Hasher::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+        }
+    }
+}
+
+impl Write for Hasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Hasher::Fnv1a64(hash) => {
+                for b in buf {
+                    *hash = (*hash ^ u64::from(*b)).wrapping_mul(0x100000001b3);
+                }
+            }
+            #[cfg(feature = "sha2")]
+            Hasher::Sha256(hasher) => sha2::Digest::update(hasher, buf),
+            #[cfg(feature = "blake3")]
+            Hasher::Blake3(hasher) => {
+                hasher.update(buf);
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,290 @@
+//! An index of the `xml:id` values of a document, see `xml_str_to_json_with_id_index`, and the
+//! resolution of IDREFs with it, see `Config.idref_paths`.
+
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{
+    element_key, key_name, parse_document, prepare_input, xml_to_map, Config, Context,
+    DiagnosticKind, Error, IdRef, NullValue,
+};
+
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Converts the given XML string into `serde::Value` just like `xml_str_to_json` and also returns
+/// a map of all `xml:id` values to the JSON Pointer of the converted element, e.g.
+/// `<a><b xml:id="x"/><b xml:id="y"/></a>` gives `{"x":"/a/b/0","y":"/a/b/1"}`.
+/// Elements that are not part of the output, like children of an element that starts with text,
+/// are not in the index. If an ID is used twice, the first element wins.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_str_to_json_with_id_index, Config};
+/// use serde_json::json;
+///
+/// let xml = r#"<doc><section xml:id="intro"><p>Hi</p></section><section xml:id="end"/></doc>"#;
+/// let (json, index) = xml_str_to_json_with_id_index(xml, &Config::new_with_defaults()).unwrap();
+/// assert_eq!(index["end"], "/doc/section/1");
+/// assert_eq!(json.pointer(&index["intro"]).unwrap()["p"], "Hi");
+/// ```
+pub fn xml_str_to_json_with_id_index(
+    xml: &str,
+    config: &Config,
+) -> Result<(Value, BTreeMap<String, String>), Error> {
+    let xml = prepare_input(xml, config)?;
+    let doc = parse_document(&xml, config)?;
+    let json = xml_to_map(&mut Context::new(config, None), &doc)?;
+    let mut index = BTreeMap::new();
+    visit_converted(config, &doc, &json, |el, pointer, _| {
+        if let Some(id) = el.attribute((XML_NAMESPACE, "id")) {
+            index
+                .entry(id.trim().to_owned())
+                .or_insert_with(|| pointer.to_owned());
+        }
+    });
+    Ok((json, index))
+}
+
+/// Calls `f` with every element of `doc` that is part of the converted `json`, the JSON Pointer
+/// of its value and its path, in document order.
+pub(crate) fn visit_converted<'a, 'input>(
+    config: &Config,
+    doc: &'a roxmltree::Document<'input>,
+    json: &Value,
+    mut f: impl FnMut(roxmltree::Node<'a, 'input>, &str, &str),
+) {
+    let root = doc.root_element();
+    let key = element_key(config, &root);
+    if let Some(value) = json.get(key.as_ref()) {
+        let mut pointer = String::from("/");
+        push_escaped(&mut pointer, &key);
+        let mut path = ["/", &key].concat();
+        visit_element(config, root, value, &mut pointer, &mut path, &mut f);
+    }
+}
+
+fn visit_element<'a, 'input>(
+    config: &Config,
+    el: roxmltree::Node<'a, 'input>,
+    value: &Value,
+    pointer: &mut String,
+    path: &mut String,
+    f: &mut impl FnMut(roxmltree::Node<'a, 'input>, &str, &str),
+) {
+    f(el, pointer, path);
+    let Value::Object(data) = value else {
+        return;
+    };
+
+    // ignored children have no place in the arrays, so the positions are counted without them
+    let children: Vec<_> = el
+        .children()
+        .filter(|c| c.is_element() && !converts_to_nothing(config, c))
+        .map(|c| (element_key(config, &c), c))
+        .collect();
+    let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+    for (key, _) in &children {
+        counts.entry(key.as_ref()).or_default().1 += 1;
+    }
+
+    let (pointer_len, path_len) = (pointer.len(), path.len());
+    for (key, child) in &children {
+        let (position, count) = counts.get_mut(key.as_ref()).expect("counted above");
+        let index = *position;
+        *position += 1;
+        pointer.push('/');
+        push_escaped(pointer, key);
+        let child_value = match data.get(key.as_ref()) {
+            // an attribute with the same key comes first
+            Some(Value::Array(values)) if values.len() >= *count => {
+                let index = values.len() - *count + index;
+                pointer.push('/');
+                pointer.push_str(&index.to_string());
+                values.get(index)
+            }
+            Some(value) if *count == 1 => Some(value),
+            _ => None,
+        };
+        if let Some(child_value) = child_value {
+            path.push('/');
+            path.push_str(key);
+            visit_element(config, *child, child_value, pointer, path, f);
+            path.truncate(path_len);
+        }
+        pointer.truncate(pointer_len);
+    }
+}
+
+/// An attribute at one of the paths of `Config.idref_paths`.
+struct Reference<'a> {
+    /// The position of its element in document order.
+    node: usize,
+    path: String,
+    pointer: String,
+    value: &'a str,
+    idref: IdRef,
+}
+
+/// The elements with an `xml:id` and the references to them in the converted document.
+struct References<'a> {
+    /// The JSON Pointers and the positions in document order of the elements by their IDs.
+    ids: HashMap<&'a str, (String, usize, usize)>,
+    /// Sorted by the position of their elements.
+    references: Vec<Reference<'a>>,
+    /// The converted document before any reference was resolved, to copy elements from.
+    original: Value,
+    inlined: HashMap<&'a str, Option<Value>>,
+    inlining: HashSet<&'a str>,
+}
+
+/// Replaces the IDREFs at the paths of `Config.idref_paths` in the converted document `json`.
+pub(crate) fn resolve_idrefs(
+    ctx: &mut Context,
+    doc: &roxmltree::Document,
+    json: &mut Value,
+) -> Result<(), Error> {
+    let config = ctx.config;
+    let attr_prefix = config.xml_attr_prefix.as_str();
+    let mut ids = HashMap::new();
+    let mut references = Vec::new();
+    visit_converted(config, doc, json, |el, pointer, path| {
+        let node = el.id().get_usize();
+        if let Some(id) = el.attribute((XML_NAMESPACE, "id")) {
+            let last = el
+                .descendants()
+                .next_back()
+                .map_or(node, |n| n.id().get_usize());
+            ids.entry(id.trim())
+                .or_insert_with(|| (pointer.to_owned(), node, last));
+        }
+        for attr in el.attributes() {
+            let name = key_name(config, attr.namespace(), attr.name());
+            let attr_path = [path, "/@", &name].concat();
+            if let Some(idref) = config.idref_paths.get(&attr_path) {
+                let mut attr_pointer = [pointer, "/"].concat();
+                push_escaped(&mut attr_pointer, &[attr_prefix, &name].concat());
+                references.push(Reference {
+                    node,
+                    path: attr_path,
+                    pointer: attr_pointer,
+                    value: attr.value(),
+                    idref: *idref,
+                });
+            }
+        }
+    });
+
+    for reference in &references {
+        for id in reference.value.split_ascii_whitespace() {
+            if !ids.contains_key(id) {
+                ctx.report(&reference.path, || {
+                    DiagnosticKind::UnresolvedReference(id.to_owned())
+                })?;
+            }
+        }
+    }
+
+    let inline = references
+        .iter()
+        .any(|r| matches!(r.idref, IdRef::Inline | IdRef::InlineList));
+    let mut resolver = References {
+        ids,
+        references,
+        original: if inline { json.clone() } else { Value::Null },
+        inlined: HashMap::new(),
+        inlining: HashSet::new(),
+    };
+    for i in 0..resolver.references.len() {
+        if let Some(value) = resolver.resolve(i) {
+            let pointer = &resolver.references[i].pointer;
+            // an attribute and a child element with the same key are merged into an array
+            if let Some(target) = json.pointer_mut(pointer).filter(|t| !t.is_array()) {
+                *target = value;
+            }
+        }
+    }
+    Ok(())
+}
+
+impl<'a> References<'a> {
+    /// Returns the replacement of the reference at `index`, or `None` if it is kept.
+    fn resolve(&mut self, index: usize) -> Option<Value> {
+        let Reference { value, idref, .. } = self.references[index];
+        match idref {
+            IdRef::Inline => self.inline(value.trim()),
+            IdRef::Pointer => self.pointer(value.trim()),
+            IdRef::InlineList => Some(Value::Array(
+                value
+                    .split_ascii_whitespace()
+                    .map(|id| self.inline(id).unwrap_or_else(|| id.into()))
+                    .collect(),
+            )),
+            IdRef::PointerList => Some(Value::Array(
+                value
+                    .split_ascii_whitespace()
+                    .map(|id| self.pointer(id).unwrap_or_else(|| id.into()))
+                    .collect(),
+            )),
+        }
+    }
+
+    fn pointer(&self, id: &str) -> Option<Value> {
+        self.ids
+            .get(id)
+            .map(|(pointer, _, _)| pointer.as_str().into())
+    }
+
+    /// Returns a copy of the converted element with the ID `id`, with the references inside resolved.
+    fn inline(&mut self, id: &'a str) -> Option<Value> {
+        if let Some(value) = self.inlined.get(id) {
+            return value.clone();
+        }
+        let (pointer, first, last) = self.ids.get(id)?.clone();
+        // an element can't contain a copy of itself
+        if !self.inlining.insert(id) {
+            return None;
+        }
+        let mut value = self.original.pointer(&pointer).cloned();
+        if let Some(value) = value.as_mut() {
+            // the references of the element and its descendants
+            let start = self.references.partition_point(|r| r.node < first);
+            let end = self.references.partition_point(|r| r.node <= last);
+            for i in start..end {
+                let relative = self.references[i]
+                    .pointer
+                    .strip_prefix(pointer.as_str())
+                    .map(str::to_owned);
+                if let (Some(relative), Some(replacement)) = (relative, self.resolve(i)) {
+                    if let Some(target) = value.pointer_mut(&relative).filter(|t| !t.is_array()) {
+                        *target = replacement;
+                    }
+                }
+            }
+        }
+        self.inlining.remove(id);
+        self.inlined.insert(id, value.clone());
+        value
+    }
+}
+
+/// Returns `true` if the element is left out of its parent because it is empty and
+/// `Config.empty_element_handling` is `NullValue::Ignore`.
+fn converts_to_nothing(config: &Config, el: &roxmltree::Node) -> bool {
+    matches!(config.empty_element_handling, NullValue::Ignore)
+        && el.attributes().len() == 0
+        && el.text().is_none_or(|text| text.trim().is_empty())
+        && el
+            .children()
+            .filter(|c| c.is_element())
+            .all(|c| converts_to_nothing(config, &c))
+}
+
+/// Appends a JSON Pointer segment with `~` and `/` escaped.
+pub(crate) fn push_escaped(pointer: &mut String, segment: &str) {
+    for c in segment.chars() {
+        match c {
+            '~' => pointer.push_str("~0"),
+            '/' => pointer.push_str("~1"),
+            c => pointer.push(c),
+        }
+    }
+}
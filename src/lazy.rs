@@ -0,0 +1,101 @@
+//! A parsed document that is converted into JSON piece by piece, as its parts are requested.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::pointer::{pointer_get, pointer_value, segments};
+use crate::{
+    parse_document, prepare_input, repeated_names, ArrayInference, Config, Context, Error,
+};
+
+/// An XML document that is parsed once, but converted into JSON only as far as the requested
+/// JSON Pointers need it. Every result is cached, so requesting the same pointer again, or a pointer
+/// into an already requested value, doesn't convert anything.
+///
+/// The values are the same as `xml_str_to_json(xml, config)?.pointer(pointer)` would return,
+/// see `xml_str_to_json_pointers` for the pointer syntax. Like there, the steps that need the whole
+/// converted document are skipped, so the values differ from `xml_str_to_json` if any of these are set:
+/// `Config.post_process`, `Config.prune_empty`, `Config.single_key_objects`, `Config.idref_paths` and
+/// `Config.xsd_validation`.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{Config, LazyXmlJson};
+/// use serde_json::json;
+///
+/// let config = Config::new_with_defaults();
+/// let xml = r#"<order id="7"><customer><name>Ann</name></customer><items>..</items></order>"#;
+/// let mut lazy = LazyXmlJson::new(xml, &config).unwrap();
+/// assert_eq!(lazy.get("/order/@id").unwrap(), Some(&json!(7)));
+/// assert_eq!(lazy.get("/order/customer").unwrap(), Some(&json!({"name": "Ann"})));
+/// // answered from the cached customer
+/// assert_eq!(lazy.get("/order/customer/name").unwrap(), Some(&json!("Ann")));
+/// ```
+pub struct LazyXmlJson<'input, 'c> {
+    // declared before `_text`, so it's dropped before the text it may borrow
+    doc: roxmltree::Document<'input>,
+    /// The input after `prepare_input`, if that had to copy it, e.g. to insert external entities.
+    _text: Option<String>,
+    config: &'c Config,
+    repeated_names: Arc<HashSet<String>>,
+    cache: HashMap<String, Option<Value>>,
+}
+
+impl<'input, 'c> LazyXmlJson<'input, 'c> {
+    /// Parses the document without converting any of it.
+    pub fn new(xml: &'input str, config: &'c Config) -> Result<Self, Error> {
+        let (doc, _text) = match prepare_input(xml, config)? {
+            Cow::Borrowed(xml) => (parse_document(xml, config)?, None),
+            Cow::Owned(xml) => {
+                // SAFETY: the heap buffer of the string doesn't move when the string is moved into
+                // the struct, and it lives as long as `doc`, which is dropped first. No references
+                // into the document or its text are handed out, so `'input` never outlives the buffer.
+                let text: &'input str = unsafe { &*(xml.as_str() as *const str) };
+                (parse_document(text, config)?, Some(xml))
+            }
+        };
+        let repeated_names = if config.array_inference == ArrayInference::DocumentWide {
+            repeated_names(&doc, config)
+        } else {
+            Arc::default()
+        };
+        Ok(LazyXmlJson {
+            doc,
+            _text,
+            config,
+            repeated_names,
+            cache: HashMap::new(),
+        })
+    }
+
+    /// Returns the value that the JSON Pointer selects, or `None` if it selects nothing.
+    /// Only the selected part of the document is converted the first time it's requested.
+    pub fn get(&mut self, pointer: &str) -> Result<Option<&Value>, Error> {
+        if !self.cache.contains_key(pointer) {
+            let value = match self.cached_ancestor(pointer) {
+                Some((ancestor, rest)) => pointer_get(ancestor.clone(), &segments(rest)),
+                None => {
+                    let mut ctx = Context::new(self.config, None);
+                    ctx.repeated_names = self.repeated_names.clone();
+                    pointer_value(&mut ctx, self.doc.root_element(), pointer)?
+                }
+            };
+            self.cache.insert(pointer.to_owned(), value);
+        }
+        Ok(self.cache[pointer].as_ref())
+    }
+
+    /// Returns the nearest cached value that contains the value of `pointer`, and the rest of the pointer
+    /// relative to it, without the leading `/`.
+    fn cached_ancestor<'p>(&self, pointer: &'p str) -> Option<(&Value, &'p str)> {
+        pointer
+            .rmatch_indices('/')
+            .map(|(i, _)| (&pointer[..i], &pointer[i + 1..]))
+            .find_map(|(prefix, rest)| match self.cache.get(prefix) {
+                Some(Some(value)) => Some((value, rest)),
+                _ => None,
+            })
+    }
+}
@@ -57,39 +57,332 @@
 
 extern crate roxmltree;
 extern crate serde_json;
+// the code that `#[xml]` generates refers to this crate by name, also in its own tests
+#[cfg(all(test, feature = "derive"))]
+extern crate self as roxmltree_to_serde;
 
 #[cfg(feature = "regex_path")]
 extern crate regex;
 
-use serde_json::{Map, Number, Value};
 #[cfg(feature = "json_types")]
-use std::collections::HashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde_json::{Map, Number, Value};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 #[cfg(feature = "regex_path")]
 use regex::Regex;
 
+#[cfg(feature = "bumpalo")]
+mod arena;
+#[cfg(feature = "arrow")]
+mod arrow;
+#[cfg(feature = "tokio")]
+mod async_io;
+#[cfg(feature = "avro")]
+mod avro;
+mod batch;
+mod borrowed;
+#[cfg(feature = "bson")]
+mod bson;
+#[cfg(feature = "capi")]
+pub mod capi;
+mod channel;
+mod compat;
+mod config_json;
+mod csv;
+mod de;
+mod diagnostics;
+mod diff;
+#[cfg(feature = "encoding")]
+mod encoding;
+mod entities;
+mod error;
+mod flatten;
+mod hash;
+mod ids;
+mod lazy;
+mod lossless;
+#[cfg(feature = "msgpack")]
+mod msgpack;
+mod node_filter;
+mod pointer;
+mod post_process;
+#[cfg(feature = "json_types")]
+pub mod presets;
+mod progress;
+mod prune;
+#[cfg(feature = "pseudonymize")]
+mod pseudonymize;
+#[cfg(feature = "python")]
+mod python;
+mod query;
+mod records;
+mod roundtrip;
+mod schema;
+#[cfg(feature = "simd-json")]
+mod simd;
+mod sorted;
+mod split;
 #[cfg(test)]
+#[allow(clippy::unnecessary_get_then_check)]
 mod tests;
+#[cfg(feature = "toml")]
+mod toml;
+mod trace;
+mod units;
+#[cfg(feature = "json_types")]
+mod usage;
+mod validate;
+mod visitor;
+#[cfg(feature = "wasm")]
+mod wasm;
+mod writer;
+mod xml_base;
+#[cfg(feature = "xsd")]
+mod xsd;
+#[cfg(feature = "yaml")]
+mod yaml;
+
+#[cfg(feature = "bumpalo")]
+pub use arena::{xml_str_to_arena_json, ArenaValue};
+#[cfg(feature = "parquet")]
+pub use arrow::record_batch_to_parquet;
+#[cfg(feature = "arrow")]
+pub use arrow::xml_records_to_arrow;
+#[cfg(feature = "tokio")]
+pub use async_io::{xml_async_read_to_json, xml_stream_to_json};
+#[cfg(feature = "avro")]
+pub use avro::xml_records_to_avro;
+pub use batch::xml_batch_to_json;
+#[cfg(feature = "rayon")]
+pub use batch::xml_batch_to_json_parallel;
+pub use borrowed::{
+    xml_document_to_borrowed_json, xml_document_to_borrowed_json_with_cache,
+    xml_document_to_borrowed_json_with_diagnostics, xml_to_serializer, BorrowedValue, KeyCache,
+};
+#[cfg(feature = "bson")]
+pub use bson::{xml_str_to_bson, BsonConfig};
+pub use channel::xml_records_channel;
+pub use compat::{
+    newtonsoft_to_json, x2js_to_json, xml2js_to_json, xmltodict_to_json, NewtonsoftOptions,
+    X2jsArrayAccessForm, X2jsEmptyNodeForm, X2jsOptions, Xml2jsOptions, XmltodictOptions,
+};
+pub use csv::xml_records_to_csv;
+pub use diagnostics::{Diagnostic, DiagnosticKind, Diagnostics};
+pub use diff::xml_diff;
+#[cfg(feature = "encoding")]
+pub use encoding::decode_xml;
+pub use entities::{EntityCatalog, EntityResolver};
+pub use error::Error;
+pub use flatten::{xml_str_to_flat_json, FlattenArrays, FlattenConfig};
+pub use hash::{xml_content_hash, HashAlgo};
+pub use ids::xml_str_to_json_with_id_index;
+pub use lazy::LazyXmlJson;
+pub use lossless::{lossless_json_to_xml, xml_str_to_lossless_json};
+#[cfg(feature = "msgpack")]
+pub use msgpack::xml_str_to_msgpack;
+pub use node_filter::NodeFilter;
+pub use pointer::xml_str_to_json_pointers;
+pub use post_process::PostProcess;
+pub use progress::{Progress, ProgressCallback};
+pub use prune::PruneEmpty;
+#[cfg(feature = "pseudonymize")]
+pub use pseudonymize::PseudonymizationKey;
+pub use query::xml_query;
+pub use records::{for_each_match, XmlJsonIter, XmlRecords, XmlTypedRecords};
+pub use roundtrip::{verify_roundtrip, RoundtripReport};
+#[cfg(feature = "derive")]
+pub use roxmltree_to_serde_derive::xml;
+pub use schema::{infer_json_schema, json_schema_for_values};
+#[cfg(feature = "simd-json")]
+pub use simd::{xml_str_to_simd_json, xml_str_to_simd_json_string};
+pub use sorted::{compare_keys, xml_str_to_json_string, SortedKeys};
+pub use split::{xml_split_records, xml_split_records_to_files, SplitConfig};
+#[cfg(feature = "toml")]
+pub use toml::{xml_str_to_toml, xml_str_to_toml_string, TomlConfig, TomlMixedArray, TomlNull};
+pub use trace::{xml_str_to_json_with_trace, AppliedOverride, TraceEntry};
+pub use units::UnitTable;
+#[cfg(feature = "json_types")]
+pub use usage::{OverrideReport, OverrideUsage};
+pub use validate::{ConfigWarning, ConfigWarningKind};
+pub use visitor::{xml_str_to_visitor, JsonBuilder, XmlVisitor};
+#[cfg(feature = "wasm")]
+pub use wasm::xml_to_json;
+pub use writer::{json_to_xml_string, json_to_xml_writer, XmlWriterConfig};
+#[cfg(feature = "xsd")]
+pub use xsd::{XsdSchema, XsdValidation};
+#[cfg(feature = "yaml")]
+pub use yaml::{xml_str_to_yaml, xml_str_to_yaml_string};
+
+/// Items used by the code that `#[xml]` generates, not part of the public API.
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::de::{deserialize_path, Untagged};
+}
 
 /// Defines how empty elements like `<x />` should be handled.
 /// `Ignore` -> exclude from JSON, `Null` -> `"x":null`, EmptyObject -> `"x":{}`.
 /// `EmptyObject` is the default option and is how it was handled prior to v.0.4
 /// Using `Ignore` on an XML document with an empty root element falls back to `Null` option.
 /// E.g. both `<a><x/></a>` and `<a/>` are converted into `{"a":null}`.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum NullValue {
     Ignore,
     Null,
     EmptyObject,
 }
 
+/// Defines when elements without a `JsonArray::Always` override are converted into JSON arrays.
+/// * `PerParent` - only if the element repeats within its parent. E.g. `<a><r><b>1</b></r><r><b>1</b><b>2</b></r></a>`
+///   becomes `{"a":{"r":[{"b":1},{"b":[1,2]}]}}`. This is the default.
+/// * `DocumentWide` - the document is scanned first and elements with a name that repeats within any parent
+///   are converted into arrays everywhere, so the same document becomes `{"a":{"r":[{"b":[1]},{"b":[1,2]}]}}`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArrayInference {
+    PerParent,
+    DocumentWide,
+}
+
+/// Defines which values are emitted together with the verbatim text they were converted from.
+/// The value is then replaced with an object of the converted value and the original text,
+/// e.g. `<a>007</a>` becomes `{"a":{"value":7,"raw":"007"}}`.
+/// * `Never` - emit converted values only. This is the default.
+/// * `Always` - wrap all element text and attribute values.
+/// * `Paths` - wrap values at the listed absolute paths only, e.g. `/a` or `/a/@id`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RawValues {
+    Never,
+    Always,
+    Paths(HashSet<String>),
+}
+
+impl RawValues {
+    fn applies_to(&self, path: &str) -> bool {
+        match self {
+            RawValues::Never => false,
+            RawValues::Always => true,
+            RawValues::Paths(paths) => paths.contains(path),
+        }
+    }
+}
+
+/// Defines what happens to a value that can't be converted into the JSON type enforced for its node,
+/// e.g. `abc` for `JsonType::AlwaysNumber` or `maybe` for `JsonType::Bool(vec!["yes"])`.
+/// Values that are not listed as `true` for `JsonType::Bool`, but are conventional representations
+/// of `false` (`false`, `0`, `no`, `off` or an empty string in any case) are not considered failures.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CoercionFailurePolicy {
+    /// Use the fallback of the JSON type: `false` for `Bool` and the original string for `AlwaysNumber`.
+    /// This is the default and is how it was handled prior to v.0.7
+    Fallback,
+    /// Keep the original text as a JSON string.
+    KeepString,
+    /// Replace the value with JSON `null`.
+    Null,
+    /// Fail the conversion with `Error::Coercion` pointing at the path of the value.
+    Error,
+}
+
+/// Defines how elements in a default namespace, i.e. declared with `xmlns="..."` and written without
+/// a prefix, are converted. Elements with a prefix are not affected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DefaultNamespace {
+    /// Treat them like elements without a namespace, e.g. `<a xmlns="urn:d"/>` becomes `{"a":{}}`,
+    /// indistinguishable from `<a/>`. This is the default.
+    Ignore,
+    /// Emit their names in Clark notation, e.g. `<a xmlns="urn:d"/>` becomes `{"{urn:d}a":{}}`, unless
+    /// the namespace has a prefix in `Config.namespace_prefixes`.
+    IncludeUri,
+    /// Fail the conversion with `Error::UnexpectedXml` at the first one.
+    Error,
+}
+
+/// Defines how the IDREF attributes at the paths of `Config.idref_paths` are resolved.
+/// The referenced element is the one with the same `xml:id`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IdRef {
+    /// Replace the IDREF with a copy of the converted element, e.g. `<a><b xml:id="x">1</b><c ref="x"/></a>`
+    /// becomes `{"a":{"b":{"@id":"x","#text":1},"c":{"@ref":{"@id":"x","#text":1}}}}`. References inside the
+    /// copy are resolved as well, unless they lead back to an element that is being copied.
+    Inline,
+    /// Replace the IDREF with the JSON Pointer of the converted element, e.g. `"/a/b"`.
+    Pointer,
+    /// Like `Inline` for an IDREFS attribute with a whitespace separated list of IDs, which becomes an array.
+    InlineList,
+    /// Like `Pointer` for an IDREFS attribute with a whitespace separated list of IDs, which becomes an array.
+    PointerList,
+}
+
+/// What is appended to an array that was truncated by `Config.array_limits`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TruncationMarker {
+    /// Nothing, the array ends with the kept items.
+    None,
+    /// The number of dropped items, e.g. `[{"n":1},{"n":2},499998]`.
+    Count,
+    /// The given string, e.g. `"..."`.
+    Text(String),
+}
+
+/// The maximum number of items of the arrays at a path, see `Config.array_limits`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArrayLimit {
+    /// The number of items that are kept.
+    pub max_items: usize,
+    /// What is appended to the kept items if there were more.
+    pub marker: TruncationMarker,
+}
+
+/// Defines which attributes of elements with text are converted, see `Config.text_attributes`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextAttributes {
+    /// Convert all attributes into an object with the text. This is the default.
+    Keep,
+    /// Drop all attributes, so the element becomes its text, e.g. `<price currency="EUR">5</price>` becomes
+    /// `"price": 5`.
+    Drop,
+    /// Convert only the attributes with these names as in the keys, but without `Config.xml_attr_prefix`,
+    /// e.g. `lang`, or `v:lang` with `Config.namespace_prefixes`. Elements without any of them become their text.
+    KeepOnly(HashSet<String>),
+}
+
+/// Defines how objects with a single property are collapsed after the conversion, see
+/// `Config.single_key_objects`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SingleKeyObjects {
+    /// Keep them as they are. This is the default.
+    Keep,
+    /// Join chains of objects with a single property into one key with the given separator, e.g.
+    /// `{"a":{"b":{"c":1}}}` becomes `{"a.b.c":1}` for `.`. A joined key replaces a property of the
+    /// same name.
+    Join(String),
+    /// Replace wrappers whose only property is an object or an array with that value, e.g.
+    /// `{"a":{"b":{"c":1}}}` becomes `{"a":{"c":1}}` and `{"orders":{"order":[1,2]}}` becomes
+    /// `{"orders":[1,2]}`. Wrappers of a single scalar value are kept, so their name isn't lost.
+    Hoist,
+}
+
+/// Defines how byte sequences that are not valid UTF-8 are handled by `xml_bytes_to_json`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Utf8Policy {
+    /// Fail the conversion with `Error::Encoding`. This is the default.
+    Strict,
+    /// Replace every invalid sequence with U+FFFD REPLACEMENT CHARACTER and convert the rest of the document,
+    /// e.g. a single corrupted character of a large feed only affects the text it's in.
+    Lossy,
+}
+
 /// Defines how the values of this Node should be converted into a JSON array with the underlying types.
 /// * `Infer` - the nodes are converted into a JSON array only if there are multiple identical elements.
-/// E.g. `<a><b>1</b></a>` becomes a map `{"a": {"b": 1 }}` and `<a><b>1</b><b>2</b><b>3</b></a>` becomes
-/// an array `{"a": {"b": [1, 2, 3] }}`
+///   E.g. `<a><b>1</b></a>` becomes a map `{"a": {"b": 1 }}` and `<a><b>1</b><b>2</b><b>3</b></a>` becomes
+///   an array `{"a": {"b": [1, 2, 3] }}`
 /// * `Always` - the nodes are converted into a JSON array regardless of how many there are.
-/// E.g. `<a><b>1</b></a>` becomes an array with a single value `{"a": {"b": [1] }}` and
-/// `<a><b>1</b><b>2</b><b>3</b></a>` also becomes an array `{"a": {"b": [1, 2, 3] }}`
+///   E.g. `<a><b>1</b></a>` becomes an array with a single value `{"a": {"b": [1] }}` and
+///   `<a><b>1</b><b>2</b><b>3</b></a>` also becomes an array `{"a": {"b": [1, 2, 3] }}`
 #[derive(Debug)]
 pub enum JsonArray {
     /// Convert the nodes into a JSON array even if there is only one element
@@ -98,8 +391,8 @@ pub enum JsonArray {
     Infer(JsonType),
 }
 
-/// Used as a parameter for `Config.add_json_type_override`. Defines how the XML path should be matched
-/// in order to apply the JSON type overriding rules. This enumerator exists to allow the same function
+/// Used as a parameter for `Config.add_json_type_override` and `for_each_match`. Defines how the XML path
+/// should be matched in order to apply the JSON type overriding rules or to select elements. This enumerator exists to allow the same function
 /// to be used for multiple different types of path matching rules.
 #[derive(Debug)]
 pub enum PathMatcher {
@@ -146,6 +439,9 @@ pub enum JsonType {
     /// Convert values included in this member into JSON bool `true` and any other value into `false`.
     /// E.g. `Bool(vec!["True", "true", "TRUE"]) will result in any of these values to become JSON bool `true`.
     Bool(Vec<&'static str>),
+    /// Convert the value into a JSON number regardless of leading zeros, e.g. `<a>007</a>` into `{"a":7}`.
+    /// Values that are not numbers are handled according to `Config.coercion_failure_policy`.
+    AlwaysNumber,
     /// Attempt to infer the type by looking at the single value of the node being converted.
     /// Not guaranteed to be consistent across multiple nodes.
     /// E.g. convert `<a>1234</a>` and `<a>001234</a>` into `{"a":1234}`, or `<a>true</a>` into `{"a":true}`
@@ -172,6 +468,21 @@ pub struct Config {
     /// name of the element. E.g. `<x>Goodbye!</x>` becomes `{"x":"Goodbye!"}`
     /// Defaults to `#text`
     pub xml_text_node_prop_name: String,
+    /// A map of absolute element paths, e.g. `/station/measurement`, to the property name of their text, which
+    /// replaces `Config.xml_text_node_prop_name` for these elements, e.g. for schemas that expect different names
+    /// in different parts of the document. `json_to_xml_writer` reads the text from the same names.
+    /// Defaults to an empty map.
+    pub text_prop_names: HashMap<String, String>,
+    /// The attributes of elements with text that are converted, e.g. to drop metadata that nobody needs,
+    /// so the elements become plain values instead of objects with the text and the attributes. Elements
+    /// without text and their attributes are not affected. See `TextAttributes`.
+    /// Defaults to `TextAttributes::Keep`.
+    pub text_attributes: TextAttributes,
+    /// Convert elements with a single attribute and without text or child elements into the value of the
+    /// attribute, e.g. `<flag value="3"/>` into `"flag": 3` instead of `"flag": {"@value": 3}`, for config dialects
+    /// that put every setting into an attribute. JSON type overrides of the attribute path apply to the value.
+    /// Defaults to `false`.
+    pub collapse_single_attributes: bool,
     /// Defines how empty elements like `<x />` should be handled.
     pub empty_element_handling: NullValue,
     /// A map of XML paths with their JsonArray overrides. They take precedence over the document-wide `json_type`
@@ -182,12 +493,208 @@ pub struct Config {
     /// - **XML**: `<a><b c="123">007</b></a>`
     /// - path for `c`: `/a/b/@c`
     /// - path for `b` text node (007): `/a/b`
+    ///
+    /// The map uses the FxHash algorithm, because the path of every node is looked up and the paths
+    /// don't need protection against collision attacks. Create an empty map with `Default::default()`.
     #[cfg(feature = "json_types")]
-    pub json_type_overrides: HashMap<String, JsonArray>,
+    pub json_type_overrides: FxHashMap<String, JsonArray>,
     /// A list of pairs of regex and JsonArray overrides. They take precedence over both the document-wide `json_type`
     /// property and the `json_type_overrides` property. The path syntax is based on xPath just like `json_type_overrides`.
     #[cfg(feature = "regex_path")]
     pub json_regex_type_overrides: Vec<(Regex, JsonArray)>,
+    /// A set of absolute XML paths of elements that are converted into JSON `null` if they have
+    /// the `xsi:nil="true"` attribute, e.g. `<a><b xsi:nil="true"/></a>` becomes `{"a":{"b":null}}` for `/a/b`.
+    #[cfg(feature = "json_types")]
+    pub nillable_paths: FxHashSet<String>,
+    /// Convert all elements with the `xsi:nil="true"` attribute into JSON `null`, not only those in `nillable_paths`.
+    /// Defaults to `false`.
+    #[cfg(feature = "json_types")]
+    pub nillable_everywhere: bool,
+    /// Defines what happens to values that don't fit the JSON type enforced by an override.
+    /// Defaults to `CoercionFailurePolicy::Fallback`.
+    pub coercion_failure_policy: CoercionFailurePolicy,
+    /// Fail the conversion with `Error::Lossy` instead of silently dropping information:
+    /// mixed content, duplicate keys and processing instructions.
+    /// Namespaces and comments are still dropped without an error.
+    /// Defaults to `false`.
+    pub strict: bool,
+    /// The maximum nesting depth of XML elements. Deeper documents fail with `Error::DepthLimitExceeded`
+    /// instead of overflowing the stack, because the conversion is recursive. Elements in entity values
+    /// count at the depth of their references.
+    /// Defaults to `128`, which is also the limit `serde_json` applies when parsing the output back.
+    pub max_depth: usize,
+    /// Defines when elements are converted into JSON arrays without an explicit override.
+    /// Defaults to `ArrayInference::PerParent`.
+    pub array_inference: ArrayInference,
+    /// Defines which values keep their original text next to the converted value.
+    /// Defaults to `RawValues::Never`.
+    pub raw_values: RawValues,
+    /// Property names of the converted value and the original text for `raw_values`.
+    /// Defaults to `value` and `raw`.
+    pub raw_value_prop_names: (String, String),
+    /// A map of absolute paths of elements or attributes, e.g. `/sensor/@weight`, to the units of their
+    /// values. Values like `10.5kg` become objects with the magnitude and the unit, e.g.
+    /// `{"value": 10.5, "unit": "kg"}`, and units in the `UnitTable` are converted, e.g. `120s` into
+    /// `{"value": 120000, "unit": "ms"}`. Numbers without a unit get a `null` unit, and texts that don't start
+    /// with a number are converted as usual. The rule takes precedence over JSON type overrides.
+    /// Defaults to an empty map.
+    pub unit_paths: HashMap<String, UnitTable>,
+    /// Property names of the magnitude and the unit for `unit_paths`.
+    /// Defaults to `value` and `unit`.
+    pub unit_prop_names: (String, String),
+    /// A property name for the source location of the element that an object was converted from.
+    /// E.g. set it to `#loc` for `<a>\n  <b c="1"/>\n</a>` to become
+    /// `{"a":{"#loc":{"line":1,"col":1,"offset":0},"b":{"#loc":{"line":2,"col":3,"offset":6},"@c":1}}}`.
+    /// Lines and columns start at 1 and the offset is in bytes. Elements converted into scalar values
+    /// are not annotated.
+    /// Defaults to `None`.
+    pub location_prop_name: Option<String>,
+    /// Collect the namespace declarations of the whole document and emit them once on the root element,
+    /// as an object of prefixes and URIs under the attribute `xmlns`, with an empty prefix for the default
+    /// namespace. E.g. `<s:a xmlns:s="urn:s" xmlns="urn:d"><b/></s:a>` becomes
+    /// `{"a":{"@xmlns":{"":"urn:d","s":"urn:s"},"b":{}}}`. The keys of elements and attributes still drop
+    /// their prefixes, but QName values like `xsi:type="s:Order"` can be resolved with the table.
+    /// If a prefix is declared again with a different URI, the first declaration wins and the
+    /// other one is reported as a `KeyCollision`.
+    /// Defaults to `false`.
+    pub emit_namespace_declarations: bool,
+    /// A map of namespace URIs to the prefixes that elements and attributes in these namespaces get in
+    /// their JSON keys, no matter which prefix the document uses, e.g. `v` for `{"v:item":{"@v:id":1}}`.
+    /// Several URIs can map to the same prefix. The paths of JSON type overrides use the same names,
+    /// e.g. `/v:order/v:item/@v:id`. Names in other namespaces lose their prefix as usual.
+    /// Defaults to an empty map.
+    pub namespace_prefixes: HashMap<String, String>,
+    /// Emit the names of elements and attributes in a namespace in Clark notation, `{namespace-uri}localname`,
+    /// so the namespace is never lost, e.g. `<a:item xmlns:a="urn:a" a:id="1"/>` becomes
+    /// `{"{urn:a}item":{"@{urn:a}id":1}}`. Names in a namespace of `Config.namespace_prefixes` keep their
+    /// mapped prefix. The paths of JSON type overrides and records use the same names, e.g. `/{urn:a}item/@{urn:a}id`.
+    /// Defaults to `false`.
+    pub clark_notation: bool,
+    /// Defines how elements in a default namespace are converted, see `DefaultNamespace`.
+    /// Defaults to `DefaultNamespace::Ignore`.
+    pub default_namespace: DefaultNamespace,
+    /// Absolute paths of attributes with URI values, e.g. `/feed/link/@href`, that are resolved against the
+    /// `xml:base` in scope of their element. E.g. `<doc xml:base="http://a.org/docs/"><img src="b.png"/></doc>`
+    /// becomes `{"doc":{"@base":"http://a.org/docs/","img":{"@src":"http://a.org/docs/b.png"}}}` for `/doc/img/@src`.
+    /// Values without a base in scope are kept as they are.
+    /// Defaults to an empty set.
+    pub xml_base_paths: HashSet<String>,
+    /// The URI of the document, the outermost base that `xml:base` and the values of `Config.xml_base_paths`
+    /// are resolved against. With it, relative `xml:base` values still give absolute URIs.
+    /// Defaults to `None`.
+    pub base_uri: Option<String>,
+    /// A map of absolute attribute paths, e.g. `/order/item/@product`, to how their IDREF values are resolved,
+    /// see `IdRef`. References to IDs that are not in the output are kept and reported as `UnresolvedReference`.
+    /// Only applies to conversions into `serde_json::Value`.
+    /// Defaults to an empty map.
+    pub idref_paths: HashMap<String, IdRef>,
+    /// Absolute paths of elements that are converted into the text of all their descendants as one string,
+    /// like the DOM `textContent`, instead of an object of their children. E.g. `<p>Some <b>bold</b> text</p>`
+    /// becomes `{"p":"Some bold text"}` for `/p`. Leading and trailing whitespace is trimmed, attributes are
+    /// dropped and JSON type overrides don't apply to the element or its descendants.
+    /// Defaults to an empty set.
+    pub inner_text_paths: HashSet<String>,
+    /// Absolute paths of elements and attributes whose values are replaced with their HMAC-SHA256 with
+    /// `Config.pseudonymization_key`, as lowercase hex digits, e.g. for customer IDs that must not be in the
+    /// output but still have to be joined across documents. The values are always strings and never
+    /// paired with their original text by `Config.raw_values`. Requires the `pseudonymize` feature.
+    /// Defaults to an empty set.
+    #[cfg(feature = "pseudonymize")]
+    pub pseudonymized_paths: HashSet<String>,
+    /// The secret key of `Config.pseudonymized_paths`. Conversions that reach a pseudonymized value
+    /// without a key fail with `Error::InvalidConfig`. Requires the `pseudonymize` feature.
+    /// Defaults to `None`.
+    #[cfg(feature = "pseudonymize")]
+    pub pseudonymization_key: Option<PseudonymizationKey>,
+    /// An optional maximum number of characters of string values, e.g. for search engines with a size limit
+    /// per field. Longer texts are cut and end with `Config.string_ellipsis`, so they have exactly this length.
+    /// Keys, numbers and the original texts of `Config.raw_values` are not changed.
+    /// Defaults to `None`.
+    pub max_string_len: Option<usize>,
+    /// The end of strings that were shortened to `Config.max_string_len`, which counts towards the length.
+    /// Defaults to `…`.
+    pub string_ellipsis: String,
+    /// A map of absolute element paths, e.g. `/feed/item`, to the maximum number of items of the arrays of
+    /// these elements. The elements after the first `max_items` are not converted at all, and the marker
+    /// of the `ArrayLimit` is appended instead, e.g. for degenerate documents with hundreds of thousands of
+    /// repeated elements.
+    /// Defaults to an empty map.
+    pub array_limits: HashMap<String, ArrayLimit>,
+    /// An optional predicate that is called with every element before it is converted, for skip logic that
+    /// paths can't express, e.g. by attribute values. Elements it rejects are left out together with their
+    /// descendants, as if they weren't in the document. A rejected root element becomes `null`.
+    /// Use `Config::with_node_filter` to set it.
+    pub node_filter: Option<NodeFilter>,
+    /// Collapses objects with a single property after the conversion, e.g. for deeply wrapped documents where
+    /// most levels only contain the next one. The root object itself is never removed.
+    /// Only applies to conversions into `serde_json::Value`.
+    /// Defaults to `SingleKeyObjects::Keep`.
+    pub single_key_objects: SingleKeyObjects,
+    /// The kinds of empty values that are removed from the output after the conversion, e.g. for sparse
+    /// documents with many empty elements. Objects and arrays that become empty are removed as well, if
+    /// their kind is pruned. The root element is always kept. See `PruneEmpty`.
+    /// Only applies to conversions into `serde_json::Value`.
+    /// Defaults to `PruneEmpty::NONE`.
+    pub prune_empty: PruneEmpty,
+    /// Serialize the keys of objects in the order of `compare_keys`, with attributes next to the child
+    /// elements of the same name, instead of the order of `serde_json::Map`, which is the insertion order
+    /// with the `preserve_order` feature of `serde_json`. The output is the same for every build, e.g. for
+    /// snapshot tests and caches. Applies to `xml_str_to_json_string`, `xml_split_records_to_files`
+    /// and the command line, use `SortedKeys` to serialize other values.
+    /// Defaults to `false`.
+    pub sort_keys: bool,
+    /// An optional function that is applied to the output after all other options, before it is returned.
+    /// It gets the whole document of `xml_str_to_json` and the other conversions into `serde_json::Value`,
+    /// and every record of `XmlRecords`, `XmlJsonIter`, `for_each_match` and the record based formats, but not
+    /// the typed records of `XmlRecords::deserialize` or other outputs that aren't `serde_json::Value`.
+    /// Use `Config::with_post_process` to set it.
+    pub post_process: Option<PostProcess>,
+    /// An optional callback that supplies the content of external entities and the external DTD subset.
+    /// DTDs are only accepted if it is set, and entities it refuses fail the conversion with
+    /// `Error::ExternalEntity`. `xml_str_to_lossless_json` accepts DTDs with internal entities only.
+    /// Use `Config::with_entity_resolver` or `Config::with_entity_catalog` to set it.
+    pub entity_resolver: Option<EntityResolver>,
+    /// Skip byte order marks, whitespace and any garbage before the XML declaration, or before the first
+    /// markup if there is no declaration, instead of failing the parse, e.g. for feeds that were concatenated
+    /// by shell scripts. Skipped text other than byte order marks and whitespace is reported as `SkippedPrefix`.
+    /// Defaults to `false`.
+    pub skip_leading_junk: bool,
+    /// Defines how `xml_bytes_to_json` handles bytes that are not valid UTF-8, or not valid in the declared
+    /// encoding with the `encoding` feature. Defaults to `Utf8Policy::Strict`.
+    pub utf8_policy: Utf8Policy,
+    /// An optional callback to report the progress of long conversions.
+    /// Use `Config::with_progress_callback` to set it.
+    pub progress_callback: Option<ProgressCallback>,
+    /// An optional flag to abort the conversion from another thread. The conversion checks it for every
+    /// element and fails with `Error::Cancelled` as soon as it's set to `true`.
+    pub cancellation_flag: Option<Arc<AtomicBool>>,
+    /// An optional limit of the approximate size of the output in bytes. The conversion fails with
+    /// `Error::MemoryBudgetExceeded` as soon as the names, texts and attribute values converted so far,
+    /// plus a fixed overhead per property, exceed it. The size of the XML is a poor predictor
+    /// of the size of the output, e.g. every short attribute becomes a property, so set it for untrusted input.
+    /// Defaults to `None`.
+    pub memory_budget: Option<usize>,
+    /// An optional maximum number of matches of the path based conversions: the records of `XmlRecords`,
+    /// `XmlJsonIter`, `for_each_match`, `xml_split_records` and the record based formats like CSV, and the
+    /// values of `xml_query`. They stop as soon as the limit is reached, e.g. to look at the first record of a
+    /// large document, and the record based formats read only as much of the document as needed.
+    /// Defaults to `None`.
+    pub match_limit: Option<usize>,
+    /// An optional XML Schema to validate documents against before the conversion.
+    /// Use `Config::with_xsd_validation` to set it.
+    #[cfg(feature = "xsd")]
+    pub xsd_validation: Option<(Arc<XsdSchema>, XsdValidation)>,
+    /// Convert the child elements of the root element in parallel on the rayon thread pool and merge
+    /// them in document order. The output is the same, it only pays off for large documents with many
+    /// independent subtrees. The conversion stays sequential if there is a `progress_callback`
+    /// or a `memory_budget`.
+    /// Defaults to `false`.
+    #[cfg(feature = "rayon")]
+    pub parallel_children: bool,
+    /// An optional record of the JSON type overrides that are applied, shared by all conversions with this
+    /// config. Use `Config::with_override_usage` to set it.
+    #[cfg(feature = "json_types")]
+    pub override_usage: Option<Arc<OverrideUsage>>,
 }
 
 impl Config {
@@ -199,11 +706,60 @@ impl Config {
             leading_zero_as_string: false,
             xml_attr_prefix: "@".to_owned(),
             xml_text_node_prop_name: "#text".to_owned(),
+            text_prop_names: HashMap::new(),
+            text_attributes: TextAttributes::Keep,
+            collapse_single_attributes: false,
             empty_element_handling: NullValue::EmptyObject,
             #[cfg(feature = "json_types")]
-            json_type_overrides: HashMap::new(),
+            json_type_overrides: FxHashMap::default(),
             #[cfg(feature = "regex_path")]
             json_regex_type_overrides: Vec::new(),
+            #[cfg(feature = "json_types")]
+            nillable_paths: FxHashSet::default(),
+            #[cfg(feature = "json_types")]
+            nillable_everywhere: false,
+            coercion_failure_policy: CoercionFailurePolicy::Fallback,
+            strict: false,
+            max_depth: 128,
+            array_inference: ArrayInference::PerParent,
+            raw_values: RawValues::Never,
+            raw_value_prop_names: ("value".to_owned(), "raw".to_owned()),
+            unit_paths: HashMap::new(),
+            unit_prop_names: ("value".to_owned(), "unit".to_owned()),
+            location_prop_name: None,
+            emit_namespace_declarations: false,
+            namespace_prefixes: HashMap::new(),
+            clark_notation: false,
+            default_namespace: DefaultNamespace::Ignore,
+            xml_base_paths: HashSet::new(),
+            base_uri: None,
+            idref_paths: HashMap::new(),
+            inner_text_paths: HashSet::new(),
+            #[cfg(feature = "pseudonymize")]
+            pseudonymized_paths: HashSet::new(),
+            #[cfg(feature = "pseudonymize")]
+            pseudonymization_key: None,
+            max_string_len: None,
+            string_ellipsis: "…".to_owned(),
+            array_limits: HashMap::new(),
+            node_filter: None,
+            single_key_objects: SingleKeyObjects::Keep,
+            prune_empty: PruneEmpty::NONE,
+            sort_keys: false,
+            post_process: None,
+            entity_resolver: None,
+            skip_leading_junk: false,
+            utf8_policy: Utf8Policy::Strict,
+            progress_callback: None,
+            cancellation_flag: None,
+            memory_budget: None,
+            match_limit: None,
+            #[cfg(feature = "xsd")]
+            xsd_validation: None,
+            #[cfg(feature = "rayon")]
+            parallel_children: false,
+            #[cfg(feature = "json_types")]
+            override_usage: None,
         }
     }
 
@@ -219,10 +775,7 @@ impl Config {
             xml_attr_prefix: xml_attr_prefix.to_owned(),
             xml_text_node_prop_name: xml_text_node_prop_name.to_owned(),
             empty_element_handling,
-            #[cfg(feature = "json_types")]
-            json_type_overrides: HashMap::new(),
-            #[cfg(feature = "regex_path")]
-            json_regex_type_overrides: Vec::new(),
+            ..Config::new_with_defaults()
         }
     }
 
@@ -251,6 +804,526 @@ impl Config {
 
         conf
     }
+
+    /// Sets a function that is applied to every converted document or record. See `Config.post_process` for details.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{xml_str_to_json, Config};
+    /// use serde_json::json;
+    ///
+    /// let conf = Config::new_with_defaults().with_post_process(|mut json| {
+    ///     json["invoice"]["source"] = json!("erp");
+    ///     json
+    /// });
+    /// let json = xml_str_to_json("<invoice><total>9.5</total></invoice>", &conf).unwrap();
+    /// assert_eq!(json, json!({"invoice": {"total": 9.5, "source": "erp"}}));
+    /// ```
+    pub fn with_post_process<F>(self, f: F) -> Self
+    where
+        F: Fn(Value) -> Value + Send + Sync + 'static,
+    {
+        Config {
+            post_process: Some(PostProcess::new(f)),
+            ..self
+        }
+    }
+
+    /// Sets a callback that is invoked with the number of converted elements every `every` elements.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::Config;
+    ///
+    /// let conf = Config::new_with_defaults()
+    ///     .with_progress_callback(10_000, |p| println!("{}/{}", p.elements, p.total_elements));
+    /// ```
+    pub fn with_progress_callback<F>(self, every: usize, callback: F) -> Self
+    where
+        F: Fn(Progress) + Send + Sync + 'static,
+    {
+        Config {
+            progress_callback: Some(ProgressCallback::new(every, callback)),
+            ..self
+        }
+    }
+
+    /// Emits the original text next to the converted value for the given values.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{xml_str_to_json, Config, RawValues};
+    ///
+    /// let conf = Config::new_with_defaults().with_raw_values(RawValues::Always);
+    /// let json = xml_str_to_json("<a>007</a>", &conf).unwrap();
+    /// assert_eq!(json, serde_json::json!({"a": {"value": 7, "raw": "007"}}));
+    /// ```
+    pub fn with_raw_values(self, raw_values: RawValues) -> Self {
+        Config { raw_values, ..self }
+    }
+
+    /// Annotates every converted object with the source location of its element under the given property name.
+    /// See `Config.location_prop_name` for details.
+    pub fn with_source_locations(self, prop_name: &str) -> Self {
+        Config {
+            location_prop_name: Some(prop_name.to_owned()),
+            ..self
+        }
+    }
+
+    /// Emits the namespace declarations of the document on the root element.
+    /// See `Config.emit_namespace_declarations` for details.
+    pub fn with_namespace_declarations(self) -> Self {
+        Config {
+            emit_namespace_declarations: true,
+            ..self
+        }
+    }
+
+    /// Gives elements and attributes in the namespace `uri` the prefix `prefix` in their JSON keys.
+    /// See `Config.namespace_prefixes` for details.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{xml_str_to_json, Config};
+    /// use serde_json::json;
+    ///
+    /// let conf = Config::new_with_defaults()
+    ///     .with_namespace_prefix("urn:vendor-a", "v")
+    ///     .with_namespace_prefix("urn:vendor-b", "v");
+    /// let xml = r#"<order xmlns:a="urn:vendor-a" xmlns:b="urn:vendor-b"><a:item b:id="1"/></order>"#;
+    /// assert_eq!(xml_str_to_json(xml, &conf).unwrap(), json!({"order": {"v:item": {"@v:id": 1}}}));
+    /// ```
+    pub fn with_namespace_prefix(self, uri: &str, prefix: &str) -> Self {
+        let mut conf = self;
+        conf.namespace_prefixes
+            .insert(uri.to_owned(), prefix.to_owned());
+        conf
+    }
+
+    /// Emits the names of elements and attributes in a namespace as `{namespace-uri}localname`.
+    /// See `Config.clark_notation` for details.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{xml_str_to_json, Config};
+    /// use serde_json::json;
+    ///
+    /// let conf = Config::new_with_defaults().with_clark_notation();
+    /// let xml = r#"<order xmlns="urn:orders" xmlns:x="urn:ext"><item x:id="1"/></order>"#;
+    /// assert_eq!(
+    ///     xml_str_to_json(xml, &conf).unwrap(),
+    ///     json!({"{urn:orders}order": {"{urn:orders}item": {"@{urn:ext}id": 1}}})
+    /// );
+    /// ```
+    pub fn with_clark_notation(self) -> Self {
+        Config {
+            clark_notation: true,
+            ..self
+        }
+    }
+
+    /// Defines how elements in a default namespace are converted, see `DefaultNamespace`.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{xml_str_to_json, Config, DefaultNamespace};
+    /// use serde_json::json;
+    ///
+    /// let conf = Config::new_with_defaults().with_default_namespace(DefaultNamespace::IncludeUri);
+    /// let xml = r#"<order><item xmlns="urn:v2"/><item/></order>"#;
+    /// assert_eq!(
+    ///     xml_str_to_json(xml, &conf).unwrap(),
+    ///     json!({"order": {"{urn:v2}item": {}, "item": {}}})
+    /// );
+    /// ```
+    pub fn with_default_namespace(self, default_namespace: DefaultNamespace) -> Self {
+        Config {
+            default_namespace,
+            ..self
+        }
+    }
+
+    /// Resolves the URI values of the attribute at the absolute `path` against `xml:base`.
+    /// See `Config.xml_base_paths` for details.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{xml_str_to_json, Config};
+    /// use serde_json::json;
+    ///
+    /// let conf = Config::new_with_defaults()
+    ///     .with_base_uri("http://example.com/book/index.xml")
+    ///     .add_xml_base_path("/book/chapter/@href");
+    /// let xml = r#"<book xml:base="chapters/"><chapter href="one.xml"/></book>"#;
+    /// assert_eq!(
+    ///     xml_str_to_json(xml, &conf).unwrap(),
+    ///     json!({"book": {"@base": "chapters/", "chapter": {"@href": "http://example.com/book/chapters/one.xml"}}})
+    /// );
+    /// ```
+    pub fn add_xml_base_path(self, path: &str) -> Self {
+        let mut conf = self;
+        conf.xml_base_paths.insert(path.to_owned());
+        conf
+    }
+
+    /// Sets the URI of the document. See `Config.base_uri` for details.
+    pub fn with_base_uri(self, uri: &str) -> Self {
+        Config {
+            base_uri: Some(uri.to_owned()),
+            ..self
+        }
+    }
+
+    /// Resolves the IDREF values of the attribute at the absolute `path`. See `Config.idref_paths` for details.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{xml_str_to_json, Config, IdRef};
+    /// use serde_json::json;
+    ///
+    /// let conf = Config::new_with_defaults().add_idref_path("/shop/order/@items", IdRef::InlineList);
+    /// let xml = r#"<shop><product xml:id="p1" name="pen"/><product xml:id="p2" name="ink"/><order items="p1 p2"/></shop>"#;
+    /// assert_eq!(
+    ///     xml_str_to_json(xml, &conf).unwrap()["shop"]["order"],
+    ///     json!({"@items": [{"@id": "p1", "@name": "pen"}, {"@id": "p2", "@name": "ink"}]})
+    /// );
+    /// ```
+    pub fn add_idref_path(self, path: &str, idref: IdRef) -> Self {
+        let mut conf = self;
+        conf.idref_paths.insert(path.to_owned(), idref);
+        conf
+    }
+
+    /// Converts the element at the absolute `path` into its text content. See `Config.inner_text_paths` for details.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{xml_str_to_json, Config};
+    /// use serde_json::json;
+    ///
+    /// let conf = Config::new_with_defaults().add_inner_text_path("/item/description");
+    /// let xml = "<item><description>A <b>bold</b> <i>claim</i>.</description></item>";
+    /// assert_eq!(xml_str_to_json(xml, &conf).unwrap(), json!({"item": {"description": "A bold claim."}}));
+    /// ```
+    pub fn add_inner_text_path(self, path: &str) -> Self {
+        let mut conf = self;
+        conf.inner_text_paths.insert(path.to_owned());
+        conf
+    }
+
+    /// Splits the values at the absolute `path` into magnitude and unit, converting the units of `units`.
+    /// See `Config.unit_paths` for details.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{xml_str_to_json, Config, UnitTable};
+    /// use serde_json::json;
+    ///
+    /// let conf = Config::new_with_defaults()
+    ///     .add_unit_path("/parcel/@weight", UnitTable::new().with_unit("g", "kg", 0.001));
+    /// assert_eq!(
+    ///     xml_str_to_json(r#"<parcel weight="10.5kg"/>"#, &conf).unwrap(),
+    ///     json!({"parcel": {"@weight": {"value": 10.5, "unit": "kg"}}})
+    /// );
+    /// assert_eq!(
+    ///     xml_str_to_json(r#"<parcel weight="250 g"/>"#, &conf).unwrap(),
+    ///     json!({"parcel": {"@weight": {"value": 0.25, "unit": "kg"}}})
+    /// );
+    /// ```
+    pub fn add_unit_path(self, path: &str, units: UnitTable) -> Self {
+        let mut conf = self;
+        conf.unit_paths.insert(path.to_owned(), units);
+        conf
+    }
+
+    /// Sets the property name of the text of the element at the absolute `path`. See `Config.text_prop_names`
+    /// for details.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{xml_str_to_json, Config};
+    /// use serde_json::json;
+    ///
+    /// let conf = Config::new_with_defaults().add_text_prop_name("/station/measurement", "value");
+    /// let xml = r#"<station id="7"><measurement unit="C">21.5</measurement></station>"#;
+    /// assert_eq!(
+    ///     xml_str_to_json(xml, &conf).unwrap(),
+    ///     json!({"station": {"@id": 7, "measurement": {"@unit": "C", "value": 21.5}}})
+    /// );
+    /// ```
+    pub fn add_text_prop_name(self, path: &str, name: &str) -> Self {
+        let mut conf = self;
+        conf.text_prop_names
+            .insert(path.to_owned(), name.to_owned());
+        conf
+    }
+
+    /// Sets the attributes of elements with text that are converted. See `Config.text_attributes` for details.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{xml_str_to_json, Config, TextAttributes};
+    /// use serde_json::json;
+    ///
+    /// let conf = Config::new_with_defaults().with_text_attributes(TextAttributes::Drop);
+    /// let xml = r#"<item id="1"><price currency="EUR" source="erp">5</price></item>"#;
+    /// assert_eq!(xml_str_to_json(xml, &conf).unwrap(), json!({"item": {"@id": 1, "price": 5}}));
+    /// ```
+    pub fn with_text_attributes(self, text_attributes: TextAttributes) -> Self {
+        Config {
+            text_attributes,
+            ..self
+        }
+    }
+
+    /// Converts elements with a single attribute and nothing else into the value of the attribute.
+    /// See `Config.collapse_single_attributes` for details.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{xml_str_to_json, Config};
+    /// use serde_json::json;
+    ///
+    /// let conf = Config::new_with_defaults().with_collapse_single_attributes();
+    /// let xml = r#"<config><flag value="3"/><mode name="fast" level="2"/></config>"#;
+    /// assert_eq!(
+    ///     xml_str_to_json(xml, &conf).unwrap(),
+    ///     json!({"config": {"flag": 3, "mode": {"@name": "fast", "@level": 2}}})
+    /// );
+    /// ```
+    pub fn with_collapse_single_attributes(self) -> Self {
+        Config {
+            collapse_single_attributes: true,
+            ..self
+        }
+    }
+
+    /// Replaces the values at the absolute `path` with their HMAC-SHA256. See `Config.pseudonymized_paths`
+    /// for details.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{xml_str_to_json, Config};
+    ///
+    /// let conf = Config::new_with_defaults()
+    ///     .with_pseudonymization_key(b"secret")
+    ///     .add_pseudonymized_path("/order/@customer");
+    /// let a = xml_str_to_json(r#"<order customer="c-17"><total>5</total></order>"#, &conf).unwrap();
+    /// let b = xml_str_to_json(r#"<order customer="c-17"><total>7</total></order>"#, &conf).unwrap();
+    /// assert_eq!(a["order"]["@customer"], b["order"]["@customer"]);
+    /// assert_eq!(a["order"]["@customer"].as_str().unwrap().len(), 64);
+    /// ```
+    #[cfg(feature = "pseudonymize")]
+    pub fn add_pseudonymized_path(self, path: &str) -> Self {
+        let mut conf = self;
+        conf.pseudonymized_paths.insert(path.to_owned());
+        conf
+    }
+
+    /// Sets the secret key of `Config.pseudonymized_paths`.
+    #[cfg(feature = "pseudonymize")]
+    pub fn with_pseudonymization_key(self, key: &[u8]) -> Self {
+        Config {
+            pseudonymization_key: Some(PseudonymizationKey::new(key)),
+            ..self
+        }
+    }
+
+    /// Shortens string values to `max_len` characters, ending with `ellipsis`. See `Config.max_string_len`
+    /// for details.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{xml_str_to_json, Config};
+    /// use serde_json::json;
+    ///
+    /// let conf = Config::new_with_defaults().with_max_string_len(10, "...");
+    /// let xml = "<post><title>Short</title><body>A very long text</body></post>";
+    /// assert_eq!(xml_str_to_json(xml, &conf).unwrap(), json!({"post": {"title": "Short", "body": "A very ..."}}));
+    /// ```
+    pub fn with_max_string_len(self, max_len: usize, ellipsis: &str) -> Self {
+        Config {
+            max_string_len: Some(max_len),
+            string_ellipsis: ellipsis.to_owned(),
+            ..self
+        }
+    }
+
+    /// Keeps only the first `max_items` elements at the absolute `path` and appends `marker` if there were more.
+    /// See `Config.array_limits` for details.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{xml_str_to_json, Config, TruncationMarker};
+    /// use serde_json::json;
+    ///
+    /// let conf = Config::new_with_defaults().add_array_limit("/log/line", 2, TruncationMarker::Count);
+    /// let xml = "<log><line>a</line><line>b</line><line>c</line><line>d</line></log>";
+    /// assert_eq!(xml_str_to_json(xml, &conf).unwrap(), json!({"log": {"line": ["a", "b", 2]}}));
+    /// ```
+    pub fn add_array_limit(self, path: &str, max_items: usize, marker: TruncationMarker) -> Self {
+        let mut conf = self;
+        conf.array_limits
+            .insert(path.to_owned(), ArrayLimit { max_items, marker });
+        conf
+    }
+
+    /// Sets a predicate that decides which elements are converted. See `Config.node_filter` for details.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{xml_str_to_json, Config};
+    /// use serde_json::json;
+    ///
+    /// let conf = Config::new_with_defaults().with_node_filter(|el| el.attribute("status") != Some("deleted"));
+    /// let xml = r#"<users><user id="1"/><user id="2" status="deleted"/><user id="3"/></users>"#;
+    /// assert_eq!(xml_str_to_json(xml, &conf).unwrap(), json!({"users": {"user": [{"@id": 1}, {"@id": 3}]}}));
+    /// ```
+    pub fn with_node_filter<F>(self, f: F) -> Self
+    where
+        F: Fn(&roxmltree::Node) -> bool + Send + Sync + 'static,
+    {
+        Config {
+            node_filter: Some(NodeFilter::new(f)),
+            ..self
+        }
+    }
+
+    /// Sets how objects with a single property are collapsed. See `Config.single_key_objects` for details.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{xml_str_to_json, Config, SingleKeyObjects};
+    /// use serde_json::json;
+    ///
+    /// let conf = Config::new_with_defaults().with_single_key_objects(SingleKeyObjects::Join(".".to_owned()));
+    /// let xml = "<msg><header><sender><id>42</id></sender></header><body>Hi</body></msg>";
+    /// assert_eq!(xml_str_to_json(xml, &conf).unwrap(), json!({"msg": {"header.sender.id": 42, "body": "Hi"}}));
+    /// ```
+    pub fn with_single_key_objects(self, single_key_objects: SingleKeyObjects) -> Self {
+        Config {
+            single_key_objects,
+            ..self
+        }
+    }
+
+    /// Sets the kinds of empty values that are removed from the output. See `Config.prune_empty` for details.
+    pub fn with_prune_empty(self, prune_empty: PruneEmpty) -> Self {
+        Config {
+            prune_empty,
+            ..self
+        }
+    }
+
+    /// Serializes the keys of objects in the order of `compare_keys`. See `Config.sort_keys` for details.
+    pub fn with_sorted_keys(self) -> Self {
+        Config {
+            sort_keys: true,
+            ..self
+        }
+    }
+
+    /// Sets a callback that supplies the content of external entities and DTD subsets by their public and
+    /// system IDs. See `Config.entity_resolver` for details.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{xml_str_to_json, Config};
+    /// use serde_json::json;
+    ///
+    /// let conf = Config::new_with_defaults().with_entity_resolver(|_public_id, system_id| {
+    ///     (system_id == "legal.xml").then(|| "<p>All rights reserved.</p>".to_owned())
+    /// });
+    /// let xml = r#"<!DOCTYPE book [<!ENTITY legal SYSTEM "legal.xml">]><book>&legal;</book>"#;
+    /// assert_eq!(xml_str_to_json(xml, &conf).unwrap(), json!({"book": {"p": "All rights reserved."}}));
+    /// ```
+    pub fn with_entity_resolver<F>(self, resolve: F) -> Self
+    where
+        F: Fn(Option<&str>, &str) -> Option<String> + Send + Sync + 'static,
+    {
+        Config {
+            entity_resolver: Some(EntityResolver::new(resolve)),
+            ..self
+        }
+    }
+
+    /// Resolves external entities and DTD subsets with the local files of `catalog`.
+    /// See `Config.entity_resolver` for details.
+    pub fn with_entity_catalog(self, catalog: EntityCatalog) -> Self {
+        Config {
+            entity_resolver: Some(catalog.into()),
+            ..self
+        }
+    }
+
+    /// Skips anything before the XML declaration or the first markup of the document.
+    /// See `Config.skip_leading_junk` for details.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{xml_str_to_json, Config};
+    /// use serde_json::json;
+    ///
+    /// let conf = Config::new_with_defaults().with_skip_leading_junk();
+    /// let xml = "\u{feff}\n\u{feff}<?xml version=\"1.0\"?><a>1</a>";
+    /// assert_eq!(xml_str_to_json(xml, &conf).unwrap(), json!({"a": 1}));
+    /// ```
+    pub fn with_skip_leading_junk(self) -> Self {
+        Config {
+            skip_leading_junk: true,
+            ..self
+        }
+    }
+
+    /// Sets how `xml_bytes_to_json` handles bytes that are not valid UTF-8.
+    /// See `Config.utf8_policy` for details.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{xml_bytes_to_json, Config, Utf8Policy};
+    /// use serde_json::json;
+    ///
+    /// let conf = Config::new_with_defaults().with_utf8_policy(Utf8Policy::Lossy);
+    /// let xml = b"<a><b>caf\xe9</b><b>ok</b></a>";
+    /// assert_eq!(xml_bytes_to_json(xml, &conf).unwrap(), json!({"a": {"b": ["caf\u{fffd}", "ok"]}}));
+    /// ```
+    pub fn with_utf8_policy(self, utf8_policy: Utf8Policy) -> Self {
+        Config {
+            utf8_policy,
+            ..self
+        }
+    }
+
+    /// Sets a flag that aborts the conversion with `Error::Cancelled` when it's set to `true`.
+    pub fn with_cancellation_flag(self, flag: Arc<AtomicBool>) -> Self {
+        Config {
+            cancellation_flag: Some(flag),
+            ..self
+        }
+    }
+
+    /// Limits the approximate size of the output to `bytes`. See `Config.memory_budget` for details.
+    pub fn with_memory_budget(self, bytes: usize) -> Self {
+        Config {
+            memory_budget: Some(bytes),
+            ..self
+        }
+    }
+
+    /// Stops the path based conversions after `limit` matches. See `Config.match_limit` for details.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{xml_query, Config};
+    /// use serde_json::json;
+    ///
+    /// let xml = "<feed><item>1</item><item>2</item><item>3</item></feed>";
+    /// let conf = Config::new_with_defaults().with_match_limit(1);
+    /// assert_eq!(xml_query(xml, "$.feed.item[*]", &conf).unwrap(), vec![json!(1)]);
+    /// ```
+    pub fn with_match_limit(self, limit: usize) -> Self {
+        Config {
+            match_limit: Some(limit),
+            ..self
+        }
+    }
+
+    /// Converts the child elements of the root element in parallel. See `Config.parallel_children` for details.
+    #[cfg(feature = "rayon")]
+    pub fn with_parallel_children(self) -> Self {
+        Config {
+            parallel_children: true,
+            ..self
+        }
+    }
+
+    /// Records which JSON type overrides are applied in `usage`. See `OverrideUsage` for details.
+    #[cfg(feature = "json_types")]
+    pub fn with_override_usage(self, usage: Arc<OverrideUsage>) -> Self {
+        Config {
+            override_usage: Some(usage),
+            ..self
+        }
+    }
 }
 
 impl Default for Config {
@@ -261,11 +1334,17 @@ impl Default for Config {
 
 /// Returns the text as one of `serde::Value` types: int, float, bool or string.
 fn parse_text(text: &str, leading_zero_as_string: bool, json_type: &JsonType) -> Value {
+    parse_scalar(text, leading_zero_as_string, json_type)
+        .unwrap_or_else(|| Value::String(text.trim().into()))
+}
+
+/// Returns the text as int, float or bool, or `None` if it's converted into the trimmed text as string.
+fn parse_scalar(text: &str, leading_zero_as_string: bool, json_type: &JsonType) -> Option<Value> {
     let text = text.trim();
 
     // enforce JSON String data type regardless of the underlying type
     if json_type == &JsonType::AlwaysString {
-        return Value::String(text.into());
+        return None;
     }
 
     // enforce JSON Bool data type
@@ -273,209 +1352,1363 @@ fn parse_text(text: &str, leading_zero_as_string: bool, json_type: &JsonType) ->
     if let JsonType::Bool(true_values) = json_type {
         if true_values.contains(&text) {
             // any values matching the `true` list are bool/true
-            return Value::Bool(true);
+            return Some(Value::Bool(true));
         } else {
             // anything else is false
-            return Value::Bool(false);
+            return Some(Value::Bool(false));
         }
     }
 
+    // enforce JSON Number data type, ignoring leading zeros
+    #[cfg(feature = "json_types")]
+    if json_type == &JsonType::AlwaysNumber {
+        return parse_number(text).map(Value::Number);
+    }
+
+    // only digits, signs and dots can start a number, so most text skips the parse attempts;
+    // `inf` and `NaN` parse as floats, but aren't valid JSON numbers anyway
+    if !matches!(
+        text.as_bytes().first(),
+        Some(b'0'..=b'9' | b'-' | b'+' | b'.')
+    ) {
+        return match text {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            _ => None,
+        };
+    }
+
     // ints
     if let Ok(v) = text.parse::<u64>() {
         // don't parse octal numbers and those with leading 0
         // `text` value "0" will always be converted into number 0, "0000" may be converted
         // into 0 or "0000" depending on `leading_zero_as_string`
         if leading_zero_as_string && text.starts_with("0") && (v != 0 || text.len() > 1) {
-            return Value::String(text.into());
+            return None;
         }
-        return Value::Number(Number::from(v));
+        return Some(Value::Number(Number::from(v)));
     }
 
     // floats
     if let Ok(v) = text.parse::<f64>() {
         if text.starts_with("0") && !text.starts_with("0.") {
-            return Value::String(text.into());
+            return None;
         }
         if let Some(val) = Number::from_f64(v) {
-            return Value::Number(val);
+            return Some(Value::Number(val));
         }
     }
 
     // booleans
     if let Ok(v) = text.parse::<bool>() {
-        return Value::Bool(v);
+        return Some(Value::Bool(v));
     }
 
-    Value::String(text.into())
+    None
 }
 
-fn convert_text(
-    el: &roxmltree::Node,
-    config: &Config,
-    text: &str,
-    json_type_value: JsonType,
-) -> Option<Value> {
-    // process node's attributes, if present
-    if el.attributes().count() > 0 {
-        Some(Value::Object(
-            el.attributes()
-                .map(|attr| {
-                    // add the current node to the path
-                    #[cfg(feature = "json_types")]
-                    let path = [path.clone(), "/@".to_owned(), attr.name().to_string()].concat();
-                    // get the json_type for this node
-                    #[cfg(feature = "json_types")]
-                    let (_, json_type_value) = get_json_type(config, &path);
-                    (
-                        [config.xml_attr_prefix.clone(), attr.name().to_string()].concat(),
-                        parse_text(
-                            attr.value(),
-                            config.leading_zero_as_string,
-                            &json_type_value,
-                        ),
-                    )
-                })
-                .chain(vec![(
-                    config.xml_text_node_prop_name.clone(),
-                    parse_text(&text[..], config.leading_zero_as_string, &json_type_value),
-                )])
-                .collect(),
-        ))
-    } else {
-        Some(parse_text(
-            &text[..],
-            config.leading_zero_as_string,
-            &json_type_value,
-        ))
+/// Parses integers and floats without the special treatment of leading zeros.
+#[cfg(feature = "json_types")]
+fn parse_number(text: &str) -> Option<Number> {
+    if let Ok(v) = text.parse::<u64>() {
+        return Some(Number::from(v));
+    }
+    if let Ok(v) = text.parse::<i64>() {
+        return Some(Number::from(v));
     }
+    text.parse::<f64>().ok().and_then(Number::from_f64)
 }
 
-fn convert_no_text(
-    el: &roxmltree::Node,
-    config: &Config,
-    path: &String,
-    json_type_value: JsonType,
-) -> Option<Value> {
-    // this element has no text, but may have other child nodes
-    let mut data = Map::new();
+/// Values that are not listed as `true` in `JsonType::Bool`, but are still conventional
+/// representations of `false`, so mapping them to `false` is not considered lossy.
+#[cfg(feature = "json_types")]
+const CONVENTIONAL_FALSE_VALUES: [&str; 5] = ["", "0", "false", "no", "off"];
 
-    for attr in el.attributes() {
-        // add the current node to the path
-        #[cfg(feature = "json_types")]
-        let path = [path.clone(), "/@".to_owned(), attr.name().to_string()].concat();
-        // get the json_type for this node
-        #[cfg(feature = "json_types")]
-        let (_, json_type_value) = get_json_type(config, &path);
-        data.insert(
-            [config.xml_attr_prefix.clone(), attr.name().to_string()].concat(),
-            parse_text(
-                attr.value(),
-                config.leading_zero_as_string,
-                &json_type_value,
-            ),
-        );
+/// Returns `true` if the text can't be represented by the enforced JSON type.
+#[cfg(feature = "json_types")]
+fn is_coercion_failure(text: &str, json_type: &JsonType) -> bool {
+    match json_type {
+        JsonType::Bool(true_values) => {
+            !true_values.contains(&text)
+                && !CONVENTIONAL_FALSE_VALUES
+                    .iter()
+                    .any(|v| v.eq_ignore_ascii_case(text))
+        }
+        JsonType::AlwaysNumber => parse_number(text).is_none(),
+        JsonType::AlwaysString | JsonType::Infer => false,
     }
+}
 
-    // process child element recursively
-    for child in el.children() {
-        match convert_node(&child, config, &path) {
-            Some(val) => {
-                let name = &child.tag_name().name().to_string();
-                if name == "" {
-                    ()
-                } else {
-                    #[cfg(feature = "json_types")]
-                    let path = [path.clone(), "/".to_owned(), name.clone()].concat();
-                    let (json_type_array, _) = get_json_type(config, &path);
-
-                    // does it have to be an array?
-                    if json_type_array || data.contains_key(name) {
-                        // was this property converted to an array earlier?
-                        if data.get(name).unwrap_or(&Value::Null).is_array() {
-                            // add the new value to an existing array
-                            data.get_mut(name)
-                                .unwrap()
-                                .as_array_mut()
-                                .unwrap()
-                                .push(val);
-                        } else {
-                            // convert the property to an array with the existing and the new values
-                            let new_val = match data.remove(name) {
-                                None => vec![val],
-                                Some(temp) => vec![temp, val],
-                            };
-                            data.insert(name.clone(), Value::Array(new_val));
-                        }
-                    } else {
-                        // this is the first time this property is encountered and it doesn't
-                        // have to be an array, so add it as-is
-                        data.insert(name.clone(), val);
-                    }
-                }
-            }
-            _ => (),
+/// The namespace of the `xsi:nil` attribute.
+#[cfg(feature = "json_types")]
+pub(crate) const XSI_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema-instance";
+
+/// Mutable state shared by all conversion functions for the duration of a single conversion.
+struct Context<'a, 'd> {
+    config: &'a Config,
+    diagnostics: Option<&'d mut Diagnostics>,
+    /// Nesting level of the element being converted.
+    depth: usize,
+    /// Progress counters, only maintained if there is a progress callback.
+    progress: Progress,
+    /// Names of elements that repeat within any parent, only collected for `ArrayInference::DocumentWide`.
+    repeated_names: Arc<HashSet<String>>,
+    /// Approximate size of the output so far, only counted if there is a `memory_budget`.
+    output_bytes: usize,
+    /// The paths of the converted nodes, only collected by `xml_str_to_json_with_trace`.
+    trace: Option<Vec<TraceEntry>>,
+    /// Converts negative integers into `i64` instead of `f64`, only set by `xml_str_to_bson`.
+    #[cfg(feature = "bson")]
+    signed_integers: bool,
+}
+
+impl<'a, 'd> Context<'a, 'd> {
+    fn new(config: &'a Config, diagnostics: Option<&'d mut Diagnostics>) -> Self {
+        Context {
+            config,
+            diagnostics,
+            depth: 0,
+            progress: Progress {
+                elements: 0,
+                total_elements: 0,
+            },
+            repeated_names: Arc::default(),
+            output_bytes: 0,
+            trace: None,
+            #[cfg(feature = "bson")]
+            signed_integers: false,
         }
     }
 
-    // return the JSON object if it's not empty
-    if !data.is_empty() {
-        return Some(Value::Object(data));
+    /// Checks the cancellation flag and invokes the progress callback if it's time to.
+    #[inline]
+    fn tick(&mut self) -> Result<(), Error> {
+        if let Some(flag) = &self.config.cancellation_flag {
+            if flag.load(Ordering::Relaxed) {
+                return Err(Error::Cancelled);
+            }
+        }
+        if let Some(progress) = &self.config.progress_callback {
+            self.progress.elements += 1;
+            if progress.is_due(self.progress.elements) {
+                (progress.callback)(self.progress);
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the approximate size of the converted element to the size of the output and fails
+    /// the conversion if it exceeds `Config.memory_budget`.
+    #[inline]
+    fn charge(&mut self, el: &roxmltree::Node) -> Result<(), Error> {
+        let Some(budget) = self.config.memory_budget else {
+            return Ok(());
+        };
+        // every property is a key and a value, on top of the text they own
+        const PROPERTY_SIZE: usize = std::mem::size_of::<String>() + std::mem::size_of::<Value>();
+        let prefix_len = self.config.xml_attr_prefix.len();
+        self.output_bytes += PROPERTY_SIZE
+            + el.tag_name().name().len()
+            + el.text().map_or(0, |text| text.trim().len())
+            + el.attributes()
+                .map(|attr| PROPERTY_SIZE + prefix_len + attr.name().len() + attr.value().len())
+                .sum::<usize>();
+        if self.output_bytes > budget {
+            return Err(Error::MemoryBudgetExceeded(budget));
+        }
+        Ok(())
+    }
+
+    /// Records the path of a node and how its JSON type is determined if the caller asked for a trace.
+    #[inline]
+    fn trace(&mut self, path: &str) {
+        if let Some(trace) = self.trace.as_mut() {
+            let (always_array, json_type) = get_json_type(self.config, path);
+            trace.push(TraceEntry {
+                path: path.to_owned(),
+                applied: trace::applied_override(self.config, path),
+                json_type: json_type.clone(),
+                always_array,
+            });
+        }
+    }
+
+    /// Records a non-fatal issue if the caller asked for diagnostics, or fails the conversion
+    /// if the issue means data loss and `Config.strict` is set.
+    /// `kind` is only evaluated when it's needed to avoid allocations otherwise.
+    #[inline]
+    fn report<F>(&mut self, path: &str, kind: F) -> Result<(), Error>
+    where
+        F: FnOnce() -> DiagnosticKind,
+    {
+        if self.diagnostics.is_none() && !self.config.strict {
+            return Ok(());
+        }
+
+        let diagnostic = Diagnostic {
+            path: path.to_owned(),
+            kind: kind(),
+        };
+        if self.config.strict && diagnostic.kind.is_data_loss() {
+            return Err(Error::Lossy(diagnostic));
+        }
+        if let Some(diagnostics) = self.diagnostics.as_mut() {
+            diagnostics.push(diagnostic);
+        }
+        Ok(())
+    }
+
+    /// Reports the namespace of the node, if any, because it is not carried over into JSON keys.
+    #[inline]
+    fn report_namespace(&mut self, path: &str, namespace: Option<&str>) -> Result<(), Error> {
+        match namespace {
+            Some(uri)
+                if !self.config.clark_notation
+                    && !self.config.namespace_prefixes.contains_key(uri) =>
+            {
+                self.report(path, || DiagnosticKind::IgnoredNamespace(uri.to_owned()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the namespace of the element against `Config.default_namespace` and reports it
+    /// like `report_namespace` if it is not carried over into its JSON key.
+    #[inline]
+    fn report_element_namespace(&mut self, path: &str, el: &roxmltree::Node) -> Result<(), Error> {
+        let namespace = el.tag_name().namespace();
+        if self.config.default_namespace != DefaultNamespace::Ignore && in_default_namespace(el) {
+            if self.config.default_namespace == DefaultNamespace::Error {
+                return Err(Error::UnexpectedXml(format!(
+                    "element {} is in the default namespace {}",
+                    path,
+                    namespace.unwrap_or_default()
+                )));
+            }
+            return Ok(());
+        }
+        self.report_namespace(path, namespace)
+    }
+
+    /// Reports a child node that is not converted into JSON. Whitespace and comments are not
+    /// considered content.
+    fn report_dropped(&mut self, path: &str, node: &roxmltree::Node) -> Result<(), Error> {
+        match node.node_type() {
+            roxmltree::NodeType::Element => self.report(path, || {
+                DiagnosticKind::DroppedElement(node.tag_name().name().to_owned())
+            }),
+            roxmltree::NodeType::Text => match node.text().map(str::trim) {
+                Some(text) if !text.is_empty() => {
+                    self.report(path, || DiagnosticKind::DroppedText(text.to_owned()))
+                }
+                _ => Ok(()),
+            },
+            roxmltree::NodeType::PI => match node.pi() {
+                Some(pi) => self.report(path, || {
+                    DiagnosticKind::DroppedProcessingInstruction(pi.target.to_owned())
+                }),
+                None => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Converts a text value with `parse_text` and reports values that did not fit the enforced type.
+/// The value is paired with the original text if `Config.raw_values` asks for it.
+fn convert_value(
+    ctx: &mut Context,
+    path: &str,
+    text: &str,
+    json_type: &JsonType,
+) -> Result<Value, Error> {
+    #[cfg(feature = "pseudonymize")]
+    if pseudonymize::is_pseudonymized(ctx.config, path) {
+        return Ok(Value::String(pseudonymize::pseudonymize(
+            ctx.config,
+            text.trim(),
+        )?));
+    }
+    let value = match units::split_unit(ctx.config, path, text) {
+        Some(value) => value,
+        None => convert_text_value(ctx, path, text, json_type)?,
+    };
+    if !ctx.config.raw_values.applies_to(path) {
+        return Ok(value);
+    }
+
+    let (value_name, raw_name) = &ctx.config.raw_value_prop_names;
+    let mut data = Map::with_capacity(2);
+    data.insert(value_name.clone(), value);
+    data.insert(raw_name.clone(), Value::String(text.to_owned()));
+    Ok(Value::Object(data))
+}
+
+fn convert_text_value(
+    ctx: &mut Context,
+    path: &str,
+    text: &str,
+    json_type: &JsonType,
+) -> Result<Value, Error> {
+    #[cfg(feature = "json_types")]
+    if is_coercion_failure(text.trim(), json_type) {
+        let text = text.trim();
+        let diagnostic = || DiagnosticKind::CoercionFailure {
+            value: text.to_owned(),
+            json_type: json_type.clone(),
+        };
+        #[cfg(feature = "tracing")]
+        tracing::debug!(path, value = text, ?json_type, "type coercion failed");
+        ctx.report(path, diagnostic)?;
+        match ctx.config.coercion_failure_policy {
+            CoercionFailurePolicy::Fallback => (),
+            CoercionFailurePolicy::KeepString => {
+                return Ok(Value::String(truncate_string(ctx.config, text).into()))
+            }
+            CoercionFailurePolicy::Null => return Ok(Value::Null),
+            CoercionFailurePolicy::Error => {
+                return Err(Error::Coercion(Diagnostic {
+                    path: path.to_owned(),
+                    kind: diagnostic(),
+                }))
+            }
+        }
+    }
+    #[cfg(not(feature = "json_types"))]
+    let _ = path;
+
+    match parse_text(text, ctx.config.leading_zero_as_string, json_type) {
+        Value::String(text) if ctx.config.max_string_len.is_some() => Ok(Value::String(
+            truncate_string(ctx.config, &text).into_owned(),
+        )),
+        // as floats, negative integers would lose precision beyond 2^53
+        #[cfg(feature = "bson")]
+        Value::Number(n) if ctx.signed_integers && n.is_f64() => Ok(text
+            .trim()
+            .parse::<i64>()
+            .map_or(Value::Number(n), Value::from)),
+        value => Ok(value),
+    }
+}
+
+/// Adds the source location of the element to `data` if `Config.location_prop_name` is set.
+fn add_location(ctx: &Context, el: &roxmltree::Node, data: &mut Map<String, Value>) {
+    if let Some(prop_name) = &ctx.config.location_prop_name {
+        let offset = el.range().start;
+        let pos = el.document().text_pos_at(offset);
+        let mut location = Map::with_capacity(3);
+        location.insert("line".to_owned(), Value::from(pos.row));
+        location.insert("col".to_owned(), Value::from(pos.col));
+        location.insert("offset".to_owned(), Value::from(offset));
+        data.insert(prop_name.clone(), Value::Object(location));
+    }
+}
+
+/// Converts the attributes of `el` into `data`, only the ones of `Config.text_attributes` if `with_text` is set.
+fn convert_attributes(
+    ctx: &mut Context,
+    el: &roxmltree::Node,
+    path: &mut String,
+    data: &mut Map<String, Value>,
+    with_text: bool,
+) -> Result<(), Error> {
+    let path_len = path.len();
+    for attr in el.attributes() {
+        let name = key_name(ctx.config, attr.namespace(), attr.name());
+        if with_text && !keep_text_attribute(ctx.config, &name) {
+            continue;
+        }
+        // add the current node to the path
+        path.push_str("/@");
+        path.push_str(&name);
+        ctx.report_namespace(path, attr.namespace())?;
+        ctx.trace(path);
+        // get the json_type for this node
+        let (_, json_type_value) = get_json_type(ctx.config, path);
+        let text = xml_base::resolve_attribute(ctx.config, el, path, attr.value());
+        let value = convert_value(ctx, path, &text, json_type_value)?;
+        path.truncate(path_len);
+        let key = [ctx.config.xml_attr_prefix.as_str(), &name].concat();
+        // `a:x` and `b:x` lose their namespaces and end up under the same key
+        if data.contains_key(&key) {
+            ctx.report(path, || DiagnosticKind::KeyCollision(key.clone()))?;
+        }
+        data.insert(key, value);
+    }
+    Ok(())
+}
+
+fn convert_text(
+    ctx: &mut Context,
+    el: &roxmltree::Node,
+    path: &mut String,
+    text: &str,
+    json_type_value: &JsonType,
+) -> Result<Option<Value>, Error> {
+    // child elements and any text after them are not converted if the element starts with text
+    for child in el.children().skip(1) {
+        ctx.report_dropped(path, &child)?;
+    }
+
+    // process node's attributes, if present
+    if has_text_attributes(ctx.config, el) {
+        let mut data = Map::with_capacity(el.attributes().len() + 1);
+        add_location(ctx, el, &mut data);
+        convert_attributes(ctx, el, path, &mut data, true)?;
+        let key = text_prop_name(ctx.config, path);
+        if data.contains_key(key) {
+            ctx.report(path, || DiagnosticKind::KeyCollision(key.to_owned()))?;
+        }
+        let value = convert_value(ctx, path, text, json_type_value)?;
+        data.insert(key.to_owned(), value);
+        Ok(Some(Value::Object(data)))
+    } else {
+        Ok(Some(convert_value(ctx, path, text, json_type_value)?))
+    }
+}
+
+fn convert_no_text(
+    ctx: &mut Context,
+    el: &roxmltree::Node,
+    path: &mut String,
+) -> Result<Option<Value>, Error> {
+    // this element has no text, but may have other child nodes
+    // the capacity only matters if serde_json's `preserve_order` feature turns `Map` into a hash map,
+    // it's an upper bound because repeated children share an entry
+    let children = el.children().filter(|c| c.is_element()).count();
+    let mut data = Map::with_capacity(el.attributes().len() + children);
+
+    convert_attributes(ctx, el, path, &mut data, false)?;
+
+    #[cfg(feature = "rayon")]
+    let mut converted = convert_children_parallel(ctx, el, path).into_iter();
+    let mut truncated = Truncated::new();
+
+    // process child element recursively
+    for child in el.children() {
+        if !child.is_element() {
+            // text mixed with child elements has no place in the JSON object
+            ctx.report_dropped(path, &child)?;
+            continue;
+        }
+
+        #[cfg(feature = "rayon")]
+        let parallel = converted.next();
+
+        if !ctx.config.array_limits.is_empty() {
+            let key = element_key(ctx.config, &child);
+            let items = match data.get(key.as_ref()) {
+                Some(Value::Array(items)) => items.len(),
+                Some(_) => 1,
+                None => 0,
+            };
+            if truncate_child(ctx.config, path, &key, items, &mut truncated) {
+                continue;
+            }
+        }
+
+        #[cfg(feature = "rayon")]
+        let val = match parallel {
+            Some(result) => {
+                let (val, diagnostics) = result?;
+                if let Some(target) = ctx.diagnostics.as_mut() {
+                    diagnostics.into_iter().for_each(|d| target.push(d));
+                }
+                val
+            }
+            None => convert_element(ctx, &child, path)?,
+        };
+        #[cfg(not(feature = "rayon"))]
+        let val = convert_element(ctx, &child, path)?;
+
+        if let Some(val) = val {
+            let key = element_key(ctx.config, &child);
+            let name = key.as_ref();
+            let path_len = path.len();
+            path.push('/');
+            path.push_str(name);
+            let (json_type_array, _) = get_json_type(ctx.config, path);
+            path.truncate(path_len);
+
+            // does it have to be an array?
+            if json_type_array || data.contains_key(name) || ctx.repeated_names.contains(name) {
+                // an attribute with the same name was converted earlier
+                if el.attributes().any(|attr| {
+                    name.strip_prefix(ctx.config.xml_attr_prefix.as_str())
+                        == Some(&key_name(ctx.config, attr.namespace(), attr.name()))
+                }) && !el
+                    .children()
+                    .take_while(|c| c != &child)
+                    .any(|c| c.is_element() && element_key(ctx.config, &c) == name)
+                {
+                    ctx.report(path, || DiagnosticKind::KeyCollision(name.to_owned()))?;
+                }
+
+                // was this property converted to an array earlier?
+                if let Some(Value::Array(existing)) = data.get_mut(name) {
+                    // add the new value to an existing array
+                    existing.push(val);
+                } else {
+                    // convert the property to an array with the existing and the new values
+                    let new_val = match data.remove(name) {
+                        None => vec![val],
+                        Some(temp) => vec![temp, val],
+                    };
+                    data.insert(name.to_owned(), Value::Array(new_val));
+                }
+            } else {
+                // this is the first time this property is encountered and it doesn't
+                // have to be an array, so add it as-is
+                data.insert(name.to_owned(), val);
+            }
+        }
+    }
+
+    for (key, limit, dropped) in truncated {
+        let items = match data.remove(&key) {
+            Some(Value::Array(items)) => items,
+            Some(item) => vec![item],
+            None => Vec::new(),
+        };
+        let marker = match &limit.marker {
+            TruncationMarker::None => None,
+            TruncationMarker::Count => Some(Value::from(dropped)),
+            TruncationMarker::Text(text) => Some(Value::String(text.clone())),
+        };
+        data.insert(key, Value::Array(items.into_iter().chain(marker).collect()));
+    }
+
+    // `<flag value="3"/>` becomes the value of its only attribute
+    if collapses_to_attribute(ctx.config, el) {
+        return Ok(data.into_values().next());
+    }
+
+    // return the JSON object if it's not empty
+    if !data.is_empty() {
+        add_location(ctx, el, &mut data);
+        return Ok(Some(Value::Object(data)));
     }
 
     // empty objects are treated according to config rules set by the caller
-    match config.empty_element_handling {
+    Ok(match ctx.config.empty_element_handling {
         NullValue::Null => Some(Value::Null),
-        NullValue::EmptyObject => Some(Value::Object(data)),
+        NullValue::EmptyObject => {
+            add_location(ctx, el, &mut data);
+            Some(Value::Object(data))
+        }
         NullValue::Ignore => None,
+    })
+}
+
+/// A converted child element with the diagnostics of its conversion.
+#[cfg(feature = "rayon")]
+type ConvertedChild = Result<(Option<Value>, Vec<Diagnostic>), Error>;
+
+/// Converts the child elements of the root element in parallel if `Config.parallel_children` is set,
+/// each with its own diagnostics, so they can be merged in document order. Returns an empty list otherwise.
+#[cfg(feature = "rayon")]
+fn convert_children_parallel(
+    ctx: &Context,
+    el: &roxmltree::Node,
+    path: &str,
+) -> Vec<ConvertedChild> {
+    use rayon::prelude::*;
+
+    let config = ctx.config;
+    if !config.parallel_children
+        || ctx.depth != 1
+        || config.progress_callback.is_some()
+        || config.memory_budget.is_some()
+        || ctx.trace.is_some()
+    {
+        return Vec::new();
     }
+    let children: Vec<_> = el.children().filter(|c| c.is_element()).collect();
+    let collect_diagnostics = ctx.diagnostics.is_some();
+    let (depth, repeated_names) = (ctx.depth, &ctx.repeated_names);
+    #[cfg(feature = "bson")]
+    let signed_integers = ctx.signed_integers;
+    children
+        .par_iter()
+        .map(|child| {
+            let mut diagnostics = Diagnostics::new();
+            let mut child_ctx = Context {
+                depth,
+                repeated_names: Arc::clone(repeated_names),
+                #[cfg(feature = "bson")]
+                signed_integers,
+                ..Context::new(config, collect_diagnostics.then_some(&mut diagnostics))
+            };
+            let val = convert_element(&mut child_ctx, child, &mut path.to_owned())?;
+            Ok((val, diagnostics.into_vec()))
+        })
+        .collect()
 }
 
 /// Converts an XML Element into a JSON property
-fn convert_node(el: &roxmltree::Node, config: &Config, path: &String) -> Option<Value> {
-    // add the current node to the path
-    #[cfg(feature = "json_types")]
-    let path = [path, "/", el.tag_name().name()].concat();
+fn convert_node(
+    ctx: &mut Context,
+    el: &roxmltree::Node,
+    parent_path: &str,
+) -> Result<Option<Value>, Error> {
+    let mut path = String::with_capacity(parent_path.len() + 64);
+    path.push_str(parent_path);
+    convert_element(ctx, el, &mut path)
+}
+
+/// Converts an XML Element into a JSON property with the path of its parent in `path`.
+/// The path is extended for the element and its descendants and restored before returning,
+/// so one buffer serves the whole conversion instead of a new string for every node.
+fn convert_element(
+    ctx: &mut Context,
+    el: &roxmltree::Node,
+    path: &mut String,
+) -> Result<Option<Value>, Error> {
+    let path_len = path.len();
+    path.push('/');
+    path.push_str(&element_key(ctx.config, el));
+    let value = convert_element_at_path(ctx, el, path);
+    path.truncate(path_len);
+    value
+}
+
+fn convert_element_at_path(
+    ctx: &mut Context,
+    el: &roxmltree::Node,
+    path: &mut String,
+) -> Result<Option<Value>, Error> {
+    if !keep_element(ctx.config, el) {
+        return Ok(None);
+    }
+    ctx.report_element_namespace(path, el)?;
+
+    ctx.tick()?;
+    ctx.charge(el)?;
+
+    // the children of the root element are the subtrees that make up the bulk of most documents
+    #[cfg(feature = "tracing")]
+    let _span =
+        (ctx.depth == 1).then(|| tracing::trace_span!("convert_subtree", path = %path).entered());
+
+    // the conversion is recursive, so the depth has to be limited to protect the stack
+    if ctx.depth >= ctx.config.max_depth {
+        return Err(Error::DepthLimitExceeded(ctx.config.max_depth));
+    }
+
+    ctx.trace(path);
+    if is_inner_text(ctx.config, path) {
+        return Ok(Some(Value::String(text_content(ctx.config, el, path)?)));
+    }
 
     // get the json_type for this node
-    let (_, json_type_value) = get_json_type(config, &path);
-    let json_type_value = json_type_value.clone();
+    let (_, json_type_value) = get_json_type(ctx.config, path);
+
+    #[cfg(feature = "json_types")]
+    if is_nil(ctx.config, el, path) {
+        return Ok(Some(Value::Null));
+    }
 
     // is it an element with text?
-    match el.text() {
-        Some(mut text) => {
-            text = text.trim();
+    ctx.depth += 1;
+    let value = match el.text() {
+        Some(text) if !text.trim().is_empty() => {
+            convert_text(ctx, el, path, text.trim(), json_type_value)
+        }
+        _ => convert_no_text(ctx, el, path),
+    };
+    ctx.depth -= 1;
+    value
+}
 
-            if text != "" {
-                convert_text(el, config, text, json_type_value)
-            } else {
-                convert_no_text(el, config, path, json_type_value)
+/// Returns the index right after the first occurrence of `pattern` at or after `from`,
+/// or the length of `bytes` if there is none.
+fn skip_past(bytes: &[u8], from: usize, pattern: &[u8]) -> usize {
+    bytes
+        .get(from..)
+        .and_then(|rest| rest.windows(pattern.len()).position(|w| w == pattern))
+        .map_or(bytes.len(), |pos| from + pos + pattern.len())
+}
+
+/// roxmltree limits the nesting of entity references to 10 levels.
+const ENTITY_REFERENCE_DEPTH: usize = 10;
+
+/// The entities declared in the DOCTYPE, with the nesting depth of their values once expanded.
+#[derive(Default)]
+struct EntityDepths<'x> {
+    values: HashMap<&'x [u8], &'x [u8]>,
+    depths: HashMap<&'x [u8], usize>,
+}
+
+impl<'x> EntityDepths<'x> {
+    /// Returns the element nesting depth of the entity `name` with the entities it references expanded,
+    /// or `0` for unknown entities. Every entity is only scanned once, even if it's referenced many times.
+    /// `level` is the number of entity references around this one.
+    fn depth(&mut self, name: &'x [u8], level: usize) -> usize {
+        if let Some(depth) = self.depths.get(name) {
+            return *depth;
+        }
+        if level >= ENTITY_REFERENCE_DEPTH {
+            // the parser rejects the document because of the entity nesting anyway
+            return 0;
+        }
+        let Some(value) = self.values.get(name).copied() else {
+            return 0;
+        };
+        // recursive entities are rejected by the parser, they just must not recurse here
+        self.depths.insert(name, 0);
+        let depth = nesting(value, self, level + 1);
+        self.depths.insert(name, depth);
+        depth
+    }
+}
+
+/// Scans the markup and returns the maximum element nesting depth in the document, with the elements of
+/// entity values counted at the depth of their references. This is only an estimate for well-formed
+/// documents, malformed markup is left to the parser.
+fn max_nesting(bytes: &[u8]) -> usize {
+    nesting(bytes, &mut EntityDepths::default(), 0)
+}
+
+/// Returns the maximum element nesting depth of `bytes`, which are nested in `level` entity references,
+/// declaring and expanding the entities in `entities`.
+fn nesting<'x>(bytes: &'x [u8], entities: &mut EntityDepths<'x>, level: usize) -> usize {
+    let (mut depth, mut max_depth) = (0usize, 0usize);
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'&' {
+            // an entity reference expands to its value at the current depth
+            let end = skip_past(bytes, i + 1, b";");
+            if bytes.get(i + 1) != Some(&b'#') {
+                let name = &bytes[i + 1..end.saturating_sub(1).max(i + 1)];
+                max_depth = max_depth.max(depth + entities.depth(name, level));
+            }
+            i = end;
+            continue;
+        }
+        if bytes[i] != b'<' {
+            i += 1;
+            continue;
+        }
+
+        let rest = &bytes[i..];
+        if rest.starts_with(b"<!--") {
+            i = skip_past(bytes, i + 4, b"-->");
+        } else if rest.starts_with(b"<![CDATA[") {
+            i = skip_past(bytes, i + 9, b"]]>");
+        } else if rest.starts_with(b"<?") {
+            i = skip_past(bytes, i + 2, b"?>");
+        } else if rest.starts_with(b"<!") {
+            // DOCTYPE with an optional internal subset, entity values may contain markup
+            let mut brackets = 0usize;
+            let mut entity = None;
+            i += 2;
+            while i < bytes.len() {
+                match bytes[i] {
+                    b'[' => brackets += 1,
+                    b']' => brackets = brackets.saturating_sub(1),
+                    b'>' if brackets == 0 => break,
+                    b'<' if bytes[i..].starts_with(b"<!ENTITY") => {
+                        // the name of a general entity, parameter entities start with `%`
+                        let start = i + 8;
+                        let start = start
+                            + bytes[start..]
+                                .iter()
+                                .take_while(|b| b.is_ascii_whitespace())
+                                .count();
+                        let len = bytes[start..]
+                            .iter()
+                            .take_while(|b| !b.is_ascii_whitespace())
+                            .count();
+                        entity = Some(&bytes[start..start + len]).filter(|name| name != b"%");
+                        i = start + len;
+                        continue;
+                    }
+                    quote @ (b'"' | b'\'') => {
+                        let end = skip_past(bytes, i + 1, &[quote]);
+                        let value = &bytes[i + 1..end.saturating_sub(1).max(i + 1)];
+                        if let Some(name) = entity.take() {
+                            entities.values.insert(name, value);
+                        }
+                        i = end;
+                        continue;
+                    }
+                    _ => (),
+                }
+                i += 1;
+            }
+            i += 1;
+        } else if rest.starts_with(b"</") {
+            depth = depth.saturating_sub(1);
+            i = skip_past(bytes, i + 2, b">");
+        } else {
+            // a start tag, attribute values may contain `>`
+            let mut quote = None;
+            i += 1;
+            while i < bytes.len() {
+                match (quote, bytes[i]) {
+                    (Some(q), b) if b == q => quote = None,
+                    (None, b @ (b'"' | b'\'')) => quote = Some(b),
+                    (None, b'>') => break,
+                    _ => (),
+                }
+                i += 1;
+            }
+            if bytes.get(i.wrapping_sub(1)) != Some(&b'/') {
+                depth += 1;
+                max_depth = max_depth.max(depth);
+            }
+            i += 1;
+        }
+    }
+
+    max_depth
+}
+
+/// Parses the document after making sure it's not nested deeper than `Config.max_depth`.
+/// Both roxmltree and the conversion are recursive and would overflow the stack otherwise.
+fn parse_document<'input>(
+    xml: &'input str,
+    config: &Config,
+) -> Result<roxmltree::Document<'input>, Error> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("parse", bytes = xml.len()).entered();
+    if max_nesting(xml.as_bytes()) > config.max_depth {
+        return Err(Error::DepthLimitExceeded(config.max_depth));
+    }
+    let options = roxmltree::ParsingOptions {
+        allow_dtd: config.entity_resolver.is_some(),
+        ..roxmltree::ParsingOptions::default()
+    };
+    Ok(roxmltree::Document::parse_with_options(xml, options)?)
+}
+
+/// Prepares the input text for `parse_document`, see `Config.skip_leading_junk` and `Config.entity_resolver`.
+fn prepare_input<'x>(xml: &'x str, config: &Config) -> Result<Cow<'x, str>, Error> {
+    let xml = if config.skip_leading_junk {
+        &xml[leading_junk(xml).len()..]
+    } else {
+        xml
+    };
+    entities::resolve_external(xml, config)
+}
+
+/// Returns the text before the XML declaration, or before the first markup if there is no declaration.
+fn leading_junk(xml: &str) -> &str {
+    let declaration = xml
+        .match_indices("<?xml")
+        .find(|(i, _)| xml[i + 5..].starts_with(|c: char| c.is_ascii_whitespace() || c == '?'));
+    let start = match declaration {
+        Some((i, _)) => i,
+        None => xml
+            .match_indices('<')
+            .find(|(i, _)| {
+                xml[i + 1..]
+                    .starts_with(|c: char| c.is_alphabetic() || matches!(c, '_' | ':' | '!' | '?'))
+            })
+            .map_or(0, |(i, _)| i),
+    };
+    &xml[..start]
+}
+
+/// Reports the text that `Config.skip_leading_junk` skips, apart from byte order marks and whitespace.
+fn report_leading_junk(ctx: &mut Context, xml: &str) -> Result<(), Error> {
+    if !ctx.config.skip_leading_junk {
+        return Ok(());
+    }
+    let junk = leading_junk(xml).replace('\u{feff}', "");
+    let junk = junk.trim();
+    if junk.is_empty() {
+        return Ok(());
+    }
+    ctx.report("", || DiagnosticKind::SkippedPrefix(junk.to_owned()))
+}
+
+/// Returns the keys of all elements that occur more than once within the same parent.
+fn repeated_names(doc: &roxmltree::Document, config: &Config) -> Arc<HashSet<String>> {
+    let mut repeated = HashSet::new();
+    let mut siblings = HashSet::new();
+    // only the elements that are converted, the descendants of skipped elements don't count
+    let mut elements = vec![doc.root_element()];
+    while let Some(el) = elements.pop() {
+        siblings.clear();
+        for child in el
+            .children()
+            .filter(|n| n.is_element() && keep_element(config, n))
+        {
+            elements.push(child);
+            let name = element_key(config, &child);
+            if !siblings.contains(&name) {
+                siblings.insert(name);
+            } else if !repeated.contains(name.as_ref()) {
+                repeated.insert(name.into_owned());
+            }
+        }
+    }
+    Arc::new(repeated)
+}
+
+/// Returns the JSON key of an element or attribute name without `Config.xml_attr_prefix`: the local name,
+/// with the prefix of its namespace in `Config.namespace_prefixes` if there is one, e.g. `v:item`, or
+/// in Clark notation if `Config.clark_notation` is set, e.g. `{urn:v}item`.
+#[inline]
+fn key_name<'n>(config: &Config, namespace: Option<&str>, name: &'n str) -> Cow<'n, str> {
+    // `xmlns=""` undeclares the default namespace
+    let Some(uri) = namespace.filter(|uri| !uri.is_empty()) else {
+        return Cow::Borrowed(name);
+    };
+    if let Some(prefix) = config.namespace_prefixes.get(uri) {
+        Cow::Owned([prefix.as_str(), ":", name].concat())
+    } else if config.clark_notation {
+        Cow::Owned(["{", uri, "}", name].concat())
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+/// Splits an absolute path like `/a/{urn:b}c` into its names, keeping the `/` of namespace URIs in
+/// Clark notation, e.g. `["", "a", "{urn:b}c"]`.
+fn split_path(path: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut start = 0;
+    let mut in_uri = false;
+    for (i, c) in path.char_indices() {
+        match c {
+            '{' => in_uri = true,
+            '}' => in_uri = false,
+            '/' if !in_uri => {
+                names.push(&path[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    names.push(&path[start..]);
+    names
+}
+
+/// Returns the JSON key of the element, see `key_name` and `DefaultNamespace::IncludeUri`.
+#[inline]
+fn element_key<'input>(config: &Config, el: &roxmltree::Node<'_, 'input>) -> Cow<'input, str> {
+    let tag_name = el.tag_name();
+    match tag_name.namespace() {
+        Some(uri)
+            if config.default_namespace == DefaultNamespace::IncludeUri
+                && !config.namespace_prefixes.contains_key(uri)
+                && in_default_namespace(el) =>
+        {
+            Cow::Owned(["{", uri, "}", tag_name.name()].concat())
+        }
+        namespace => key_name(config, namespace, tag_name.name()),
+    }
+}
+
+/// Returns `true` if the element is in a namespace and written without a prefix, e.g. `<a xmlns="urn:d"/>`.
+fn in_default_namespace(el: &roxmltree::Node) -> bool {
+    if el.tag_name().namespace().is_none_or(str::is_empty) {
+        return false;
+    }
+    // roxmltree doesn't keep the prefix, so it is looked up in the start tag
+    let start_tag = &el.document().input_text()[el.range().start + 1..];
+    let qname_len = start_tag
+        .find(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>')
+        .unwrap_or(start_tag.len());
+    !start_tag[..qname_len].contains(':')
+}
+
+/// Validates the document and prepares the context before the root element is converted.
+fn start_conversion(ctx: &mut Context, doc: &roxmltree::Document) -> Result<(), Error> {
+    #[cfg(feature = "xsd")]
+    if let Some((schema, validation)) = &ctx.config.xsd_validation {
+        let findings = xsd::validate(schema, doc);
+        match validation {
+            XsdValidation::Fail if !findings.is_empty() => return Err(Error::Invalid(findings)),
+            XsdValidation::Fail => (),
+            XsdValidation::Report => {
+                for finding in findings {
+                    ctx.report(&finding.path, || finding.kind.clone())?;
+                }
+            }
+        }
+    }
+
+    // processing instructions outside of the root element have nowhere to go
+    for node in doc.root().children() {
+        if node.is_pi() {
+            ctx.report_dropped("", &node)?;
+        }
+    }
+
+    // the names can be given for several documents at once, e.g. by `xml_diff`
+    if ctx.config.array_inference == ArrayInference::DocumentWide && ctx.repeated_names.is_empty() {
+        ctx.repeated_names = repeated_names(doc, ctx.config);
+    }
+
+    if let Some(progress) = &ctx.config.progress_callback {
+        ctx.progress.total_elements = doc.descendants().filter(|n| n.is_element()).count();
+        (progress.callback)(ctx.progress);
+    }
+    Ok(())
+}
+
+/// Sends the final progress report, unless it was just sent.
+fn finish_conversion(ctx: &Context) {
+    if let Some(progress) = &ctx.config.progress_callback {
+        if !progress.is_due(ctx.progress.elements) {
+            (progress.callback)(ctx.progress);
+        }
+    }
+}
+
+/// Returns the prefixes and URIs of all namespace declarations in the document for
+/// `Config.emit_namespace_declarations`, with an empty prefix for the default namespace.
+fn namespace_declarations(
+    ctx: &mut Context,
+    doc: &roxmltree::Document,
+) -> Result<Map<String, Value>, Error> {
+    let mut declarations = Map::new();
+    for el in doc.descendants().filter(|n| n.is_element()) {
+        // the namespaces in scope include the ones declared by the ancestors
+        for ns in el.namespaces() {
+            let prefix = ns.name().unwrap_or_default();
+            match declarations.get(prefix) {
+                Some(uri) if uri != ns.uri() => {
+                    let path: String = el
+                        .ancestors()
+                        .filter(|n| n.is_element())
+                        .map(|n| ["/", n.tag_name().name()].concat())
+                        .collect::<Vec<_>>()
+                        .into_iter()
+                        .rev()
+                        .collect();
+                    let key = ["xmlns:", prefix].concat();
+                    ctx.report(&path, || DiagnosticKind::KeyCollision(key))?;
+                }
+                Some(_) => (),
+                None => {
+                    declarations.insert(prefix.to_owned(), Value::from(ns.uri()));
+                }
             }
         }
-        None => convert_no_text(el, config, path, json_type_value),
     }
+    Ok(declarations)
+}
+
+/// Adds the namespace declarations to the converted root element if `Config.emit_namespace_declarations`
+/// is set. Roots that were converted into a text value keep it under their text property name.
+fn add_namespace_declarations(
+    ctx: &mut Context,
+    doc: &roxmltree::Document,
+    value: Option<Value>,
+) -> Result<Option<Value>, Error> {
+    if !ctx.config.emit_namespace_declarations {
+        return Ok(value);
+    }
+    let declarations = namespace_declarations(ctx, doc)?;
+    if declarations.is_empty() {
+        return Ok(value);
+    }
+    let mut data = match value {
+        Some(Value::Object(data)) => data,
+        None | Some(Value::Null) => Map::new(),
+        Some(text) => {
+            let path = ["/", &element_key(ctx.config, &doc.root_element())].concat();
+            let mut data = Map::new();
+            data.insert(text_prop_name(ctx.config, &path).to_owned(), text);
+            data
+        }
+    };
+    let key = [ctx.config.xml_attr_prefix.as_str(), "xmlns"].concat();
+    if data.contains_key(&key) {
+        let path = ["/", doc.root_element().tag_name().name()].concat();
+        ctx.report(&path, || DiagnosticKind::KeyCollision(key.clone()))?;
+    }
+    data.insert(key, Value::Object(declarations));
+    Ok(Some(Value::Object(data)))
 }
 
-fn xml_to_map(e: &roxmltree::Node, config: &Config) -> Value {
+fn xml_to_map(ctx: &mut Context, doc: &roxmltree::Document) -> Result<Value, Error> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("convert").entered();
+    start_conversion(ctx, doc)?;
+    let e = doc.root_element();
+    let value = convert_node(ctx, &e, "")?;
+    let value = add_namespace_declarations(ctx, doc, value)?;
     let mut data = Map::new();
     data.insert(
-        e.tag_name().name().to_string(),
-        convert_node(&e, &config, &String::new()).unwrap_or(Value::Null),
+        element_key(ctx.config, &e).into_owned(),
+        value.unwrap_or(Value::Null),
     );
-    Value::Object(data)
+    let mut json = Value::Object(data);
+    if !ctx.config.idref_paths.is_empty() {
+        ids::resolve_idrefs(ctx, doc, &mut json)?;
+    }
+    if ctx.config.prune_empty != PruneEmpty::NONE {
+        for value in json
+            .as_object_mut()
+            .into_iter()
+            .flat_map(|data| data.values_mut())
+        {
+            prune::prune_empty_values(value, &ctx.config.prune_empty);
+        }
+    }
+    if ctx.config.single_key_objects != SingleKeyObjects::Keep {
+        flatten::collapse_single_key_objects(&mut json, &ctx.config.single_key_objects);
+    }
+    let json = post_process(ctx.config, json);
+    finish_conversion(ctx);
+    Ok(json)
 }
 
 /// Converts the given XML string into `serde::Value` using settings from `Config` struct.
-pub fn xml_str_to_json(xml: &str, config: &Config) -> Result<Value, roxmltree::Error> {
-    let doc = roxmltree::Document::parse(xml)?;
-    let root = doc.root_element();
-    Ok(xml_to_map(&root, config))
+pub fn xml_str_to_json(xml: &str, config: &Config) -> Result<Value, Error> {
+    let xml = prepare_input(xml, config)?;
+    let doc = parse_document(&xml, config)?;
+    xml_to_map(&mut Context::new(config, None), &doc)
 }
 
 /// Converts the given XML string into `serde::Value` using settings from `Config` struct.
-pub fn xml_string_to_json(xml: String, config: &Config) -> Result<Value, roxmltree::Error> {
+pub fn xml_string_to_json(xml: String, config: &Config) -> Result<Value, Error> {
     xml_str_to_json(xml.as_str(), config)
 }
 
+/// Converts the bytes of an XML document into `serde::Value` just like `xml_str_to_json`.
+/// The bytes are decoded by `xml_bytes_to_str`.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_bytes_to_json, Config};
+/// use serde_json::json;
+///
+/// let xml = "<price>€20</price>";
+/// assert_eq!(xml_bytes_to_json(xml.as_bytes(), &Config::new_with_defaults()).unwrap(), json!({"price": "€20"}));
+/// ```
+pub fn xml_bytes_to_json(bytes: &[u8], config: &Config) -> Result<Value, Error> {
+    xml_str_to_json(&xml_bytes_to_str(bytes, config)?, config)
+}
+
+/// Decodes the bytes of an XML document into a string for the other entry points. They are UTF-8, or any
+/// encoding supported by `decode_xml` with the `encoding` feature. Invalid bytes fail with `Error::Encoding`
+/// or are replaced with U+FFFD, depending on `Config.utf8_policy`. Valid UTF-8 input is borrowed.
+pub fn xml_bytes_to_str<'b>(bytes: &'b [u8], config: &Config) -> Result<Cow<'b, str>, Error> {
+    #[cfg(feature = "encoding")]
+    return encoding::decode(bytes, config.utf8_policy);
+    #[cfg(not(feature = "encoding"))]
+    match config.utf8_policy {
+        Utf8Policy::Strict => std::str::from_utf8(bytes)
+            .map(Cow::Borrowed)
+            .map_err(|e| Error::Encoding(format!("invalid UTF-8: {}", e))),
+        Utf8Policy::Lossy => Ok(String::from_utf8_lossy(bytes)),
+    }
+}
+
+/// Converts the given XML string into `serde::Value` just like `xml_str_to_json` and records
+/// every lossy event (dropped mixed content, key collisions, failed type coercions, ignored
+/// namespaces) in `diagnostics`. The conversion itself never fails because of them, unless
+/// `Config.strict` is set.
+pub fn xml_str_to_json_with_diagnostics(
+    xml: &str,
+    config: &Config,
+    diagnostics: &mut Diagnostics,
+) -> Result<Value, Error> {
+    let mut ctx = Context::new(config, Some(diagnostics));
+    report_leading_junk(&mut ctx, xml)?;
+    let xml = prepare_input(xml, config)?;
+    let doc = parse_document(&xml, config)?;
+    xml_to_map(&mut ctx, &doc)
+}
+
+/// Returns all elements at the absolute `record_path` in document order, e.g. every `item` for `/feed/item`.
+fn find_records<'a, 'input>(
+    doc: &'a roxmltree::Document<'input>,
+    record_path: &str,
+    config: &Config,
+) -> Vec<roxmltree::Node<'a, 'input>> {
+    let split = split_path(record_path);
+    let mut names = split.iter().skip(1).copied();
+    let root = doc.root_element();
+    let mut nodes = match names.next() {
+        Some(name) if element_key(config, &root) == name => vec![root],
+        _ => return Vec::new(),
+    };
+    for name in names {
+        nodes = nodes
+            .iter()
+            .flat_map(|n| n.children())
+            .filter(|n| n.is_element() && element_key(config, n) == name)
+            .collect();
+    }
+    nodes
+}
+
+/// Converts every element at the absolute `record_path` with the same rules as `xml_str_to_json`,
+/// e.g. `<feed><item><a>1</a></item><item><a>2</a></item></feed>` into `[{"a":1},{"a":2}]` for `/feed/item`.
+/// The values are not wrapped into an object with the element name.
+pub(crate) fn xml_records_to_json(
+    xml: &str,
+    record_path: &str,
+    config: &Config,
+) -> Result<Vec<Value>, Error> {
+    if config.match_limit.is_some() {
+        // the records are read in chunks, so the document is only parsed up to the last one
+        return XmlRecords::new(xml.as_bytes(), record_path, config).collect();
+    }
+    let xml = prepare_input(xml, config)?;
+    let doc = parse_document(&xml, config)?;
+    let mut ctx = Context::new(config, None);
+    let record_name = split_path(record_path).pop().unwrap_or_default();
+    let parent_path = record_path
+        .strip_suffix(record_name)
+        .and_then(|parent| parent.strip_suffix('/'))
+        .unwrap_or_default();
+    find_records(&doc, record_path, config)
+        .iter()
+        .map(|node| {
+            let value = convert_node(&mut ctx, node, parent_path)?.unwrap_or(Value::Null);
+            Ok(post_process(config, value))
+        })
+        .collect()
+}
+
+/// Applies `Config.post_process` to a converted document or record.
+#[inline]
+fn post_process(config: &Config, value: Value) -> Value {
+    match &config.post_process {
+        Some(PostProcess(f)) => f(value),
+        None => value,
+    }
+}
+
+/// The keys of the arrays of an element that were truncated by `Config.array_limits`, with their limit and
+/// the number of dropped elements.
+type Truncated<'c> = Vec<(String, &'c ArrayLimit, usize)>;
+
+/// Returns `true` if the child element with `key` of the element at `path` is dropped, because its array
+/// already has `items` items and reached its limit in `Config.array_limits`. The child is counted in `truncated`.
+fn truncate_child<'c>(
+    config: &'c Config,
+    path: &str,
+    key: &str,
+    items: usize,
+    truncated: &mut Truncated<'c>,
+) -> bool {
+    let Some(limit) = config.array_limits.get(&[path, "/", key].concat()) else {
+        return false;
+    };
+    if items < limit.max_items {
+        return false;
+    }
+    match truncated.iter_mut().find(|(k, _, _)| k == key) {
+        Some((_, _, dropped)) => *dropped += 1,
+        None => truncated.push((key.to_owned(), limit, 1)),
+    }
+    true
+}
+
+/// Returns `false` if `Config.node_filter` skips the element.
+#[inline]
+fn keep_element(config: &Config, el: &roxmltree::Node) -> bool {
+    match &config.node_filter {
+        Some(NodeFilter(f)) => f(el),
+        None => true,
+    }
+}
+
+/// Returns whether the element with text has attributes that are converted, see `Config.text_attributes`.
+fn has_text_attributes(config: &Config, el: &roxmltree::Node) -> bool {
+    match &config.text_attributes {
+        TextAttributes::Keep => el.attributes().len() > 0,
+        TextAttributes::Drop => false,
+        TextAttributes::KeepOnly(names) => el
+            .attributes()
+            .any(|attr| names.contains(key_name(config, attr.namespace(), attr.name()).as_ref())),
+    }
+}
+
+/// Returns whether the attribute `name` of an element with text is converted, see `Config.text_attributes`.
+fn keep_text_attribute(config: &Config, name: &str) -> bool {
+    match &config.text_attributes {
+        TextAttributes::Keep => true,
+        TextAttributes::Drop => false,
+        TextAttributes::KeepOnly(names) => names.contains(name),
+    }
+}
+
+/// Returns whether the element without text is converted into the value of its only attribute, see
+/// `Config.collapse_single_attributes`.
+fn collapses_to_attribute(config: &Config, el: &roxmltree::Node) -> bool {
+    config.collapse_single_attributes
+        && el.attributes().len() == 1
+        && !el
+            .children()
+            .any(|child| child.is_element() && keep_element(config, &child))
+}
+
+/// Returns the property name of the text of the element at `path`, see `Config.text_prop_names`.
+fn text_prop_name<'c>(config: &'c Config, path: &str) -> &'c str {
+    if config.text_prop_names.is_empty() {
+        return &config.xml_text_node_prop_name;
+    }
+    config
+        .text_prop_names
+        .get(path)
+        .unwrap_or(&config.xml_text_node_prop_name)
+}
+
+/// Returns `true` if the element at `path` is converted into its text content, see `Config.inner_text_paths`.
+#[inline]
+fn is_inner_text(config: &Config, path: &str) -> bool {
+    !config.inner_text_paths.is_empty() && config.inner_text_paths.contains(path)
+}
+
+/// Returns the text of all descendants of the element in document order without leading and trailing
+/// whitespace, like the DOM `textContent`.
+fn text_content(config: &Config, el: &roxmltree::Node, path: &str) -> Result<String, Error> {
+    let text: String = el
+        .descendants()
+        .filter(|n| n.is_text())
+        .filter_map(|n| n.text())
+        .collect();
+    #[cfg(feature = "pseudonymize")]
+    if pseudonymize::is_pseudonymized(config, path) {
+        return pseudonymize::pseudonymize(config, text.trim());
+    }
+    #[cfg(not(feature = "pseudonymize"))]
+    let _ = path;
+    Ok(truncate_string(config, text.trim()).into_owned())
+}
+
+/// Returns `text` shortened to `Config.max_string_len` characters, including the `Config.string_ellipsis`
+/// at its end, or `text` itself if it's short enough.
+#[inline]
+fn truncate_string<'t>(config: &Config, text: &'t str) -> Cow<'t, str> {
+    let Some(max_len) = config.max_string_len else {
+        return Cow::Borrowed(text);
+    };
+    // a text with at most `max_len` bytes can't have more characters
+    if text.len() <= max_len || text.chars().count() <= max_len {
+        return Cow::Borrowed(text);
+    }
+    let kept = max_len.saturating_sub(config.string_ellipsis.chars().count());
+    let end = text.char_indices().nth(kept).map_or(text.len(), |(i, _)| i);
+    Cow::Owned([&text[..end], &config.string_ellipsis].concat())
+}
+
+/// Returns `true` if the element at `path` has the `xsi:nil="true"` attribute and is converted into `null`,
+/// see `Config.nillable_paths` and `Config.nillable_everywhere`.
+#[cfg(feature = "json_types")]
+#[inline]
+fn is_nil(config: &Config, el: &roxmltree::Node, path: &str) -> bool {
+    (config.nillable_everywhere || config.nillable_paths.contains(path))
+        && matches!(
+            el.attribute((XSI_NAMESPACE, "nil")),
+            Some("true") | Some("1")
+        )
+}
+
+/// The JSON type of all nodes without an override, borrowed instead of cloned for every node.
+#[cfg(feature = "json_types")]
+static INFER: JsonArray = JsonArray::Infer(JsonType::Infer);
+
 /// Returns a tuple for Array and Value enforcements for the current node or
 /// `(false, JsonArray::Infer(JsonType::Infer)` if the current path is not found
 /// in the list of paths with custom config.
@@ -483,13 +2716,24 @@ pub fn xml_string_to_json(xml: String, config: &Config) -> Result<Value, roxmltr
 #[inline]
 fn get_json_type_with_absolute_path<'conf>(
     config: &'conf Config,
-    path: &String,
+    path: &str,
 ) -> (bool, &'conf JsonType) {
-    match config
-        .json_type_overrides
-        .get(path)
-        .unwrap_or(&JsonArray::Infer(JsonType::Infer))
-    {
+    // most configs have no overrides at all, so don't even hash the path
+    if config.json_type_overrides.is_empty() {
+        return (false, &JsonType::Infer);
+    }
+    let json_type = match config.json_type_overrides.get(path) {
+        Some(json_type) => {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(path, "override applied");
+            if let Some(usage) = &config.override_usage {
+                usage.record_path(path);
+            }
+            json_type
+        }
+        None => &INFER,
+    };
+    match json_type {
         JsonArray::Infer(v) => (false, v),
         JsonArray::Always(v) => (true, v),
     }
@@ -499,7 +2743,7 @@ fn get_json_type_with_absolute_path<'conf>(
 #[cfg(feature = "json_types")]
 #[cfg(not(feature = "regex_path"))]
 #[inline]
-fn get_json_type<'conf>(config: &'conf Config, path: &String) -> (bool, &'conf JsonType) {
+fn get_json_type<'conf>(config: &'conf Config, path: &str) -> (bool, &'conf JsonType) {
     get_json_type_with_absolute_path(config, path)
 }
 
@@ -509,9 +2753,14 @@ fn get_json_type<'conf>(config: &'conf Config, path: &String) -> (bool, &'conf J
 #[cfg(feature = "json_types")]
 #[cfg(feature = "regex_path")]
 #[inline]
-fn get_json_type<'conf>(config: &'conf Config, path: &String) -> (bool, &'conf JsonType) {
-    for (regex, json_array) in &config.json_regex_type_overrides {
+fn get_json_type<'conf>(config: &'conf Config, path: &str) -> (bool, &'conf JsonType) {
+    for (i, (regex, json_array)) in config.json_regex_type_overrides.iter().enumerate() {
         if regex.is_match(path) {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(path, regex = regex.as_str(), "regex override applied");
+            if let Some(usage) = &config.override_usage {
+                usage.record_regex(i);
+            }
             return match json_array {
                 JsonArray::Infer(v) => (false, v),
                 JsonArray::Always(v) => (true, v),
@@ -525,6 +2774,6 @@ fn get_json_type<'conf>(config: &'conf Config, path: &String) -> (bool, &'conf J
 /// Always returns `(false, JsonArray::Infer(JsonType::Infer)` if `json_types` feature is not enabled.
 #[cfg(not(feature = "json_types"))]
 #[inline]
-fn get_json_type<'conf>(_config: &'conf Config, _path: &String) -> (bool, &'conf JsonType) {
+fn get_json_type<'conf>(_config: &'conf Config, _path: &str) -> (bool, &'conf JsonType) {
     (false, &JsonType::Infer)
 }
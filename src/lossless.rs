@@ -0,0 +1,384 @@
+//! A lossless JSON representation of XML documents that can be converted back byte-for-byte.
+//!
+//! The regular conversion produces idiomatic JSON, but drops the order of mixed content, comments,
+//! processing instructions, namespace prefixes and formatting. This representation keeps all of it
+//! as an ordered tree of nodes, so `lossless_json_to_xml(&xml_str_to_lossless_json(xml, &conf)?)?`
+//! reproduces `xml` exactly.
+
+use serde_json::{Map, Value};
+
+use crate::{parse_document, Config, Error};
+
+/// Converts an XML document into a lossless JSON representation.
+///
+/// Every node is a JSON object identified by one of these keys:
+/// * `{"document": [...]}` - the document with all top-level nodes, including whitespace between them
+/// * `{"element": "ns:a", "attributes": [...], "children": [...]}` - an element with its qualified name
+///   as written in the source. `attributes` and `children` are omitted if there are none. Namespace
+///   declarations are kept as `xmlns` attributes. `"empty": true` marks a self-closing tag like `<a/>`.
+/// * `{"text": "a < b"}` - a text node with entity references resolved
+/// * `{"cdata": "a < b"}` - a CDATA section
+/// * `{"comment": " note "}` - a comment
+/// * `{"pi": "target", "data": " value"}` - a processing instruction, including the XML declaration
+///
+/// Attributes are objects with `name` and `value`. Formatting that differs from the defaults is kept in
+/// optional properties: `quote` (`'` instead of `"`), `ws` (whitespace before an attribute if not a single
+/// space, or before the end of a tag), `eq` (the `=` with surrounding whitespace) and `end_ws` (whitespace
+/// in an end tag). Text and attribute values that were not written with the minimal escaping keep their
+/// source form in `raw`. It is used for the output as long as it still matches the value, so values can be
+/// edited without caring about `raw`.
+///
+/// The document is checked for well-formedness and `Config.max_depth` first, other `Config` options
+/// don't apply to this representation.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{lossless_json_to_xml, xml_str_to_lossless_json, Config};
+///
+/// let xml = "<a x='1'>text <!-- note --><b/>tail</a>";
+/// let json = xml_str_to_lossless_json(xml, &Config::new_with_defaults()).unwrap();
+/// assert_eq!(json["document"][0]["children"][1], serde_json::json!({"comment": " note "}));
+/// assert_eq!(lossless_json_to_xml(&json).unwrap(), xml);
+/// ```
+pub fn xml_str_to_lossless_json(xml: &str, config: &Config) -> Result<Value, Error> {
+    parse_document(xml, config)?;
+
+    // the document is well-formed, so the tokenizer doesn't need to handle any errors
+    let mut stack: Vec<(Map<String, Value>, Vec<Value>)> = vec![(Map::new(), Vec::new())];
+    let mut i = 0;
+    while i < xml.len() {
+        let rest = &xml[i..];
+        let (node, end) = if rest.starts_with("<!--") {
+            let end = find_after(xml, i + 4, "-->");
+            (node("comment", &xml[i + 4..end - 3]), end)
+        } else if rest.starts_with("<![CDATA[") {
+            let end = find_after(xml, i + 9, "]]>");
+            (node("cdata", &xml[i + 9..end - 3]), end)
+        } else if rest.starts_with("<?") {
+            let end = find_after(xml, i + 2, "?>");
+            let content = &xml[i + 2..end - 2];
+            let target_end = content
+                .find(|c: char| c.is_ascii_whitespace())
+                .unwrap_or(content.len());
+            let mut pi = node("pi", &content[..target_end]);
+            if target_end < content.len() {
+                insert(&mut pi, "data", &content[target_end..]);
+            }
+            (pi, end)
+        } else if rest.starts_with("</") {
+            let end = find_after(xml, i + 2, ">");
+            let tag = &xml[i + 2..end - 1];
+            let name_end = tag.trim_end().len();
+            let (mut element, children) = stack.pop().unwrap_or_default();
+            if !children.is_empty() {
+                element.insert("children".to_owned(), Value::Array(children));
+            }
+            if name_end < tag.len() {
+                insert_value(&mut element, "end_ws", &tag[name_end..]);
+            }
+            (Value::Object(element), end)
+        } else if rest.starts_with('<') {
+            let (element, end, empty) = start_tag(xml, i);
+            if !empty {
+                stack.push((element, Vec::new()));
+                i = end;
+                continue;
+            }
+            (Value::Object(element), end)
+        } else {
+            let end = xml[i..].find('<').map_or(xml.len(), |pos| i + pos);
+            (text_node(&xml[i..end]), end)
+        };
+
+        if let Some((_, children)) = stack.last_mut() {
+            children.push(node);
+        }
+        i = end;
+    }
+
+    let (_, children) = stack.pop().unwrap_or_default();
+    let mut document = Map::new();
+    document.insert("document".to_owned(), Value::Array(children));
+    Ok(Value::Object(document))
+}
+
+/// Converts the lossless JSON representation produced by `xml_str_to_lossless_json` back into XML.
+/// Fails with `Error::UnexpectedJson` if the value doesn't have the expected structure.
+pub fn lossless_json_to_xml(json: &Value) -> Result<String, Error> {
+    let mut xml = String::new();
+    match json.get("document") {
+        Some(Value::Array(nodes)) => {
+            for node in nodes {
+                write_node(&mut xml, node)?;
+            }
+        }
+        // a single node is accepted as well, e.g. an element taken out of a document
+        _ => write_node(&mut xml, json)?,
+    }
+    Ok(xml)
+}
+
+/// Returns the index right after the first occurrence of `pattern` at or after `from`.
+fn find_after(xml: &str, from: usize, pattern: &str) -> usize {
+    xml[from..]
+        .find(pattern)
+        .map_or(xml.len(), |pos| from + pos + pattern.len())
+}
+
+/// Parses the start tag at `from` and returns the element without children, the index after the tag
+/// and whether the element is self-closing.
+fn start_tag(xml: &str, from: usize) -> (Map<String, Value>, usize, bool) {
+    let bytes = xml.as_bytes();
+    let is_name_end = |b: u8| b.is_ascii_whitespace() || b == b'/' || b == b'>' || b == b'=';
+    let skip_while = |mut i: usize, f: &dyn Fn(u8) -> bool| {
+        while i < bytes.len() && f(bytes[i]) {
+            i += 1;
+        }
+        i
+    };
+
+    let name_end = skip_while(from + 1, &|b| !is_name_end(b));
+    let mut element = Map::new();
+    insert_value(&mut element, "element", &xml[from + 1..name_end]);
+
+    let mut attributes = Vec::new();
+    let mut i = name_end;
+    loop {
+        let ws_end = skip_while(i, &|b| b.is_ascii_whitespace());
+        let ws = &xml[i..ws_end];
+        i = ws_end;
+        if i >= bytes.len() || bytes[i] == b'/' || bytes[i] == b'>' {
+            if !ws.is_empty() {
+                insert_value(&mut element, "ws", ws);
+            }
+            break;
+        }
+
+        let name_end = skip_while(i, &|b| !is_name_end(b));
+        let mut attribute = Map::new();
+        insert_value(&mut attribute, "name", &xml[i..name_end]);
+        let quote_start = skip_while(name_end, &|b| b != b'"' && b != b'\'');
+        let quote = bytes.get(quote_start).copied().unwrap_or(b'"');
+        let value_end = skip_while(quote_start + 1, &|b| b != quote);
+        let raw = &xml[(quote_start + 1).min(value_end)..value_end];
+        let (value, raw) = decode_value(raw, quote == b'\'');
+        attribute.insert("value".to_owned(), Value::String(value));
+        if let Some(raw) = raw {
+            insert_value(&mut attribute, "raw", raw);
+        }
+        if quote == b'\'' {
+            insert_value(&mut attribute, "quote", "'");
+        }
+        if ws != " " {
+            insert_value(&mut attribute, "ws", ws);
+        }
+        let eq = &xml[name_end..quote_start.min(xml.len())];
+        if eq != "=" {
+            insert_value(&mut attribute, "eq", eq);
+        }
+        attributes.push(Value::Object(attribute));
+        i = value_end + 1;
+    }
+    if !attributes.is_empty() {
+        element.insert("attributes".to_owned(), Value::Array(attributes));
+    }
+
+    let empty = bytes.get(i) == Some(&b'/');
+    if empty {
+        element.insert("empty".to_owned(), Value::Bool(true));
+        i += 1;
+    }
+    (element, i + 1, empty)
+}
+
+/// Resolves predefined and character entity references. References to entities declared
+/// in the DTD are left as they are.
+fn unescape(raw: &str) -> String {
+    let mut text = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(pos) = rest.find('&') {
+        text.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        let resolved = rest.find(';').and_then(|end| {
+            let c = match &rest[1..end] {
+                "lt" => '<',
+                "gt" => '>',
+                "amp" => '&',
+                "apos" => '\'',
+                "quot" => '"',
+                name => {
+                    let code = match name.strip_prefix("#x") {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => name.strip_prefix('#').and_then(|d| d.parse().ok()),
+                    };
+                    code.and_then(char::from_u32)?
+                }
+            };
+            Some((c, end))
+        });
+        match resolved {
+            Some((c, end)) => {
+                text.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                text.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    text.push_str(rest);
+    text
+}
+
+/// Escapes the text with the minimum of entity references for a text node or an attribute value
+/// in the given quotes.
+fn escape(text: &str, quote: Option<char>) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '"' if quote == Some('"') => escaped.push_str("&quot;"),
+            '\'' if quote == Some('\'') => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Returns the resolved value and the source form if escaping the value wouldn't reproduce it.
+fn decode_value(raw: &str, single_quoted: bool) -> (String, Option<&str>) {
+    let value = unescape(raw);
+    let quote = if single_quoted { '\'' } else { '"' };
+    if escape(&value, Some(quote)) == raw {
+        (value, None)
+    } else {
+        (value, Some(raw))
+    }
+}
+
+fn node(key: &str, value: &str) -> Value {
+    let mut node = Map::new();
+    insert_value(&mut node, key, value);
+    Value::Object(node)
+}
+
+fn text_node(raw: &str) -> Value {
+    let mut node = Map::new();
+    let value = unescape(raw);
+    if escape(&value, None) != raw {
+        insert_value(&mut node, "raw", raw);
+    }
+    node.insert("text".to_owned(), Value::String(value));
+    Value::Object(node)
+}
+
+fn insert(node: &mut Value, key: &str, value: &str) {
+    if let Value::Object(map) = node {
+        insert_value(map, key, value);
+    }
+}
+
+fn insert_value(map: &mut Map<String, Value>, key: &str, value: &str) {
+    map.insert(key.to_owned(), Value::String(value.to_owned()));
+}
+
+fn unexpected(message: &str, node: &Value) -> Error {
+    Error::UnexpectedJson(format!("{}: {}", message, node))
+}
+
+fn get_str<'a>(node: &'a Value, key: &str) -> Result<Option<&'a str>, Error> {
+    match node.get(key) {
+        None => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s)),
+        Some(_) => Err(unexpected(&format!("`{}` must be a string", key), node)),
+    }
+}
+
+fn get_array<'a>(node: &'a Value, key: &str) -> Result<&'a [Value], Error> {
+    match node.get(key) {
+        None => Ok(&[]),
+        Some(Value::Array(items)) => Ok(items),
+        Some(_) => Err(unexpected(&format!("`{}` must be an array", key), node)),
+    }
+}
+
+/// Writes the value or its source form if it still resolves to the same value.
+fn write_value(
+    xml: &mut String,
+    node: &Value,
+    value: &str,
+    quote: Option<char>,
+) -> Result<(), Error> {
+    match get_str(node, "raw")? {
+        Some(raw) if unescape(raw) == value => xml.push_str(raw),
+        _ => xml.push_str(&escape(value, quote)),
+    }
+    Ok(())
+}
+
+fn write_node(xml: &mut String, node: &Value) -> Result<(), Error> {
+    if let Some(name) = get_str(node, "element")? {
+        xml.push('<');
+        xml.push_str(name);
+        for attribute in get_array(node, "attributes")? {
+            let (Some(name), Some(value)) =
+                (get_str(attribute, "name")?, get_str(attribute, "value")?)
+            else {
+                return Err(unexpected(
+                    "attributes need a `name` and a `value`",
+                    attribute,
+                ));
+            };
+            let quote = match get_str(attribute, "quote")? {
+                Some("'") => '\'',
+                _ => '"',
+            };
+            xml.push_str(get_str(attribute, "ws")?.unwrap_or(" "));
+            xml.push_str(name);
+            xml.push_str(get_str(attribute, "eq")?.unwrap_or("="));
+            xml.push(quote);
+            write_value(xml, attribute, value, Some(quote))?;
+            xml.push(quote);
+        }
+        xml.push_str(get_str(node, "ws")?.unwrap_or_default());
+
+        let children = get_array(node, "children")?;
+        if children.is_empty() && node.get("empty") == Some(&Value::Bool(true)) {
+            xml.push_str("/>");
+            return Ok(());
+        }
+        xml.push('>');
+        for child in children {
+            write_node(xml, child)?;
+        }
+        xml.push_str("</");
+        xml.push_str(name);
+        xml.push_str(get_str(node, "end_ws")?.unwrap_or_default());
+        xml.push('>');
+    } else if let Some(text) = get_str(node, "text")? {
+        write_value(xml, node, text, None)?;
+    } else if let Some(text) = get_str(node, "cdata")? {
+        xml.push_str("<![CDATA[");
+        xml.push_str(text);
+        xml.push_str("]]>");
+    } else if let Some(text) = get_str(node, "comment")? {
+        xml.push_str("<!--");
+        xml.push_str(text);
+        xml.push_str("-->");
+    } else if let Some(target) = get_str(node, "pi")? {
+        xml.push_str("<?");
+        xml.push_str(target);
+        if let Some(data) = get_str(node, "data")? {
+            // the data must be separated from the target
+            if !data.starts_with(|c: char| c.is_ascii_whitespace()) {
+                xml.push(' ');
+            }
+            xml.push_str(data);
+        }
+        xml.push_str("?>");
+    } else {
+        return Err(unexpected("unknown node", node));
+    }
+    Ok(())
+}
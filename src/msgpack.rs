@@ -0,0 +1,28 @@
+//! Conversion of XML into MessagePack, available with the `msgpack` feature.
+
+use crate::{xml_to_serializer, Config, Error};
+
+/// Converts an XML document into MessagePack with the same rules as `xml_to_serializer`, without
+/// building a `serde_json::Value` first. Objects are encoded as maps with string keys, so the output
+/// decodes back into the same JSON. Errors of the encoder are returned as `Error::Serialize`.
+///
+/// The steps that need the whole converted document are skipped, so the output differs from
+/// `xml_str_to_json` if any of these are set: `Config.post_process`, `Config.prune_empty`,
+/// `Config.single_key_objects` and `Config.idref_paths`.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_str_to_msgpack, Config};
+///
+/// let bytes = xml_str_to_msgpack("<a><b>1</b></a>", &Config::new_with_defaults()).unwrap();
+/// let json: serde_json::Value = rmp_serde::from_slice(&bytes).unwrap();
+/// assert_eq!(json, serde_json::json!({"a": {"b": 1}}));
+/// ```
+pub fn xml_str_to_msgpack(xml: &str, config: &Config) -> Result<Vec<u8>, Error> {
+    let mut buf = Vec::new();
+    xml_to_serializer(
+        xml,
+        config,
+        &mut rmp_serde::Serializer::new(&mut buf).with_struct_map(),
+    )?;
+    Ok(buf)
+}
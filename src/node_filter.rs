@@ -0,0 +1,22 @@
+//! A predicate that decides which elements are converted.
+
+use std::fmt;
+
+/// A function that is called with every element before it is converted. Elements it returns `false`
+/// for are skipped together with their descendants. Set it with `Config::with_node_filter`.
+pub struct NodeFilter(pub(crate) Box<dyn Fn(&roxmltree::Node) -> bool + Send + Sync>);
+
+impl NodeFilter {
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&roxmltree::Node) -> bool + Send + Sync + 'static,
+    {
+        NodeFilter(Box::new(f))
+    }
+}
+
+impl fmt::Debug for NodeFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("NodeFilter").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,190 @@
+//! Selection of parts of the converted output with JSON Pointers, without converting the whole document.
+
+use serde_json::Value;
+
+#[cfg(feature = "json_types")]
+use crate::is_nil;
+use crate::{
+    convert_node, element_key, get_json_type, is_inner_text, key_name, parse_document,
+    prepare_input, repeated_names, ArrayInference, Config, Context, Error, NullValue,
+};
+
+/// Converts only the parts of the document that the JSON Pointers (RFC 6901) select and returns one
+/// value per pointer, or `None` if the pointer selects nothing. The values are the same as
+/// `xml_str_to_json(xml, config)?.pointer(pointer)` would return, without converting the whole document.
+///
+/// The steps that need the whole converted document are skipped, so the values differ from
+/// `xml_str_to_json` if any of these are set: `Config.post_process`, `Config.prune_empty`,
+/// `Config.single_key_objects`, `Config.idref_paths` and `Config.xsd_validation`.
+///
+/// Without repeated elements, the pointers look like the paths of `Config.add_json_type_override`,
+/// e.g. `/a/b/@c`. Repeated elements are selected by index, e.g. `/a/b/1/@c` for the second `b`.
+/// Elements that are not selected are skipped, so they are neither converted nor reported as diagnostics.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_str_to_json_pointers, Config};
+/// use serde_json::json;
+///
+/// let xml = r#"<a><b id="1">x</b><b id="2">y</b><c>large</c></a>"#;
+/// let values = xml_str_to_json_pointers(xml, &["/a/b/1/@id", "/a/d"], &Config::new_with_defaults()).unwrap();
+/// assert_eq!(values, vec![Some(json!(2)), None]);
+/// ```
+pub fn xml_str_to_json_pointers(
+    xml: &str,
+    pointers: &[&str],
+    config: &Config,
+) -> Result<Vec<Option<Value>>, Error> {
+    let xml = prepare_input(xml, config)?;
+    let doc = parse_document(&xml, config)?;
+    let mut ctx = Context::new(config, None);
+    if config.array_inference == ArrayInference::DocumentWide {
+        ctx.repeated_names = repeated_names(&doc, config);
+    }
+
+    let root = doc.root_element();
+    pointers
+        .iter()
+        .map(|pointer| pointer_value(&mut ctx, root, pointer))
+        .collect()
+}
+
+/// Returns the value that the JSON Pointer selects from the converted document with the root element `root`.
+pub(crate) fn pointer_value(
+    ctx: &mut Context,
+    root: roxmltree::Node,
+    pointer: &str,
+) -> Result<Option<Value>, Error> {
+    if pointer.is_empty() {
+        let mut data = serde_json::Map::new();
+        data.insert(
+            element_key(ctx.config, &root).into_owned(),
+            convert_node(ctx, &root, "")?.unwrap_or(Value::Null),
+        );
+        return Ok(Some(Value::Object(data)));
+    }
+    let Some(pointer) = pointer.strip_prefix('/') else {
+        return Ok(None);
+    };
+    let segments = segments(pointer);
+    if segments[0] != element_key(ctx.config, &root) {
+        return Ok(None);
+    }
+    ctx.depth = 0;
+    select(ctx, root, "", &segments[1..])
+}
+
+/// Splits a JSON Pointer without the leading `/` into its unescaped segments.
+pub(crate) fn segments(pointer: &str) -> Vec<String> {
+    pointer
+        .split('/')
+        .map(|s| s.replace("~1", "/").replace("~0", "~"))
+        .collect()
+}
+
+/// Returns the part of the converted element `el` that `segments` select. The document is followed
+/// element by element as long as the shape of the output is known, and the rest is converted.
+fn select(
+    ctx: &mut Context,
+    el: roxmltree::Node,
+    parent_path: &str,
+    segments: &[String],
+) -> Result<Option<Value>, Error> {
+    let Some((name, rest)) = segments.split_first() else {
+        return convert_node(ctx, &el, parent_path);
+    };
+    let path = [parent_path, "/", &element_key(ctx.config, &el)].concat();
+    let Some((children, is_array)) = known_children(ctx, el, &path, name) else {
+        let value = convert_node(ctx, &el, parent_path)?;
+        return Ok(value.and_then(|v| pointer_get(v, segments)));
+    };
+
+    ctx.tick()?;
+    if ctx.depth >= ctx.config.max_depth {
+        return Err(Error::DepthLimitExceeded(ctx.config.max_depth));
+    }
+    ctx.depth += 1;
+    let value = if is_array {
+        match rest.split_first() {
+            None => {
+                let mut items = Vec::with_capacity(children.len());
+                for child in &children {
+                    items.extend(convert_node(ctx, child, &path)?);
+                }
+                Ok(Some(Value::Array(items)))
+            }
+            Some((index, rest)) => match parse_index(index).and_then(|i| children.get(i)) {
+                Some(child) => select(ctx, *child, &path, rest),
+                None => Ok(None),
+            },
+        }
+    } else {
+        select(ctx, children[0], &path, rest)
+    };
+    ctx.depth -= 1;
+    value
+}
+
+/// Returns the child elements that become the property `name` of the converted element `el` at `path`,
+/// and whether the property is an array, or `None` if that's not known without converting `el`.
+pub(crate) fn known_children<'a, 'input>(
+    ctx: &Context,
+    el: roxmltree::Node<'a, 'input>,
+    path: &str,
+    name: &str,
+) -> Option<(Vec<roxmltree::Node<'a, 'input>>, bool)> {
+    let children: Vec<_> = el
+        .children()
+        .filter(|c| c.is_element() && element_key(ctx.config, c) == name)
+        .collect();
+
+    let has_text = el.text().is_some_and(|t| !t.trim().is_empty());
+    let attr_key = |attr: roxmltree::Attribute| {
+        let key = key_name(ctx.config, attr.namespace(), attr.name());
+        [ctx.config.xml_attr_prefix.as_str(), &key].concat() == name
+    };
+    #[cfg(feature = "json_types")]
+    let nillable = is_nil(ctx.config, &el, path);
+    #[cfg(not(feature = "json_types"))]
+    let nillable = false;
+    // ignored empty and skipped elements change the number of items, attributes can collide with the elements
+    if children.is_empty()
+        || has_text
+        || nillable
+        || is_inner_text(ctx.config, path)
+        || matches!(ctx.config.empty_element_handling, NullValue::Ignore)
+        || ctx.config.node_filter.is_some()
+        || el.attributes().any(attr_key)
+    {
+        return None;
+    }
+
+    let child_path = [path, "/", name].concat();
+    if ctx.config.array_limits.contains_key(&child_path) {
+        return None;
+    }
+    let (json_type_array, _) = get_json_type(ctx.config, &child_path);
+    let is_array = json_type_array || children.len() > 1 || ctx.repeated_names.contains(name);
+    Some((children, is_array))
+}
+
+/// Returns the array index of a pointer segment, which has no leading zeros or signs.
+fn parse_index(segment: &str) -> Option<usize> {
+    if segment.len() > 1 && segment.starts_with('0') || segment.starts_with('+') {
+        return None;
+    }
+    segment.parse().ok()
+}
+
+/// Returns the part of an already converted value that `segments` select.
+pub(crate) fn pointer_get(value: Value, segments: &[String]) -> Option<Value> {
+    segments
+        .iter()
+        .try_fold(value, |value, segment| match value {
+            Value::Object(mut map) => map.remove(segment),
+            Value::Array(mut items) => {
+                let index = parse_index(segment)?;
+                (index < items.len()).then(|| items.swap_remove(index))
+            }
+            _ => None,
+        })
+}
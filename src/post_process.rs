@@ -0,0 +1,24 @@
+//! A hook for custom changes of the converted output.
+
+use std::fmt;
+
+use serde_json::Value;
+
+/// A function that is applied to every converted document, and to every record of the streaming
+/// conversions, before it is returned. Set it with `Config::with_post_process`.
+pub struct PostProcess(pub(crate) Box<dyn Fn(Value) -> Value + Send + Sync>);
+
+impl PostProcess {
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(Value) -> Value + Send + Sync + 'static,
+    {
+        PostProcess(Box::new(f))
+    }
+}
+
+impl fmt::Debug for PostProcess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PostProcess").finish_non_exhaustive()
+    }
+}
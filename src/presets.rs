@@ -0,0 +1,862 @@
+//! Ready-made configs for common XML formats, available with the `json_types` feature.
+
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use crate::{
+    convert_attributes, convert_node, finish_conversion, parse_document, prepare_input,
+    start_conversion, text_prop_name, Config, Context, Error, JsonArray, JsonType,
+};
+
+const SOAP_11_NAMESPACE: &str = "http://schemas.xmlsoap.org/soap/envelope/";
+const SOAP_12_NAMESPACE: &str = "http://www.w3.org/2003/05/soap-envelope";
+
+/// Returns a config for the payloads of SOAP 1.1 and 1.2 messages, to be used with `soap_body`.
+/// All elements with `xsi:nil="true"` become `null`, and the namespace prefixes of the payload are
+/// dropped from the keys like everywhere else.
+pub fn soap() -> Config {
+    Config {
+        nillable_everywhere: true,
+        ..Config::new_with_defaults()
+    }
+}
+
+/// A SOAP fault, returned as `Error::SoapFault` by `soap_body`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoapFault {
+    /// `faultcode` of SOAP 1.1 or `Code/Value` of SOAP 1.2, e.g. `soap:Server`.
+    pub code: String,
+    /// `faultstring` of SOAP 1.1 or the first `Reason/Text` of SOAP 1.2.
+    pub reason: String,
+    /// `faultactor` of SOAP 1.1 or `Role` of SOAP 1.2.
+    pub actor: Option<String>,
+    /// The converted content of `detail` or `Detail`.
+    pub detail: Option<Value>,
+}
+
+impl fmt::Display for SoapFault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.reason)
+    }
+}
+
+/// Returns the converted content of the `Body` of a SOAP 1.1 or 1.2 envelope, without the `Envelope`,
+/// `Header` and `Body` layers, e.g. `{"GetPriceResponse":{"Price":1.9}}`. Faults are returned as
+/// `Error::SoapFault` and documents that are not SOAP envelopes as `Error::UnexpectedXml`.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{presets, Error};
+/// use serde_json::json;
+///
+/// let xml = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+///     <soap:Body><m:GetPriceResponse xmlns:m="urn:shop"><m:Price>1.9</m:Price></m:GetPriceResponse></soap:Body>
+/// </soap:Envelope>"#;
+/// let body = presets::soap_body(xml, &presets::soap()).unwrap();
+/// assert_eq!(body, json!({"GetPriceResponse": {"Price": 1.9}}));
+/// ```
+pub fn soap_body(xml: &str, config: &Config) -> Result<Value, Error> {
+    let xml = prepare_input(xml, config)?;
+    let doc = parse_document(&xml, config)?;
+    let envelope = doc.root_element();
+    let namespace = envelope.tag_name().namespace();
+    if envelope.tag_name().name() != "Envelope"
+        || !matches!(namespace, Some(SOAP_11_NAMESPACE) | Some(SOAP_12_NAMESPACE))
+    {
+        return Err(Error::UnexpectedXml(
+            "the root element is not a SOAP Envelope".to_owned(),
+        ));
+    }
+    let body = envelope
+        .children()
+        .find(|c| {
+            c.is_element() && c.tag_name().name() == "Body" && c.tag_name().namespace() == namespace
+        })
+        .ok_or_else(|| Error::UnexpectedXml("the SOAP Envelope has no Body".to_owned()))?;
+
+    let mut ctx = Context::new(config, None);
+    start_conversion(&mut ctx, &doc)?;
+    let mut data = Map::new();
+    for child in body.children().filter(|c| c.is_element()) {
+        ctx.depth = 2;
+        if child.tag_name().name() == "Fault" && child.tag_name().namespace() == namespace {
+            return Err(Error::SoapFault(fault(&mut ctx, child)?));
+        }
+        let Some(value) = convert_node(&mut ctx, &child, "/Envelope/Body")? else {
+            continue;
+        };
+        insert_repeated(&mut data, child.tag_name().name(), value);
+    }
+    finish_conversion(&ctx);
+    Ok(Value::Object(data))
+}
+
+/// Appends the value to the array property with the name, which is created for the first item.
+fn push_item(data: &mut Map<String, Value>, name: &str, value: Value) {
+    match data.get_mut(name) {
+        Some(Value::Array(items)) => items.push(value),
+        _ => {
+            data.insert(name.to_owned(), Value::Array(vec![value]));
+        }
+    }
+}
+
+/// Inserts the value, turning the property into an array when the name repeats.
+fn insert_repeated(data: &mut Map<String, Value>, name: &str, value: Value) {
+    match data.get_mut(name) {
+        Some(Value::Array(items)) => items.push(value),
+        Some(existing) => *existing = Value::Array(vec![existing.take(), value]),
+        None => {
+            data.insert(name.to_owned(), value);
+        }
+    }
+}
+
+/// Returns a config for GPX 1.0 and 1.1 files. Waypoints, routes, route points, tracks, track segments and
+/// track points are always arrays, so single-point tracks have the same structure as longer ones.
+/// `lat`, `lon` and `ele` are numbers and `time` stays an ISO 8601 string.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{presets, xml_str_to_json};
+/// use serde_json::json;
+///
+/// let xml = r#"<gpx><trk><trkseg><trkpt lat="47.6" lon="8.5"><ele>420</ele></trkpt></trkseg></trk></gpx>"#;
+/// let json = xml_str_to_json(xml, &presets::gpx()).unwrap();
+/// assert_eq!(json["gpx"]["trk"][0]["trkseg"][0]["trkpt"][0], json!({"@lat": 47.6, "@lon": 8.5, "ele": 420}));
+/// ```
+pub fn gpx() -> Config {
+    let mut rules: Vec<(String, JsonArray)> = ["/gpx/rte", "/gpx/trk", "/gpx/trk/trkseg"]
+        .into_iter()
+        .map(|path| (path.to_owned(), JsonArray::Always(JsonType::Infer)))
+        .collect();
+    for point in ["/gpx/wpt", "/gpx/rte/rtept", "/gpx/trk/trkseg/trkpt"] {
+        rules.push((point.to_owned(), JsonArray::Always(JsonType::Infer)));
+        for (field, json_type) in [
+            ("/@lat", JsonType::AlwaysNumber),
+            ("/@lon", JsonType::AlwaysNumber),
+            ("/ele", JsonType::AlwaysNumber),
+            ("/time", JsonType::AlwaysString),
+        ] {
+            rules.push(([point, field].concat(), JsonArray::Infer(json_type)));
+        }
+    }
+    with_rules(Config::new_with_defaults(), rules)
+}
+
+/// The number of nesting levels of OPML outlines that `opml` has rules for.
+const OPML_OUTLINE_LEVELS: usize = 16;
+
+/// Returns a config for OPML 1.0 and 2.0 outlines, e.g. podcast and feed subscription lists.
+/// Outlines are always arrays, up to 16 levels deep, and their common attributes like `text`, `title`,
+/// `xmlUrl` and `htmlUrl` are always strings, so a feed named `1984` keeps its name.
+/// The dates of the `head` stay RFC 822 strings and its window coordinates are numbers.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{presets, xml_str_to_json};
+/// use serde_json::json;
+///
+/// let xml = r#"<opml version="2.0"><body><outline text="1984" xmlUrl="https://example.com/feed"/></body></opml>"#;
+/// let json = xml_str_to_json(xml, &presets::opml()).unwrap();
+/// assert_eq!(json["opml"]["body"]["outline"], json!([{"@text": "1984", "@xmlUrl": "https://example.com/feed"}]));
+/// ```
+pub fn opml() -> Config {
+    let mut rules: Vec<(String, JsonArray)> = vec![
+        (
+            "/opml/@version".to_owned(),
+            JsonArray::Infer(JsonType::AlwaysString),
+        ),
+        (
+            "/opml/head/title".to_owned(),
+            JsonArray::Infer(JsonType::AlwaysString),
+        ),
+        (
+            "/opml/head/dateCreated".to_owned(),
+            JsonArray::Infer(JsonType::AlwaysString),
+        ),
+        (
+            "/opml/head/dateModified".to_owned(),
+            JsonArray::Infer(JsonType::AlwaysString),
+        ),
+        (
+            "/opml/head/expansionState".to_owned(),
+            JsonArray::Infer(JsonType::AlwaysString),
+        ),
+    ];
+    for field in [
+        "vertScrollState",
+        "windowTop",
+        "windowLeft",
+        "windowBottom",
+        "windowRight",
+    ] {
+        rules.push((
+            ["/opml/head/", field].concat(),
+            JsonArray::Infer(JsonType::AlwaysNumber),
+        ));
+    }
+    let mut outline = String::from("/opml/body");
+    for _ in 0..OPML_OUTLINE_LEVELS {
+        outline.push_str("/outline");
+        rules.push((outline.clone(), JsonArray::Always(JsonType::Infer)));
+        for attribute in [
+            "text",
+            "title",
+            "type",
+            "xmlUrl",
+            "htmlUrl",
+            "url",
+            "description",
+            "category",
+            "created",
+            "language",
+            "version",
+        ] {
+            rules.push((
+                [outline.as_str(), "/@", attribute].concat(),
+                JsonArray::Infer(JsonType::AlwaysString),
+            ));
+        }
+    }
+    with_rules(Config::new_with_defaults(), rules)
+}
+
+/// Returns a config for sitemaps and sitemap indexes. `url` and `sitemap` entries are always arrays,
+/// `priority` is a number, and `loc`, `lastmod` and `changefreq` are always strings, so W3C datetimes
+/// like `2024` stay strings.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{presets, xml_str_to_json};
+/// use serde_json::json;
+///
+/// let xml = r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+///     <url><loc>https://example.com/</loc><lastmod>2024</lastmod><priority>1.0</priority></url>
+/// </urlset>"#;
+/// let json = xml_str_to_json(xml, &presets::sitemap()).unwrap();
+/// assert_eq!(json["urlset"]["url"], json!([{"loc": "https://example.com/", "lastmod": "2024", "priority": 1.0}]));
+/// ```
+pub fn sitemap() -> Config {
+    let mut rules = Vec::new();
+    for entry in ["/urlset/url", "/sitemapindex/sitemap"] {
+        rules.push((entry.to_owned(), JsonArray::Always(JsonType::Infer)));
+        for field in ["/loc", "/lastmod", "/changefreq"] {
+            rules.push((
+                [entry, field].concat(),
+                JsonArray::Infer(JsonType::AlwaysString),
+            ));
+        }
+    }
+    rules.push((
+        "/urlset/url/priority".to_owned(),
+        JsonArray::Infer(JsonType::AlwaysNumber),
+    ));
+    with_rules(Config::new_with_defaults(), rules)
+}
+
+/// Converts an SVG document into an ordered tree, because the order of SVG elements is their painting order.
+/// Every element becomes `{"name": "path", "attributes": {..}, "children": [..]}` and every text,
+/// e.g. of `<text>` or `<style>`, becomes `{"text": ".."}`. Attribute values are kept as they are
+/// written, so path data like `M0 005.5` and `style` declarations are never converted into numbers.
+/// Attributes in namespaces keep their prefix, e.g. `xlink:href`. Empty `attributes` and `children`
+/// are omitted, and so are whitespace-only texts, comments and namespace declarations.
+///
+/// Only `Config.max_depth` applies to this conversion.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{presets, Config};
+/// use serde_json::json;
+///
+/// let xml = r#"<svg viewBox="0 0 10 10"><rect width="010"/><path d="M0 005.5"/></svg>"#;
+/// let json = presets::svg_to_json(xml, &Config::new_with_defaults()).unwrap();
+/// assert_eq!(json, json!({"name": "svg", "attributes": {"viewBox": "0 0 10 10"}, "children": [
+///     {"name": "rect", "attributes": {"width": "010"}},
+///     {"name": "path", "attributes": {"d": "M0 005.5"}},
+/// ]}));
+/// ```
+pub fn svg_to_json(xml: &str, config: &Config) -> Result<Value, Error> {
+    let xml = prepare_input(xml, config)?;
+    let doc = parse_document(&xml, config)?;
+    Ok(svg_node(doc.root_element()))
+}
+
+fn svg_node(el: roxmltree::Node) -> Value {
+    let mut node = Map::new();
+    node.insert("name".to_owned(), Value::from(el.tag_name().name()));
+
+    let mut attributes = Map::new();
+    for attr in el.attributes() {
+        let prefix = attr.namespace().and_then(|ns| el.lookup_prefix(ns));
+        let name = match prefix {
+            Some(prefix) => [prefix, ":", attr.name()].concat(),
+            None => attr.name().to_owned(),
+        };
+        attributes.insert(name, Value::from(attr.value()));
+    }
+    if !attributes.is_empty() {
+        node.insert("attributes".to_owned(), Value::Object(attributes));
+    }
+
+    let children: Vec<Value> =
+        el.children()
+            .filter_map(|child| match child.node_type() {
+                roxmltree::NodeType::Element => Some(svg_node(child)),
+                roxmltree::NodeType::Text => child
+                    .text()
+                    .filter(|text| !text.trim().is_empty())
+                    .map(|text| {
+                        let mut node = Map::new();
+                        node.insert("text".to_owned(), Value::from(text));
+                        Value::Object(node)
+                    }),
+                _ => None,
+            })
+            .collect();
+    if !children.is_empty() {
+        node.insert("children".to_owned(), Value::Array(children));
+    }
+    Value::Object(node)
+}
+
+/// Converts an Apple property list in XML format into the JSON it represents, instead of the structure
+/// of its elements: `<dict>` becomes an object of its `<key>` and value pairs, `<array>` an array,
+/// `<integer>` and `<real>` numbers, `<true/>` and `<false/>` booleans, and `<string>`, `<date>` and
+/// the base64 text of `<data>` strings. The root is the value inside of `<plist>`.
+///
+/// Documents that are not valid property lists fail with `Error::UnexpectedXml`.
+/// Only `Config.max_depth` applies to this conversion.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{presets, Config};
+/// use serde_json::json;
+///
+/// let xml = r#"<plist version="1.0"><dict>
+///     <key>CFBundleName</key><string>App</string>
+///     <key>LSRequiresIPhoneOS</key><true/>
+///     <key>UIDeviceFamily</key><array><integer>1</integer><integer>2</integer></array>
+/// </dict></plist>"#;
+/// let json = presets::plist_to_json(xml, &Config::new_with_defaults()).unwrap();
+/// assert_eq!(json, json!({"CFBundleName": "App", "LSRequiresIPhoneOS": true, "UIDeviceFamily": [1, 2]}));
+/// ```
+pub fn plist_to_json(xml: &str, config: &Config) -> Result<Value, Error> {
+    let xml = prepare_input(xml, config)?;
+    let doc = parse_document(&xml, config)?;
+    let root = doc.root_element();
+    if root.tag_name().name() != "plist" {
+        return plist_value(root);
+    }
+    let mut values = root.children().filter(|c| c.is_element());
+    match (values.next(), values.next()) {
+        (Some(value), None) => plist_value(value),
+        _ => Err(unexpected_plist(root, "must contain a single value")),
+    }
+}
+
+fn unexpected_plist(el: roxmltree::Node, reason: &str) -> Error {
+    Error::UnexpectedXml(format!(
+        "plist <{}> at {}: {}",
+        el.tag_name().name(),
+        el.document().text_pos_at(el.range().start),
+        reason
+    ))
+}
+
+fn plist_value(el: roxmltree::Node) -> Result<Value, Error> {
+    let text = el.text().unwrap_or_default();
+    Ok(match el.tag_name().name() {
+        "dict" => {
+            let mut map = Map::new();
+            let mut children = el.children().filter(|c| c.is_element());
+            while let Some(key) = children.next() {
+                if key.tag_name().name() != "key" {
+                    return Err(unexpected_plist(key, "expected a <key>"));
+                }
+                let value = children
+                    .next()
+                    .ok_or_else(|| unexpected_plist(key, "the key has no value"))?;
+                map.insert(
+                    key.text().unwrap_or_default().to_owned(),
+                    plist_value(value)?,
+                );
+            }
+            Value::Object(map)
+        }
+        "array" => Value::Array(
+            el.children()
+                .filter(|c| c.is_element())
+                .map(plist_value)
+                .collect::<Result<_, _>>()?,
+        ),
+        "string" | "date" => Value::from(text),
+        // base64 may be wrapped over several lines
+        "data" => Value::from(text.split_whitespace().collect::<String>()),
+        "integer" => {
+            let text = text.trim();
+            match text.parse::<i64>() {
+                Ok(i) => Value::from(i),
+                Err(_) => Value::from(
+                    text.parse::<u64>()
+                        .map_err(|_| unexpected_plist(el, "not an integer"))?,
+                ),
+            }
+        }
+        "real" => text
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number)
+            .ok_or_else(|| unexpected_plist(el, "not a finite number"))?,
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => return Err(unexpected_plist(el, "unknown value type")),
+    })
+}
+
+/// Converts a JUnit or xUnit test report with a `<testsuites>` or `<testsuite>` root, as written by
+/// Maven Surefire, Gradle, pytest and most other test runners.
+/// `testsuite` and `testcase` are always arrays, and every suite has a `testcase` array, even an empty one.
+/// The `time` attributes are durations in seconds and always floats, also when they are written as
+/// `1,234.5` or `2`. `<properties>` become an object of the property names and values. The bodies of
+/// `failure`, `error`, `skipped` and the rerun elements, usually stack traces, and `system-out`
+/// and `system-err` are kept verbatim, including their whitespace.
+///
+/// The other attributes and elements are converted with the config as usual.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{presets, Config};
+/// use serde_json::json;
+///
+/// let xml = r#"<testsuite name="unit" tests="1" failures="1" time="2">
+///     <properties><property name="java.version" value="17"/></properties>
+///     <testcase name="adds" time="0.25"><failure message="expected 2">at Test.adds(Test.java:7)
+/// </failure></testcase>
+/// </testsuite>"#;
+/// let json = presets::junit_to_json(xml, &Config::new_with_defaults()).unwrap();
+/// assert_eq!(json, json!({"testsuite": [{"@name": "unit", "@tests": 1, "@failures": 1, "@time": 2.0,
+///     "properties": {"java.version": "17"},
+///     "testcase": [{"@name": "adds", "@time": 0.25,
+///         "failure": {"@message": "expected 2", "#text": "at Test.adds(Test.java:7)\n"}}],
+/// }]}));
+/// ```
+pub fn junit_to_json(xml: &str, config: &Config) -> Result<Value, Error> {
+    let xml = prepare_input(xml, config)?;
+    let doc = parse_document(&xml, config)?;
+    let root = doc.root_element();
+    let name = root.tag_name().name();
+    if name != "testsuites" && name != "testsuite" {
+        return Err(Error::UnexpectedXml(
+            "the root element is not a JUnit testsuites or testsuite".to_owned(),
+        ));
+    }
+
+    let mut ctx = Context::new(config, None);
+    start_conversion(&mut ctx, &doc)?;
+    let mut path = String::new();
+    let value = junit_element(&mut ctx, root, &mut path)?;
+    finish_conversion(&ctx);
+    let value = match name {
+        "testsuite" => Value::Array(vec![value]),
+        _ => value,
+    };
+    let mut data = Map::with_capacity(1);
+    data.insert(name.to_owned(), value);
+    Ok(Value::Object(data))
+}
+
+/// Converts a `testsuites`, `testsuite` or `testcase` element.
+fn junit_element(
+    ctx: &mut Context,
+    el: roxmltree::Node,
+    path: &mut String,
+) -> Result<Value, Error> {
+    ctx.tick()?;
+    ctx.charge(&el)?;
+    let path_len = path.len();
+    path.push('/');
+    path.push_str(el.tag_name().name());
+    ctx.depth += 1;
+
+    let mut data = Map::new();
+    convert_attributes(ctx, &el, path, &mut data, false)?;
+    if let Some(time) = el.attribute("time").and_then(duration) {
+        data.insert([ctx.config.xml_attr_prefix.as_str(), "time"].concat(), time);
+    }
+    for child in el.children().filter(|c| c.is_element()) {
+        let name = child.tag_name().name();
+        let value = match name {
+            "testsuite" | "testcase" => {
+                push_item(&mut data, name, junit_element(ctx, child, path)?);
+                continue;
+            }
+            "properties" => Value::Object(
+                child
+                    .children()
+                    .filter(|c| c.is_element() && c.tag_name().name() == "property")
+                    .filter_map(|property| {
+                        let value = property
+                            .attribute("value")
+                            .map(str::to_owned)
+                            .unwrap_or_else(|| verbatim_text(property));
+                        Some((property.attribute("name")?.to_owned(), Value::String(value)))
+                    })
+                    .collect(),
+            ),
+            "failure" | "error" | "skipped" | "flakyFailure" | "flakyError" | "rerunFailure"
+            | "rerunError" => {
+                let mut result = Map::new();
+                path.push('/');
+                path.push_str(name);
+                convert_attributes(ctx, &child, path, &mut result, false)?;
+                let text = verbatim_text(child);
+                if !text.is_empty() {
+                    result.insert(
+                        text_prop_name(ctx.config, path).to_owned(),
+                        Value::String(text),
+                    );
+                }
+                path.truncate(path.len() - name.len() - 1);
+                Value::Object(result)
+            }
+            "system-out" | "system-err" => Value::String(verbatim_text(child)),
+            _ => match convert_node(ctx, &child, path)? {
+                Some(value) => value,
+                None => continue,
+            },
+        };
+        insert_repeated(&mut data, name, value);
+    }
+    if el.tag_name().name() == "testsuite" && !data.contains_key("testcase") {
+        data.insert("testcase".to_owned(), Value::Array(Vec::new()));
+    }
+
+    ctx.depth -= 1;
+    path.truncate(path_len);
+    Ok(Value::Object(data))
+}
+
+/// Parses a duration in seconds. Some runners format it with thousands separators.
+fn duration(time: &str) -> Option<Value> {
+    let time = time.trim().replace(',', "");
+    serde_json::Number::from_f64(time.parse().ok()?).map(Value::Number)
+}
+
+/// Returns all the text inside the element, including CDATA sections, without trimming.
+fn verbatim_text(el: roxmltree::Node) -> String {
+    el.descendants()
+        .filter(|n| n.is_text())
+        .filter_map(|n| n.text())
+        .collect()
+}
+
+/// The list elements of the Maven POM model as pairs of the parent and the item name.
+const POM_LISTS: &[(&str, &str)] = &[
+    ("contributors", "contributor"),
+    ("dependencies", "dependency"),
+    ("developers", "developer"),
+    ("exclusions", "exclusion"),
+    ("executions", "execution"),
+    ("extensions", "extension"),
+    ("goals", "goal"),
+    ("licenses", "license"),
+    ("mailingLists", "mailingList"),
+    ("modules", "module"),
+    ("pluginRepositories", "pluginRepository"),
+    ("plugins", "plugin"),
+    ("profiles", "profile"),
+    ("reportSets", "reportSet"),
+    ("reports", "report"),
+    ("repositories", "repository"),
+    ("resources", "resource"),
+    ("roles", "role"),
+    ("testResources", "testResource"),
+];
+
+/// Converts a Maven `pom.xml`. The items of the lists of the POM model, like `dependencies/dependency`,
+/// `plugins/plugin` and `modules/module`, are always arrays, also when a project has a single dependency.
+/// `version` and `modelVersion` are always strings, so `1.10` doesn't become `1.1`. `<properties>` become
+/// a flat object of the property names and their texts as strings, e.g. `{"java.version": "17"}`.
+///
+/// The free-form `configuration` of plugins and all other elements are converted with the config as usual.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{presets, Config};
+/// use serde_json::json;
+///
+/// let xml = r#"<project xmlns="http://maven.apache.org/POM/4.0.0">
+///     <version>1.10</version>
+///     <properties><java.version>17</java.version></properties>
+///     <dependencies><dependency><artifactId>junit</artifactId><version>4.13</version></dependency></dependencies>
+/// </project>"#;
+/// let json = presets::pom_to_json(xml, &Config::new_with_defaults()).unwrap();
+/// assert_eq!(json, json!({"project": {"version": "1.10", "properties": {"java.version": "17"},
+///     "dependencies": {"dependency": [{"artifactId": "junit", "version": "4.13"}]}}}));
+/// ```
+pub fn pom_to_json(xml: &str, config: &Config) -> Result<Value, Error> {
+    let xml = prepare_input(xml, config)?;
+    let doc = parse_document(&xml, config)?;
+    let root = doc.root_element();
+    if root.tag_name().name() != "project" {
+        return Err(Error::UnexpectedXml(
+            "the root element is not a Maven project".to_owned(),
+        ));
+    }
+
+    let mut ctx = Context::new(config, None);
+    start_conversion(&mut ctx, &doc)?;
+    let mut path = String::new();
+    let value = pom_element(&mut ctx, root, &mut path)?;
+    finish_conversion(&ctx);
+    let mut data = Map::with_capacity(1);
+    data.insert("project".to_owned(), value);
+    Ok(Value::Object(data))
+}
+
+/// Converts an element of the POM model with child elements.
+fn pom_element(ctx: &mut Context, el: roxmltree::Node, path: &mut String) -> Result<Value, Error> {
+    ctx.tick()?;
+    ctx.charge(&el)?;
+    let path_len = path.len();
+    path.push('/');
+    path.push_str(el.tag_name().name());
+    ctx.depth += 1;
+
+    let mut data = Map::new();
+    convert_attributes(ctx, &el, path, &mut data, false)?;
+    for child in el.children().filter(|c| c.is_element()) {
+        let name = child.tag_name().name();
+        let value = match name {
+            "properties" => Value::Object(
+                child
+                    .children()
+                    .filter(|c| c.is_element())
+                    .map(|property| {
+                        let text = property.text().unwrap_or_default().trim();
+                        (property.tag_name().name().to_owned(), Value::from(text))
+                    })
+                    .collect(),
+            ),
+            "version" | "modelVersion" => Value::from(child.text().unwrap_or_default().trim()),
+            "configuration" => match convert_node(ctx, &child, path)? {
+                Some(value) => value,
+                None => continue,
+            },
+            _ if child.children().any(|c| c.is_element()) => pom_element(ctx, child, path)?,
+            _ => match convert_node(ctx, &child, path)? {
+                Some(value) => value,
+                None => continue,
+            },
+        };
+        if POM_LISTS.contains(&(el.tag_name().name(), name)) {
+            push_item(&mut data, name, value);
+        } else {
+            insert_repeated(&mut data, name, value);
+        }
+    }
+
+    ctx.depth -= 1;
+    path.truncate(path_len);
+    Ok(Value::Object(data))
+}
+
+/// The text elements of XMLTV that can be repeated in several languages.
+const XMLTV_LANG_ELEMENTS: &[&str] = &[
+    "category",
+    "country",
+    "desc",
+    "display-name",
+    "keyword",
+    "last-chance",
+    "premiere",
+    "sub-title",
+    "title",
+];
+
+/// Converts XMLTV electronic program guide data. `channel` and `programme` are always arrays.
+/// The `start`, `stop`, `pdc-start` and `vps-start` times, like `20240131120000 +0100`, become Unix
+/// timestamps in seconds, or stay strings if they can't be parsed. Texts that can be repeated in several
+/// languages, like `title`, `desc` and `display-name`, are grouped by their `lang` attribute into objects,
+/// e.g. `{"en": "News", "de": "Nachrichten"}`, with an empty key for texts without a `lang`.
+/// A language that repeats, as categories often do, gets an array. Channel ids are always strings.
+///
+/// All other elements and attributes are converted with the config as usual.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{presets, Config};
+/// use serde_json::json;
+///
+/// let xml = r#"<tv><programme start="20240131120000 +0100" channel="1">
+///     <title lang="en">News</title><title lang="de">Nachrichten</title>
+/// </programme></tv>"#;
+/// let json = presets::xmltv_to_json(xml, &Config::new_with_defaults()).unwrap();
+/// assert_eq!(json, json!({"tv": {"programme": [{"@start": 1706698800, "@channel": "1",
+///     "title": {"en": "News", "de": "Nachrichten"}}]}}));
+/// ```
+pub fn xmltv_to_json(xml: &str, config: &Config) -> Result<Value, Error> {
+    let xml = prepare_input(xml, config)?;
+    let doc = parse_document(&xml, config)?;
+    let root = doc.root_element();
+    if root.tag_name().name() != "tv" {
+        return Err(Error::UnexpectedXml(
+            "the root element is not an XMLTV tv".to_owned(),
+        ));
+    }
+
+    let mut ctx = Context::new(config, None);
+    start_conversion(&mut ctx, &doc)?;
+    let mut path = String::new();
+    let value = xmltv_element(&mut ctx, root, &mut path)?;
+    finish_conversion(&ctx);
+    let mut data = Map::with_capacity(1);
+    data.insert("tv".to_owned(), value);
+    Ok(Value::Object(data))
+}
+
+/// Converts the `tv` root element or one of its `channel` and `programme` elements.
+fn xmltv_element(
+    ctx: &mut Context,
+    el: roxmltree::Node,
+    path: &mut String,
+) -> Result<Value, Error> {
+    ctx.tick()?;
+    ctx.charge(&el)?;
+    let path_len = path.len();
+    path.push('/');
+    path.push_str(el.tag_name().name());
+    ctx.depth += 1;
+
+    let mut data = Map::new();
+    convert_attributes(ctx, &el, path, &mut data, false)?;
+    for attr in el.attributes() {
+        let value = match attr.name() {
+            "start" | "stop" | "pdc-start" | "vps-start" => {
+                xmltv_timestamp(attr.value()).map_or_else(|| Value::from(attr.value()), Value::from)
+            }
+            "channel" | "id" => Value::from(attr.value()),
+            _ => continue,
+        };
+        data.insert(
+            [ctx.config.xml_attr_prefix.as_str(), attr.name()].concat(),
+            value,
+        );
+    }
+    for child in el.children().filter(|c| c.is_element()) {
+        let name = child.tag_name().name();
+        if el.parent_element().is_none() && (name == "channel" || name == "programme") {
+            push_item(&mut data, name, xmltv_element(ctx, child, path)?);
+        } else if XMLTV_LANG_ELEMENTS.contains(&name) {
+            let languages = data
+                .entry(name)
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Value::Object(languages) = languages {
+                let text = child.text().unwrap_or_default().trim();
+                insert_repeated(
+                    languages,
+                    child.attribute("lang").unwrap_or_default(),
+                    Value::from(text),
+                );
+            }
+        } else if let Some(value) = convert_node(ctx, &child, path)? {
+            insert_repeated(&mut data, name, value);
+        }
+    }
+
+    ctx.depth -= 1;
+    path.truncate(path_len);
+    Ok(Value::Object(data))
+}
+
+/// Parses an XMLTV time like `20240131120000 +0100` into a Unix timestamp. The trailing parts of the time
+/// can be omitted, e.g. `202401311200`, and so can the offset for UTC.
+fn xmltv_timestamp(time: &str) -> Option<i64> {
+    let (datetime, offset) = match time.trim().split_once(' ') {
+        Some((datetime, offset)) => (datetime, offset.trim()),
+        None => (time.trim(), ""),
+    };
+    if datetime.len() < 4 || datetime.len() > 14 || !datetime.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let field = |start: usize, default: i64| {
+        datetime
+            .get(start..start + 2)
+            .map_or(Some(default), |digits| digits.parse().ok())
+    };
+    let year: i64 = datetime[..4].parse().ok()?;
+    let (month, day) = (field(4, 1)?, field(6, 1)?);
+    let (hour, minute, second) = (field(8, 0)?, field(10, 0)?, field(12, 0)?);
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+    let offset = match offset {
+        "" | "UTC" | "GMT" | "Z" => 0,
+        _ => {
+            let sign = match offset.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            let digits = &offset[1..];
+            if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            sign * (digits[..2].parse::<i64>().ok()? * 3600 + digits[2..].parse::<i64>().ok()? * 60)
+        }
+    };
+    // days since 1970-01-01 of the proleptic Gregorian calendar
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+    Some(days * 86_400 + hour * 3600 + minute * 60 + second - offset)
+}
+
+/// Adds the JSON type overrides to the config.
+fn with_rules<P: AsRef<str>>(
+    config: Config,
+    rules: impl IntoIterator<Item = (P, JsonArray)>,
+) -> Config {
+    rules.into_iter().fold(config, |config, (path, json_type)| {
+        config.add_json_type_override(path.as_ref(), json_type)
+    })
+}
+
+/// Reads the fields of a SOAP 1.1 or 1.2 `Fault` element.
+fn fault(ctx: &mut Context, fault: roxmltree::Node) -> Result<SoapFault, Error> {
+    let code = text(
+        child(fault, "faultcode").or_else(|| child(fault, "Code").and_then(|c| child(c, "Value"))),
+    );
+    let reason = text(
+        child(fault, "faultstring")
+            .or_else(|| child(fault, "Reason").and_then(|r| child(r, "Text"))),
+    );
+    let actor = text(child(fault, "faultactor").or_else(|| child(fault, "Role")));
+    let detail = match child(fault, "detail").or_else(|| child(fault, "Detail")) {
+        Some(detail) => convert_node(ctx, &detail, "/Envelope/Body/Fault")?,
+        None => None,
+    };
+    Ok(SoapFault {
+        code: code.unwrap_or_default(),
+        reason: reason.unwrap_or_default(),
+        actor,
+        detail,
+    })
+}
+
+/// Returns the first child element with the local name `name`.
+fn child<'a, 'input>(
+    el: roxmltree::Node<'a, 'input>,
+    name: &str,
+) -> Option<roxmltree::Node<'a, 'input>> {
+    el.children()
+        .find(|c| c.is_element() && c.tag_name().name() == name)
+}
+
+/// Returns the trimmed text of an element, empty if it has none.
+fn text(el: Option<roxmltree::Node>) -> Option<String> {
+    el.map(|el| el.text().unwrap_or_default().trim().to_owned())
+}
@@ -0,0 +1,46 @@
+//! Progress reporting for long running conversions.
+
+use std::fmt;
+
+/// Counters passed to the progress callback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// Number of XML elements converted so far.
+    pub elements: usize,
+    /// Total number of XML elements in the document.
+    pub total_elements: usize,
+}
+
+/// A callback invoked once the document is parsed, every `every` converted elements and once more
+/// when the conversion is finished.
+/// Set it with `Config::with_progress_callback`.
+pub struct ProgressCallback {
+    pub(crate) every: usize,
+    pub(crate) callback: Box<dyn Fn(Progress) + Send + Sync>,
+}
+
+impl ProgressCallback {
+    pub fn new<F>(every: usize, callback: F) -> Self
+    where
+        F: Fn(Progress) + Send + Sync + 'static,
+    {
+        ProgressCallback {
+            every: every.max(1),
+            callback: Box::new(callback),
+        }
+    }
+
+    /// Returns `true` if the callback is due after `elements` converted elements.
+    #[inline]
+    pub(crate) fn is_due(&self, elements: usize) -> bool {
+        elements % self.every == 0
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressCallback")
+            .field("every", &self.every)
+            .finish_non_exhaustive()
+    }
+}
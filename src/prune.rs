@@ -0,0 +1,70 @@
+//! Removal of empty values from the converted output.
+
+use serde_json::Value;
+
+/// The kinds of empty values that `Config.prune_empty` removes from the output.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_str_to_json, Config, PruneEmpty};
+/// use serde_json::json;
+///
+/// let conf = Config::new_with_defaults().with_prune_empty(PruneEmpty { strings: false, ..PruneEmpty::ALL });
+/// let xml = r#"<person name=""><phone/><address><street/></address><age>42</age></person>"#;
+/// assert_eq!(xml_str_to_json(xml, &conf).unwrap(), json!({"person": {"@name": "", "age": 42}}));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PruneEmpty {
+    /// Remove `null` values.
+    pub nulls: bool,
+    /// Remove empty objects `{}`, including the ones that only contained removed values.
+    pub objects: bool,
+    /// Remove empty arrays `[]`, including the ones that only contained removed values.
+    pub arrays: bool,
+    /// Remove empty strings `""`.
+    pub strings: bool,
+}
+
+impl PruneEmpty {
+    /// Removes nothing. This is the default.
+    pub const NONE: PruneEmpty = PruneEmpty {
+        nulls: false,
+        objects: false,
+        arrays: false,
+        strings: false,
+    };
+
+    /// Removes all kinds of empty values.
+    pub const ALL: PruneEmpty = PruneEmpty {
+        nulls: true,
+        objects: true,
+        arrays: true,
+        strings: true,
+    };
+
+    /// Returns `true` if `value` is one of the removed kinds.
+    fn removes(&self, value: &Value) -> bool {
+        match value {
+            Value::Null => self.nulls,
+            Value::Object(map) => self.objects && map.is_empty(),
+            Value::Array(items) => self.arrays && items.is_empty(),
+            Value::String(text) => self.strings && text.is_empty(),
+            _ => false,
+        }
+    }
+}
+
+/// Removes the empty values below `value`, bottom-up, so that objects and arrays which only contained
+/// removed values are removed as well. `value` itself is kept.
+pub(crate) fn prune_empty_values(value: &mut Value, prune: &PruneEmpty) {
+    match value {
+        Value::Object(map) => map.retain(|_, child| {
+            prune_empty_values(child, prune);
+            !prune.removes(child)
+        }),
+        Value::Array(items) => items.retain_mut(|item| {
+            prune_empty_values(item, prune);
+            !prune.removes(item)
+        }),
+        _ => {}
+    }
+}
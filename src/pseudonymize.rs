@@ -0,0 +1,50 @@
+//! Replacement of identifying values with their keyed hashes.
+
+use std::fmt;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::{Config, Error};
+
+/// The secret key for the HMAC-SHA256 of `Config.pseudonymized_paths`. It isn't shown by `Debug`,
+/// so it doesn't end up in logs of the `Config`.
+#[derive(Clone)]
+pub struct PseudonymizationKey(Vec<u8>);
+
+impl PseudonymizationKey {
+    pub fn new(key: &[u8]) -> Self {
+        PseudonymizationKey(key.to_vec())
+    }
+}
+
+impl fmt::Debug for PseudonymizationKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PseudonymizationKey").finish_non_exhaustive()
+    }
+}
+
+/// Returns `true` if the value at `path` is replaced with its HMAC, see `Config.pseudonymized_paths`.
+#[inline]
+pub(crate) fn is_pseudonymized(config: &Config, path: &str) -> bool {
+    !config.pseudonymized_paths.is_empty() && config.pseudonymized_paths.contains(path)
+}
+
+/// Returns the HMAC-SHA256 of `text` with `Config.pseudonymization_key` as lowercase hex digits.
+/// Fails with `Error::InvalidConfig` if there is no key, so the text never ends up in the output.
+pub(crate) fn pseudonymize(config: &Config, text: &str) -> Result<String, Error> {
+    let Some(PseudonymizationKey(key)) = &config.pseudonymization_key else {
+        return Err(Error::InvalidConfig(
+            "`pseudonymized_paths` require a `pseudonymization_key`".to_owned(),
+        ));
+    };
+    // HMAC accepts keys of any length
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any size");
+    mac.update(text.as_bytes());
+    Ok(mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
@@ -0,0 +1,120 @@
+//! Bindings for Python with pyo3, available with the `python` feature.
+//! Build the `roxmltree_to_serde_py` extension module with `maturin build`.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple};
+use serde_json::{Map, Number, Value};
+
+use crate::{xml_str_to_json, Config};
+
+/// Converts an XML document into a dict with the same rules as `xml_str_to_json`. The optional `config`
+/// is a dict with the fields of `Config::from_json`, e.g. `{"json_type_overrides": {"/a/@id": "string"}}`.
+/// Malformed documents and invalid configs raise a `ValueError`.
+#[pyfunction]
+#[pyo3(signature = (xml, config = None))]
+pub(crate) fn xml_to_dict<'py>(
+    py: Python<'py>,
+    xml: &str,
+    config: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let config = match config {
+        Some(config) => {
+            let Value::Object(fields) = from_python(config.as_any())? else {
+                unreachable!("a dict is converted into an object");
+            };
+            Config::from_json_fields(&fields)
+        }
+        None => Ok(Config::new_with_defaults()),
+    };
+    let json = config
+        .and_then(|config| xml_str_to_json(xml, &config))
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    to_python(py, &json)
+}
+
+#[pymodule]
+fn roxmltree_to_serde_py(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(xml_to_dict, module)?)
+}
+
+/// Converts a JSON value into the equivalent Python object.
+pub(crate) fn to_python<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match value {
+        Value::Null => py.None().into_bound(py),
+        Value::Bool(b) => PyBool::new(py, *b).to_owned().into_any(),
+        Value::Number(n) => {
+            if let Some(u) = n.as_u64() {
+                u.into_pyobject(py)?.into_any()
+            } else if let Some(i) = n.as_i64() {
+                i.into_pyobject(py)?.into_any()
+            } else {
+                n.as_f64().unwrap_or(f64::NAN).into_pyobject(py)?.into_any()
+            }
+        }
+        Value::String(s) => PyString::new(py, s).into_any(),
+        Value::Array(items) => {
+            let items = items
+                .iter()
+                .map(|item| to_python(py, item))
+                .collect::<PyResult<Vec<_>>>()?;
+            PyList::new(py, items)?.into_any()
+        }
+        Value::Object(map) => {
+            let dict = PyDict::new(py);
+            for (key, value) in map {
+                dict.set_item(key, to_python(py, value)?)?;
+            }
+            dict.into_any()
+        }
+    })
+}
+
+/// Converts a Python object of JSON types, i.e. dicts with string keys, lists, tuples, strings,
+/// numbers, booleans and `None`, into a JSON value.
+pub(crate) fn from_python(object: &Bound<'_, PyAny>) -> PyResult<Value> {
+    // bool is a subclass of int, so it's checked first
+    Ok(if object.is_none() {
+        Value::Null
+    } else if let Ok(b) = object.downcast::<PyBool>() {
+        Value::Bool(b.is_true())
+    } else if object.is_instance_of::<PyInt>() {
+        match object.extract::<i64>() {
+            Ok(i) => Value::from(i),
+            Err(_) => Value::from(object.extract::<u64>()?),
+        }
+    } else if let Ok(f) = object.downcast::<PyFloat>() {
+        Number::from_f64(f.value())
+            .map(Value::Number)
+            .ok_or_else(|| PyValueError::new_err("NaN and infinity are not valid JSON"))?
+    } else if let Ok(s) = object.downcast::<PyString>() {
+        Value::String(s.to_str()?.to_owned())
+    } else if let Ok(list) = object.downcast::<PyList>() {
+        Value::Array(
+            list.iter()
+                .map(|item| from_python(&item))
+                .collect::<PyResult<_>>()?,
+        )
+    } else if let Ok(tuple) = object.downcast::<PyTuple>() {
+        Value::Array(
+            tuple
+                .iter()
+                .map(|item| from_python(&item))
+                .collect::<PyResult<_>>()?,
+        )
+    } else if let Ok(dict) = object.downcast::<PyDict>() {
+        let mut map = Map::new();
+        for (key, value) in dict {
+            let key = key
+                .downcast::<PyString>()
+                .map_err(|_| PyValueError::new_err("dict keys must be strings"))?;
+            map.insert(key.to_str()?.to_owned(), from_python(&value)?);
+        }
+        Value::Object(map)
+    } else {
+        return Err(PyValueError::new_err(format!(
+            "{} is not a JSON type",
+            object.get_type().name()?
+        )));
+    })
+}
@@ -0,0 +1,258 @@
+//! JSONPath queries that convert only as much of the document as necessary to answer them.
+
+use serde_json::Value;
+
+use crate::pointer::known_children;
+use crate::{
+    convert_node, element_key, parse_document, prepare_input, repeated_names, ArrayInference,
+    Config, Context, Error,
+};
+
+/// A single step of a JSONPath query.
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    /// `.name` or `['name']`
+    Name(String),
+    /// `[0]`
+    Index(usize),
+    /// `.*` or `[*]`
+    Wildcard,
+    /// `..name`, or `..*` for `None`
+    Descendants(Option<String>),
+}
+
+/// A part of the document that is converted into JSON only when the query needs it.
+enum Item<'a, 'input> {
+    /// The document itself, i.e. `$`.
+    Document(roxmltree::Node<'a, 'input>),
+    /// The element with the path of its parent and its nesting level.
+    Element(roxmltree::Node<'a, 'input>, String, usize),
+    /// The repeated elements of an array with the path of their parent and their nesting level.
+    Elements(Vec<roxmltree::Node<'a, 'input>>, String, usize),
+    Value(Value),
+}
+
+/// Returns all values that the JSONPath `query` selects from `xml_str_to_json(xml, config)`, in document order.
+/// Only the selected parts of the document are converted, so it's much faster than running a JSONPath
+/// library over the full conversion if only a few values are needed.
+///
+/// The supported subset of JSONPath is `$` followed by any of these steps:
+/// * `.name` or `['name']` for a property, e.g. `.@id` for an attribute
+/// * `[0]` for an array item
+/// * `.*` or `[*]` for all properties or array items
+/// * `..name` or `..*` for all matching properties at any depth, which converts the whole subtree
+///
+/// Queries with other syntax, e.g. filters or slices, fail with `Error::InvalidQuery`.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_query, Config};
+/// use serde_json::json;
+///
+/// let xml = "<catalog><book><price>10</price></book><book><price>12.5</price></book></catalog>";
+/// let prices = xml_query(xml, "$.catalog.book[*].price", &Config::new_with_defaults()).unwrap();
+/// assert_eq!(prices, vec![json!(10), json!(12.5)]);
+/// ```
+pub fn xml_query(xml: &str, query: &str, config: &Config) -> Result<Vec<Value>, Error> {
+    let steps = parse_query(query)?;
+    let xml = prepare_input(xml, config)?;
+    let doc = parse_document(&xml, config)?;
+    let mut ctx = Context::new(config, None);
+    if config.array_inference == ArrayInference::DocumentWide {
+        ctx.repeated_names = repeated_names(&doc, config);
+    }
+
+    let mut items = vec![Item::Document(doc.root_element())];
+    for step in &steps {
+        let mut next = Vec::new();
+        for item in items {
+            apply(&mut ctx, item, step, &mut next)?;
+        }
+        items = next;
+    }
+
+    let limit = config.match_limit.unwrap_or(usize::MAX);
+    let mut values = Vec::with_capacity(items.len().min(limit));
+    for item in items {
+        if values.len() == limit {
+            break;
+        }
+        values.extend(into_value(&mut ctx, item)?);
+    }
+    Ok(values)
+}
+
+fn invalid(query: &str, reason: &str) -> Error {
+    Error::InvalidQuery(format!("{}: {}", query, reason))
+}
+
+fn parse_query(query: &str) -> Result<Vec<Step>, Error> {
+    let Some(mut rest) = query.strip_prefix('$') else {
+        return Err(invalid(query, "must start with `$`"));
+    };
+    // the end of a name in dot notation
+    let name_end = |s: &str| s.find(['.', '[']).unwrap_or(s.len());
+
+    let mut steps = Vec::new();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("..") {
+            let end = name_end(after);
+            let name = &after[..end];
+            if name.is_empty() {
+                return Err(invalid(query, "missing name after `..`"));
+            }
+            steps.push(Step::Descendants((name != "*").then(|| name.to_owned())));
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('.') {
+            let end = name_end(after);
+            let name = &after[..end];
+            steps.push(match name {
+                "" => return Err(invalid(query, "missing name after `.`")),
+                "*" => Step::Wildcard,
+                name => Step::Name(name.to_owned()),
+            });
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let Some(end) = after.find(']') else {
+                return Err(invalid(query, "missing `]`"));
+            };
+            let selector = after[..end].trim();
+            let quoted = selector
+                .strip_prefix('\'')
+                .and_then(|s| s.strip_suffix('\''))
+                .or_else(|| selector.strip_prefix('"').and_then(|s| s.strip_suffix('"')));
+            steps.push(if selector == "*" {
+                Step::Wildcard
+            } else if let Some(name) = quoted {
+                Step::Name(name.to_owned())
+            } else if let Ok(index) = selector.parse() {
+                Step::Index(index)
+            } else {
+                return Err(invalid(
+                    query,
+                    &format!("unsupported selector `{}`", selector),
+                ));
+            });
+            rest = &after[end + 1..];
+        } else {
+            return Err(invalid(query, &format!("unexpected `{}`", rest)));
+        }
+    }
+    Ok(steps)
+}
+
+/// Converts the element the same way `xml_str_to_json` would at its nesting level.
+fn convert_at(
+    ctx: &mut Context,
+    el: roxmltree::Node,
+    parent_path: &str,
+    depth: usize,
+) -> Result<Option<Value>, Error> {
+    ctx.depth = depth;
+    convert_node(ctx, &el, parent_path)
+}
+
+fn into_value(ctx: &mut Context, item: Item) -> Result<Option<Value>, Error> {
+    Ok(match item {
+        Item::Document(root) => {
+            let mut data = serde_json::Map::new();
+            let value = convert_at(ctx, root, "", 0)?.unwrap_or(Value::Null);
+            data.insert(element_key(ctx.config, &root).into_owned(), value);
+            Some(Value::Object(data))
+        }
+        Item::Element(el, parent_path, depth) => convert_at(ctx, el, &parent_path, depth)?,
+        Item::Elements(elements, parent_path, depth) => {
+            let mut items = Vec::with_capacity(elements.len());
+            for el in elements {
+                items.extend(convert_at(ctx, el, &parent_path, depth)?);
+            }
+            Some(Value::Array(items))
+        }
+        Item::Value(value) => Some(value),
+    })
+}
+
+/// Applies the step to the item and adds the results to `out`. Elements are only converted if the
+/// result of the step isn't known otherwise.
+fn apply<'a, 'input>(
+    ctx: &mut Context,
+    item: Item<'a, 'input>,
+    step: &Step,
+    out: &mut Vec<Item<'a, 'input>>,
+) -> Result<(), Error> {
+    match (item, step) {
+        (Item::Document(root), Step::Name(name)) if element_key(ctx.config, &root) == *name => {
+            out.push(Item::Element(root, String::new(), 0));
+        }
+        (Item::Document(root), Step::Wildcard) => out.push(Item::Element(root, String::new(), 0)),
+        (Item::Document(_), Step::Name(_) | Step::Index(_)) => (),
+        (Item::Element(el, parent_path, depth), Step::Name(name)) => {
+            let path = [parent_path.as_str(), "/", &element_key(ctx.config, &el)].concat();
+            match known_children(ctx, el, &path, name) {
+                Some((children, true)) => out.push(Item::Elements(children, path, depth + 1)),
+                Some((children, false)) => out.push(Item::Element(children[0], path, depth + 1)),
+                None => {
+                    if let Some(value) = convert_at(ctx, el, &parent_path, depth)? {
+                        apply_to_value(value, step, out);
+                    }
+                }
+            }
+        }
+        (Item::Elements(elements, parent_path, depth), Step::Index(index)) => {
+            if let Some(el) = elements.get(*index) {
+                out.push(Item::Element(*el, parent_path, depth));
+            }
+        }
+        (Item::Elements(elements, parent_path, depth), Step::Wildcard) => {
+            for el in elements {
+                out.push(Item::Element(el, parent_path.clone(), depth));
+            }
+        }
+        (Item::Elements(..), Step::Name(_)) => (),
+        (item, step) => {
+            if let Some(value) = into_value(ctx, item)? {
+                apply_to_value(value, step, out);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn apply_to_value(value: Value, step: &Step, out: &mut Vec<Item>) {
+    match (value, step) {
+        (Value::Object(mut map), Step::Name(name)) => {
+            out.extend(map.remove(name).map(Item::Value));
+        }
+        (Value::Array(mut items), Step::Index(index)) if *index < items.len() => {
+            out.push(Item::Value(items.swap_remove(*index)));
+        }
+        (Value::Object(map), Step::Wildcard) => {
+            out.extend(map.into_iter().map(|(_, v)| Item::Value(v)))
+        }
+        (Value::Array(items), Step::Wildcard) => out.extend(items.into_iter().map(Item::Value)),
+        (value, Step::Descendants(name)) => descendants(&value, name.as_deref(), out),
+        _ => (),
+    }
+}
+
+/// Adds all properties named `name`, or all properties and array items for `None`, at any depth.
+fn descendants(value: &Value, name: Option<&str>, out: &mut Vec<Item>) {
+    match value {
+        Value::Object(map) => {
+            for (key, v) in map {
+                if name.is_none_or(|name| name == key) {
+                    out.push(Item::Value(v.clone()));
+                }
+                descendants(v, name, out);
+            }
+        }
+        Value::Array(items) => {
+            for v in items {
+                if name.is_none() {
+                    out.push(Item::Value(v.clone()));
+                }
+                descendants(v, name, out);
+            }
+        }
+        _ => (),
+    }
+}
@@ -0,0 +1,687 @@
+//! Conversion of the records of a document that is read in chunks, see `XmlRecords` and `for_each_match`.
+
+use std::io::{self, Read};
+use std::marker::PhantomData;
+use std::ops::{ControlFlow, Range};
+
+use serde::de::{DeserializeOwned, DeserializeSeed};
+
+use serde_json::Value;
+
+use crate::borrowed;
+use crate::de::{Keys, ValueDeserializer};
+use crate::{
+    convert_node, element_key, parse_document, post_process, prepare_input, split_path,
+    xml_bytes_to_str, BorrowedValue, Config, Context, Error, PathMatcher,
+};
+
+/// The number of bytes requested from the reader at once.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// An iterator over the converted elements at an absolute record path, e.g. every `item` for `/feed/item`,
+/// that reads the document in chunks from an `io::Read`, e.g. stdin or a file. Only the current record
+/// and the start tags of its ancestors are held in memory, so documents of any size are converted
+/// in constant memory, as long as the records themselves are small.
+///
+/// The records are converted with the same rules as in `xml_str_to_json`, but not wrapped into an object
+/// with the element name, e.g. `<feed><item><a>1</a></item><item><a>2</a></item></feed>` gives `{"a":1}`
+/// and `{"a":2}` for `/feed/item`. Namespaces, `xml:base` and entities of the prolog and the ancestors
+/// are in scope of every record. The bytes are decoded like in `xml_bytes_to_json`, with
+/// `Config.utf8_policy`.
+///
+/// A record that fails to parse or convert is returned as an error and the iteration continues with the
+/// next one. Read errors and documents that end before the root element is closed end the iteration.
+/// Use a `BufReader` if the reader is unbuffered and slow to call, e.g. a socket.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{Config, XmlRecords};
+/// use serde_json::json;
+///
+/// let config = Config::new_with_defaults();
+/// let xml = r#"<feed><title>News</title><item id="1">One</item><item id="2">Two</item></feed>"#;
+/// let records: Vec<_> = XmlRecords::new(xml.as_bytes(), "/feed/item", &config)
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(records, [json!({"@id": 1, "#text": "One"}), json!({"@id": 2, "#text": "Two"})]);
+/// ```
+pub struct XmlRecords<'c, R>(Matches<'c, R>);
+
+impl<'c, R: Read> XmlRecords<'c, R> {
+    /// Creates an iterator over the elements at `record_path` of the document in `reader`.
+    /// Nothing is read until the first record is requested.
+    pub fn new(reader: R, record_path: &str, config: &'c Config) -> Self {
+        XmlRecords(Matches::new(
+            reader,
+            Target::Path(record_path.into()),
+            config,
+        ))
+    }
+
+    /// Deserializes every record into a `T` instead of a `serde_json::Value`. The records are converted
+    /// with the same rules, but into a `BorrowedValue` that borrows names and texts from the record,
+    /// so no `Value` is built and strings are only copied into the fields of `T`.
+    ///
+    /// The fields of attributes and texts have the keys of the `Value`, e.g. `#[serde(rename = "@id")]` and
+    /// `#[serde(rename = "#text")]`. A struct with a field for the text property also takes an element
+    /// with only a text, which is a plain value in the `Value` output, and a struct without required fields
+    /// takes an empty element. Numbers and booleans are accepted for `String` fields, as XML doesn't
+    /// distinguish them.
+    ///
+    /// A record that doesn't fit `T` is returned as `Error::Deserialize` and the iteration continues.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{Config, XmlRecords};
+    /// use serde_derive::Deserialize;
+    ///
+    /// #[derive(Deserialize, Debug, PartialEq)]
+    /// struct Item {
+    ///     #[serde(rename = "@id")]
+    ///     id: String,
+    ///     title: String,
+    /// }
+    ///
+    /// let config = Config::new_with_defaults();
+    /// let xml = r#"<feed><item id="1"><title>One</title></item><item id="2"><title>Two</title></item></feed>"#;
+    /// let items: Vec<Item> = XmlRecords::new(xml.as_bytes(), "/feed/item", &config)
+    ///     .deserialize()
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    /// assert_eq!(items[1], Item { id: "2".to_owned(), title: "Two".to_owned() });
+    /// ```
+    pub fn deserialize<T: DeserializeOwned>(self) -> XmlTypedRecords<'c, R, PhantomData<T>> {
+        self.deserialize_seed(PhantomData)
+    }
+
+    /// Deserializes every record with a clone of `seed`, like `deserialize`, for types that need state
+    /// to be deserialized.
+    pub fn deserialize_seed<S>(self, seed: S) -> XmlTypedRecords<'c, R, S>
+    where
+        S: Clone + for<'de> DeserializeSeed<'de>,
+    {
+        XmlTypedRecords {
+            matches: self.0,
+            seed,
+        }
+    }
+}
+
+impl<R: Read> Iterator for XmlRecords<'_, R> {
+    type Item = Result<Value, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.0.next()?.map(|(_, value)| value))
+    }
+}
+
+/// An iterator over the records of `XmlRecords` deserialized with a `DeserializeSeed`,
+/// see `XmlRecords::deserialize`.
+pub struct XmlTypedRecords<'c, R, S> {
+    matches: Matches<'c, R>,
+    seed: S,
+}
+
+impl<R, S, T> Iterator for XmlTypedRecords<'_, R, S>
+where
+    R: Read,
+    S: Clone + for<'de> DeserializeSeed<'de, Value = T>,
+{
+    type Item = Result<T, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let config = self.matches.config;
+        let seed = self.seed.clone();
+        let record = self.matches.next_with(|el, parent_path| {
+            let mut ctx = Context::new(config, None);
+            let mut path = parent_path.to_owned();
+            let value = borrowed::convert_element(&mut ctx, None, el, &mut path)?;
+            let value = value.unwrap_or(BorrowedValue::Null);
+            seed.deserialize(ValueDeserializer::new(value, Keys::new(config)))
+        })?;
+        Some(record.map(|(_, value)| value))
+    }
+}
+
+/// An iterator over the direct children of the root element of a document, with their keys and values,
+/// for documents with one element per record that don't need a record path, e.g.
+/// `<export><user>..</user><group>..</group></export>` gives `("user", {..})` and `("group", {..})`.
+///
+/// The children are converted one by one with the same rules as in `xml_str_to_json`, with the key that
+/// the element would have in its output. Text of the root element is skipped. Like in `XmlRecords`,
+/// an element that fails to parse or convert is returned as an error and the iteration continues.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{Config, XmlJsonIter};
+/// use serde_json::json;
+///
+/// let config = Config::new_with_defaults();
+/// let xml = r#"<export><user id="1"/><user id="2"/><group>admins</group></export>"#;
+/// let children: Vec<_> = XmlJsonIter::new(xml, &config).collect::<Result<_, _>>().unwrap();
+/// assert_eq!(children, [
+///     ("user".to_owned(), json!({"@id": 1})),
+///     ("user".to_owned(), json!({"@id": 2})),
+///     ("group".to_owned(), json!("admins")),
+/// ]);
+/// ```
+pub struct XmlJsonIter<'x, 'c>(Matches<'c, &'x [u8]>);
+
+impl<'x, 'c> XmlJsonIter<'x, 'c> {
+    /// Creates an iterator over the children of the root element of `xml`. Nothing is parsed until
+    /// the first child is requested.
+    pub fn new(xml: &'x str, config: &'c Config) -> Self {
+        XmlJsonIter(Matches::new(xml.as_bytes(), Target::RootChildren, config))
+    }
+}
+
+impl Iterator for XmlJsonIter<'_, '_> {
+    type Item = Result<(String, Value), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.0.next()?.map(|(path, value)| {
+            // keys in Clark notation may contain slashes
+            let key = split_path(&path).pop().unwrap_or_default().to_owned();
+            (key, value)
+        }))
+    }
+}
+
+/// Reads the document in `reader` in chunks like `XmlRecords` and calls `f` with the path and the converted
+/// value of every element that matches `matcher`, as soon as the element is complete. `f` stops the
+/// conversion early with `ControlFlow::Break`. Returns the number of elements `f` was called with.
+///
+/// The matcher is an absolute path, e.g. `/feed/item`, or a regex that is checked against the paths of all
+/// elements, e.g. `(\w/)*item$`, with the `regex_path` feature. The descendants of a matching element
+/// are part of its value and are not matched themselves. Like for `XmlRecords`, the values are converted
+/// with the same rules as in `xml_str_to_json` without being wrapped into an object with the element name.
+/// The first element that fails to parse or convert stops the conversion and is returned.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{for_each_match, Config};
+/// use serde_json::json;
+/// use std::ops::ControlFlow;
+///
+/// let xml = "<orders><order><id>1</id></order><order><id>2</id></order><order><id>3</id></order></orders>";
+/// let mut found = None;
+/// let visited = for_each_match(xml.as_bytes(), "/orders/order", &Config::new_with_defaults(), |_, order| {
+///     if order["id"] == 2 {
+///         found = Some(order);
+///         return ControlFlow::Break(());
+///     }
+///     ControlFlow::Continue(())
+/// });
+/// assert_eq!(visited.unwrap(), 2);
+/// assert_eq!(found, Some(json!({"id": 2})));
+/// ```
+pub fn for_each_match<R, P>(
+    reader: R,
+    matcher: P,
+    config: &Config,
+    mut f: impl FnMut(&str, Value) -> ControlFlow<()>,
+) -> Result<usize, Error>
+where
+    R: Read,
+    P: Into<PathMatcher>,
+{
+    let mut count = 0;
+    for matched in Matches::new(reader, Target::Path(matcher.into()), config) {
+        let (path, value) = matched?;
+        count += 1;
+        if f(&path, value).is_break() {
+            break;
+        }
+    }
+    Ok(count)
+}
+
+/// The path and the converted value of a matching element.
+type Match = (String, Value);
+
+/// A matching element that is complete in the buffer.
+struct Found {
+    range: Range<usize>,
+    path: String,
+    /// The length of the path of its parent.
+    parent_len: usize,
+}
+
+/// The elements that `Matches` converts.
+pub(crate) enum Target {
+    Path(PathMatcher),
+    /// The children of the root element.
+    RootChildren,
+}
+
+/// An open element that contains, or may contain, matching elements.
+struct Open {
+    /// The start tag of the element.
+    tag: Vec<u8>,
+    name_len: usize,
+    /// The length of the path of its parent.
+    parent_len: usize,
+    /// `true` if namespaces are declared on the element or its ancestors.
+    namespaces: bool,
+}
+
+/// An iterator over the paths and converted values of the elements of a document in an `io::Read`
+/// that match a `PathMatcher`, see `XmlRecords`.
+pub(crate) struct Matches<'c, R> {
+    reader: R,
+    config: &'c Config,
+    target: Target,
+    /// The element names of an absolute path of the target, starting with the root element.
+    names: Vec<String>,
+    buffer: Vec<u8>,
+    /// The position in `buffer` up to which the document has been scanned.
+    pos: usize,
+    /// Everything before the root element, once it's found.
+    prolog: Option<Vec<u8>>,
+    /// The open elements from the root element down to the current one.
+    open: Vec<Open>,
+    /// The path of the current element.
+    path: String,
+    /// The nesting level inside of an element that can't contain matching elements.
+    skipped: usize,
+    /// The position of the current match in `buffer`, its nesting level and the length of the path
+    /// of its parent.
+    record: Option<(usize, usize, usize)>,
+    /// The number of matching elements found so far, for `Config.match_limit`.
+    matched: usize,
+    eof: bool,
+    done: bool,
+}
+
+impl<'c, R: Read> Matches<'c, R> {
+    pub(crate) fn new(reader: R, target: Target, config: &'c Config) -> Self {
+        let names: Vec<String> = match &target {
+            Target::Path(PathMatcher::Absolute(path)) => split_path(path)
+                .iter()
+                .skip(1)
+                .map(|name| (*name).to_owned())
+                .collect(),
+            _ => Vec::new(),
+        };
+        Matches {
+            reader,
+            config,
+            done: matches!(target, Target::Path(PathMatcher::Absolute(_))) && names.is_empty(),
+            target,
+            names,
+            buffer: Vec::new(),
+            pos: 0,
+            prolog: None,
+            open: Vec::new(),
+            path: String::new(),
+            skipped: 0,
+            record: None,
+            matched: 0,
+            eof: false,
+        }
+    }
+
+    /// Scans the document up to the end of the next matching element and converts it with `convert`,
+    /// which gets the element and the path of its parent. Returns the path of the element with the result.
+    pub(crate) fn next_with<T>(
+        &mut self,
+        convert: impl FnOnce(roxmltree::Node, &str) -> Result<T, Error>,
+    ) -> Option<Result<(String, T), Error>> {
+        if self.done || self.config.match_limit == Some(self.matched) {
+            return None;
+        }
+        let found = match self.next_match().transpose()? {
+            Ok(found) => found,
+            Err(e) => return Some(Err(e)),
+        };
+        self.matched += 1;
+        let value = self.with_element(&self.buffer[found.range], |el| {
+            convert(el, &found.path[..found.parent_len])
+        });
+        Some(value.map(|value| (found.path, value)))
+    }
+
+    /// Scans the document up to the end of the next matching element.
+    fn next_match(&mut self) -> Result<Option<Found>, Error> {
+        loop {
+            let Some((markup, len)) = next_markup(&self.buffer[self.pos..]) else {
+                if self.eof {
+                    self.done = true;
+                    return Err(self.truncated());
+                }
+                if let Err(e) = self.fill() {
+                    self.done = true;
+                    return Err(e.into());
+                }
+                continue;
+            };
+            let start = self.pos;
+            self.pos += len;
+            let converted = match (markup, &mut self.record) {
+                (Markup::Start { empty: false, .. }, Some((_, depth, _))) => {
+                    *depth += 1;
+                    None
+                }
+                (Markup::End, Some((record_start, depth, parent_len))) => {
+                    *depth -= 1;
+                    if *depth == 0 {
+                        let (record_start, parent_len) = (*record_start, *parent_len);
+                        self.record = None;
+                        Some(Ok(self.found(record_start..self.pos, parent_len)))
+                    } else {
+                        None
+                    }
+                }
+                (_, Some(_)) => None,
+                (Markup::Start { empty, .. }, None) if self.skipped > 0 => {
+                    self.skipped += usize::from(!empty);
+                    None
+                }
+                (Markup::End, None) if self.skipped > 0 => {
+                    self.skipped -= 1;
+                    None
+                }
+                (Markup::Start { name_len, empty }, None) => {
+                    if self.prolog.is_none() {
+                        self.prolog = Some(self.buffer[..start].to_vec());
+                    }
+                    self.start_element(start, name_len, empty)
+                }
+                (Markup::End, None) => {
+                    if let Some(open) = self.open.pop() {
+                        self.path.truncate(open.parent_len);
+                    }
+                    None
+                }
+                (Markup::Other, None) => None,
+            };
+            if self.prolog.is_some()
+                && self.open.is_empty()
+                && self.skipped == 0
+                && self.record.is_none()
+            {
+                // the root element is closed, anything after it is ignored
+                self.done = true;
+            }
+            match converted {
+                None if self.done => return Ok(None),
+                None => (),
+                Some(result) => return result.map(Some),
+            }
+        }
+    }
+
+    /// Handles the start tag at `start`: converts it if it's an empty matching element, starts a match,
+    /// opens it if it may contain matches or skips it.
+    fn start_element(
+        &mut self,
+        start: usize,
+        name_len: usize,
+        empty: bool,
+    ) -> Option<Result<Found, Error>> {
+        let tag = &self.buffer[start..self.pos];
+        let name = &tag[1..1 + name_len];
+        let level = self.open.len();
+        if !self.names.is_empty() && !self.names.get(level).is_some_and(|n| may_match(name, n)) {
+            self.skipped = usize::from(!empty);
+            return None;
+        }
+        let namespaces = self.open.last().map_or_else(
+            || contains(self.prolog.as_deref().unwrap_or_default(), b"xmlns"),
+            |parent| parent.namespaces,
+        ) || contains(tag, b"xmlns");
+        let key = if namespaces {
+            let element = match tag.strip_suffix(b"/>") {
+                Some(_) => tag.to_vec(),
+                None => [&tag[..tag.len() - 1], b"/>"].concat(),
+            };
+            match self.with_element(
+                &element,
+                |el| Ok(element_key(self.config, &el).into_owned()),
+            ) {
+                Ok(key) => key,
+                Err(e) => {
+                    // the element is malformed, so it's reported in place of a match
+                    self.skipped = usize::from(!empty);
+                    return Some(Err(e));
+                }
+            }
+        } else {
+            String::from_utf8_lossy(name).into_owned()
+        };
+
+        let parent_len = self.path.len();
+        self.path.push('/');
+        self.path.push_str(&key);
+        if self.is_match() {
+            if empty {
+                return Some(Ok(self.found(start..self.pos, parent_len)));
+            }
+            self.record = Some((start, 1, parent_len));
+        } else if !empty && self.may_contain_match() {
+            let tag = tag.to_vec();
+            self.open.push(Open {
+                tag,
+                name_len,
+                parent_len,
+                namespaces,
+            });
+        } else {
+            self.path.truncate(parent_len);
+            self.skipped = usize::from(!empty);
+        }
+        None
+    }
+
+    fn is_match(&self) -> bool {
+        match &self.target {
+            Target::Path(PathMatcher::Absolute(path)) => self.path == *path,
+            #[cfg(feature = "regex_path")]
+            Target::Path(PathMatcher::Regex(regex)) => regex.is_match(&self.path),
+            Target::RootChildren => self.open.len() == 1,
+        }
+    }
+
+    /// Returns `false` if the descendants of the current element can't match.
+    fn may_contain_match(&self) -> bool {
+        match &self.target {
+            Target::Path(PathMatcher::Absolute(path)) => path
+                .strip_prefix(self.path.as_str())
+                .is_some_and(|rest| rest.starts_with('/')),
+            #[cfg(feature = "regex_path")]
+            Target::Path(PathMatcher::Regex(_)) => true,
+            Target::RootChildren => self.open.is_empty(),
+        }
+    }
+
+    /// Reads the next chunk, after dropping the scanned part of the buffer that is no longer needed.
+    fn fill(&mut self) -> io::Result<()> {
+        let keep = match self.record {
+            Some((record_start, _, _)) => record_start,
+            None if self.prolog.is_none() => 0,
+            None => self.pos,
+        };
+        self.buffer.drain(..keep);
+        self.pos -= keep;
+        if let Some((record_start, _, _)) = &mut self.record {
+            *record_start -= keep;
+        }
+        let len = self.buffer.len();
+        self.buffer.resize(len + CHUNK_SIZE, 0);
+        let read = loop {
+            match self.reader.read(&mut self.buffer[len..]) {
+                Ok(read) => break read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => {
+                    self.buffer.truncate(len);
+                    return Err(e);
+                }
+            }
+        };
+        self.buffer.truncate(len + read);
+        self.eof = read == 0;
+        Ok(())
+    }
+
+    /// Returns the matching element in `range` of the buffer with its path. The path of the current element
+    /// is reset to its parent.
+    fn found(&mut self, range: Range<usize>, parent_len: usize) -> Found {
+        let path = self.path.clone();
+        self.path.truncate(parent_len);
+        Found {
+            range,
+            path,
+            parent_len,
+        }
+    }
+
+    /// Parses `element` in the open ancestors after the prolog, so namespaces, `xml:base` and entities
+    /// are in scope, and calls `f` with it.
+    fn with_element<T>(
+        &self,
+        element: &[u8],
+        f: impl FnOnce(roxmltree::Node) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let mut fragment = Vec::new();
+        fragment.extend_from_slice(self.prolog.as_deref().unwrap_or_default());
+        for open in &self.open {
+            fragment.extend_from_slice(&open.tag);
+        }
+        fragment.extend_from_slice(element);
+        for open in self.open.iter().rev() {
+            fragment.extend_from_slice(b"</");
+            fragment.extend_from_slice(&open.tag[1..1 + open.name_len]);
+            fragment.push(b'>');
+        }
+        let xml = xml_bytes_to_str(&fragment, self.config)?;
+        let xml = prepare_input(&xml, self.config)?;
+        let doc = parse_document(&xml, self.config)?;
+        let mut el = doc.root_element();
+        for _ in &self.open {
+            match el.first_element_child() {
+                Some(child) => el = child,
+                None => return Err(Error::UnexpectedXml("malformed start tag".to_owned())),
+            }
+        }
+        f(el)
+    }
+
+    /// Returns the error for a document that ends before its root element is closed.
+    fn truncated(&self) -> Error {
+        let start = self
+            .record
+            .map_or(self.pos, |(record_start, _, _)| record_start);
+        match self.with_element(&self.buffer[start..], |_| Ok(())) {
+            Err(e) => e,
+            Ok(()) => Error::UnexpectedXml("unexpected end of the document".to_owned()),
+        }
+    }
+}
+
+impl<R: Read> Iterator for Matches<'_, R> {
+    type Item = Result<Match, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let config = self.config;
+        self.next_with(|el, parent_path| {
+            // converted with the same rules as in `xml_records_to_json`
+            let mut ctx = Context::new(config, None);
+            let value = convert_node(&mut ctx, &el, parent_path)?.unwrap_or(Value::Null);
+            Ok(post_process(config, value))
+        })
+    }
+}
+
+/// The markup at the start of the unscanned part of the document.
+enum Markup {
+    /// A start tag with the length of its name, `empty` if it ends with `/>`.
+    Start {
+        name_len: usize,
+        empty: bool,
+    },
+    End,
+    /// Text, comments, CDATA sections, processing instructions and the document type declaration.
+    Other,
+}
+
+/// Returns the markup at the start of `bytes` and its length, or `None` if it isn't complete yet.
+fn next_markup(bytes: &[u8]) -> Option<(Markup, usize)> {
+    if bytes.first() != Some(&b'<') {
+        let len = bytes.iter().position(|&b| b == b'<').unwrap_or(bytes.len());
+        return (len > 0).then_some((Markup::Other, len));
+    }
+    if bytes.starts_with(b"<!--") {
+        return Some((Markup::Other, find(bytes, 4, b"-->")?));
+    }
+    if bytes.starts_with(b"<![CDATA[") {
+        return Some((Markup::Other, find(bytes, 9, b"]]>")?));
+    }
+    if bytes.starts_with(b"<?") {
+        return Some((Markup::Other, find(bytes, 2, b"?>")?));
+    }
+    if bytes.starts_with(b"<!") {
+        return Some((Markup::Other, declaration_len(bytes)?));
+    }
+    if bytes.starts_with(b"</") {
+        return Some((Markup::End, find(bytes, 2, b">")?));
+    }
+    // `>` may appear in quoted attribute values
+    let mut quote = None;
+    for (i, &b) in bytes.iter().enumerate().skip(1) {
+        match (quote, b) {
+            (None, b'"' | b'\'') => quote = Some(b),
+            (Some(q), _) if q == b => quote = None,
+            (None, b'>') => {
+                let name_len = bytes[1..]
+                    .iter()
+                    .position(|&b| b.is_ascii_whitespace() || b == b'/' || b == b'>')
+                    .unwrap_or(0);
+                let empty = bytes[i - 1] == b'/';
+                return Some((Markup::Start { name_len, empty }, i + 1));
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Returns the position after the first `pattern` in `bytes` from `from` on.
+fn find(bytes: &[u8], from: usize, pattern: &[u8]) -> Option<usize> {
+    bytes
+        .get(from..)?
+        .windows(pattern.len())
+        .position(|w| w == pattern)
+        .map(|i| from + i + pattern.len())
+}
+
+/// Returns the length of a document type declaration with its internal subset.
+fn declaration_len(bytes: &[u8]) -> Option<usize> {
+    let (mut quote, mut brackets, mut i) = (None, 0usize, 2);
+    while i < bytes.len() {
+        let b = bytes[i];
+        match (quote, b) {
+            (Some(q), _) if q == b => quote = None,
+            (Some(_), _) => (),
+            (None, b'"' | b'\'') => quote = Some(b),
+            (None, b'<') if bytes[i..].starts_with(b"<!--") => {
+                i = find(bytes, i + 4, b"-->")?;
+                continue;
+            }
+            (None, b'[') => brackets += 1,
+            (None, b']') => brackets = brackets.saturating_sub(1),
+            (None, b'>') if brackets == 0 => return Some(i + 1),
+            _ => (),
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Returns `false` if an element with the qualified `name` can't have the key `key`, which saves parsing it.
+/// The key ends with the local name, after a prefix or a namespace URI in Clark notation.
+fn may_match(name: &[u8], key: &str) -> bool {
+    let local = name.rsplit(|&b| b == b':').next().unwrap_or(name);
+    key.as_bytes()
+        .strip_suffix(local)
+        .is_some_and(|rest| rest.is_empty() || rest.ends_with(b":") || rest.ends_with(b"}"))
+}
+
+fn contains(bytes: &[u8], pattern: &[u8]) -> bool {
+    bytes.windows(pattern.len()).any(|w| w == pattern)
+}
@@ -0,0 +1,147 @@
+//! Checks which information of a document doesn't survive the conversion to JSON and back.
+
+use std::collections::HashMap;
+
+use crate::{
+    json_to_xml_string, parse_document, prepare_input, xml_str_to_json_with_diagnostics, Config,
+    Diagnostic, DiagnosticKind, Diagnostics, Error, XmlWriterConfig,
+};
+
+/// The result of `verify_roundtrip`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundtripReport {
+    /// Everything that would be lost: the conversion diagnostics first, followed by the differences
+    /// found after converting back into XML in document order.
+    pub issues: Vec<Diagnostic>,
+}
+
+impl RoundtripReport {
+    /// Returns `true` if converting the document to JSON and back doesn't lose any information.
+    pub fn is_lossless(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Converts the document into JSON with `config`, converts it back into XML with `json_to_xml_string`
+/// and reports all information that would be lost on the way: the conversion diagnostics plus
+/// comments, element order and values that come back different, e.g. `007` that comes back as `7`.
+/// Use it on a sample of your documents to check that a `Config` is safe for them.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{verify_roundtrip, Config, DiagnosticKind};
+///
+/// let report = verify_roundtrip("<a><b>007</b><!-- note --></a>", &Config::new_with_defaults()).unwrap();
+/// assert_eq!(report.issues[0].kind, DiagnosticKind::DroppedComment(" note ".to_owned()));
+/// assert_eq!(report.issues[1].path, "/a/b");
+/// assert_eq!(
+///     report.issues[1].kind,
+///     DiagnosticKind::ChangedValue { original: "007".to_owned(), restored: Some("7".to_owned()) }
+/// );
+/// ```
+pub fn verify_roundtrip(xml: &str, config: &Config) -> Result<RoundtripReport, Error> {
+    let mut diagnostics = Diagnostics::new();
+    let json = xml_str_to_json_with_diagnostics(xml, config, &mut diagnostics)?;
+    let mut issues = diagnostics.into_vec();
+
+    let restored_xml = json_to_xml_string(&json, config, &XmlWriterConfig::new_with_defaults())?;
+    let xml = prepare_input(xml, config)?;
+    let original = parse_document(&xml, config)?;
+    let restored = parse_document(&restored_xml, config)?;
+    compare(
+        original.root_element(),
+        Some(restored.root_element()),
+        "",
+        &mut issues,
+    );
+
+    Ok(RoundtripReport { issues })
+}
+
+/// Compares the original element with its restored counterpart and its children recursively.
+fn compare(
+    original: roxmltree::Node,
+    restored: Option<roxmltree::Node>,
+    parent_path: &str,
+    issues: &mut Vec<Diagnostic>,
+) {
+    let name = original.tag_name().name();
+    let path = [parent_path, "/", name].concat();
+    let Some(restored) = restored else {
+        push_once(
+            issues,
+            parent_path,
+            DiagnosticKind::DroppedElement(name.to_owned()),
+        );
+        return;
+    };
+
+    for attr in original.attributes() {
+        let restored_value = restored.attribute(attr.name());
+        if restored_value != Some(attr.value()) {
+            issues.push(Diagnostic {
+                path: [path.as_str(), "/@", attr.name()].concat(),
+                kind: DiagnosticKind::ChangedValue {
+                    original: attr.value().to_owned(),
+                    restored: restored_value.map(str::to_owned),
+                },
+            });
+        }
+    }
+
+    if let Some(text) = original.text().filter(|t| !t.trim().is_empty()) {
+        let restored_text = restored.text();
+        if restored_text != Some(text) {
+            issues.push(Diagnostic {
+                path: path.clone(),
+                kind: DiagnosticKind::ChangedValue {
+                    original: text.to_owned(),
+                    restored: restored_text.map(str::to_owned),
+                },
+            });
+        }
+    }
+
+    for comment in original.children().filter(|n| n.is_comment()) {
+        issues.push(Diagnostic {
+            path: path.clone(),
+            kind: DiagnosticKind::DroppedComment(comment.text().unwrap_or_default().to_owned()),
+        });
+    }
+
+    // same-named elements are matched in order of their appearance
+    let mut restored_children: HashMap<&str, Vec<roxmltree::Node>> = HashMap::new();
+    for child in restored.children().filter(|n| n.is_element()).rev() {
+        restored_children
+            .entry(child.tag_name().name())
+            .or_default()
+            .push(child);
+    }
+    let original_order = original.children().filter(|n| n.is_element());
+    let restored_order = restored.children().filter(|n| n.is_element());
+    if let Some((child, _)) = original_order
+        .zip(restored_order)
+        .find(|(o, r)| o.tag_name().name() != r.tag_name().name())
+    {
+        issues.push(Diagnostic {
+            path: path.clone(),
+            kind: DiagnosticKind::ReorderedElement(child.tag_name().name().to_owned()),
+        });
+    }
+
+    for child in original.children().filter(|n| n.is_element()) {
+        let counterpart = restored_children
+            .get_mut(child.tag_name().name())
+            .and_then(Vec::pop);
+        compare(child, counterpart, &path, issues);
+    }
+}
+
+/// Adds the issue unless it was already reported by the conversion.
+fn push_once(issues: &mut Vec<Diagnostic>, path: &str, kind: DiagnosticKind) {
+    if !issues.iter().any(|i| i.path == path && i.kind == kind) {
+        issues.push(Diagnostic {
+            path: path.to_owned(),
+            kind,
+        });
+    }
+}
@@ -0,0 +1,148 @@
+//! Inference of a JSON Schema describing the output of the converter.
+
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+use crate::{xml_str_to_json, Config, Error};
+
+/// The union of all JSON types seen at the same position of the output.
+#[derive(Default)]
+struct Shape {
+    null: bool,
+    boolean: bool,
+    integer: bool,
+    number: bool,
+    string: bool,
+    array: Option<Box<Shape>>,
+    object: Option<ObjectShape>,
+}
+
+/// Properties of all objects seen at the same position, with the number of objects each of them was found in.
+#[derive(Default)]
+struct ObjectShape {
+    count: usize,
+    properties: BTreeMap<String, (Shape, usize)>,
+}
+
+impl Shape {
+    /// Adds the type of `value` to the shape.
+    fn add(&mut self, value: &Value) {
+        match value {
+            Value::Null => self.null = true,
+            Value::Bool(_) => self.boolean = true,
+            Value::Number(n) if n.is_f64() => self.number = true,
+            Value::Number(_) => self.integer = true,
+            Value::String(_) => self.string = true,
+            Value::Array(items) => {
+                let shape = self.array.get_or_insert_with(Default::default);
+                for item in items {
+                    shape.add(item);
+                }
+            }
+            Value::Object(map) => {
+                let shape = self.object.get_or_insert_with(Default::default);
+                shape.count += 1;
+                for (key, value) in map {
+                    let (property, count) = shape.properties.entry(key.clone()).or_default();
+                    property.add(value);
+                    *count += 1;
+                }
+            }
+        }
+    }
+
+    /// Converts the shape into a JSON Schema. Multiple types are combined with `anyOf`.
+    fn to_schema(&self) -> Value {
+        let mut variants = Vec::new();
+
+        // scalars are combined into a single `type` list
+        let mut types = Vec::new();
+        if self.null {
+            types.push("null");
+        }
+        if self.boolean {
+            types.push("boolean");
+        }
+        // integers are valid numbers
+        if self.number {
+            types.push("number");
+        } else if self.integer {
+            types.push("integer");
+        }
+        if self.string {
+            types.push("string");
+        }
+        match types.len() {
+            0 => (),
+            1 => variants.push(json!({ "type": types[0] })),
+            _ => variants.push(json!({ "type": types })),
+        }
+
+        if let Some(items) = &self.array {
+            let mut schema = json!({ "type": "array" });
+            let items = items.to_schema();
+            // an empty schema means there were no items
+            if items.as_object().is_some_and(|o| !o.is_empty()) {
+                schema["items"] = items;
+            }
+            variants.push(schema);
+        }
+
+        if let Some(object) = &self.object {
+            let mut properties = Map::new();
+            let mut required = Vec::new();
+            for (key, (shape, count)) in &object.properties {
+                properties.insert(key.clone(), shape.to_schema());
+                if *count == object.count {
+                    required.push(Value::String(key.clone()));
+                }
+            }
+            let mut schema = json!({ "type": "object", "properties": properties });
+            if !required.is_empty() {
+                schema["required"] = Value::Array(required);
+            }
+            variants.push(schema);
+        }
+
+        match variants.len() {
+            0 => json!({}),
+            1 => variants.remove(0),
+            _ => json!({ "anyOf": variants }),
+        }
+    }
+}
+
+/// Infers a JSON Schema (draft-07) for the JSON produced from the given XML with the same `Config`.
+///
+/// Properties found in all objects at the same position are `required`. Elements that are converted
+/// into an array in one place and into a single value in another produce an `anyOf` schema,
+/// which is a hint to enforce arrays with `JsonArray::Always`.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{infer_json_schema, Config};
+///
+/// let schema = infer_json_schema("<a><b>1</b><b>x</b></a>", &Config::new_with_defaults()).unwrap();
+/// assert_eq!(schema["properties"]["a"]["properties"]["b"]["items"]["type"], serde_json::json!(["integer", "string"]));
+/// ```
+pub fn infer_json_schema(xml: &str, config: &Config) -> Result<Value, Error> {
+    let value = xml_str_to_json(xml, config)?;
+    Ok(json_schema_for_values(std::slice::from_ref(&value)))
+}
+
+/// Infers a single JSON Schema that describes all of the given values, e.g. the output of several
+/// documents of the same feed. See `infer_json_schema` for details.
+pub fn json_schema_for_values(values: &[Value]) -> Value {
+    let mut shape = Shape::default();
+    for value in values {
+        shape.add(value);
+    }
+
+    let mut schema = shape.to_schema();
+    if let Value::Object(map) = &mut schema {
+        map.insert(
+            "$schema".to_owned(),
+            Value::String("http://json-schema.org/draft-07/schema#".to_owned()),
+        );
+    }
+    schema
+}
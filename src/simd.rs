@@ -0,0 +1,48 @@
+//! Conversion of XML into simd-json values, available with the `simd-json` feature.
+
+use serde_json::Value;
+use simd_json::prelude::Writable;
+use simd_json::{OwnedValue, StaticNode};
+
+use crate::{xml_str_to_json, Config, Error};
+
+/// Converts an XML document into a `simd_json::OwnedValue` with the same rules as `xml_str_to_json`.
+/// The objects of `OwnedValue` are hash maps, so their keys are not sorted.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_str_to_simd_json, Config};
+/// use simd_json::prelude::*;
+///
+/// let value = xml_str_to_simd_json("<a><b>1</b></a>", &Config::new_with_defaults()).unwrap();
+/// assert_eq!(value["a"]["b"].as_u64(), Some(1));
+/// ```
+pub fn xml_str_to_simd_json(xml: &str, config: &Config) -> Result<OwnedValue, Error> {
+    Ok(json_to_simd(xml_str_to_json(xml, config)?))
+}
+
+/// Converts an XML document into a JSON string with the same rules as `xml_str_to_json`,
+/// serialized by simd-json.
+pub fn xml_str_to_simd_json_string(xml: &str, config: &Config) -> Result<String, Error> {
+    Ok(xml_str_to_simd_json(xml, config)?.encode())
+}
+
+/// Moves the JSON value into the equivalent simd-json value without serializing it.
+fn json_to_simd(value: Value) -> OwnedValue {
+    match value {
+        Value::Null => OwnedValue::Static(StaticNode::Null),
+        Value::Bool(b) => OwnedValue::Static(StaticNode::Bool(b)),
+        Value::Number(n) => OwnedValue::Static(if let Some(u) = n.as_u64() {
+            StaticNode::U64(u)
+        } else if let Some(i) = n.as_i64() {
+            StaticNode::I64(i)
+        } else {
+            StaticNode::from(n.as_f64().unwrap_or(f64::NAN))
+        }),
+        Value::String(s) => OwnedValue::String(s),
+        Value::Array(items) => items.into_iter().map(json_to_simd).collect(),
+        Value::Object(map) => map
+            .into_iter()
+            .map(|(key, value)| (key, json_to_simd(value)))
+            .collect(),
+    }
+}
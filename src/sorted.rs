@@ -0,0 +1,108 @@
+//! Serialization of the output with the object keys in a fixed order, see `Config.sort_keys`.
+
+use std::cmp::Ordering;
+
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use serde_json::Value;
+
+use crate::{xml_str_to_json, Config, Error};
+
+/// Compares two keys of the output by their names without the attribute prefix, so `@id` and `id` are next
+/// to each other, with the attribute first. Names are compared by their bytes, which doesn't depend on the
+/// locale and gives the order of Unicode code points, e.g. `B` before `a`.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::compare_keys;
+///
+/// let mut keys = vec!["name", "id", "@id", "#text", "@lang"];
+/// keys.sort_by(|a, b| compare_keys(a, b, "@"));
+/// assert_eq!(keys, ["#text", "@id", "id", "@lang", "name"]);
+/// ```
+pub fn compare_keys(a: &str, b: &str, attr_prefix: &str) -> Ordering {
+    // elements sort after attributes of the same name
+    fn name<'k>(key: &'k str, attr_prefix: &str) -> (&'k str, bool) {
+        match key.strip_prefix(attr_prefix) {
+            Some(name) if !attr_prefix.is_empty() => (name, false),
+            _ => (key, true),
+        }
+    }
+    name(a, attr_prefix)
+        .cmp(&name(b, attr_prefix))
+        .then_with(|| a.cmp(b))
+}
+
+/// Serializes a `serde_json::Value` with the keys of all objects ordered by `compare_keys`, independent of
+/// the order of `serde_json::Map`, which keeps the insertion order with the `preserve_order` feature of
+/// `serde_json`.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_str_to_json, Config, SortedKeys};
+///
+/// let config = Config::new_with_defaults();
+/// let json = xml_str_to_json(r#"<a><b lang="en">x</b><lang>de</lang></a>"#, &config).unwrap();
+/// let sorted = serde_json::to_string(&SortedKeys::new(&json, &config)).unwrap();
+/// assert_eq!(sorted, r##"{"a":{"b":{"#text":"x","@lang":"en"},"lang":"de"}}"##);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct SortedKeys<'v> {
+    value: &'v Value,
+    attr_prefix: &'v str,
+}
+
+impl<'v> SortedKeys<'v> {
+    /// Orders the keys of `value` with the attribute prefix of `config`.
+    pub fn new(value: &'v Value, config: &'v Config) -> Self {
+        SortedKeys {
+            value,
+            attr_prefix: &config.xml_attr_prefix,
+        }
+    }
+}
+
+impl Serialize for SortedKeys<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let nested = |value| SortedKeys {
+            value,
+            attr_prefix: self.attr_prefix,
+        };
+        match self.value {
+            Value::Object(map) => {
+                let mut entries: Vec<_> = map.iter().collect();
+                entries.sort_by(|(a, _), (b, _)| compare_keys(a, b, self.attr_prefix));
+                let mut object = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    object.serialize_entry(key, &nested(value))?;
+                }
+                object.end()
+            }
+            Value::Array(items) => {
+                let mut array = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    array.serialize_element(&nested(item))?;
+                }
+                array.end()
+            }
+            value => value.serialize(serializer),
+        }
+    }
+}
+
+/// Converts the given XML string into a JSON string, with the keys in the order of `compare_keys` if
+/// `Config.sort_keys` is set, and in the order of `serde_json::Map` otherwise.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_str_to_json_string, Config};
+///
+/// let config = Config::new_with_defaults().with_sorted_keys();
+/// let json = xml_str_to_json_string(r#"<a id="1"><b/><id>x</id></a>"#, &config).unwrap();
+/// assert_eq!(json, r#"{"a":{"b":{},"@id":1,"id":"x"}}"#);
+/// ```
+pub fn xml_str_to_json_string(xml: &str, config: &Config) -> Result<String, Error> {
+    let json = xml_str_to_json(xml, config)?;
+    let string = if config.sort_keys {
+        serde_json::to_string(&SortedKeys::new(&json, config))
+    } else {
+        serde_json::to_string(&json)
+    };
+    string.map_err(|e| Error::Serialize(e.to_string()))
+}
@@ -0,0 +1,156 @@
+//! Splitting of a document into one JSON value or file per record, see `xml_split_records`.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::{Config, Error, SortedKeys, XmlRecords};
+
+/// Options of `xml_split_records_to_files`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitConfig {
+    /// The path of the file of each record, with `{n}` replaced by the counter, e.g. `out/item-{n}.json`.
+    /// `{n:W}` pads the counter with zeros to `W` digits, e.g. `{n:6}` gives `000042`.
+    pub file_pattern: String,
+    /// The counter of the first record. Defaults to `0`.
+    pub first_index: usize,
+    /// Write indented JSON. Defaults to `false`.
+    pub pretty: bool,
+}
+
+impl SplitConfig {
+    /// Writes the records to the files of `file_pattern`, see `SplitConfig.file_pattern`.
+    pub fn new(file_pattern: &str) -> Self {
+        SplitConfig {
+            file_pattern: file_pattern.to_owned(),
+            first_index: 0,
+            pretty: false,
+        }
+    }
+
+    /// Starts counting the records at the given index, e.g. `1`.
+    pub fn with_first_index(self, first_index: usize) -> Self {
+        SplitConfig {
+            first_index,
+            ..self
+        }
+    }
+
+    /// Writes indented JSON.
+    pub fn with_pretty(self) -> Self {
+        SplitConfig {
+            pretty: true,
+            ..self
+        }
+    }
+
+    /// Returns the path of the file of the record with the counter `n`.
+    fn file_path(&self, n: usize) -> Result<PathBuf, Error> {
+        let invalid = || {
+            Error::InvalidConfig(format!(
+                "file pattern {}: expected `{{n}}` or `{{n:WIDTH}}` for the counter",
+                self.file_pattern
+            ))
+        };
+        let start = self.file_pattern.find("{n").ok_or_else(invalid)?;
+        let end = start + self.file_pattern[start..].find('}').ok_or_else(invalid)?;
+        let counter = match &self.file_pattern[start + 2..end] {
+            "" => n.to_string(),
+            width => match width.strip_prefix(':').map(str::parse::<usize>) {
+                Some(Ok(width)) => format!("{:0width$}", n, width = width),
+                _ => return Err(invalid()),
+            },
+        };
+        Ok([
+            &self.file_pattern[..start],
+            &counter,
+            &self.file_pattern[end + 1..],
+        ]
+        .concat()
+        .into())
+    }
+}
+
+/// Converts every element at the absolute `record_path` of the document in `reader`, e.g. every `item`
+/// for `/feed/item`, like `XmlRecords` and calls `f` with its counter, starting at `0`, and its value.
+/// The document is read in chunks, so only one record is in memory at a time.
+///
+/// The first record that fails to parse or convert, and the first error of `f`, stop the split and
+/// are returned. Otherwise the number of records is returned.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_split_records, Config};
+/// use serde_json::json;
+///
+/// let xml = "<export><row><id>1</id></row><row><id>2</id></row></export>";
+/// let mut ids = Vec::new();
+/// let count = xml_split_records(xml.as_bytes(), "/export/row", &Config::new_with_defaults(), |n, row| {
+///     ids.push((n, row["id"].clone()));
+///     Ok(())
+/// });
+/// assert_eq!(count.unwrap(), 2);
+/// assert_eq!(ids, [(0, json!(1)), (1, json!(2))]);
+/// ```
+pub fn xml_split_records<R: Read>(
+    reader: R,
+    record_path: &str,
+    config: &Config,
+    mut f: impl FnMut(usize, Value) -> Result<(), Error>,
+) -> Result<usize, Error> {
+    let mut count = 0;
+    for record in XmlRecords::new(reader, record_path, config) {
+        f(count, record?)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Splits the document in `reader` like `xml_split_records` and writes every record to its own JSON file,
+/// named after `SplitConfig.file_pattern` with the counter of the record, e.g. `out/item-{n}.json`.
+/// Missing directories are created and existing files are overwritten.
+/// Returns the number of files, or the first conversion or `Error::Io` error.
+/// # Example
+/// ```no_run
+/// use roxmltree_to_serde::{xml_split_records_to_files, Config, SplitConfig};
+///
+/// let export = std::io::BufReader::new(std::fs::File::open("export.xml").unwrap());
+/// let split = SplitConfig::new("out/row-{n:6}.json").with_first_index(1);
+/// xml_split_records_to_files(export, "/export/row", &Config::new_with_defaults(), &split).unwrap();
+/// ```
+pub fn xml_split_records_to_files<R: Read>(
+    reader: R,
+    record_path: &str,
+    config: &Config,
+    split: &SplitConfig,
+) -> Result<usize, Error> {
+    // fail before anything is read if the pattern has no counter
+    split.file_path(split.first_index)?;
+    let mut created_dir: Option<PathBuf> = None;
+    xml_split_records(reader, record_path, config, |n, record| {
+        let path = split.file_path(split.first_index + n)?;
+        let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+        if let Some(dir) = dir {
+            if created_dir.as_deref() != Some(dir) {
+                fs::create_dir_all(dir)?;
+                created_dir = Some(dir.to_owned());
+            }
+        }
+        write_file(&path, &record, config, split.pretty)
+    })
+}
+
+fn write_file(path: &Path, value: &Value, config: &Config, pretty: bool) -> Result<(), Error> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    let sorted = SortedKeys::new(value, config);
+    let written = match (config.sort_keys, pretty) {
+        (true, true) => serde_json::to_writer_pretty(&mut writer, &sorted),
+        (true, false) => serde_json::to_writer(&mut writer, &sorted),
+        (false, true) => serde_json::to_writer_pretty(&mut writer, value),
+        (false, false) => serde_json::to_writer(&mut writer, value),
+    };
+    written.map_err(io::Error::from)?;
+    writer.write_all(b"\n")?;
+    Ok(writer.flush()?)
+}
@@ -292,6 +292,8 @@ fn test_parse_text() {
     assert_eq!("0000", parse_text("0000", true, &JsonType::Infer));
     assert_eq!(0.42, parse_text("0.4200", false, &JsonType::Infer));
     assert_eq!(142.42, parse_text("142.4200", false, &JsonType::Infer));
+    assert_eq!(-42.0, parse_text("-42", false, &JsonType::Infer));
+    assert_eq!(-4.2, parse_text("-4.2", false, &JsonType::Infer));
     assert_eq!("0xAC", parse_text("0xAC", true, &JsonType::Infer));
     assert_eq!("0x03", parse_text("0x03", true, &JsonType::Infer));
     assert_eq!("142,4200", parse_text("142,4200", true, &JsonType::Infer));
@@ -308,6 +310,14 @@ fn test_parse_text() {
     assert_eq!(false, parse_text("false", false, &JsonType::Infer));
     assert_eq!(true, parse_text("true", true, &JsonType::Infer));
     assert_eq!("True", parse_text("True", true, &JsonType::Infer));
+    assert_eq!(0.5, parse_text(".5", false, &JsonType::Infer));
+    assert_eq!(5, parse_text("+5", false, &JsonType::Infer));
+    assert_eq!(100000.0, parse_text(" 1e5 ", false, &JsonType::Infer));
+    assert_eq!("inf", parse_text("inf", false, &JsonType::Infer));
+    assert_eq!("-inf", parse_text("-inf", false, &JsonType::Infer));
+    assert_eq!("NaN", parse_text("NaN", false, &JsonType::Infer));
+    assert_eq!("e5", parse_text("e5", false, &JsonType::Infer));
+    assert_eq!("", parse_text("", false, &JsonType::Infer));
 
     // always enforce JSON bool type
     #[cfg(feature = "json_types")]
@@ -325,6 +335,15 @@ fn test_parse_text() {
         assert_eq!(true, parse_text(" ", false, &bool_type));
     }
 
+    // always enforce JSON number type
+    #[cfg(feature = "json_types")]
+    {
+        assert_eq!(7, parse_text("007", true, &JsonType::AlwaysNumber));
+        assert_eq!(-7, parse_text("-7", true, &JsonType::AlwaysNumber));
+        assert_eq!(0.5, parse_text(" 0.50 ", true, &JsonType::AlwaysNumber));
+        assert_eq!("7a", parse_text("7a", true, &JsonType::AlwaysNumber));
+    }
+
     // always enforce JSON string type
     assert_eq!("abc", parse_text("abc", false, &JsonType::AlwaysString));
     assert_eq!("true", parse_text("true", false, &JsonType::AlwaysString));
@@ -459,3 +478,4654 @@ fn test_regex_json_type_overrides() {
     let result = xml_string_to_json(String::from(xml), &config);
     assert_eq!(expected, result.unwrap());
 }
+
+#[test]
+fn test_diagnostics() {
+    let xml = r#"<a xmlns:x="urn:x" xmlns:y="urn:y" x:id="1" y:id="2"><b>text<c/>tail</b><d/>loose<x:e/></a>"#;
+    let mut diagnostics = Diagnostics::new();
+    let result =
+        xml_str_to_json_with_diagnostics(xml, &Config::new_with_defaults(), &mut diagnostics);
+    assert_eq!(
+        json!({"a": {"@id": 2, "b": "text", "d": {}, "e": {}}}),
+        result.unwrap()
+    );
+
+    let kinds: Vec<(&str, &DiagnosticKind)> = diagnostics
+        .iter()
+        .map(|d| (d.path.as_str(), &d.kind))
+        .collect();
+    assert_eq!(
+        vec![
+            ("/a/@id", &DiagnosticKind::IgnoredNamespace("urn:x".into())),
+            ("/a/@id", &DiagnosticKind::IgnoredNamespace("urn:y".into())),
+            ("/a", &DiagnosticKind::KeyCollision("@id".into())),
+            ("/a/b", &DiagnosticKind::DroppedElement("c".into())),
+            ("/a/b", &DiagnosticKind::DroppedText("tail".into())),
+            ("/a", &DiagnosticKind::DroppedText("loose".into())),
+        ],
+        kinds
+    );
+
+    // attribute and element names clashing without a prefix
+    let conf = Config::new_with_custom_values(true, "", "text", NullValue::Null);
+    let mut diagnostics = Diagnostics::new();
+    let xml = r#"<a attr1="val1"><attr1>1</attr1><attr1>2</attr1></a>"#;
+    xml_str_to_json_with_diagnostics(xml, &conf, &mut diagnostics).unwrap();
+    assert_eq!(1, diagnostics.len());
+    assert_eq!(
+        DiagnosticKind::KeyCollision("attr1".into()),
+        diagnostics.iter().next().unwrap().kind
+    );
+}
+
+#[cfg(feature = "json_types")]
+#[test]
+fn test_override_usage() {
+    use std::sync::Arc;
+
+    let usage = Arc::new(OverrideUsage::default());
+    let conf = Config::new_with_defaults()
+        .add_json_type_override("/a/b", JsonArray::Always(JsonType::Infer))
+        .add_json_type_override("/a/b/@id", JsonArray::Infer(JsonType::AlwaysString))
+        .add_json_type_override("/a/c", JsonArray::Infer(JsonType::AlwaysString))
+        .add_json_type_override("/x", JsonArray::Infer(JsonType::AlwaysString))
+        .with_override_usage(usage.clone());
+    #[cfg(feature = "regex_path")]
+    let conf = conf
+        .add_json_type_override(
+            regex::Regex::new("/c$").unwrap(),
+            JsonArray::Infer(JsonType::AlwaysNumber),
+        )
+        .add_json_type_override(
+            regex::Regex::new("/d$").unwrap(),
+            JsonArray::Infer(JsonType::AlwaysNumber),
+        );
+
+    let report = usage.report(&conf);
+    assert!(report.matched.is_empty());
+    let rules = if cfg!(feature = "regex_path") { 6 } else { 4 };
+    assert_eq!(rules, report.unmatched.len());
+
+    xml_str_to_json("<a><b>1</b></a>", &conf).unwrap();
+    xml_str_to_json(r#"<a><b id="1"/><c>2</c></a>"#, &conf).unwrap();
+    let report = usage.report(&conf);
+    #[cfg(not(feature = "regex_path"))]
+    {
+        assert_eq!(vec!["/a/b", "/a/b/@id", "/a/c"], report.matched);
+        assert_eq!(vec!["/x"], report.unmatched);
+    }
+    // the regex shadows `/a/c`
+    #[cfg(feature = "regex_path")]
+    {
+        assert_eq!(vec!["/a/b", "/a/b/@id", "/c$"], report.matched);
+        assert_eq!(vec!["/a/c", "/x", "/d$"], report.unmatched);
+    }
+}
+
+#[cfg(feature = "json_types")]
+#[test]
+fn test_xml_str_to_json_with_trace() {
+    let xml = r#"<a xmlns:v="urn:v"><v:b id="1">x</v:b><v:b id="2">y</v:b></a>"#;
+    let conf = Config::new_with_defaults()
+        .with_namespace_prefix("urn:v", "v")
+        .add_json_type_override("/a/b/@id", JsonArray::Infer(JsonType::AlwaysString))
+        .add_json_type_override("/a/v:b", JsonArray::Always(JsonType::AlwaysString));
+    let (json, trace) = xml_str_to_json_with_trace(xml, &conf).unwrap();
+    assert_eq!(json, xml_str_to_json(xml, &conf).unwrap());
+    assert_eq!(
+        TraceEntry {
+            path: "/a/v:b".to_owned(),
+            applied: Some(AppliedOverride::Path("/a/v:b".to_owned())),
+            json_type: JsonType::AlwaysString,
+            always_array: true,
+        },
+        trace[1]
+    );
+    // the override of `/a/b/@id` misses the prefix
+    assert_eq!("/a/v:b/@id", trace[2].path);
+    assert_eq!(None, trace[2].applied);
+    let paths: Vec<_> = trace.iter().map(|entry| entry.path.as_str()).collect();
+    assert_eq!(
+        vec!["/a", "/a/v:b", "/a/v:b/@id", "/a/v:b", "/a/v:b/@id"],
+        paths
+    );
+
+    #[cfg(feature = "regex_path")]
+    {
+        let conf = conf.add_json_type_override(
+            regex::Regex::new("b$").unwrap(),
+            JsonArray::Infer(JsonType::Infer),
+        );
+        let (_, trace) = xml_str_to_json_with_trace(xml, &conf).unwrap();
+        assert_eq!(
+            Some(AppliedOverride::Regex("b$".to_owned())),
+            trace[1].applied
+        );
+        assert!(!trace[1].always_array);
+    }
+}
+
+#[cfg(all(feature = "tracing", feature = "json_types"))]
+#[test]
+fn test_tracing_spans() {
+    use std::sync::{Arc, Mutex};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    /// Collects the names of all spans and events.
+    struct Names(Arc<Mutex<Vec<String>>>);
+
+    impl Subscriber for Names {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            self.0
+                .lock()
+                .unwrap()
+                .push(span.metadata().name().to_owned());
+            Id::from_u64(1)
+        }
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+        fn event(&self, event: &Event<'_>) {
+            let mut message = String::new();
+            event.record(
+                &mut |field: &tracing::field::Field, value: &dyn std::fmt::Debug| {
+                    if field.name() == "message" {
+                        message = format!("{:?}", value);
+                    }
+                },
+            );
+            self.0.lock().unwrap().push(message);
+        }
+        fn enter(&self, _: &Id) {}
+        fn exit(&self, _: &Id) {}
+    }
+
+    let names = Arc::new(Mutex::new(Vec::new()));
+    let conf = Config::new_with_defaults()
+        .add_json_type_override("/a/b", JsonArray::Infer(JsonType::AlwaysNumber));
+    tracing::subscriber::with_default(Names(names.clone()), || {
+        xml_str_to_json("<a><b>x</b><c><d/></c></a>", &conf).unwrap();
+    });
+    assert_eq!(
+        vec![
+            "parse",
+            "convert",
+            "convert_subtree",
+            "override applied",
+            "type coercion failed",
+            "override applied",
+            "convert_subtree"
+        ],
+        *names.lock().unwrap()
+    );
+}
+
+#[test]
+fn test_config_validate() {
+    assert!(Config::new_with_defaults().validate().is_empty());
+
+    let paths = [
+        "/a/b",
+        "a/b",
+        "/a//b",
+        "/a/b/",
+        "/a/b[1]",
+        "/a/*",
+        "/a/b@c",
+        "/a/@b/c",
+        "/a/b/@c",
+        "/{http://a.org/ns}a/v:b/@c",
+    ];
+    let conf = Config::new_with_defaults().with_raw_values(RawValues::Paths(
+        paths.iter().map(|p| p.to_string()).collect(),
+    ));
+    let warnings: Vec<_> = conf.validate().iter().map(|w| w.to_string()).collect();
+    assert_eq!(
+        vec![
+            r#"raw_values "/a/*": "*" is not a name"#,
+            r#"raw_values "/a//b": path has an empty step"#,
+            r#"raw_values "/a/@b/c": attribute is not the last step"#,
+            r#"raw_values "/a/b/": path has an empty step"#,
+            r#"raw_values "/a/b@c": "b@c" has an `@` that is not at its start"#,
+            r#"raw_values "/a/b[1]": "b[1]" is not a name"#,
+            r#"raw_values "a/b": path doesn't start with `/`"#,
+        ],
+        warnings
+    );
+
+    let conf = Config::new_with_defaults()
+        .add_xml_base_path("/feed/link/@href")
+        .add_xml_base_path("/feed/link/href")
+        .add_idref_path("/order/@items", IdRef::InlineList);
+    let warnings = conf.validate();
+    assert_eq!(1, warnings.len());
+    assert_eq!("xml_base_paths", warnings[0].option);
+    assert_eq!("/feed/link/href", warnings[0].path);
+    assert_eq!(ConfigWarningKind::NotAnAttribute, warnings[0].kind);
+
+    #[cfg(feature = "json_types")]
+    {
+        let mut conf = Config::new_with_defaults()
+            .add_json_type_override("/a/b/@id", JsonArray::Infer(JsonType::AlwaysString))
+            .add_json_type_override("/a/b//c", JsonArray::Infer(JsonType::AlwaysString));
+        conf.nillable_paths.insert("/a/b/@id".to_owned());
+        let warnings = conf.validate();
+        assert_eq!(
+            vec![
+                ConfigWarningKind::EmptyStep,
+                ConfigWarningKind::NotAnElement
+            ],
+            warnings.into_iter().map(|w| w.kind).collect::<Vec<_>>()
+        );
+    }
+
+    #[cfg(feature = "regex_path")]
+    {
+        let conf = Config::new_with_defaults()
+            .add_json_type_override("/a/b", JsonArray::Infer(JsonType::AlwaysString))
+            .add_json_type_override("/a/c", JsonArray::Infer(JsonType::AlwaysString))
+            .add_json_type_override(
+                regex::Regex::new("/b$").unwrap(),
+                JsonArray::Infer(JsonType::AlwaysNumber),
+            );
+        assert_eq!(
+            vec![ConfigWarning {
+                option: "json_type_overrides",
+                path: "/a/b".to_owned(),
+                kind: ConfigWarningKind::ShadowedByRegex("/b$".to_owned()),
+            }],
+            conf.validate()
+        );
+    }
+}
+
+#[cfg(feature = "json_types")]
+#[test]
+fn test_diagnostics_coercion() {
+    let xml = r#"<a><b>yes</b><b>No</b><b>maybe</b></a>"#;
+    let conf = Config::new_with_defaults()
+        .add_json_type_override("/a/b", JsonArray::Infer(JsonType::Bool(vec!["yes"])));
+    let mut diagnostics = Diagnostics::new();
+    let result = xml_str_to_json_with_diagnostics(xml, &conf, &mut diagnostics);
+    assert_eq!(json!({"a": {"b": [true, false, false]}}), result.unwrap());
+    assert_eq!(
+        vec![Diagnostic {
+            path: "/a/b".into(),
+            kind: DiagnosticKind::CoercionFailure {
+                value: "maybe".into(),
+                json_type: JsonType::Bool(vec!["yes"]),
+            },
+        }],
+        diagnostics.into_vec()
+    );
+}
+
+#[test]
+fn test_strict() {
+    let mut conf = Config::new_with_defaults();
+    conf.strict = true;
+
+    // nothing is lost, comments and namespaces are fine
+    let xml = r#"<a xmlns="urn:a"><!-- note --><b>1</b><b>2</b></a>"#;
+    assert_eq!(
+        json!({"a": {"b": [1, 2]}}),
+        xml_str_to_json(xml, &conf).unwrap()
+    );
+
+    let lossy = [
+        (
+            r#"<a>text<b/></a>"#,
+            DiagnosticKind::DroppedElement("b".into()),
+        ),
+        (
+            r#"<a><b/>tail</a>"#,
+            DiagnosticKind::DroppedText("tail".into()),
+        ),
+        (
+            r#"<a><?pi data?><b/></a>"#,
+            DiagnosticKind::DroppedProcessingInstruction("pi".into()),
+        ),
+        (
+            r#"<?pi data?><a/>"#,
+            DiagnosticKind::DroppedProcessingInstruction("pi".into()),
+        ),
+        (
+            r#"<a xmlns:x="urn:x" x:id="1" id="2"/>"#,
+            DiagnosticKind::KeyCollision("@id".into()),
+        ),
+    ];
+    for (xml, kind) in lossy {
+        match xml_str_to_json(xml, &conf) {
+            Err(Error::Lossy(diagnostic)) => assert_eq!(kind, diagnostic.kind),
+            other => panic!("expected a lossy error for {}, got {:?}", xml, other),
+        }
+        // the same documents are converted without errors by default
+        assert!(xml_str_to_json(xml, &Config::new_with_defaults()).is_ok());
+    }
+}
+
+#[cfg(feature = "json_types")]
+#[test]
+fn test_coercion_failure_policy() {
+    let xml = r#"<a><n>007</n><n>-1.5</n><n>n/a</n><b>yes</b><b>off</b><b>maybe</b></a>"#;
+    let mut conf = Config::new_with_defaults()
+        .add_json_type_override("/a/n", JsonArray::Infer(JsonType::AlwaysNumber))
+        .add_json_type_override("/a/b", JsonArray::Infer(JsonType::Bool(vec!["yes"])));
+
+    // the default keeps the behavior of each type
+    let expected = json!({"a": {"n": [7, -1.5, "n/a"], "b": [true, false, false]}});
+    assert_eq!(expected, xml_str_to_json(xml, &conf).unwrap());
+
+    conf.coercion_failure_policy = CoercionFailurePolicy::KeepString;
+    let expected = json!({"a": {"n": [7, -1.5, "n/a"], "b": [true, false, "maybe"]}});
+    assert_eq!(expected, xml_str_to_json(xml, &conf).unwrap());
+
+    conf.coercion_failure_policy = CoercionFailurePolicy::Null;
+    let expected = json!({"a": {"n": [7, -1.5, null], "b": [true, false, null]}});
+    assert_eq!(expected, xml_str_to_json(xml, &conf).unwrap());
+
+    conf.coercion_failure_policy = CoercionFailurePolicy::Error;
+    match xml_str_to_json(xml, &conf) {
+        Err(Error::Coercion(diagnostic)) => {
+            assert_eq!("/a/n", diagnostic.path);
+            assert_eq!(
+                DiagnosticKind::CoercionFailure {
+                    value: "n/a".into(),
+                    json_type: JsonType::AlwaysNumber
+                },
+                diagnostic.kind
+            );
+        }
+        other => panic!("expected a coercion error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_depth_limit() {
+    let nested = |depth: usize| "<a>".repeat(depth) + &"</a>".repeat(depth);
+    let conf = Config::new_with_defaults();
+
+    // the default limit fits into the stack of a test thread
+    assert!(xml_str_to_json(&nested(conf.max_depth), &conf).is_ok());
+
+    match xml_str_to_json(&nested(100_000), &conf) {
+        Err(Error::DepthLimitExceeded(limit)) => assert_eq!(128, limit),
+        other => panic!("expected a depth error, got {:?}", other.map(|_| ())),
+    }
+
+    // markup in comments and CDATA doesn't count
+    let xml = format!(
+        r#"<a b="/>"><!--{0}--><![CDATA[{0}]]></a>"#,
+        "<a>".repeat(10_000)
+    );
+    assert!(xml_str_to_json(&xml, &conf).is_ok());
+
+    // nesting hidden in entities is taken into account
+    let xml = format!(
+        r#"<!DOCTYPE a [<!ENTITY e "{}">]><a>&e;</a>"#,
+        nested(100_000)
+    );
+    assert!(matches!(
+        xml_str_to_json(&xml, &conf),
+        Err(Error::DepthLimitExceeded(_))
+    ));
+
+    // entities count at the depth of their references, declaring nested entities is fine
+    let conf = Config::new_with_defaults().with_entity_resolver(|_, _| None);
+    let doctype = format!(
+        r#"<!DOCTYPE a [<!ENTITY inner "{}"><!ENTITY outer "<b>&inner;</b>"><!ENTITY unused "{}">]>"#,
+        nested(100),
+        nested(1_000)
+    );
+    assert!(xml_str_to_json(&format!("{doctype}<a>&outer;</a>"), &conf).is_ok());
+    let xml = format!("{doctype}{}&outer;{}", "<a>".repeat(30), "</a>".repeat(30));
+    assert!(matches!(
+        xml_str_to_json(&xml, &conf),
+        Err(Error::DepthLimitExceeded(_))
+    ));
+}
+
+/// A tiny xorshift generator, so the fuzz tests are reproducible and need no extra dependencies.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 as usize
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.next() % items.len()]
+    }
+}
+
+/// Generates a random, mostly well-formed XML fragment.
+fn random_xml(rng: &mut Rng, depth: usize, out: &mut String) {
+    const NAMES: [&str; 6] = ["a", "b", "x:c", "y:c", "text", "_"];
+    const TEXTS: [&str; 12] = [
+        "",
+        " ",
+        "0",
+        "007",
+        "-1.5e3",
+        "true",
+        "True",
+        "NaN",
+        "1e999",
+        "18446744073709551616",
+        "&amp;&lt;",
+        "Ω ünïcödé",
+    ];
+    const MISC: [&str; 4] = [
+        "<!-- c -->",
+        "<?pi data?>",
+        "<![CDATA[<raw>]]>",
+        "&#x1F600;",
+    ];
+
+    let name = rng.pick(&NAMES);
+    out.push('<');
+    out.push_str(name);
+    if depth == 0 {
+        out.push_str(r#" xmlns:x="urn:x" xmlns:y="urn:y""#);
+    }
+    for _ in 0..rng.next() % 3 {
+        let attr = rng.pick(&["id", "x:id", "y:id", "text"]);
+        if !out[out.rfind('<').unwrap_or(0)..].contains(&format!(" {}=", attr)) {
+            out.push_str(&format!(r#" {}="{}""#, attr, rng.pick(&TEXTS)));
+        }
+    }
+    out.push('>');
+    for _ in 0..rng.next() % 4 {
+        match rng.next() % 3 {
+            0 if depth < 6 => random_xml(rng, depth + 1, out),
+            1 => out.push_str(rng.pick(&MISC)),
+            _ => out.push_str(rng.pick(&TEXTS)),
+        }
+    }
+    out.push_str("</");
+    out.push_str(name);
+    out.push('>');
+}
+
+#[test]
+fn test_fuzz_no_panics() {
+    let mut configs = vec![
+        Config::new_with_defaults(),
+        Config::new_with_custom_values(true, "", "text", NullValue::Ignore),
+        Config::new_with_custom_values(false, "_", "_", NullValue::Null),
+    ];
+    let mut strict = Config::new_with_defaults();
+    strict.strict = true;
+    strict.max_depth = 3;
+    configs.push(strict);
+    #[cfg(feature = "json_types")]
+    {
+        let mut conf = Config::new_with_defaults()
+            .add_json_type_override("/a/b", JsonArray::Always(JsonType::AlwaysNumber))
+            .add_json_type_override("/b/@id", JsonArray::Always(JsonType::Bool(vec!["0"])))
+            .add_json_type_override("/a", JsonArray::Always(JsonType::AlwaysString));
+        conf.coercion_failure_policy = CoercionFailurePolicy::Error;
+        configs.push(conf);
+    }
+
+    let mut rng = Rng(0x2545_F491_4F6C_DD1D);
+    for _ in 0..2000 {
+        let mut xml = String::new();
+        random_xml(&mut rng, 0, &mut xml);
+
+        // break some of the documents by cutting or duplicating parts of them
+        let mut cut = rng.next() % (xml.len() + 1);
+        while !xml.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        let mutated = match rng.next() % 5 {
+            0 => xml[..cut].to_string(),
+            1 => [&xml[..cut], &xml[..]].concat(),
+            2 => "<a>".repeat(rng.next() % 2000) + &xml,
+            _ => xml.clone(),
+        };
+
+        for conf in &configs {
+            let _ = xml_str_to_json(&mutated, conf);
+            let mut diagnostics = Diagnostics::new();
+            let _ = xml_str_to_json_with_diagnostics(&mutated, conf, &mut diagnostics);
+            for d in &diagnostics {
+                let _ = d.to_string();
+            }
+        }
+    }
+}
+
+#[test]
+fn test_progress_and_cancellation() {
+    use std::sync::atomic::AtomicBool;
+    use std::sync::{Arc, Mutex};
+
+    let xml = format!("<a>{}</a>", "<b>1</b>".repeat(9));
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let sink = reports.clone();
+    let conf = Config::new_with_defaults()
+        .with_progress_callback(4, move |p| sink.lock().unwrap().push(p.elements));
+    xml_str_to_json(&xml, &conf).unwrap();
+    // the initial report, every 4 elements and the final one
+    assert_eq!(vec![0, 4, 8, 10], *reports.lock().unwrap());
+
+    let flag = Arc::new(AtomicBool::new(false));
+    let cancel = flag.clone();
+    let conf = Config::new_with_defaults()
+        .with_cancellation_flag(flag)
+        .with_progress_callback(5, move |p| {
+            if p.elements == 5 {
+                cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+            }
+        });
+    assert!(matches!(
+        xml_str_to_json(&xml, &conf),
+        Err(Error::Cancelled)
+    ));
+}
+
+#[test]
+fn test_memory_budget() {
+    let xml = format!("<a>{}</a>", r#"<b id="1">text</b>"#.repeat(100));
+    let conf = Config::new_with_defaults().with_memory_budget(1_000_000);
+    assert!(xml_str_to_json(&xml, &conf).is_ok());
+    let conf = Config::new_with_defaults().with_memory_budget(1_000);
+    assert!(matches!(
+        xml_str_to_json(&xml, &conf),
+        Err(Error::MemoryBudgetExceeded(1_000))
+    ));
+
+    // every short attribute becomes a property of its own
+    let attrs: String = (0..50).map(|i| format!(" a{}=\"\"", i)).collect();
+    let fan_out = format!("<a><b{}/><b{}/></a>", attrs, attrs);
+    let conf = Config::new_with_defaults().with_memory_budget(fan_out.len() * 4);
+    assert!(matches!(
+        xml_str_to_json(&fan_out, &conf),
+        Err(Error::MemoryBudgetExceeded(_))
+    ));
+    let doc = roxmltree::Document::parse(&fan_out).unwrap();
+    assert!(matches!(
+        xml_document_to_borrowed_json(&doc, &conf),
+        Err(Error::MemoryBudgetExceeded(_))
+    ));
+}
+
+#[test]
+fn test_infer_json_schema() {
+    let xml = r#"<a>
+        <record id="1"><name>x</name><tag>t1</tag><tag>t2</tag></record>
+        <record id="2.5"><name/><tag>t3</tag></record>
+    </a>"#;
+    let schema = infer_json_schema(xml, &Config::new_with_defaults()).unwrap();
+    let expected = json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "type": "object",
+        "required": ["a"],
+        "properties": {
+            "a": {
+                "type": "object",
+                "required": ["record"],
+                "properties": {
+                    "record": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "required": ["@id", "name", "tag"],
+                            "properties": {
+                                "@id": {"type": "number"},
+                                "name": {"anyOf": [{"type": "string"}, {"type": "object", "properties": {}}]},
+                                "tag": {"anyOf": [{"type": "string"}, {"type": "array", "items": {"type": "string"}}]}
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    });
+    assert_eq!(expected, schema);
+
+    // optional properties are not required
+    let schema = json_schema_for_values(&[json!({"a": 1}), json!({"a": 2, "b": null})]);
+    assert_eq!(json!(["a"]), schema["required"]);
+    assert_eq!(json!({"type": "null"}), schema["properties"]["b"]);
+}
+
+#[cfg(feature = "xsd")]
+#[test]
+fn test_xsd_driven_conversion() {
+    let xsd = r#"<?xml version="1.0"?>
+    <xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:simpleType name="Code">
+        <xs:restriction base="xs:string"><xs:pattern value="[0-9]+"/></xs:restriction>
+      </xs:simpleType>
+      <xs:complexType name="Price">
+        <xs:simpleContent>
+          <xs:extension base="xs:decimal">
+            <xs:attribute name="currency" type="xs:string" use="required"/>
+          </xs:extension>
+        </xs:simpleContent>
+      </xs:complexType>
+      <xs:complexType name="Node">
+        <xs:sequence>
+          <xs:element name="node" type="Node" minOccurs="0" maxOccurs="unbounded"/>
+        </xs:sequence>
+        <xs:attribute name="id" type="xs:int"/>
+      </xs:complexType>
+      <xs:element name="code" type="Code"/>
+      <xs:element name="catalog">
+        <xs:complexType>
+          <xs:sequence>
+            <xs:element name="item" maxOccurs="unbounded">
+              <xs:complexType>
+                <xs:sequence>
+                  <xs:element ref="code"/>
+                  <xs:element name="price" type="Price"/>
+                  <xs:element name="discount" type="xs:double" nillable="true"/>
+                  <xs:choice>
+                    <xs:element name="available" type="xs:boolean"/>
+                    <xs:element name="since" type="xs:date"/>
+                  </xs:choice>
+                </xs:sequence>
+              </xs:complexType>
+            </xs:element>
+            <xs:element name="tree" type="Node"/>
+          </xs:sequence>
+        </xs:complexType>
+      </xs:element>
+    </xs:schema>"#;
+    let xml = r#"<catalog xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+      <item><code>007</code><price currency="123">010.50</price><discount xsi:nil="true"/><available>1</available></item>
+      <tree id="01"><node id="2"/></tree>
+    </catalog>"#;
+
+    let conf = Config::new_with_defaults()
+        .add_json_type_override(
+            "/catalog/tree/node/@id",
+            JsonArray::Infer(JsonType::AlwaysString),
+        )
+        .add_xsd(xsd)
+        .unwrap();
+    let expected = json!({
+        "catalog": {
+            "item": [{
+                "code": "007",
+                "price": {"@currency": "123", "#text": 10.5},
+                "discount": null,
+                "available": true
+            }],
+            "tree": {"@id": 1, "node": [{"@id": "2"}]}
+        }
+    });
+    assert_eq!(expected, xml_str_to_json(xml, &conf).unwrap());
+
+    assert!(matches!(XsdSchema::parse("<schema/>"), Err(Error::Xsd(_))));
+}
+
+#[cfg(feature = "xsd")]
+#[test]
+fn test_xsd_validation() {
+    use std::sync::Arc;
+
+    let xsd = r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+      <xs:element name="order">
+        <xs:complexType>
+          <xs:sequence>
+            <xs:element name="id" type="xs:int"/>
+            <xs:element name="line" maxOccurs="2">
+              <xs:complexType>
+                <xs:attribute name="qty" type="xs:int" use="required"/>
+              </xs:complexType>
+            </xs:element>
+            <xs:element name="note" type="xs:string" minOccurs="0"/>
+          </xs:sequence>
+        </xs:complexType>
+      </xs:element>
+    </xs:schema>"#;
+    let schema = Arc::new(XsdSchema::parse(xsd).unwrap());
+
+    let valid = r#"<order><id>1</id><line qty="2"/><!-- c --><note>n</note></order>"#;
+    let invalid =
+        r#"<order><id>x</id><line/><line qty="1"/><line qty="a" extra="1"/><foo/></order>"#;
+
+    let conf = Config::new_with_defaults().with_xsd_validation(schema.clone(), XsdValidation::Fail);
+    assert!(xml_str_to_json(valid, &conf).is_ok());
+    let findings = match xml_str_to_json(invalid, &conf) {
+        Err(Error::Invalid(findings)) => findings,
+        other => panic!("expected a validation error, got {:?}", other),
+    };
+    let findings: Vec<String> = findings.iter().map(|d| d.to_string()).collect();
+    assert_eq!(
+        vec![
+            "/order/line: too many elements",
+            "/order/id: invalid value",
+            "/order/line/@qty: missing attribute",
+            "/order/line/@qty: invalid value",
+            "/order/line/@extra: undeclared attribute",
+            "/order/foo: undeclared element",
+        ],
+        findings
+    );
+
+    // the document is converted anyway and the findings end up in the diagnostics
+    let conf = Config::new_with_defaults().with_xsd_validation(schema, XsdValidation::Report);
+    let mut diagnostics = Diagnostics::new();
+    assert!(xml_str_to_json_with_diagnostics(invalid, &conf, &mut diagnostics).is_ok());
+    assert_eq!(6, diagnostics.len());
+}
+
+#[test]
+fn test_document_wide_array_inference() {
+    let xml = r#"<a><r><b>1</b><c/></r><r><b>1</b><b>2</b></r><s><b>3</b></s></a>"#;
+    let mut conf = Config::new_with_defaults();
+    assert_eq!(
+        json!({"a": {"r": [{"b": 1, "c": {}}, {"b": [1, 2]}], "s": {"b": 3}}}),
+        xml_str_to_json(xml, &conf).unwrap()
+    );
+
+    conf.array_inference = ArrayInference::DocumentWide;
+    assert_eq!(
+        json!({"a": {"r": [{"b": [1], "c": {}}, {"b": [1, 2]}], "s": {"b": [3]}}}),
+        xml_str_to_json(xml, &conf).unwrap()
+    );
+}
+
+#[test]
+fn test_raw_values() {
+    let xml = r#"<a id="01"><b>007</b><c>x</c></a>"#;
+    let conf = Config::new_with_defaults().with_raw_values(RawValues::Always);
+    assert_eq!(
+        json!({"a": {
+            "@id": {"value": 1, "raw": "01"},
+            "b": {"value": 7, "raw": "007"},
+            "c": {"value": "x", "raw": "x"}
+        }}),
+        xml_str_to_json(xml, &conf).unwrap()
+    );
+
+    let mut conf = Config::new_with_defaults()
+        .with_raw_values(RawValues::Paths(["/a/b".to_owned()].into_iter().collect()));
+    conf.raw_value_prop_names = ("parsed".to_owned(), "source".to_owned());
+    assert_eq!(
+        json!({"a": {"@id": 1, "b": {"parsed": 7, "source": "007"}, "c": "x"}}),
+        xml_str_to_json(xml, &conf).unwrap()
+    );
+}
+
+#[test]
+fn test_source_locations() {
+    let xml = "<a>\n  <b c=\"1\"/>\n  <d>text</d>\n  <e f=\"2\">x</e>\n</a>";
+    let conf = Config::new_with_defaults().with_source_locations("#loc");
+    assert_eq!(
+        json!({"a": {
+            "#loc": {"line": 1, "col": 1, "offset": 0},
+            "b": {"#loc": {"line": 2, "col": 3, "offset": 6}, "@c": 1},
+            "d": "text",
+            "e": {"#loc": {"line": 4, "col": 3, "offset": 33}, "@f": 2, "#text": "x"}
+        }}),
+        xml_str_to_json(xml, &conf).unwrap()
+    );
+}
+
+#[test]
+fn test_lossless_roundtrip() {
+    let conf = Config::new_with_defaults();
+    let documents = [
+        "<a/>",
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<a>&#x3c;</a>\n",
+        "<ns:a xmlns:ns='urn:x'  ns:b = \"1\"\n c='it&apos;s &amp; &#x41;' >\n  text &lt; &gt; more<!-- note --><?pi  data ?><![CDATA[<raw>]]>\n  <b></b ><c/><c />tail\n</ns:a>",
+        "<!-- head --><a>\r\n\t<b x=\"&quot;\">&#169;</b></a><?tail?>",
+    ];
+    for xml in documents {
+        let json = xml_str_to_lossless_json(xml, &conf).unwrap();
+        // the representation survives serialization
+        let json: Value = serde_json::from_str(&json.to_string()).unwrap();
+        assert_eq!(xml, lossless_json_to_xml(&json).unwrap());
+    }
+
+    let mut json = xml_str_to_lossless_json("<a b='&#49;'>x &amp; y</a>", &conf).unwrap();
+    assert_eq!(
+        json!({"document": [{
+            "element": "a",
+            "attributes": [{"name": "b", "value": "1", "raw": "&#49;", "quote": "'"}],
+            "children": [{"text": "x & y"}]
+        }]}),
+        json
+    );
+    // edited values are escaped again and the stale source form is ignored
+    json["document"][0]["attributes"][0]["value"] = json!("it's");
+    json["document"][0]["children"][0]["text"] = json!("<b>");
+    assert_eq!(
+        "<a b='it&apos;s'>&lt;b></a>",
+        lossless_json_to_xml(&json).unwrap()
+    );
+
+    assert!(matches!(
+        lossless_json_to_xml(&json!({"document": [{"unknown": 1}]})),
+        Err(Error::UnexpectedJson(_))
+    ));
+}
+
+#[test]
+fn test_json_to_xml_writer() {
+    let conf = Config::new_with_defaults();
+    let json = json!({"a": {
+        "@id": "x&\"y\"",
+        "b": [1, {"@c": true, "#text": "t<"}],
+        "d": null,
+        "e": {}
+    }});
+    assert_eq!(
+        r#"<a id="x&amp;&quot;y&quot;"><b>1</b><b c="true">t&lt;</b><d/><e/></a>"#,
+        json_to_xml_string(&json, &conf, &XmlWriterConfig::new_with_defaults()).unwrap()
+    );
+
+    let writer_conf = XmlWriterConfig::new_with_defaults()
+        .with_indent("  ")
+        .with_declaration();
+    assert_eq!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<a>\n  <b>\n    <c>1</c>\n    <c>2</c>\n  </b>\n</a>",
+        json_to_xml_string(&json!({"a": {"b": {"c": [1, 2]}}}), &conf, &writer_conf).unwrap()
+    );
+
+    // the output converts back into the same JSON
+    let xml = r#"<a x="1"><b>one</b><b>two</b><c><d>007</d></c></a>"#;
+    let json = xml_str_to_json(xml, &conf).unwrap();
+    let mut written = Vec::new();
+    json_to_xml_writer(&json, &conf, &XmlWriterConfig::default(), &mut written).unwrap();
+    assert_eq!(
+        json,
+        xml_str_to_json(std::str::from_utf8(&written).unwrap(), &conf).unwrap()
+    );
+
+    for json in [json!([1]), json!({"a": 1, "b": 2}), json!({"a": [1, 2]})] {
+        assert!(matches!(
+            json_to_xml_string(&json, &conf, &XmlWriterConfig::default()),
+            Err(Error::UnexpectedJson(_))
+        ));
+    }
+    assert!(matches!(
+        json_to_xml_string(
+            &json!({"a": {"#loc": 1}}),
+            &conf,
+            &XmlWriterConfig::default()
+        ),
+        Err(Error::UnexpectedJson(_))
+    ));
+}
+
+#[test]
+fn test_verify_roundtrip() {
+    let conf = Config::new_with_defaults();
+    let report = verify_roundtrip(r#"<a x="1"><b>one</b><c>2</c></a>"#, &conf).unwrap();
+    assert!(report.is_lossless(), "{:?}", report);
+
+    let xml = r#"<a x="01"><c>1.50</c><b>text<i>dropped</i></b><!-- note --><c> x </c></a>"#;
+    let report = verify_roundtrip(xml, &conf).unwrap();
+    let issues: Vec<_> = report.issues.iter().map(|i| i.to_string()).collect();
+    assert_eq!(
+        vec![
+            "/a/b: dropped child element <i>",
+            "/a/@x: value \"01\" comes back as \"1\"",
+            "/a: dropped comment \" note \"",
+            "/a: element <c> changes its position",
+            "/a/c: value \"1.50\" comes back as \"1.5\"",
+            "/a/c: value \" x \" comes back as \"x\"",
+        ],
+        issues
+    );
+    assert!(!report.is_lossless());
+}
+
+#[test]
+fn test_xml_diff() {
+    let conf = Config::new_with_defaults();
+    let a = r#"<a><r id="1"><v>x</v></r><r id="2"><v>y</v></r><r id="3"/><s n="1"/></a>"#;
+    let b = r#"<a><r id="2"><v>y</v></r><r id="1"><v>z</v></r><s n="2"/><t/></a>"#;
+    let diff = xml_diff(a, b, &conf).unwrap();
+    assert_eq!(
+        json!({
+            "added": {"/a/t": {}},
+            "removed": {"/a/r/2": {"@id": 3}},
+            "changed": {
+                "/a/r/0/v": {"old": "x", "new": "z"},
+                "/a/s/@n": {"old": 1, "new": 2}
+            }
+        }),
+        diff
+    );
+
+    let a = "<a><b>1</b></a>";
+    let b = "<a><b>1</b><b>2</b></a>";
+    let diff = xml_diff(a, b, &conf).unwrap();
+    assert_eq!(json!({"old": 1, "new": [1, 2]}), diff["changed"]["/a/b"]);
+
+    let mut conf = Config::new_with_defaults();
+    conf.array_inference = ArrayInference::DocumentWide;
+    let diff = xml_diff(a, b, &conf).unwrap();
+    assert_eq!(
+        json!({"added": {"/a/b/1": 2}, "removed": {}, "changed": {}}),
+        diff
+    );
+    assert_eq!(
+        json!({"added": {}, "removed": {}, "changed": {}}),
+        xml_diff(b, "<a><b>2</b><b>1</b></a>", &conf).unwrap()
+    );
+}
+
+#[test]
+fn test_xml_content_hash() {
+    let conf = Config::new_with_defaults();
+    let hash = |xml| xml_content_hash(xml, &conf, HashAlgo::Fnv1a64).unwrap();
+    // FNV-1a of `{"a":1}`
+    let mut expected: u64 = 0xcbf29ce484222325;
+    for b in br#"{"a":1}"# {
+        expected = (expected ^ u64::from(*b)).wrapping_mul(0x100000001b3);
+    }
+    assert_eq!(format!("{:016x}", expected), hash("<a>1</a>"));
+    assert_eq!(hash("<a>1</a>"), hash("<a> 01 </a>"));
+    assert_eq!(
+        hash(r#"<a x="1"><b/><c>t</c></a>"#),
+        hash(r#"<a x="1"><c>t</c><b/></a>"#)
+    );
+    assert_ne!(
+        hash("<a><b>1</b><b>2</b></a>"),
+        hash("<a><b>2</b><b>1</b></a>")
+    );
+    assert_ne!(hash("<a>1</a>"), hash("<a>\"1\"</a>"));
+    assert!(matches!(
+        xml_content_hash("<a>", &conf, HashAlgo::Fnv1a64),
+        Err(Error::Xml(_))
+    ));
+
+    #[cfg(feature = "sha2")]
+    assert_eq!(
+        "015abd7f5cc57a2dd94b7590f04ad8084273905ee33ec5cebeae62276a97f862",
+        xml_content_hash("<a>1</a>", &conf, HashAlgo::Sha256).unwrap()
+    );
+    #[cfg(feature = "blake3")]
+    assert_eq!(
+        blake3::hash(br#"{"a":1}"#).to_hex().to_string(),
+        xml_content_hash("<a>1</a>", &conf, HashAlgo::Blake3).unwrap()
+    );
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_yaml() {
+    let xml = r#"<a x="1"><b>one</b><b>2.5</b><c/></a>"#;
+    let conf = Config::new_with_defaults();
+    let yaml = xml_str_to_yaml(xml, &conf).unwrap();
+    assert_eq!(
+        serde_yaml::to_value(xml_str_to_json(xml, &conf).unwrap()).unwrap(),
+        yaml
+    );
+    assert_eq!(
+        "a:\n  '@x': 1\n  b:\n  - one\n  - 2.5\n  c: {}\n",
+        xml_str_to_yaml_string(xml, &conf).unwrap()
+    );
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn test_toml() {
+    let conf = Config::new_with_defaults();
+    let xml = r#"<a x="1"><b>one</b><b>2</b><c/><d><e>1.5</e></d></a>"#;
+    let toml_conf = TomlConfig::new_with_defaults();
+    assert_eq!(
+        "[a]\n\"@x\" = 1\nb = [\"one\", 2]\n\n[a.c]\n\n[a.d]\ne = 1.5\n",
+        xml_str_to_toml_string(xml, &conf, &toml_conf).unwrap()
+    );
+
+    let toml_conf = TomlConfig {
+        null_values: TomlNull::EmptyString,
+        mixed_arrays: TomlMixedArray::Stringify,
+    };
+    let conf_null = Config::new_with_custom_values(false, "@", "#text", NullValue::Null);
+    let table = xml_str_to_toml(xml, &conf_null, &toml_conf).unwrap();
+    assert_eq!(Some(""), table["a"]["c"].as_str());
+    assert_eq!(
+        ::toml::Value::Array(vec!["one".into(), "2".into()]),
+        table["a"]["b"]
+    );
+
+    let toml_conf = TomlConfig {
+        null_values: TomlNull::Error,
+        mixed_arrays: TomlMixedArray::Error,
+    };
+    assert!(matches!(
+        xml_str_to_toml(xml, &conf, &toml_conf),
+        Err(Error::Toml(_))
+    ));
+    assert!(matches!(
+        xml_str_to_toml("<a><c/></a>", &conf_null, &toml_conf),
+        Err(Error::Toml(_))
+    ));
+}
+
+#[cfg(feature = "msgpack")]
+#[test]
+fn test_msgpack() {
+    let conf = Config::new_with_defaults();
+    let xml = r#"<a x="1"><b>one</b><b>-2</b><c/><d>1.5</d><e>true</e></a>"#;
+    let bytes = xml_str_to_msgpack(xml, &conf).unwrap();
+    let json = xml_str_to_json(xml, &conf).unwrap();
+    // the same bytes as encoding the output of `xml_str_to_json`
+    assert_eq!(rmp_serde::to_vec(&json).unwrap(), bytes);
+    assert_eq!(json, rmp_serde::from_slice::<Value>(&bytes).unwrap());
+    assert!(bytes.len() < json.to_string().len());
+}
+
+#[cfg(feature = "simd-json")]
+#[test]
+fn test_simd_json() {
+    use simd_json::prelude::*;
+
+    let conf = Config::new_with_defaults();
+    let xml =
+        r#"<a x="1"><b>one</b><b>-2</b><c/><d>1.5</d><e>true</e><n>18446744073709551615</n></a>"#;
+    let expected = xml_str_to_json(xml, &conf).unwrap();
+    let value = xml_str_to_simd_json(xml, &conf).unwrap();
+    assert_eq!(value["a"]["n"].as_u64(), Some(u64::MAX));
+    assert!(value["a"]["c"].is_object());
+    // the same values with the same number types as `xml_str_to_json`
+    assert_eq!(expected, serde_json::to_value(&value).unwrap());
+
+    let text = xml_str_to_simd_json_string(xml, &conf).unwrap();
+    let json: Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(expected, json);
+}
+
+#[cfg(feature = "bson")]
+#[test]
+fn test_bson() {
+    use ::bson::{doc, Bson, DateTime};
+
+    let conf = Config::new_with_defaults();
+    let xml = r#"<a n="1"><big>-9007199254740993</big><huge>18446744073709551615</huge><f>1.5</f>
+        <d>2024-01-31</d><t>2024-01-31T12:30:00+01:00</t><s>2024-1-31</s><e/><b>true</b></a>"#;
+    let doc = xml_str_to_bson(xml, &conf, &BsonConfig::new_with_defaults()).unwrap();
+    assert_eq!(
+        doc! {"a": {
+            "@n": 1,
+            "b": true,
+            "big": Bson::Int64(-9007199254740993),
+            "d": DateTime::parse_rfc3339_str("2024-01-31T00:00:00Z").unwrap(),
+            "e": {},
+            "f": 1.5,
+            "huge": "18446744073709551615",
+            "s": "2024-1-31",
+            "t": DateTime::parse_rfc3339_str("2024-01-31T11:30:00Z").unwrap(),
+        }},
+        doc
+    );
+
+    let doc = xml_str_to_bson(xml, &conf, &BsonConfig { parse_dates: false }).unwrap();
+    assert_eq!(
+        "2024-01-31",
+        doc.get_document("a").unwrap().get_str("d").unwrap()
+    );
+}
+
+#[cfg(all(feature = "bson", feature = "rayon"))]
+#[test]
+fn test_bson_parallel_children() {
+    use ::bson::Bson;
+
+    let conf = Config::new_with_defaults().with_parallel_children();
+    let xml = "<a><b>-42</b><c><d>-9007199254740993</d></c><e>1.5</e></a>";
+    let doc = xml_str_to_bson(xml, &conf, &BsonConfig::new_with_defaults()).unwrap();
+    let a = doc.get_document("a").unwrap();
+    assert_eq!(Some(&Bson::Int32(-42)), a.get("b"));
+    assert_eq!(
+        Some(&Bson::Int64(-9007199254740993)),
+        a.get_document("c").unwrap().get("d")
+    );
+    assert_eq!(Some(&Bson::Double(1.5)), a.get("e"));
+}
+
+#[cfg(feature = "avro")]
+#[test]
+fn test_avro() {
+    use avro_rs::types::Value as AvroValue;
+    use avro_rs::{Reader, Schema, Writer};
+
+    let schema = Schema::parse_str(
+        r#"{"type": "record", "name": "item", "fields": [
+            {"name": "id", "type": "string"},
+            {"name": "qty", "type": "int"},
+            {"name": "price", "type": ["null", "double"]},
+            {"name": "tags", "type": {"type": "array", "items": "string"}},
+            {"name": "status", "type": {"type": "enum", "name": "s", "symbols": ["new", "done"]}, "default": "new"},
+            {"name": "due", "type": {"type": "int", "logicalType": "date"}}
+        ]}"#,
+    )
+    .unwrap();
+    let xml = r#"<feed>
+        <item id="007"><qty>1</qty><price>9.5</price><tags>a</tags><due>1970-01-11</due></item>
+        <item id="x"><qty>2</qty><tags>b</tags><tags>3</tags><status>done</status><due>2024-02-29</due></item>
+    </feed>"#;
+    let conf = Config::new_with_custom_values(true, "@", "#text", NullValue::Null);
+    let records = xml_records_to_avro(xml, "/feed/item", &schema, &conf).unwrap();
+    assert_eq!(
+        AvroValue::Record(vec![
+            ("id".to_owned(), AvroValue::String("007".to_owned())),
+            ("qty".to_owned(), AvroValue::Int(1)),
+            (
+                "price".to_owned(),
+                AvroValue::Union(Box::new(AvroValue::Double(9.5)))
+            ),
+            (
+                "tags".to_owned(),
+                AvroValue::Array(vec![AvroValue::String("a".to_owned())])
+            ),
+            ("status".to_owned(), AvroValue::Enum(0, "new".to_owned())),
+            ("due".to_owned(), AvroValue::Date(10)),
+        ]),
+        records[0]
+    );
+    // a missing nullable field and an explicit enum symbol
+    assert!(matches!(&records[1], AvroValue::Record(fields)
+        if fields[2].1 == AvroValue::Union(Box::new(AvroValue::Null))
+            && fields[4].1 == AvroValue::Enum(1, "done".to_owned())));
+
+    // the values can be written into a container file
+    let mut writer = Writer::new(&schema, Vec::new());
+    for record in records {
+        writer.append(record).unwrap();
+    }
+    let bytes = writer.into_inner().unwrap();
+    let read: Vec<_> = Reader::new(&bytes[..]).unwrap().collect();
+    assert_eq!(2, read.len());
+    assert!(
+        matches!(&read[1], Ok(AvroValue::Record(fields)) if fields[5].1 == AvroValue::Date(19782))
+    );
+
+    let xml = "<feed><item id='1'><qty>many</qty></item></feed>";
+    assert!(matches!(
+        xml_records_to_avro(xml, "/feed/item", &schema, &conf),
+        Err(Error::Avro(_))
+    ));
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn test_arrow() {
+    use arrow_array::{Array, BooleanArray, Float64Array, Int32Array, Int64Array, StringArray};
+    use arrow_schema::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    let xml = r#"<feed>
+        <item id="1"><name>a</name><price>9.5</price><ok>true</ok></item>
+        <item id="2"><price>3</price><ok>false</ok><tag>x</tag><tag>y</tag></item>
+    </feed>"#;
+    let conf = Config::new_with_defaults();
+
+    // inferred schema in the order of first appearance
+    let batch = xml_records_to_arrow(xml, "/feed/item", None, &conf).unwrap();
+    assert_eq!(2, batch.num_rows());
+    let schema = batch.schema();
+    let names: Vec<_> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+    assert_eq!(vec!["@id", "name", "ok", "price", "tag"], names);
+    assert_eq!(&DataType::Int64, schema.field(0).data_type());
+    assert_eq!(&DataType::Boolean, schema.field(2).data_type());
+    assert_eq!(&DataType::Float64, schema.field(3).data_type());
+    let ids = batch
+        .column(0)
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap();
+    assert_eq!(2, ids.value(1));
+    let names = batch
+        .column(1)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert!(names.is_null(1));
+    let ok = batch
+        .column(2)
+        .as_any()
+        .downcast_ref::<BooleanArray>()
+        .unwrap();
+    assert!(!ok.value(1));
+    let prices = batch
+        .column(3)
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .unwrap();
+    assert_eq!(3.0, prices.value(1));
+    let tags = batch
+        .column(4)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert_eq!(r#"["x","y"]"#, tags.value(1));
+
+    // a given schema selects and coerces the columns, attributes match without prefix
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("price", DataType::Utf8, true),
+    ]));
+    let batch = xml_records_to_arrow(xml, "/feed/item", Some(schema), &conf).unwrap();
+    assert_eq!(2, batch.num_columns());
+    let ids = batch
+        .column(0)
+        .as_any()
+        .downcast_ref::<Int32Array>()
+        .unwrap();
+    assert_eq!(1, ids.value(0));
+    let prices = batch
+        .column(1)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert_eq!("9.5", prices.value(0));
+
+    let schema = Arc::new(Schema::new(vec![Field::new("name", DataType::Int64, true)]));
+    assert!(matches!(
+        xml_records_to_arrow(xml, "/feed/item", Some(schema), &conf),
+        Err(Error::Arrow(_))
+    ));
+
+    #[cfg(feature = "parquet")]
+    {
+        let mut file = Vec::new();
+        record_batch_to_parquet(&batch, &mut file).unwrap();
+        assert!(file.starts_with(b"PAR1"));
+    }
+}
+
+#[test]
+fn test_xml_records_to_csv() {
+    let xml = r#"<feed>
+        <item id="1"><price><net>5</net><tax>1.5</tax></price><tag>a</tag><tag>b</tag></item>
+        <item id="2"><note>say "hi"
+        twice</note><tag>c</tag></item>
+        <other id="3"/>
+    </feed>"#;
+    let conf = Config::new_with_defaults();
+    let to_csv = |flatten_config: &FlattenConfig| {
+        let mut csv = Vec::new();
+        xml_records_to_csv(xml, "/feed/item", &mut csv, flatten_config, &conf).unwrap();
+        String::from_utf8(csv).unwrap()
+    };
+
+    assert_eq!(
+        "@id,price.net,price.tax,tag[0],tag[1],note,tag\n\
+         1,5,1.5,a,b,,\n\
+         2,,,,,\"say \"\"hi\"\"\n        twice\",c\n",
+        to_csv(&FlattenConfig::default())
+    );
+    assert_eq!(
+        "@id,price_net,price_tax,tag,note\n1,5,1.5,a;b,\n2,,,c,\"say \"\"hi\"\"\n        twice\"\n",
+        to_csv(
+            &FlattenConfig::new_with_defaults()
+                .with_separator("_")
+                .with_arrays(FlattenArrays::Join(";".to_owned()))
+        )
+    );
+    assert!(
+        to_csv(&FlattenConfig::new_with_defaults().with_arrays(FlattenArrays::Json))
+            .starts_with("@id,price.net,price.tax,tag,note\n1,5,1.5,\"[\"\"a\"\",\"\"b\"\"]\",\n")
+    );
+
+    // scalar records get a column named after the element
+    let mut csv = Vec::new();
+    let xml = "<list><v>1</v><v/><v>x</v></list>";
+    xml_records_to_csv(xml, "/list/v", &mut csv, &FlattenConfig::default(), &conf).unwrap();
+    assert_eq!("v\n1\n\nx\n", String::from_utf8(csv).unwrap());
+}
+
+#[test]
+fn test_xml_str_to_flat_json() {
+    let xml = r#"<order id="7">
+        <customer><name>Ann</name><vip/></customer>
+        <items><price>9.99</price><sku>A</sku></items>
+        <items><price>5</price><sku>B</sku></items>
+    </order>"#;
+    let conf = Config::new_with_defaults();
+    let flat = xml_str_to_flat_json(xml, &conf, &FlattenConfig::default()).unwrap();
+    let expected = json!({
+        "order.@id": 7,
+        "order.customer.name": "Ann",
+        "order.customer.vip": null,
+        "order.items[0].price": 9.99,
+        "order.items[0].sku": "A",
+        "order.items[1].price": 5,
+        "order.items[1].sku": "B"
+    });
+    assert_eq!(expected, Value::Object(flat));
+
+    let flat_config = FlattenConfig::new_with_defaults()
+        .with_separator("/")
+        .with_arrays(FlattenArrays::Json);
+    let flat = xml_str_to_flat_json(xml, &conf, &flat_config).unwrap();
+    assert_eq!(
+        json!(r#"[{"price":9.99,"sku":"A"},{"price":5,"sku":"B"}]"#),
+        flat["order/items"]
+    );
+    assert_eq!(json!("Ann"), flat["order/customer/name"]);
+}
+
+#[test]
+fn test_xml_str_to_json_pointers() {
+    let xml = r#"<a x="1">
+        <b id="1"><c>007</c><d/></b>
+        <b id="2">text<c>dropped</c></b>
+        <e><f>1</f><f>2</f></e>
+        <g><f>single</f></g>
+        <h attr="/~">a/b</h>
+    </a>"#;
+    let pointers = [
+        "",
+        "/a",
+        "/a/@x",
+        "/a/b",
+        "/a/b/0/c",
+        "/a/b/0/d",
+        "/a/b/1",
+        "/a/b/1/#text",
+        "/a/b/1/c",
+        "/a/b/2",
+        "/a/b/c",
+        "/a/e/f",
+        "/a/e/f/1",
+        "/a/e/f/01",
+        "/a/g/f",
+        "/a/g/f/0",
+        "/a/h/@attr",
+        "/a/missing",
+        "/b",
+        "a",
+    ];
+    #[allow(unused_mut)]
+    let mut configs = vec![
+        Config::new_with_defaults(),
+        Config::new_with_custom_values(false, "", "txt", NullValue::Ignore),
+        Config {
+            array_inference: ArrayInference::DocumentWide,
+            ..Config::new_with_defaults()
+        },
+    ];
+    #[cfg(feature = "json_types")]
+    configs.push(
+        Config::new_with_defaults()
+            .add_json_type_override("/a/g/f", JsonArray::Always(JsonType::Infer)),
+    );
+    for conf in &configs {
+        let json = xml_str_to_json(xml, conf).unwrap();
+        let values = xml_str_to_json_pointers(xml, &pointers, conf).unwrap();
+        for (pointer, value) in pointers.iter().zip(values) {
+            assert_eq!(json.pointer(pointer).cloned(), value, "{}", pointer);
+        }
+    }
+}
+
+#[test]
+fn test_xml_str_to_json_pointers_skip_whole_document_steps() {
+    let xml = "<a><b/><c>1</c></a>";
+    let conf = Config::new_with_defaults()
+        .with_prune_empty(PruneEmpty::ALL)
+        .with_post_process(|mut json| {
+            json["a"]["d"] = json!(true);
+            json
+        });
+    assert_eq!(
+        json!({"a": {"c": 1, "d": true}}),
+        xml_str_to_json(xml, &conf).unwrap()
+    );
+    let values = xml_str_to_json_pointers(xml, &["/a/b", "/a/c", "/a/d"], &conf).unwrap();
+    assert_eq!(vec![Some(json!({})), Some(json!(1)), None], values);
+}
+
+#[test]
+fn test_xml_query() {
+    let xml = r#"<catalog>
+        <book id="1"><title>A</title><price>10</price></book>
+        <book id="2"><title>B</title><price>12.5</price><price>11</price></book>
+        <magazine><price>3</price></magazine>
+    </catalog>"#;
+    let conf = Config::new_with_defaults();
+    let query = |q| xml_query(xml, q, &conf).unwrap();
+
+    assert_eq!(
+        vec![json!(10), json!([12.5, 11])],
+        query("$.catalog.book[*].price")
+    );
+    assert_eq!(vec![json!("B")], query("$.catalog.book[1].title"));
+    assert_eq!(vec![json!(2)], query("$['catalog']['book'][1]['@id']"));
+    assert_eq!(vec![json!(11)], query("$.catalog.book[1].price[1]"));
+    assert_eq!(vec![json!({"price": 3})], query("$.catalog.magazine"));
+    assert_eq!(
+        vec![json!(10), json!([12.5, 11]), json!(3)],
+        query("$..price")
+    );
+    assert_eq!(vec![json!(1), json!(2)], query("$.catalog.book.*.@id"));
+    assert_eq!(xml_str_to_json(xml, &conf).unwrap(), query("$")[0]);
+    assert_eq!(
+        xml_str_to_json(xml, &conf).unwrap()["catalog"]["book"],
+        query("$.*.book")[0]
+    );
+    assert!(query("$.catalog.book.title").is_empty());
+    assert!(query("$.catalog.magazine[0]").is_empty());
+    assert!(query("$.other").is_empty());
+
+    for invalid in ["catalog", "$.", "$[?(@.price)]", "$.a[1", "$..", "$x"] {
+        assert!(
+            matches!(xml_query(xml, invalid, &conf), Err(Error::InvalidQuery(_))),
+            "{}",
+            invalid
+        );
+    }
+}
+
+#[test]
+fn test_xml_batch_to_json() {
+    let conf = Config::new_with_defaults();
+    let docs = [
+        "<a>1</a>".to_owned(),
+        "<a><b></a>".to_owned(),
+        "<c x='y'/>".to_owned(),
+    ];
+    let results = xml_batch_to_json(docs.iter().map(String::as_str), &conf);
+    assert_eq!(3, results.len());
+    assert_eq!(json!({"a": 1}), *results[0].as_ref().unwrap());
+    assert!(matches!(results[1], Err(Error::Xml(_))));
+    assert_eq!(json!({"c": {"@x": "y"}}), *results[2].as_ref().unwrap());
+
+    assert!(xml_batch_to_json(Vec::new(), &conf).is_empty());
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_xml_batch_to_json_parallel() {
+    let mut docs: Vec<String> = (0..1000)
+        .map(|i| format!("<a><b>{}</b><c>{}</c></a>", i, i % 3))
+        .collect();
+    docs[500] = "<a>".to_owned();
+    let conf = Config::new_with_defaults();
+    let parallel = xml_batch_to_json_parallel(&docs, &conf);
+    let sequential = xml_batch_to_json(docs.iter().map(String::as_str), &conf);
+    assert_eq!(docs.len(), parallel.len());
+    for (p, s) in parallel.iter().zip(&sequential) {
+        assert_eq!(p.as_ref().ok(), s.as_ref().ok());
+    }
+    assert!(parallel[500].is_err());
+    assert_eq!(
+        json!({"a": {"b": 999, "c": 0}}),
+        *parallel[999].as_ref().unwrap()
+    );
+
+    let cancelled = Arc::new(AtomicBool::new(true));
+    let conf = Config::new_with_defaults().with_cancellation_flag(cancelled);
+    assert!(xml_batch_to_json_parallel(&["<a/>", "<b/>"], &conf)
+        .iter()
+        .all(|r| matches!(r, Err(Error::Cancelled))));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_parallel_children() {
+    let mut xml = String::from("<root a='1'>");
+    for i in 0..500 {
+        xml.push_str(&format!(
+            "<item id='{}'><v>{}</v>text<x/><!-- c --></item><single><v>{}</v></single>",
+            i,
+            i * 2,
+            i
+        ));
+    }
+    xml.push_str("<last>x<dropped/></last></root>");
+
+    let conf = Config::new_with_defaults();
+    let parallel_conf = Config::new_with_defaults().with_parallel_children();
+    assert_eq!(
+        xml_str_to_json(&xml, &conf).unwrap(),
+        xml_str_to_json(&xml, &parallel_conf).unwrap()
+    );
+
+    let mut sequential = Diagnostics::new();
+    let mut parallel = Diagnostics::new();
+    let json = xml_str_to_json_with_diagnostics(&xml, &conf, &mut sequential).unwrap();
+    let parallel_json =
+        xml_str_to_json_with_diagnostics(&xml, &parallel_conf, &mut parallel).unwrap();
+    assert_eq!(json, parallel_json);
+    assert!(!sequential.is_empty());
+    assert_eq!(sequential.into_vec(), parallel.into_vec());
+
+    // the first data loss in document order fails the conversion
+    let strict = |conf: Config| match xml_str_to_json(
+        &xml,
+        &Config {
+            strict: true,
+            ..conf
+        },
+    ) {
+        Err(Error::Lossy(d)) => d,
+        other => panic!("{:?}", other),
+    };
+    assert_eq!(
+        strict(Config::new_with_defaults()),
+        strict(Config::new_with_defaults().with_parallel_children())
+    );
+}
+
+#[test]
+fn test_xml_document_to_borrowed_json() {
+    use std::borrow::Cow;
+
+    let xml = r#"<a x="1" y="&amp;"><b>007</b><b>text</b><c k="v">  more  </c><d/><e>mixed<f/></e>
+        <g><h>true</h><h>-1.5</h></g><?pi x?></a>"#;
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    #[allow(unused_mut)]
+    let mut configs = vec![
+        Config::new_with_defaults(),
+        Config::new_with_custom_values(true, "", "txt", NullValue::Null),
+        Config::new_with_custom_values(false, "_", "#t", NullValue::Ignore),
+        Config::new_with_defaults()
+            .with_raw_values(RawValues::Always)
+            .with_source_locations("#loc"),
+    ];
+    #[cfg(feature = "json_types")]
+    configs.push(
+        Config::new_with_defaults()
+            .add_json_type_override("/a/b", JsonArray::Always(JsonType::AlwaysString))
+            .add_json_type_override("/a/@x", JsonArray::Infer(JsonType::Bool(vec!["1"]))),
+    );
+    for conf in &configs {
+        let expected = xml_str_to_json(xml, conf).unwrap();
+        let borrowed = xml_document_to_borrowed_json(&doc, conf).unwrap();
+        assert_eq!(
+            expected.to_string(),
+            serde_json::to_string(&borrowed).unwrap()
+        );
+        assert_eq!(expected, borrowed.into_owned());
+
+        let mut diagnostics = Diagnostics::new();
+        let mut borrowed_diagnostics = Diagnostics::new();
+        xml_str_to_json_with_diagnostics(xml, conf, &mut diagnostics).unwrap();
+        xml_document_to_borrowed_json_with_diagnostics(&doc, conf, &mut borrowed_diagnostics)
+            .unwrap();
+        assert_eq!(diagnostics.into_vec(), borrowed_diagnostics.into_vec());
+    }
+
+    // names and texts are borrowed from the document
+    let conf = Config::new_with_defaults();
+    let BorrowedValue::Object(root) = xml_document_to_borrowed_json(&doc, &conf).unwrap() else {
+        panic!("not an object");
+    };
+    let Some(BorrowedValue::Object(a)) = root.get("a") else {
+        panic!("not an object");
+    };
+    assert!(a.keys().any(|k| matches!(k, Cow::Borrowed("c"))));
+    assert!(matches!(
+        a.get("@y"),
+        Some(BorrowedValue::String(Cow::Borrowed("&")))
+    ));
+    assert!(matches!(
+        a.get("b"),
+        Some(BorrowedValue::Array(b)) if b[1] == BorrowedValue::String(Cow::Borrowed("text"))
+    ));
+}
+
+#[cfg(feature = "bumpalo")]
+#[test]
+fn test_xml_str_to_arena_json() {
+    let xml = r#"<a x="1" y="&amp;" xmlns:n="urn:n"><b>007</b><b>text</b><c k="v">  more  </c><d/>
+        <e>mixed<f/></e><n:g><h>true</h><h>-1.5</h><i/><h>2</h></n:g></a>"#;
+    #[allow(unused_mut)]
+    let mut configs = vec![
+        Config::new_with_defaults(),
+        Config::new_with_custom_values(true, "", "txt", NullValue::Null),
+        Config::new_with_custom_values(false, "_", "#t", NullValue::Ignore),
+        Config::new_with_defaults()
+            .with_raw_values(RawValues::Always)
+            .with_source_locations("#loc")
+            .with_namespace_declarations(),
+    ];
+    #[cfg(feature = "json_types")]
+    configs.push(
+        Config::new_with_defaults()
+            .add_json_type_override("/a/b", JsonArray::Always(JsonType::AlwaysString))
+            .add_json_type_override("/a/@x", JsonArray::Infer(JsonType::Bool(vec!["1"]))),
+    );
+    let mut bump = bumpalo::Bump::new();
+    for conf in &configs {
+        let expected = xml_str_to_json(xml, conf).unwrap();
+        let json = xml_str_to_arena_json(xml, conf, &bump).unwrap();
+        assert_eq!(expected.to_string(), serde_json::to_string(&json).unwrap());
+        assert_eq!(expected, json.to_value());
+        drop(json);
+        bump.reset();
+    }
+
+    let conf = Config::new_with_defaults().with_namespace_declarations();
+    let json = xml_str_to_arena_json("<a xmlns='urn:a'>text</a>", &conf, &bump).unwrap();
+    assert_eq!(
+        json!({"a": {"#text": "text", "@xmlns": {"": "urn:a"}}}),
+        json.to_value()
+    );
+    assert!(matches!(
+        xml_str_to_arena_json("<a>", &conf, &bump),
+        Err(Error::Xml(_))
+    ));
+}
+
+#[test]
+fn test_borrowed_json_key_cache() {
+    use std::borrow::Cow;
+
+    let xml = r#"<a><b id="1" x:id="2" xmlns:x="urn:x"/><b id="3"><c id="4">t</c></b></a>"#;
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    let conf = Config::new_with_defaults();
+    let keys = KeyCache::new(&doc, &conf);
+    assert_eq!(1, keys.len());
+    let cached = xml_document_to_borrowed_json_with_cache(&doc, &conf, &keys).unwrap();
+    assert_eq!(
+        xml_str_to_json(xml, &conf).unwrap(),
+        cached.clone().into_owned()
+    );
+
+    // all `@id` keys point to the same string
+    let mut id_keys = Vec::new();
+    fn collect<'a>(value: &'a BorrowedValue, out: &mut Vec<&'a Cow<'a, str>>) {
+        match value {
+            BorrowedValue::Object(map) => {
+                out.extend(map.keys().filter(|k| *k == "@id"));
+                map.values().for_each(|v| collect(v, out));
+            }
+            BorrowedValue::Array(items) => items.iter().for_each(|v| collect(v, out)),
+            _ => (),
+        }
+    }
+    collect(&cached, &mut id_keys);
+    assert_eq!(3, id_keys.len());
+    assert!(id_keys.iter().all(|k| matches!(k, Cow::Borrowed(_))));
+    assert!(id_keys.windows(2).all(|w| w[0].as_ptr() == w[1].as_ptr()));
+
+    // without a prefix the keys are borrowed from the document anyway
+    let conf = Config::new_with_custom_values(false, "", "#text", NullValue::Null);
+    assert!(KeyCache::new(&doc, &conf).is_empty());
+}
+
+#[test]
+fn test_lazy_xml_json() {
+    let conf = Config::new_with_defaults();
+    let xml = r#"<order id="7"><customer><name>Ann</name><tag>a</tag><tag>b</tag></customer><item>x</item></order>"#;
+    let full = xml_str_to_json(xml, &conf).unwrap();
+    let mut lazy = LazyXmlJson::new(xml, &conf).unwrap();
+    for pointer in [
+        "/order/customer/tag/1",
+        "/order/customer",
+        "/order/customer/tag/0",
+        "/order/customer/missing",
+        "/order/@id",
+        "/order/item",
+        "/other",
+        "",
+        "/order/customer/name",
+    ] {
+        assert_eq!(
+            full.pointer(pointer),
+            lazy.get(pointer).unwrap(),
+            "{}",
+            pointer
+        );
+    }
+
+    let conf = Config {
+        array_inference: ArrayInference::DocumentWide,
+        ..Config::new_with_defaults()
+    };
+    let xml = "<a><b><c>1</c></b><b><c>2</c><c>3</c></b></a>";
+    let mut lazy = LazyXmlJson::new(xml, &conf).unwrap();
+    assert_eq!(Some(&json!([1])), lazy.get("/a/b/0/c").unwrap());
+    assert!(LazyXmlJson::new("<a>", &conf).is_err());
+
+    // the input is prepared like for `xml_str_to_json`, and owned by the struct if that copies it
+    let conf = Config::new_with_defaults()
+        .with_skip_leading_junk()
+        .with_entity_resolver(|_, system_id| (system_id == "e.xml").then(|| "<c>1</c>".to_owned()));
+    let xml = r#"junk<!DOCTYPE a [<!ENTITY e SYSTEM "e.xml">]><a><b>&e;</b></a>"#;
+    let mut lazy = LazyXmlJson::new(xml, &conf).unwrap();
+    assert_eq!(Some(&json!(1)), lazy.get("/a/b/c").unwrap());
+    assert_eq!(
+        xml_str_to_json(xml, &conf).unwrap().pointer("/a"),
+        lazy.get("/a").unwrap()
+    );
+}
+
+#[test]
+fn test_xml_to_serializer() {
+    let conf = Config::new_with_defaults();
+    let xml = r#"<a x="007"><b>1</b><b>two</b><c/><d>true</d></a>"#;
+    let mut out = Vec::new();
+    xml_to_serializer(xml, &conf, &mut serde_json::Serializer::new(&mut out)).unwrap();
+    let json: Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(xml_str_to_json(xml, &conf).unwrap(), json);
+
+    // serializers that build values work just as well
+    let result = xml_to_serializer(xml, &conf, serde_json::value::Serializer);
+    assert_eq!(json, result.unwrap());
+    struct Failing;
+    impl Write for Failing {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("closed"))
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let result = xml_to_serializer(xml, &conf, &mut serde_json::Serializer::new(Failing));
+    assert!(matches!(result, Err(Error::Serialize(_))));
+    assert!(matches!(
+        xml_to_serializer("<a>", &conf, serde_json::value::Serializer),
+        Err(Error::Xml(_))
+    ));
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn test_async_input() {
+    use std::collections::VecDeque;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    struct Chunks(VecDeque<Result<Vec<u8>, std::io::Error>>);
+    impl futures_core::Stream for Chunks {
+        type Item = Result<Vec<u8>, std::io::Error>;
+        fn poll_next(mut self: Pin<&mut Self>, _: &mut Context) -> Poll<Option<Self::Item>> {
+            Poll::Ready(self.0.pop_front())
+        }
+    }
+
+    let conf = Config::new_with_defaults();
+    let xml = "<a><b>1</b><c>grün</c></a>";
+    let expected = xml_str_to_json(xml, &conf).unwrap();
+    assert_eq!(
+        expected,
+        xml_async_read_to_json(xml.as_bytes(), &conf).await.unwrap()
+    );
+
+    // split inside of the multi-byte character
+    let split = xml.find('ü').unwrap() + 1;
+    let chunks = vec![
+        Ok(xml.as_bytes()[..split].to_vec()),
+        Ok(xml.as_bytes()[split..].to_vec()),
+    ];
+    let json = xml_stream_to_json(Chunks(chunks.into()), &conf).await;
+    assert_eq!(expected, json.unwrap());
+
+    let failing = vec![Ok(b"<a>".to_vec()), Err(std::io::Error::other("reset"))];
+    let json = xml_stream_to_json(Chunks(failing.into()), &conf).await;
+    assert!(matches!(json, Err(Error::Io(_))));
+    let invalid: &[u8] = b"<a>\xff</a>";
+    let json = xml_async_read_to_json(invalid, &conf).await;
+    assert!(matches!(json, Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::InvalidData));
+}
+
+#[test]
+fn test_config_from_json() {
+    let conf = Config::from_json(
+        r#"{"leading_zero_as_string": true, "xml_attr_prefix": "_", "xml_text_node_prop_name": "text",
+            "empty_element_handling": "null", "array_inference": "document_wide", "strict": true,
+            "coercion_failure_policy": "keep_string", "max_depth": 10, "memory_budget": 100000,
+            "raw_values": ["/a/@v"], "location_prop_name": null}"#,
+    )
+    .unwrap();
+    let xml = r#"<a v="007"><b>x<c/></b><d>1</d></a>"#;
+    assert!(matches!(xml_str_to_json(xml, &conf), Err(Error::Lossy(_))));
+    let xml = r#"<a v="007"><b t="1">x</b><c/><d><e>1</e></d><d><e>2</e><e>3</e></d></a>"#;
+    assert_eq!(
+        json!({"a": {"_v": {"value": "007", "raw": "007"}, "b": {"_t": 1, "text": "x"}, "c": null,
+            "d": [{"e": [1]}, {"e": [2, 3]}]}}),
+        xml_str_to_json(xml, &conf).unwrap()
+    );
+    assert_eq!(10, conf.max_depth);
+    assert_eq!(Some(100000), conf.memory_budget);
+
+    for invalid in [
+        "[]",
+        r#"{"unknown": 1}"#,
+        r#"{"strict": "yes"}"#,
+        r#"{"max_depth": -1}"#,
+        r#"{"empty_element_handling": "none"}"#,
+    ] {
+        assert!(
+            matches!(Config::from_json(invalid), Err(Error::InvalidConfig(_))),
+            "{}",
+            invalid
+        );
+    }
+}
+
+#[cfg(feature = "json_types")]
+#[test]
+fn test_config_from_json_overrides() {
+    let conf = Config::from_json(
+        r#"{"json_type_overrides": {"/a/b": "number[]", "/a/@c": "string", "/a/d": "bool:yes,Y"}}"#,
+    )
+    .unwrap();
+    let xml = r#"<a c="1"><b>007</b><d>Y</d></a>"#;
+    assert_eq!(
+        json!({"a": {"@c": "1", "b": [7], "d": true}}),
+        xml_str_to_json(xml, &conf).unwrap()
+    );
+    assert!(matches!(
+        "float".parse::<JsonArray>(),
+        Err(Error::InvalidConfig(_))
+    ));
+    assert!(matches!(
+        Config::from_json(r#"{"json_type_overrides": {"/a": 1}}"#),
+        Err(Error::InvalidConfig(_))
+    ));
+
+    #[cfg(feature = "regex_path")]
+    {
+        let conf =
+            Config::from_json(r#"{"json_regex_type_overrides": [["b$", "string"]]}"#).unwrap();
+        assert_eq!(
+            json!({"a": {"b": "1"}}),
+            xml_str_to_json("<a><b>1</b></a>", &conf).unwrap()
+        );
+        assert!(Config::from_json(r#"{"json_regex_type_overrides": [["(", "string"]]}"#).is_err());
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[test]
+fn test_wasm_convert() {
+    assert_eq!(
+        r#"{"a":{"b":1}}"#,
+        wasm::convert("<a><b>1</b></a>", "").unwrap()
+    );
+    assert_eq!(
+        r#"{"a":{"b":"1"}}"#,
+        wasm::convert(
+            "<a><b>1</b></a>",
+            r#"{"json_type_overrides": {"/a/b": "string"}}"#
+        )
+        .unwrap()
+    );
+    assert!(wasm::convert("<a>", "{}").is_err());
+}
+
+#[cfg(feature = "python")]
+#[test]
+fn test_python_xml_to_dict() {
+    use pyo3::prelude::*;
+    use pyo3::types::PyDict;
+
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let xml = r#"<a id="007"><b>1</b><b>2.5</b><c>true</c><d/><e>18446744073709551615</e></a>"#;
+        let dict = python::xml_to_dict(py, xml, None).unwrap();
+        let expected = xml_str_to_json(xml, &Config::new_with_defaults()).unwrap();
+        assert_eq!(expected, python::from_python(&dict).unwrap());
+        assert_eq!(
+            "{'a': {'@id': 7, 'b': [1, 2.5], 'c': True, 'd': {}, 'e': 18446744073709551615}}",
+            dict.str().unwrap().to_str().unwrap()
+        );
+
+        let config = PyDict::new(py);
+        config.set_item("xml_attr_prefix", "").unwrap();
+        config.set_item("empty_element_handling", "null").unwrap();
+        let overrides = PyDict::new(py);
+        overrides.set_item("/a/@id", "string").unwrap();
+        config.set_item("json_type_overrides", overrides).unwrap();
+        let dict = python::xml_to_dict(py, xml, Some(&config)).unwrap();
+        let json = python::from_python(&dict).unwrap();
+        assert_eq!(json!("007"), json["a"]["id"]);
+        assert_eq!(Value::Null, json["a"]["d"]);
+
+        config.set_item("strict", "yes").unwrap();
+        let error = python::xml_to_dict(py, xml, Some(&config)).unwrap_err();
+        assert!(error.is_instance_of::<pyo3::exceptions::PyValueError>(py));
+        assert!(python::xml_to_dict(py, "<a>", None).is_err());
+    });
+}
+
+#[cfg(feature = "capi")]
+#[test]
+fn test_capi() {
+    use capi::*;
+    use std::ffi::{CStr, CString};
+    use std::ptr;
+
+    unsafe fn take(s: *mut std::ffi::c_char) -> String {
+        let text = CStr::from_ptr(s).to_str().unwrap().to_owned();
+        rxts_string_free(s);
+        text
+    }
+
+    unsafe {
+        let xml = CString::new(r#"<a id="007"><b>1</b></a>"#).unwrap();
+        let mut out = ptr::null_mut();
+        assert_eq!(
+            RXTS_OK,
+            rxts_xml_to_json(xml.as_ptr(), ptr::null(), &mut out)
+        );
+        assert_eq!(r#"{"a":{"@id":7,"b":1}}"#, take(out));
+
+        let json = CString::new(r#"{"json_type_overrides": {"/a/@id": "string"}}"#).unwrap();
+        let config = rxts_config_from_json(json.as_ptr(), ptr::null_mut());
+        assert!(!config.is_null());
+        assert_eq!(RXTS_OK, rxts_xml_to_json(xml.as_ptr(), config, &mut out));
+        assert_eq!(r#"{"a":{"@id":"007","b":1}}"#, take(out));
+
+        let malformed = CString::new("<a>").unwrap();
+        assert_eq!(
+            RXTS_CONVERSION_ERROR,
+            rxts_xml_to_json(malformed.as_ptr(), config, &mut out)
+        );
+        assert!(take(out).starts_with("malformed XML"));
+        assert_eq!(
+            RXTS_INVALID_ARGUMENT,
+            rxts_xml_to_json(ptr::null(), config, &mut out)
+        );
+        take(out);
+        assert_eq!(
+            RXTS_INVALID_ARGUMENT,
+            rxts_xml_to_json(xml.as_ptr(), config, ptr::null_mut())
+        );
+        rxts_config_free(config);
+
+        let mut error = ptr::null_mut();
+        let invalid = CString::new(r#"{"strict": 1}"#).unwrap();
+        assert!(rxts_config_from_json(invalid.as_ptr(), &mut error).is_null());
+        assert!(take(error).starts_with("invalid configuration"));
+        rxts_config_free(rxts_config_new());
+        rxts_config_free(ptr::null_mut());
+        rxts_string_free(ptr::null_mut());
+    }
+
+    // the header declares every exported function
+    let header = include_str!("../include/roxmltree_to_serde.h");
+    for line in include_str!("capi.rs").lines() {
+        if let Some(rest) = line.split("extern \"C\" fn ").nth(1) {
+            let name = &rest[..rest.find('(').unwrap()];
+            assert!(header.contains(&format!("{}(", name)), "{}", name);
+        }
+    }
+}
+
+#[cfg(feature = "json_types")]
+#[test]
+fn test_presets_soap() {
+    use presets::{soap, soap_body, SoapFault};
+
+    let xml = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"
+        xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+        <soap:Header><auth>token</auth></soap:Header>
+        <soap:Body>
+            <m:GetOrderResponse xmlns:m="urn:shop">
+                <m:Id>7</m:Id><m:Note xsi:nil="true"/><m:Item>a</m:Item><m:Item>b</m:Item>
+            </m:GetOrderResponse>
+        </soap:Body>
+    </soap:Envelope>"#;
+    assert_eq!(
+        json!({"GetOrderResponse": {"Id": 7, "Note": null, "Item": ["a", "b"]}}),
+        soap_body(xml, &soap()).unwrap()
+    );
+    // without the preset only the listed paths are nillable
+    let body = soap_body(xml, &Config::new_with_defaults()).unwrap();
+    assert_eq!(json!({"@nil": true}), body["GetOrderResponse"]["Note"]);
+
+    let fault_11 = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/"><soap:Body>
+        <soap:Fault><faultcode>soap:Client</faultcode><faultstring>Invalid id</faultstring>
+        <detail><code>42</code></detail></soap:Fault></soap:Body></soap:Envelope>"#;
+    match soap_body(fault_11, &soap()) {
+        Err(Error::SoapFault(fault)) => assert_eq!(
+            SoapFault {
+                code: "soap:Client".to_owned(),
+                reason: "Invalid id".to_owned(),
+                actor: None,
+                detail: Some(json!({"code": 42})),
+            },
+            fault
+        ),
+        other => panic!("{:?}", other),
+    }
+
+    let fault_12 = r#"<env:Envelope xmlns:env="http://www.w3.org/2003/05/soap-envelope"><env:Body>
+        <env:Fault><env:Code><env:Value>env:Receiver</env:Value></env:Code>
+        <env:Reason><env:Text xml:lang="en">Out of stock</env:Text></env:Reason>
+        <env:Role>urn:shop:stock</env:Role></env:Fault></env:Body></env:Envelope>"#;
+    let Err(Error::SoapFault(fault)) = soap_body(fault_12, &soap()) else {
+        panic!("expected a fault");
+    };
+    assert_eq!("env:Receiver: Out of stock", fault.to_string());
+    assert_eq!(Some("urn:shop:stock".to_owned()), fault.actor);
+    assert_eq!(None, fault.detail);
+
+    assert!(matches!(
+        soap_body("<Envelope><Body/></Envelope>", &soap()),
+        Err(Error::UnexpectedXml(_))
+    ));
+    assert!(matches!(
+        soap_body(
+            r#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"/>"#,
+            &soap()
+        ),
+        Err(Error::UnexpectedXml(_))
+    ));
+}
+
+#[cfg(feature = "json_types")]
+#[test]
+fn test_presets_gpx() {
+    let conf = presets::gpx();
+    let single = r#"<gpx version="1.1"><wpt lat="1" lon="2"><name>007</name></wpt>
+        <trk><name>run</name><trkseg><trkpt lat="47.6" lon="8.5"><ele>420.5</ele><time>2024-05-01T10:00:00Z</time></trkpt></trkseg></trk>
+        <rte><rtept lat="1.5" lon="2.5"/></rte></gpx>"#;
+    let json = xml_str_to_json(single, &conf).unwrap();
+    assert_eq!(
+        json!([{"@lat": 47.6, "@lon": 8.5, "ele": 420.5, "time": "2024-05-01T10:00:00Z"}]),
+        json["gpx"]["trk"][0]["trkseg"][0]["trkpt"]
+    );
+    assert_eq!(
+        json!([{"@lat": 1, "@lon": 2, "name": 7}]),
+        json["gpx"]["wpt"]
+    );
+    assert_eq!(
+        json!([{"rtept": [{"@lat": 1.5, "@lon": 2.5}]}]),
+        json["gpx"]["rte"]
+    );
+
+    // more points don't change the structure
+    let multi = r#"<gpx><trk><trkseg><trkpt lat="1" lon="2"/><trkpt lat="3" lon="4"/></trkseg></trk></gpx>"#;
+    let json = xml_str_to_json(multi, &conf).unwrap();
+    assert_eq!(
+        2,
+        json["gpx"]["trk"][0]["trkseg"][0]["trkpt"]
+            .as_array()
+            .unwrap()
+            .len()
+    );
+}
+
+#[cfg(feature = "json_types")]
+#[test]
+fn test_presets_svg() {
+    let xml = r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" viewBox="0 0 24 24">
+        <!-- icon -->
+        <style>.a { fill: #000; }</style>
+        <path d="M005.5 1e2L10 10z" style="stroke-width: 2"/>
+        <g><circle r="001"/><use xlink:href="#a"/></g>
+        <rect x="1"/>
+        <text>Hello <tspan>world</tspan></text>
+    </svg>"##;
+    let json = presets::svg_to_json(xml, &Config::new_with_defaults()).unwrap();
+    assert_eq!(
+        json!({"name": "svg", "attributes": {"viewBox": "0 0 24 24"}, "children": [
+            {"name": "style", "children": [{"text": ".a { fill: #000; }"}]},
+            {"name": "path", "attributes": {"d": "M005.5 1e2L10 10z", "style": "stroke-width: 2"}},
+            {"name": "g", "children": [
+                {"name": "circle", "attributes": {"r": "001"}},
+                {"name": "use", "attributes": {"xlink:href": "#a"}},
+            ]},
+            {"name": "rect", "attributes": {"x": "1"}},
+            {"name": "text", "children": [{"text": "Hello "}, {"name": "tspan", "children": [{"text": "world"}]}]},
+        ]}),
+        json
+    );
+    assert!(presets::svg_to_json("<svg>", &Config::new_with_defaults()).is_err());
+}
+
+#[cfg(feature = "json_types")]
+#[test]
+fn test_presets_plist() {
+    let conf = Config::new_with_defaults();
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- DOCTYPE omitted, roxmltree rejects DTDs -->
+<plist version="1.0">
+<dict>
+    <key>Name</key><string>007</string>
+    <key>Count</key><integer>-3</integer>
+    <key>Big</key><integer>18446744073709551615</integer>
+    <key>Ratio</key><real>0.5</real>
+    <key>Enabled</key><false/>
+    <key>Created</key><date>2024-01-31T12:00:00Z</date>
+    <key>Icon</key><data>
+        AAEC
+        AwQ=
+    </data>
+    <key>Nested</key><dict><key>Empty</key><array/></dict>
+    <key>Empty string</key><string></string>
+</dict>
+</plist>"#;
+    assert_eq!(
+        json!({"Name": "007", "Count": -3, "Big": 18446744073709551615u64, "Ratio": 0.5, "Enabled": false,
+            "Created": "2024-01-31T12:00:00Z", "Icon": "AAECAwQ=", "Nested": {"Empty": []},
+            "Empty string": ""}),
+        presets::plist_to_json(xml, &conf).unwrap()
+    );
+    assert_eq!(
+        json!([true, "x"]),
+        presets::plist_to_json("<array><true/><string>x</string></array>", &conf).unwrap()
+    );
+    for invalid in [
+        "<plist><dict><string>x</string></dict></plist>",
+        "<plist><dict><key>a</key></dict></plist>",
+        "<plist><integer>1.5</integer></plist>",
+        "<plist><real>nan</real></plist>",
+        "<plist><set/></plist>",
+        "<plist><true/><false/></plist>",
+        "<plist/>",
+    ] {
+        let result = presets::plist_to_json(invalid, &conf);
+        assert!(
+            matches!(result, Err(Error::UnexpectedXml(_))),
+            "{}",
+            invalid
+        );
+    }
+}
+
+#[cfg(feature = "json_types")]
+#[test]
+fn test_presets_junit() {
+    let conf = Config::new_with_defaults();
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites tests="3" time="1,234.5">
+    <testsuite name="empty" tests="0" time="0"/>
+    <testsuite name="math" tests="3" failures="1" skipped="1" time="1.5">
+        <properties>
+            <property name="os" value="linux"/>
+            <property name="version" value="017"/>
+            <property name="args">-v  --fast</property>
+        </properties>
+        <testcase classname="Math" name="adds" time="1"/>
+        <testcase classname="Math" name="divides" time="0.5">
+            <failure message="boom" type="AssertionError"><![CDATA[
+  at Math.divides(Math.java:12)
+]]></failure>
+            <system-out>  1 / 0  </system-out>
+        </testcase>
+        <testcase classname="Math" name="later"><skipped/></testcase>
+    </testsuite>
+</testsuites>"#;
+    assert_eq!(
+        json!({"testsuites": {"@tests": 3, "@time": 1234.5, "testsuite": [
+            {"@name": "empty", "@tests": 0, "@time": 0.0, "testcase": []},
+            {"@name": "math", "@tests": 3, "@failures": 1, "@skipped": 1, "@time": 1.5,
+                "properties": {"os": "linux", "version": "017", "args": "-v  --fast"},
+                "testcase": [
+                    {"@classname": "Math", "@name": "adds", "@time": 1.0},
+                    {"@classname": "Math", "@name": "divides", "@time": 0.5,
+                        "failure": {"@message": "boom", "@type": "AssertionError",
+                            "#text": "\n  at Math.divides(Math.java:12)\n"},
+                        "system-out": "  1 / 0  "},
+                    {"@classname": "Math", "@name": "later", "skipped": {}},
+                ]},
+        ]}}),
+        presets::junit_to_json(xml, &conf).unwrap()
+    );
+
+    let json = presets::junit_to_json(
+        r#"<testsuite name="single"><testcase name="t"/></testsuite>"#,
+        &conf,
+    )
+    .unwrap();
+    assert_eq!(json!([{"@name": "t"}]), json["testsuite"][0]["testcase"]);
+    assert!(matches!(
+        presets::junit_to_json("<report/>", &conf),
+        Err(Error::UnexpectedXml(_))
+    ));
+}
+
+#[cfg(feature = "json_types")]
+#[test]
+fn test_presets_pom() {
+    let conf = Config::new_with_defaults();
+    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<project xmlns="http://maven.apache.org/POM/4.0.0">
+    <modelVersion>4.0.0</modelVersion>
+    <groupId>com.example</groupId>
+    <version>2.0</version>
+    <packaging>pom</packaging>
+    <modules><module>core</module></modules>
+    <properties>
+        <java.version>17</java.version>
+        <skipTests>true</skipTests>
+        <build.number>007</build.number>
+    </properties>
+    <dependencies>
+        <dependency>
+            <groupId>junit</groupId>
+            <artifactId>junit</artifactId>
+            <version>4.10</version>
+            <exclusions><exclusion><artifactId>hamcrest</artifactId></exclusion></exclusions>
+        </dependency>
+    </dependencies>
+    <build>
+        <plugins>
+            <plugin>
+                <artifactId>maven-compiler-plugin</artifactId>
+                <configuration><release>17</release></configuration>
+            </plugin>
+        </plugins>
+    </build>
+    <profiles><profile><id>ci</id><properties><ci>1</ci></properties></profile></profiles>
+</project>"#;
+    assert_eq!(
+        json!({"project": {
+            "modelVersion": "4.0.0", "groupId": "com.example", "version": "2.0", "packaging": "pom",
+            "modules": {"module": ["core"]},
+            "properties": {"java.version": "17", "skipTests": "true", "build.number": "007"},
+            "dependencies": {"dependency": [{"groupId": "junit", "artifactId": "junit", "version": "4.10",
+                "exclusions": {"exclusion": [{"artifactId": "hamcrest"}]}}]},
+            "build": {"plugins": {"plugin": [
+                {"artifactId": "maven-compiler-plugin", "configuration": {"release": 17}}]}},
+            "profiles": {"profile": [{"id": "ci", "properties": {"ci": "1"}}]},
+        }}),
+        presets::pom_to_json(xml, &conf).unwrap()
+    );
+    assert!(matches!(
+        presets::pom_to_json("<settings/>", &conf),
+        Err(Error::UnexpectedXml(_))
+    ));
+}
+
+#[cfg(feature = "json_types")]
+#[test]
+fn test_presets_opml_sitemap() {
+    let xml = r#"<opml version="2.0">
+    <head><title>2024</title><dateCreated>Mon, 1 Jan 2024 00:00:00 GMT</dateCreated><windowTop>61</windowTop>
+        <expansionState>1</expansionState></head>
+    <body>
+        <outline text="Podcasts">
+            <outline text="99" type="rss" xmlUrl="https://example.com/99.xml"/>
+        </outline>
+    </body>
+</opml>"#;
+    assert_eq!(
+        json!({"opml": {"@version": "2.0",
+            "head": {"title": "2024", "dateCreated": "Mon, 1 Jan 2024 00:00:00 GMT", "windowTop": 61,
+                "expansionState": "1"},
+            "body": {"outline": [{"@text": "Podcasts", "outline": [
+                {"@text": "99", "@type": "rss", "@xmlUrl": "https://example.com/99.xml"}]}]}}}),
+        xml_str_to_json(xml, &presets::opml()).unwrap()
+    );
+
+    let xml = r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+    <url><loc>https://example.com/</loc><lastmod>2024-01-31</lastmod><changefreq>daily</changefreq>
+        <priority>0.8</priority></url>
+</urlset>"#;
+    assert_eq!(
+        json!({"urlset": {"url": [{"loc": "https://example.com/", "lastmod": "2024-01-31",
+            "changefreq": "daily", "priority": 0.8}]}}),
+        xml_str_to_json(xml, &presets::sitemap()).unwrap()
+    );
+    let xml = r#"<sitemapindex><sitemap><loc>https://example.com/1.xml</loc><lastmod>2024</lastmod></sitemap></sitemapindex>"#;
+    assert_eq!(
+        json!({"sitemapindex": {"sitemap": [{"loc": "https://example.com/1.xml", "lastmod": "2024"}]}}),
+        xml_str_to_json(xml, &presets::sitemap()).unwrap()
+    );
+}
+
+#[cfg(feature = "json_types")]
+#[test]
+fn test_presets_xmltv() {
+    let xml = r#"<tv generator-info-name="epg">
+    <channel id="1"><display-name lang="en">One</display-name><display-name>1</display-name></channel>
+    <programme start="20240131120000 +0100" stop="20240131123000" channel="1">
+        <title lang="en">News</title>
+        <title lang="de">Nachrichten</title>
+        <category lang="en">News</category>
+        <category lang="en">Weather</category>
+        <episode-num system="onscreen">S1E2</episode-num>
+        <length units="minutes">30</length>
+    </programme>
+    <programme start="19691231 -0330" stop="tomorrow" channel="1"><title>Late</title></programme>
+    <programme start="200002290000" channel="1"/>
+</tv>"#;
+    assert_eq!(
+        json!({"tv": {"@generator-info-name": "epg",
+        "channel": [{"@id": "1", "display-name": {"en": "One", "": "1"}}],
+        "programme": [
+            {"@start": 1706698800, "@stop": 1706704200, "@channel": "1",
+                "title": {"en": "News", "de": "Nachrichten"},
+                "category": {"en": ["News", "Weather"]},
+                "episode-num": {"@system": "onscreen", "#text": "S1E2"},
+                "length": {"@units": "minutes", "#text": 30}},
+            {"@start": -73800, "@stop": "tomorrow", "@channel": "1", "title": {"": "Late"}},
+            {"@start": 951782400, "@channel": "1"},
+        ]}}),
+        presets::xmltv_to_json(xml, &Config::new_with_defaults()).unwrap()
+    );
+    assert!(matches!(
+        presets::xmltv_to_json("<epg/>", &Config::new_with_defaults()),
+        Err(Error::UnexpectedXml(_))
+    ));
+}
+
+#[test]
+fn test_xml2js() {
+    let conf = Config::new_with_defaults();
+    let xml = r#"<soap:Envelope xmlns:soap="urn:soap" version="1.0">
+    <soap:Body>
+        <item id="1">  007  </item>
+        <item><name>Pen</name>text <b/> tail</item>
+        <blank>   </blank>
+        <empty/>
+    </soap:Body>
+</soap:Envelope>"#;
+    assert_eq!(
+        json!({"soap:Envelope": {"$": {"xmlns:soap": "urn:soap", "version": "1.0"}, "soap:Body": [{
+            "item": [
+                {"$": {"id": "1"}, "_": "  007  "},
+                {"_": "text  tail", "name": ["Pen"], "b": [""]},
+            ],
+            "blank": ["   "],
+            "empty": [""],
+        }]}}),
+        xml2js_to_json(xml, &conf, &Xml2jsOptions::default()).unwrap()
+    );
+
+    let options = Xml2jsOptions {
+        explicit_array: false,
+        explicit_root: false,
+        merge_attrs: true,
+        trim: true,
+        char_key: "#".to_owned(),
+        ..Xml2jsOptions::default()
+    };
+    assert_eq!(
+        json!({"id": "1", "b": ["x", {"id": "2", "#": "y"}], "c": "z"}),
+        xml2js_to_json(
+            r#"<a id="1"><b>x</b><b id="2"> y </b><c> z </c></a>"#,
+            &conf,
+            &options
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn test_xmltodict() {
+    let conf = Config::new_with_defaults();
+    let xml = r#"<soap:Envelope xmlns:soap="urn:soap" version="1.0">
+    <soap:Body>
+        <item id="1">  007  </item>
+        <item><name>Pen</name>text <b/> tail</item>
+        <blank>   </blank>
+        <empty/>
+        <tag id="2"/>
+    </soap:Body>
+</soap:Envelope>"#;
+    assert_eq!(
+        json!({"soap:Envelope": {"@xmlns:soap": "urn:soap", "@version": "1.0", "soap:Body": {
+            "item": [
+                {"@id": "1", "#text": "007"},
+                {"name": "Pen", "b": null, "#text": "text  tail"},
+            ],
+            "blank": null,
+            "empty": null,
+            "tag": {"@id": "2"},
+        }}}),
+        xmltodict_to_json(xml, &conf, &XmltodictOptions::default()).unwrap()
+    );
+
+    let options = XmltodictOptions {
+        attr_prefix: "".to_owned(),
+        force_cdata: true,
+        strip_whitespace: false,
+        ..XmltodictOptions::default()
+    }
+    .with_force_list(["a", "c"]);
+    assert_eq!(
+        json!({"a": [{"id": "1", "b": {"#text": " x "}, "c": [{"#text": "  "}]}]}),
+        xmltodict_to_json(r#"<a id="1"><b> x </b><c>  </c></a>"#, &conf, &options).unwrap()
+    );
+}
+
+#[test]
+fn test_newtonsoft() {
+    let conf = Config::new_with_defaults();
+    let xml = "<?xml version='1.0' encoding=\"utf-8\" standalone='yes'?>
+<?app mode=\"fast\"?>
+<soap:Envelope xmlns:soap=\"urn:soap\" xmlns:json=\"http://james.newtonking.com/projects/json\">
+    <item id=\"1\">  007  </item>
+    <item>x &amp; y<b/>tail<![CDATA[<raw>]]></item>
+    <list json:Array=\"true\"><n>1</n></list>
+    <blank>   </blank>
+    <empty/>
+    <cdata>
+        <![CDATA[a\r\nb]]>
+    </cdata>
+    <!-- comments are not kept -->
+</soap:Envelope>";
+    assert_eq!(
+        json!({
+            "?xml": {"@version": "1.0", "@encoding": "utf-8", "@standalone": "yes"},
+            "?app": "mode=\"fast\"",
+            "soap:Envelope": {"@xmlns:soap": "urn:soap",
+                "item": [
+                    {"@id": "1", "#text": "  007  "},
+                    {"#text": ["x & y", "tail"], "b": null, "#cdata-section": "<raw>"},
+                ],
+                "list": [{"n": "1"}],
+                "blank": "",
+                "empty": null,
+                "cdata": {"#cdata-section": "a\nb"},
+            }
+        }),
+        newtonsoft_to_json(xml, &conf, &NewtonsoftOptions::default()).unwrap()
+    );
+    let options = NewtonsoftOptions {
+        omit_root_object: true,
+    };
+    assert_eq!(
+        json!({"@a": "1", "b": "x"}),
+        newtonsoft_to_json(
+            r#"<?xml version="1.0"?><r a="1"><b>x</b></r>"#,
+            &conf,
+            &options
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn test_x2js() {
+    let conf = Config::new_with_defaults();
+    let xml = r#"<soap:orders xmlns:soap="urn:soap">
+    <order id="1">  007  </order>
+    <order>x &amp;amp; y<b/>tail<![CDATA[<raw>]]></order>
+    <soap:note>n</soap:note>
+    <blank>   </blank>
+    <empty/>
+    <!-- comments are not kept -->
+</soap:orders>"#;
+    assert_eq!(
+        json!({"orders": {"_xmlns:soap": "urn:soap", "__prefix": "soap",
+            "order": [
+                {"_id": "1", "__text": "007"},
+                {"__text": "x & y\ntail", "b": "", "__cdata": "<raw>"},
+            ],
+            "note": {"__prefix": "soap", "__text": "n"},
+            "blank": "",
+            "empty": "",
+        }}),
+        x2js_to_json(xml, &conf, &X2jsOptions::default()).unwrap()
+    );
+
+    let options = X2jsOptions {
+        attribute_prefix: "@".to_owned(),
+        array_access_form: X2jsArrayAccessForm::Property,
+        array_access_form_paths: vec!["a.c".to_owned()],
+        empty_node_form: X2jsEmptyNodeForm::Object,
+        escape_mode: false,
+        strip_whitespaces: false,
+        ..X2jsOptions::default()
+    };
+    assert_eq!(
+        json!({"a": {"@id": "1", "b": [" &amp; ", {}], "b_asArray": [" &amp; ", {}],
+            "c": ["x"], "c_asArray": ["x"]}}),
+        x2js_to_json(
+            r#"<a id="1"><b> &amp;amp; </b><b/><c>x</c></a>"#,
+            &conf,
+            &options
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn test_namespace_declarations() {
+    let xml = r#"<s:a xmlns:s="urn:s" xmlns="urn:d" xsi:type="s:Order" xmlns:xsi="urn:xsi">
+    <b xmlns:t="urn:t"><c xmlns:s="urn:other"/></b>
+</s:a>"#;
+    let conf = Config::new_with_defaults().with_namespace_declarations();
+    let expected = json!({"a": {
+        "@xmlns": {"": "urn:d", "s": "urn:s", "t": "urn:t", "xsi": "urn:xsi"},
+        "@type": "s:Order",
+        "b": {"c": {}},
+    }});
+    assert_eq!(expected, xml_str_to_json(xml, &conf).unwrap());
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    assert_eq!(
+        expected,
+        serde_json::to_value(xml_document_to_borrowed_json(&doc, &conf).unwrap()).unwrap()
+    );
+
+    let mut diagnostics = Diagnostics::new();
+    xml_str_to_json_with_diagnostics(xml, &conf, &mut diagnostics).unwrap();
+    assert!(diagnostics.iter().any(
+        |d| d.path == "/a/b/c" && d.kind == DiagnosticKind::KeyCollision("xmlns:s".to_owned())
+    ));
+
+    // text roots keep their text next to the declarations
+    assert_eq!(
+        json!({"a": {"@xmlns": {"": "urn:d"}, "#text": 1}}),
+        xml_str_to_json(r#"<a xmlns="urn:d">1</a>"#, &conf).unwrap()
+    );
+    // documents without namespaces are unchanged
+    assert_eq!(json!({"a": 1}), xml_str_to_json("<a>1</a>", &conf).unwrap());
+    assert!(
+        Config::from_json(r#"{"emit_namespace_declarations": true}"#)
+            .unwrap()
+            .emit_namespace_declarations
+    );
+}
+
+#[test]
+fn test_namespace_prefixes() {
+    let conf = Config::new_with_defaults()
+        .with_namespace_prefix("urn:vendor-a", "v")
+        .with_namespace_prefix("urn:vendor-b", "v");
+    let supplier_a = r#"<x:order xmlns:x="urn:vendor-a"><x:item x:id="1"/><x:item x:id="2"/><note>n</note></x:order>"#;
+    let supplier_b = r#"<order xmlns="urn:vendor-b" xmlns:q="urn:vendor-b"><item q:id="1"/><item q:id="2"/><note xmlns="">n</note></order>"#;
+    let expected = json!({"v:order": {"v:item": [{"@v:id": 1}, {"@v:id": 2}], "note": "n"}});
+    assert_eq!(expected, xml_str_to_json(supplier_a, &conf).unwrap());
+    assert_eq!(expected, xml_str_to_json(supplier_b, &conf).unwrap());
+    let doc = roxmltree::Document::parse(supplier_b).unwrap();
+    assert_eq!(
+        expected,
+        serde_json::to_value(xml_document_to_borrowed_json(&doc, &conf).unwrap()).unwrap()
+    );
+    let cache = KeyCache::new(&doc, &conf);
+    assert_eq!(
+        expected,
+        serde_json::to_value(
+            xml_document_to_borrowed_json_with_cache(&doc, &conf, &cache).unwrap()
+        )
+        .unwrap()
+    );
+    assert_eq!(
+        vec![Some(json!(2))],
+        xml_str_to_json_pointers(supplier_a, &["/v:order/v:item/1/@v:id"], &conf).unwrap()
+    );
+
+    // mapped namespaces are not reported as ignored
+    let mut diagnostics = Diagnostics::new();
+    xml_str_to_json_with_diagnostics(
+        r#"<a xmlns="urn:vendor-a" xmlns:o="urn:other"><o:b/></a>"#,
+        &conf,
+        &mut diagnostics,
+    )
+    .unwrap();
+    assert_eq!(
+        vec![DiagnosticKind::IgnoredNamespace("urn:other".to_owned())],
+        diagnostics
+            .iter()
+            .map(|d| d.kind.clone())
+            .collect::<Vec<_>>()
+    );
+
+    let conf = Config::from_json(r#"{"namespace_prefixes": {"urn:vendor-a": "v"}}"#).unwrap();
+    assert_eq!(
+        json!({"v:order": {"v:item": [{"@v:id": 1}, {"@v:id": 2}], "note": "n"}}),
+        xml_str_to_json(supplier_a, &conf).unwrap()
+    );
+}
+
+#[cfg(feature = "json_types")]
+#[test]
+fn test_namespace_prefixes_overrides() {
+    let conf = Config::new_with_defaults()
+        .with_namespace_prefix("urn:vendor-a", "v")
+        .add_json_type_override("/v:order/v:item", JsonArray::Always(JsonType::Infer))
+        .add_json_type_override(
+            "/v:order/v:item/@v:id",
+            JsonArray::Infer(JsonType::AlwaysString),
+        );
+    let xml = r#"<x:order xmlns:x="urn:vendor-a"><x:item x:id="1"/></x:order>"#;
+    assert_eq!(
+        json!({"v:order": {"v:item": [{"@v:id": "1"}]}}),
+        xml_str_to_json(xml, &conf).unwrap()
+    );
+}
+
+#[test]
+fn test_clark_notation() {
+    let conf = Config::new_with_defaults()
+        .with_clark_notation()
+        .with_namespace_prefix("urn:mapped", "m");
+    let xml = r#"<feed xmlns="http://example.com/feed" xmlns:x="urn:ext" xmlns:m="urn:mapped"><item x:id="1"><m:a>1</m:a><a xmlns="">2</a></item><item x:id="2"/></feed>"#;
+    let expected = json!({"{http://example.com/feed}feed": {"{http://example.com/feed}item": [
+        {"@{urn:ext}id": 1, "m:a": 1, "a": 2},
+        {"@{urn:ext}id": 2}
+    ]}});
+    assert_eq!(expected, xml_str_to_json(xml, &conf).unwrap());
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    assert_eq!(
+        expected,
+        serde_json::to_value(xml_document_to_borrowed_json(&doc, &conf).unwrap()).unwrap()
+    );
+    assert_eq!(
+        vec![Some(json!(2))],
+        xml_str_to_json_pointers(
+            xml,
+            &["/{http:~1~1example.com~1feed}feed/{http:~1~1example.com~1feed}item/1/@{urn:ext}id"],
+            &conf
+        )
+        .unwrap()
+    );
+    assert_eq!(
+        vec![
+            json!({"@{urn:ext}id": 1, "m:a": 1, "a": 2}),
+            json!({"@{urn:ext}id": 2})
+        ],
+        xml_records_to_json(
+            xml,
+            "/{http://example.com/feed}feed/{http://example.com/feed}item",
+            &conf
+        )
+        .unwrap()
+    );
+
+    // namespaces in Clark notation are not reported as ignored
+    let mut diagnostics = Diagnostics::new();
+    xml_str_to_json_with_diagnostics(xml, &conf, &mut diagnostics).unwrap();
+    assert!(diagnostics.is_empty());
+
+    let conf = Config::from_json(r#"{"clark_notation": true}"#).unwrap();
+    assert_eq!(
+        json!({"{urn:a}a": {"@{urn:b}b": 1}}),
+        xml_str_to_json(r#"<a xmlns="urn:a" xmlns:b="urn:b" b:b="1"/>"#, &conf).unwrap()
+    );
+}
+
+#[cfg(feature = "json_types")]
+#[test]
+fn test_clark_notation_overrides() {
+    let conf = Config::new_with_defaults()
+        .with_clark_notation()
+        .add_json_type_override(
+            "/{http://example.com/o}order/@{urn:x}id",
+            JsonArray::Infer(JsonType::AlwaysString),
+        );
+    let xml = r#"<order xmlns="http://example.com/o" xmlns:x="urn:x" x:id="1"/>"#;
+    assert_eq!(
+        json!({"{http://example.com/o}order": {"@{urn:x}id": "1"}}),
+        xml_str_to_json(xml, &conf).unwrap()
+    );
+}
+
+#[test]
+fn test_default_namespace() {
+    let xml = r#"<order xmlns:p="urn:v2"><item xmlns="urn:v2"><id>1</id></item><item><id>2</id></item><p:item/></order>"#;
+    assert_eq!(
+        json!({"order": {"item": [{"id": 1}, {"id": 2}, {}]}}),
+        xml_str_to_json(xml, &Config::new_with_defaults()).unwrap()
+    );
+
+    let conf = Config::new_with_defaults().with_default_namespace(DefaultNamespace::IncludeUri);
+    let expected = json!({"order": {
+        "{urn:v2}item": {"{urn:v2}id": 1},
+        "item": [{"id": 2}, {}]
+    }});
+    assert_eq!(expected, xml_str_to_json(xml, &conf).unwrap());
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    assert_eq!(
+        expected,
+        serde_json::to_value(xml_document_to_borrowed_json(&doc, &conf).unwrap()).unwrap()
+    );
+    // only the prefixed element is reported
+    let mut diagnostics = Diagnostics::new();
+    xml_str_to_json_with_diagnostics(xml, &conf, &mut diagnostics).unwrap();
+    assert_eq!(
+        vec![DiagnosticKind::IgnoredNamespace("urn:v2".to_owned())],
+        diagnostics
+            .iter()
+            .map(|d| d.kind.clone())
+            .collect::<Vec<_>>()
+    );
+    // a prefix from `Config.namespace_prefixes` wins
+    let conf = conf.with_namespace_prefix("urn:v2", "v");
+    assert_eq!(
+        json!({"order": {"v:item": [{"v:id": 1}, {}], "item": {"id": 2}}}),
+        xml_str_to_json(xml, &conf).unwrap()
+    );
+
+    let conf = Config::from_json(r#"{"default_namespace": "error"}"#).unwrap();
+    assert!(matches!(
+        xml_str_to_json(xml, &conf),
+        Err(Error::UnexpectedXml(message)) if message == "element /order/item is in the default namespace urn:v2"
+    ));
+    assert!(xml_str_to_json(r#"<a xmlns:p="urn:p"><p:b/><c xmlns=""/></a>"#, &conf).is_ok());
+}
+
+#[test]
+fn test_xml_base_resolve() {
+    // the examples of RFC 3986 section 5.4
+    let base = "http://a/b/c/d;p?q";
+    for (reference, expected) in [
+        ("g:h", "g:h"),
+        ("g", "http://a/b/c/g"),
+        ("./g", "http://a/b/c/g"),
+        ("g/", "http://a/b/c/g/"),
+        ("/g", "http://a/g"),
+        ("//g", "http://g"),
+        ("?y", "http://a/b/c/d;p?y"),
+        ("g?y", "http://a/b/c/g?y"),
+        ("#s", "http://a/b/c/d;p?q#s"),
+        ("g#s", "http://a/b/c/g#s"),
+        ("", "http://a/b/c/d;p?q"),
+        (".", "http://a/b/c/"),
+        ("..", "http://a/b/"),
+        ("../g", "http://a/b/g"),
+        ("../..", "http://a/"),
+        ("../../../g", "http://a/g"),
+        ("/./g", "http://a/g"),
+        ("g.", "http://a/b/c/g."),
+        ("..g", "http://a/b/c/..g"),
+        ("./../g", "http://a/b/g"),
+        ("g/./h", "http://a/b/c/g/h"),
+        ("g/../h", "http://a/b/c/h"),
+        ("g;x=1/../y", "http://a/b/c/y"),
+    ] {
+        assert_eq!(
+            expected,
+            xml_base::resolve(base, reference),
+            "{}",
+            reference
+        );
+    }
+    assert_eq!(
+        "docs/img/a.png",
+        xml_base::resolve("docs/ch1/", "../img/a.png")
+    );
+}
+
+#[test]
+fn test_xml_base() {
+    let xml = r#"<doc xml:base="http://example.com/docs/">
+        <part xml:base="part1/"><link href="a.html#top"/><link href="/b.html"/></part>
+        <link href="c.html" other="c.html"/>
+        <part xml:base="../other/"><link href=" d.html " xml:base="sub/"/></part>
+    </doc>"#;
+    let conf = Config::new_with_defaults()
+        .add_xml_base_path("/doc/part/link/@href")
+        .add_xml_base_path("/doc/link/@href");
+    let expected = json!({"doc": {
+        "@base": "http://example.com/docs/",
+        "part": [
+            {"@base": "part1/", "link": [
+                {"@href": "http://example.com/docs/part1/a.html#top"},
+                {"@href": "http://example.com/b.html"}
+            ]},
+            {"@base": "../other/", "link": {"@href": "http://example.com/other/sub/d.html", "@base": "sub/"}}
+        ],
+        "link": {"@href": "http://example.com/docs/c.html", "@other": "c.html"}
+    }});
+    assert_eq!(expected, xml_str_to_json(xml, &conf).unwrap());
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    assert_eq!(
+        expected,
+        serde_json::to_value(xml_document_to_borrowed_json(&doc, &conf).unwrap()).unwrap()
+    );
+
+    // relative bases are resolved against the URI of the document, and values without a base are kept
+    let xml = r#"<a><b xml:base="sub/" href="x.xml"/><b href="y.xml"/></a>"#;
+    let conf = Config::from_json(r#"{"xml_base_paths": ["/a/b/@href"]}"#).unwrap();
+    assert_eq!(
+        json!({"a": {"b": [{"@base": "sub/", "@href": "sub/x.xml"}, {"@href": "y.xml"}]}}),
+        xml_str_to_json(xml, &conf).unwrap()
+    );
+    let conf = conf.with_base_uri("file:///data/a.xml");
+    assert_eq!(
+        json!({"a": {"b": [
+            {"@base": "sub/", "@href": "file:///data/sub/x.xml"},
+            {"@href": "file:///data/y.xml"}
+        ]}}),
+        xml_str_to_json(xml, &conf).unwrap()
+    );
+}
+
+#[test]
+fn test_id_index() {
+    let xml = r#"<doc xml:id="root">
+        <section xml:id="s1"><title>One</title><p xml:id="p1">a</p><p>b</p></section>
+        <note xml:id="n1"/>
+        <section xml:id=" s2 "><empty/><p xml:id="p2">c</p></section>
+        <text>first<hidden xml:id="h1"/></text>
+        <x:item xmlns:x="urn:x" xml:id="a/b~c"/>
+        <dup xml:id="s1"/>
+    </doc>"#;
+    let (json, index) = xml_str_to_json_with_id_index(xml, &Config::new_with_defaults()).unwrap();
+    assert_eq!(
+        json,
+        xml_str_to_json(xml, &Config::new_with_defaults()).unwrap()
+    );
+    let expected: std::collections::BTreeMap<String, String> = [
+        ("root", "/doc"),
+        ("s1", "/doc/section/0"),
+        ("p1", "/doc/section/0/p/0"),
+        ("n1", "/doc/note"),
+        ("s2", "/doc/section/1"),
+        ("p2", "/doc/section/1/p"),
+        ("a/b~c", "/doc/item"),
+    ]
+    .into_iter()
+    .map(|(id, pointer)| (id.to_owned(), pointer.to_owned()))
+    .collect();
+    assert_eq!(expected, index);
+    assert_eq!(
+        json!("a"),
+        json.pointer("/doc/section/0/p/0/#text").cloned().unwrap()
+    );
+
+    // ignored elements are not counted, and attributes with the same key come first
+    let xml = r#"<a b="0"><b/><b xml:id="x"><c/></b><b xml:id="y">2</b></a>"#;
+    let conf = Config::new_with_custom_values(true, "", "text", NullValue::Ignore);
+    let (json, index) = xml_str_to_json_with_id_index(xml, &conf).unwrap();
+    assert_eq!(
+        json!({"a": {"b": [0, {"id": "x"}, {"id": "y", "text": 2}]}}),
+        json
+    );
+    assert_eq!("/a/b/1", index["x"]);
+    assert_eq!("/a/b/2", index["y"]);
+}
+
+#[test]
+fn test_inner_text_paths() {
+    let xml = r#"<feed><item id="1"><title>One</title><description class="html">
+        A <b>bold</b> <![CDATA[<claim>]]><br/> and &amp; more. </description></item>
+        <item id="2"><description>007</description></item><item><description/></item></feed>"#;
+    let conf = Config::new_with_defaults().add_inner_text_path("/feed/item/description");
+    let expected = json!({"feed": {"item": [
+        {"@id": 1, "title": "One", "description": "A bold <claim> and & more."},
+        {"@id": 2, "description": "007"},
+        {"description": ""}
+    ]}});
+    let mut diagnostics = Diagnostics::new();
+    let json = xml_str_to_json_with_diagnostics(xml, &conf, &mut diagnostics).unwrap();
+    assert_eq!(expected, json);
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    let borrowed = xml_document_to_borrowed_json(&doc, &conf).unwrap();
+    assert_eq!(expected, borrowed.into_owned());
+    assert_eq!(
+        vec![Some(json!("A bold <claim> and & more.")), None],
+        xml_str_to_json_pointers(
+            xml,
+            &["/feed/item/0/description", "/feed/item/0/description/b"],
+            &conf
+        )
+        .unwrap()
+    );
+
+    // the root element can be converted into its text, too
+    let conf = Config::from_json(r#"{"inner_text_paths": ["/p"]}"#).unwrap();
+    assert_eq!(
+        json!({"p": "Some bold text"}),
+        xml_str_to_json("<p>Some <b>bold</b> text</p>", &conf).unwrap()
+    );
+}
+
+#[test]
+fn test_text_prop_names() {
+    let xml = r#"<station id="7"><measurement unit="C">21.5</measurement><note lang="en">ok</note></station>"#;
+    let conf = Config::new_with_defaults().add_text_prop_name("/station/measurement", "value");
+    let expected = json!({"station": {
+        "@id": 7,
+        "measurement": {"@unit": "C", "value": 21.5},
+        "note": {"@lang": "en", "#text": "ok"}
+    }});
+    assert_eq!(expected, xml_str_to_json(xml, &conf).unwrap());
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    assert_eq!(
+        expected,
+        xml_document_to_borrowed_json(&doc, &conf)
+            .unwrap()
+            .into_owned()
+    );
+    let mut builder = JsonBuilder::new(&conf);
+    xml_str_to_visitor(xml, &conf, &mut builder).unwrap();
+    assert_eq!(expected, builder.into_value());
+
+    // the writer reads the text from the same names
+    assert_eq!(
+        xml,
+        json_to_xml_string(&expected, &conf, &XmlWriterConfig::new_with_defaults()).unwrap()
+    );
+
+    let conf = Config::from_json(r#"{"text_prop_names": {"/p": "text"}}"#).unwrap();
+    assert_eq!(
+        json!({"p": {"@id": 1, "text": "x"}}),
+        xml_str_to_json(r#"<p id="1">x</p>"#, &conf).unwrap()
+    );
+    assert_eq!(
+        vec![ConfigWarning {
+            option: "text_prop_names",
+            path: "/p/@id".to_owned(),
+            kind: ConfigWarningKind::NotAnElement,
+        }],
+        Config::new_with_defaults()
+            .add_text_prop_name("/p/@id", "text")
+            .validate()
+    );
+}
+
+#[test]
+fn test_text_attributes() {
+    let xml = r#"<item id="1" src="erp"><price currency="EUR" source="erp">5</price><title xml:lang="en" src="x">Tea</title><tags src="x"/></item>"#;
+    let conf = Config::new_with_defaults().with_text_attributes(TextAttributes::Drop);
+    let expected = json!({"item": {
+        "@id": 1, "@src": "erp", "price": 5, "title": "Tea", "tags": {"@src": "x"}
+    }});
+    assert_eq!(expected, xml_str_to_json(xml, &conf).unwrap());
+
+    // only the listed attributes are kept, elements without any of them become their text
+    let conf = Config::new_with_defaults().with_text_attributes(TextAttributes::KeepOnly(
+        ["lang".to_owned()].into_iter().collect(),
+    ));
+    let expected = json!({"item": {
+        "@id": 1,
+        "@src": "erp",
+        "price": 5,
+        "title": {"@lang": "en", "#text": "Tea"},
+        "tags": {"@src": "x"}
+    }});
+    assert_eq!(expected, xml_str_to_json(xml, &conf).unwrap());
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    assert_eq!(
+        expected,
+        xml_document_to_borrowed_json(&doc, &conf)
+            .unwrap()
+            .into_owned()
+    );
+    let mut builder = JsonBuilder::new(&conf);
+    xml_str_to_visitor(xml, &conf, &mut builder).unwrap();
+    assert_eq!(expected, builder.into_value());
+
+    let conf = Config::from_json(r#"{"text_attributes": "drop"}"#).unwrap();
+    assert_eq!(
+        json!({"p": "x"}),
+        xml_str_to_json(r#"<p n="abc">x</p>"#, &conf).unwrap()
+    );
+    assert_eq!(
+        TextAttributes::KeepOnly(["lang".to_owned()].into_iter().collect()),
+        Config::from_json(r#"{"text_attributes": ["lang"]}"#)
+            .unwrap()
+            .text_attributes
+    );
+}
+
+#[test]
+fn test_collapse_single_attributes() {
+    let xml = r#"<config><flag value="3"/><name value="x"><!-- c --></name><mode name="fast" level="2"/><opt value="1"><x/></opt><empty/></config>"#;
+    let conf = Config::new_with_defaults().with_collapse_single_attributes();
+    let expected = json!({"config": {
+        "flag": 3,
+        "name": "x",
+        "mode": {"@name": "fast", "@level": 2},
+        "opt": {"@value": 1, "x": {}},
+        "empty": {}
+    }});
+    assert_eq!(expected, xml_str_to_json(xml, &conf).unwrap());
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    assert_eq!(
+        expected,
+        xml_document_to_borrowed_json(&doc, &conf)
+            .unwrap()
+            .into_owned()
+    );
+    let mut builder = JsonBuilder::new(&conf);
+    xml_str_to_visitor(xml, &conf, &mut builder).unwrap();
+    assert_eq!(expected, builder.into_value());
+
+    // skipped child elements don't count, and repeated elements become arrays of values
+    let conf = Config::from_json(r#"{"collapse_single_attributes": true}"#)
+        .unwrap()
+        .with_node_filter(|node| node.tag_name().name() != "x");
+    assert_eq!(
+        json!({"a": {"opt": [1, 2]}}),
+        xml_str_to_json(r#"<a><opt v="1"><x/></opt><opt v="2"/></a>"#, &conf).unwrap()
+    );
+}
+
+#[test]
+fn test_unit_paths() {
+    let xml = r#"<sensor weight="10.5kg" size="2em"><delay>120ms</delay><delay> 1.5 s </delay><delay>3</delay><delay>soon</delay><delay>2e3us</delay></sensor>"#;
+    let conf = Config::new_with_defaults()
+        .add_unit_path("/sensor/@weight", UnitTable::new())
+        .add_unit_path("/sensor/@size", UnitTable::new())
+        .add_unit_path("/sensor/delay", UnitTable::durations());
+    let expected = json!({"sensor": {
+        "@weight": {"value": 10.5, "unit": "kg"},
+        "@size": {"value": 2, "unit": "em"},
+        "delay": [
+            {"value": 120, "unit": "ms"},
+            {"value": 1500, "unit": "ms"},
+            {"value": 3, "unit": null},
+            "soon",
+            {"value": 2, "unit": "ms"}
+        ]
+    }});
+    assert_eq!(expected, xml_str_to_json(xml, &conf).unwrap());
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    assert_eq!(
+        expected,
+        xml_document_to_borrowed_json(&doc, &conf)
+            .unwrap()
+            .into_owned()
+    );
+
+    // texts with more than a unit are converted as usual
+    assert_eq!(
+        json!({"sensor": {"@weight": "10 kg net"}}),
+        xml_str_to_json(r#"<sensor weight="10 kg net"/>"#, &conf).unwrap()
+    );
+
+    let conf = Config::from_json(
+        r#"{"unit_paths": {"/a/p": {"g": ["kg", 0.001], "kg": ["kg", 1]}, "/a/t": "durations"}}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        json!({"a": {"p": [{"value": 0.25, "unit": "kg"}, {"value": -4, "unit": "kg"}, {"value": 1, "unit": "lb"}], "t": {"value": 60000, "unit": "ms"}}}),
+        xml_str_to_json("<a><p>250g</p><p>-4kg</p><p>1lb</p><t>1min</t></a>", &conf).unwrap()
+    );
+
+    // factors don't leave binary rounding errors in decimal fractions
+    assert_eq!(
+        json!({"a": {"p": [{"value": 0.0003, "unit": "kg"}, {"value": 1.234, "unit": "kg"}], "t": [
+            {"value": 261000, "unit": "ms"},
+            {"value": 1005, "unit": "ms"},
+            {"value": 0.0000123, "unit": "ms"},
+            {"value": 1.1, "unit": "ms"}
+        ]}}),
+        xml_str_to_json(
+            "<a><p>0.3g</p><p>1234g</p><t>4.35min</t><t>1.005s</t><t>12.3ns</t><t>1100us</t></a>",
+            &conf
+        )
+        .unwrap()
+    );
+    assert!(matches!(
+        Config::from_json(r#"{"unit_paths": {"/p": {"g": "kg"}}}"#),
+        Err(Error::InvalidConfig(_))
+    ));
+}
+
+#[test]
+fn test_node_filter() {
+    let xml = r#"<shop>
+        <item id="1"><price>5</price></item>
+        <item id="2" hidden="true"><price>7</price></item>
+        <internal><note>x</note></internal>
+    </shop>"#;
+    let conf = Config::new_with_defaults().with_node_filter(|el| {
+        el.attribute("hidden") != Some("true") && el.tag_name().name() != "internal"
+    });
+    // the remaining item isn't an array anymore
+    let expected = json!({"shop": {"item": {"@id": 1, "price": 5}}});
+    assert_eq!(expected, xml_str_to_json(xml, &conf).unwrap());
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    assert_eq!(
+        expected,
+        xml_document_to_borrowed_json(&doc, &conf)
+            .unwrap()
+            .into_owned()
+    );
+
+    // the filter applies to the document wide array inference, too
+    let mut conf = conf;
+    conf.array_inference = ArrayInference::DocumentWide;
+    assert_eq!(expected, xml_str_to_json(xml, &conf).unwrap());
+
+    // by depth, with the ancestors of the node
+    let conf = Config::new_with_defaults().with_node_filter(|el| el.ancestors().count() < 4);
+    assert_eq!(
+        json!({"a": {"b": {}}}),
+        xml_str_to_json("<a><b><c>1</c></b></a>", &conf).unwrap()
+    );
+    let pointers = xml_str_to_json_pointers("<a><b>1</b><b>2</b></a>", &["/a/b/0"], &conf).unwrap();
+    assert_eq!(Some(json!(1)), pointers[0]);
+}
+
+#[test]
+fn test_xml_str_to_visitor() {
+    let docs = [
+        r#"<a id="1"><b>x</b><b>y</b><c n="2">text</c><d/><e><f>007</f></e></a>"#,
+        "<a><b><c>1</c></b><b><c>2</c><c>3</c></b>mixed</a>",
+        "<a>text<b>dropped</b></a>",
+        "<a/>",
+    ];
+    let mut document_wide = Config::new_with_defaults();
+    document_wide.array_inference = ArrayInference::DocumentWide;
+    let configs = [
+        Config::new_with_defaults(),
+        Config::new_with_custom_values(true, "", "text", NullValue::Null),
+        Config::new_with_custom_values(false, "_", "#text", NullValue::Ignore)
+            .add_inner_text_path("/a/e"),
+        document_wide,
+    ];
+    for config in &configs {
+        for xml in docs {
+            let mut builder = JsonBuilder::new(config);
+            xml_str_to_visitor(xml, config, &mut builder).unwrap();
+            assert_eq!(
+                xml_str_to_json(xml, config).unwrap(),
+                builder.into_value(),
+                "{}",
+                xml
+            );
+        }
+    }
+
+    // the events in document order
+    #[derive(Default)]
+    struct Events(Vec<String>);
+    impl XmlVisitor for Events {
+        fn on_element_start(&mut self, key: &str, _path: &str, always_array: bool) {
+            self.0.push(format!("start {} {}", key, always_array));
+        }
+        fn on_attribute(&mut self, key: &str, path: &str, value: Value) {
+            self.0.push(format!("attribute {} {} {}", key, path, value));
+        }
+        fn on_text(&mut self, path: &str, value: Value) {
+            self.0.push(format!("text {} {}", path, value));
+        }
+        fn on_element_end(&mut self, key: &str, path: &str) {
+            self.0.push(format!("end {} {}", key, path));
+        }
+    }
+    let mut events = Events::default();
+    let conf = Config::new_with_defaults().with_node_filter(|el| el.tag_name().name() != "skip");
+    xml_str_to_visitor(r#"<a><b id="7">x</b><skip/></a>"#, &conf, &mut events).unwrap();
+    assert_eq!(
+        events.0,
+        [
+            "start a false",
+            "start b false",
+            "attribute @id /a/b/@id 7",
+            "text /a/b \"x\"",
+            "end b /a/b",
+            "end a /a",
+        ]
+    );
+}
+
+#[test]
+fn test_match_limit() {
+    use std::ops::ControlFlow;
+
+    // the document is broken after the second item, which is never read
+    let xml = "<feed><item><a>1</a></item><item><a>2</a></item><item><a>";
+    let conf = Config::new_with_defaults().with_match_limit(2);
+
+    let records: Vec<Value> = XmlRecords::new(xml.as_bytes(), "/feed/item", &conf)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(vec![json!({"a": 1}), json!({"a": 2})], records);
+    assert_eq!(
+        records,
+        xml_records_to_json(xml, "/feed/item", &conf).unwrap()
+    );
+    assert!(xml_records_to_json(xml, "/feed/item", &Config::new_with_defaults()).is_err());
+
+    let mut visited = 0;
+    let count = for_each_match(xml.as_bytes(), "/feed/item/a", &conf, |_, _| {
+        visited += 1;
+        ControlFlow::Continue(())
+    })
+    .unwrap();
+    assert_eq!((2, 2), (count, visited));
+
+    let xml = "<feed><item><a>1</a></item><item><a>2</a></item><item><a>3</a></item></feed>";
+    let conf = Config::from_json(r#"{"match_limit": 1}"#).unwrap();
+    assert_eq!(vec![json!(1)], xml_query(xml, "$..a", &conf).unwrap());
+    assert_eq!(1, XmlJsonIter::new(xml, &conf).count());
+}
+
+#[test]
+fn test_array_limits() {
+    let xml = "<feed><item><n>1</n></item><item><n>2</n></item><item><n>3</n></item><item><n>4</n></item></feed>";
+
+    let conf =
+        Config::new_with_defaults().add_array_limit("/feed/item", 2, TruncationMarker::Count);
+    let expected = json!({"feed": {"item": [{"n": 1}, {"n": 2}, 2]}});
+    assert_eq!(expected, xml_str_to_json(xml, &conf).unwrap());
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    assert_eq!(
+        expected,
+        xml_document_to_borrowed_json(&doc, &conf)
+            .unwrap()
+            .into_owned()
+    );
+    #[cfg(feature = "bumpalo")]
+    assert_eq!(
+        expected,
+        xml_str_to_arena_json(xml, &conf, &bumpalo::Bump::new())
+            .unwrap()
+            .to_value()
+    );
+    assert_eq!(
+        vec![json!(2)],
+        xml_query(xml, "$.feed.item[2]", &conf).unwrap()
+    );
+
+    // an array with a single kept item stays an array
+    let conf = Config::new_with_defaults().add_array_limit(
+        "/feed/item",
+        1,
+        TruncationMarker::Text("...".to_owned()),
+    );
+    assert_eq!(
+        json!({"feed": {"item": [{"n": 1}, "..."]}}),
+        xml_str_to_json(xml, &conf).unwrap()
+    );
+    let conf = Config::new_with_defaults().add_array_limit("/feed/item", 1, TruncationMarker::None);
+    assert_eq!(
+        json!({"feed": {"item": [{"n": 1}]}}),
+        xml_str_to_json(xml, &conf).unwrap()
+    );
+
+    // arrays below the limit are unchanged
+    let conf = Config::from_json(
+        r#"{"array_limits": {"/feed/item": {"max_items": 5, "marker": {"text": "..."}}}}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        xml_str_to_json(xml, &Config::new_with_defaults()).unwrap(),
+        xml_str_to_json(xml, &conf).unwrap()
+    );
+}
+
+#[test]
+fn test_max_string_len() {
+    let xml = r#"<a note="a long attribute"><b>abcdefghij</b><c>abcdefghijk</c><d>Grüße aus Köln</d><n>123456789012</n></a>"#;
+    let conf = Config::new_with_defaults().with_max_string_len(10, "...");
+    let expected = json!({"a": {
+        "@note": "a long ...",
+        "b": "abcdefghij",
+        "c": "abcdefg...",
+        "d": "Grüße a...",
+        "n": 123456789012u64
+    }});
+    assert_eq!(expected, xml_str_to_json(xml, &conf).unwrap());
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    assert_eq!(
+        expected,
+        xml_document_to_borrowed_json(&doc, &conf)
+            .unwrap()
+            .into_owned()
+    );
+
+    // the default ellipsis is a single character, and texts of subtrees are shortened, too
+    let conf = Config::from_json(r#"{"max_string_len": 5, "inner_text_paths": ["/p"]}"#).unwrap();
+    assert_eq!(
+        json!({"p": "Some…"}),
+        xml_str_to_json("<p>Some <b>bold</b> text</p>", &conf).unwrap()
+    );
+}
+
+#[cfg(feature = "pseudonymize")]
+#[test]
+fn test_pseudonymized_paths() {
+    let xml = r#"<orders>
+        <order customer="c-17"><email>ann@example.com</email><total>5</total></order>
+        <order customer="c-17"><email> bob@example.com </email><total>7</total></order>
+    </orders>"#;
+    let conf = Config::new_with_defaults()
+        .with_pseudonymization_key(b"key")
+        .add_pseudonymized_path("/orders/order/@customer")
+        .add_pseudonymized_path("/orders/order/email");
+    let json = xml_str_to_json(xml, &conf).unwrap();
+    let orders = json["orders"]["order"].as_array().unwrap();
+    assert_eq!(orders[0]["@customer"], orders[1]["@customer"]);
+    assert_ne!(orders[0]["email"], orders[1]["email"]);
+    assert_eq!(json!(5), orders[0]["total"]);
+    // HMAC-SHA256 of `bob@example.com` with the key `key`, without the surrounding whitespace
+    let mut mac = <hmac::Hmac<sha2::Sha256> as hmac::Mac>::new_from_slice(b"key").unwrap();
+    hmac::Mac::update(&mut mac, b"bob@example.com");
+    let expected: String = hmac::Mac::finalize(mac)
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    assert_eq!(json!(expected), orders[1]["email"]);
+    assert!(!json.to_string().contains("example.com"));
+
+    // the raw text is never added, and the borrowed output is the same
+    let mut raw = Config::new_with_defaults()
+        .with_pseudonymization_key(b"key")
+        .add_pseudonymized_path("/orders/order/@customer")
+        .add_pseudonymized_path("/orders/order/email");
+    raw.raw_values = RawValues::Always;
+    assert!(!xml_str_to_json(xml, &raw)
+        .unwrap()
+        .to_string()
+        .contains("example.com"));
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    assert_eq!(
+        json,
+        xml_document_to_borrowed_json(&doc, &conf)
+            .unwrap()
+            .into_owned()
+    );
+
+    // a different key gives different values, no key fails
+    let other = Config::new_with_defaults()
+        .with_pseudonymization_key(b"other")
+        .add_pseudonymized_path("/orders/order/@customer");
+    assert_ne!(
+        orders[0]["@customer"],
+        xml_str_to_json(xml, &other).unwrap()["orders"]["order"][0]["@customer"]
+    );
+    let no_key = Config::from_json(r#"{"pseudonymized_paths": ["/orders/order/email"]}"#).unwrap();
+    assert!(matches!(
+        xml_str_to_json(xml, &no_key),
+        Err(Error::InvalidConfig(_))
+    ));
+    assert_eq!(
+        "Some(PseudonymizationKey(..))",
+        format!("{:?}", conf.pseudonymization_key)
+    );
+    // the text of a subtree is pseudonymized as a whole
+    let inner = Config::new_with_defaults()
+        .with_pseudonymization_key(b"key")
+        .add_pseudonymized_path("/p")
+        .add_inner_text_path("/p");
+    let inner_json = xml_str_to_json("<p>Ann <b>Smith</b></p>", &inner).unwrap();
+    assert_eq!(
+        xml_str_to_json("<p>Ann Smith</p>", &inner).unwrap(),
+        inner_json
+    );
+    assert!(!inner_json.to_string().contains("Smith"));
+}
+
+#[test]
+fn test_single_key_objects() {
+    let xml = r#"<msg>
+        <header><routing><sender><id>42</id></sender></routing></header>
+        <orders><order><n>1</n></order><order><n>2</n></order></orders>
+        <note>Hi</note>
+    </msg>"#;
+
+    let conf =
+        Config::new_with_defaults().with_single_key_objects(SingleKeyObjects::Join(".".to_owned()));
+    assert_eq!(
+        json!({"msg": {
+            "header.routing.sender.id": 42,
+            "orders.order": [{"n": 1}, {"n": 2}],
+            "note": "Hi"
+        }}),
+        xml_str_to_json(xml, &conf).unwrap()
+    );
+
+    // the items of arrays keep their objects, but their properties are collapsed
+    let conf =
+        Config::new_with_defaults().with_single_key_objects(SingleKeyObjects::Join("/".to_owned()));
+    assert_eq!(
+        json!({"a/b": [{"c/d": 1}, {"c/d": 2}]}),
+        xml_str_to_json("<a><b><c><d>1</d></c></b><b><c><d>2</d></c></b></a>", &conf).unwrap()
+    );
+
+    let conf = Config::from_json(r#"{"single_key_objects": "hoist"}"#).unwrap();
+    assert_eq!(
+        json!({"msg": {
+            "header": {"id": 42},
+            "orders": [{"n": 1}, {"n": 2}],
+            "note": "Hi"
+        }}),
+        xml_str_to_json(xml, &conf).unwrap()
+    );
+
+    // the root is kept, even if it only wraps another object
+    assert_eq!(
+        json!({"a": {"c": 1}}),
+        xml_str_to_json("<a><b><c>1</c></b></a>", &conf).unwrap()
+    );
+
+    let conf = Config::from_json(r#"{"single_key_objects": {"join": "_"}}"#).unwrap();
+    assert_eq!(
+        SingleKeyObjects::Join("_".to_owned()),
+        conf.single_key_objects
+    );
+    assert!(Config::from_json(r#"{"single_key_objects": "join"}"#).is_err());
+}
+
+#[test]
+fn test_sort_keys() {
+    use std::cmp::Ordering;
+
+    let xml = r#"<item id="7" Name="x"><name>a</name><b lang="en">x</b><_id>2</_id></item>"#;
+    let sorted = Config::new_with_defaults().with_sorted_keys();
+    assert_eq!(
+        r##"{"item":{"@Name":"x","_id":2,"b":{"#text":"x","@lang":"en"},"@id":7,"name":"a"}}"##,
+        xml_str_to_json_string(xml, &sorted).unwrap()
+    );
+    // without the flag, the order of serde_json::Map is kept
+    let unsorted = Config::new_with_defaults();
+    assert_eq!(
+        serde_json::to_string(&xml_str_to_json(xml, &unsorted).unwrap()).unwrap(),
+        xml_str_to_json_string(xml, &unsorted).unwrap()
+    );
+
+    // attributes come first for the same name, and an empty prefix compares whole keys
+    assert_eq!(Ordering::Less, compare_keys("@id", "id", "@"));
+    assert_eq!(Ordering::Greater, compare_keys("id", "@id", "@"));
+    assert_eq!(Ordering::Less, compare_keys("@b", "a", ""));
+    assert_eq!(Ordering::Less, compare_keys("_id", "id", "_"));
+    assert_eq!(Ordering::Equal, compare_keys("@id", "@id", "@"));
+
+    // nested arrays and the pretty printer use the same order
+    let json = json!({"a": [{"z": 1, "@y": 2}], "@b": null});
+    assert_eq!(
+        "{\n  \"a\": [\n    {\n      \"@y\": 2,\n      \"z\": 1\n    }\n  ],\n  \"@b\": null\n}",
+        to_string_pretty(&SortedKeys::new(&json, &sorted)).unwrap()
+    );
+    assert!(
+        Config::from_json(r#"{"sort_keys": true}"#)
+            .unwrap()
+            .sort_keys
+    );
+}
+
+#[test]
+fn test_prune_empty() {
+    let xml = r#"<list><item a=""><x/><y><z/></y></item><item>1</item><empty/></list>"#;
+
+    let conf = Config::new_with_defaults().with_prune_empty(PruneEmpty::ALL);
+    assert_eq!(
+        json!({"list": {"item": [1]}}),
+        xml_str_to_json(xml, &conf).unwrap()
+    );
+
+    // objects that become empty are only removed if objects are pruned
+    let mut conf = Config::new_with_defaults().with_prune_empty(PruneEmpty {
+        nulls: true,
+        strings: true,
+        ..PruneEmpty::NONE
+    });
+    conf.empty_element_handling = NullValue::Null;
+    assert_eq!(
+        json!({"list": {"item": [{"y": {}}, 1]}}),
+        xml_str_to_json(xml, &conf).unwrap()
+    );
+
+    // the root element is kept
+    let conf = Config::from_json(r#"{"prune_empty": ["objects"]}"#).unwrap();
+    assert_eq!(
+        json!({"a": {}}),
+        xml_str_to_json("<a><b/></a>", &conf).unwrap()
+    );
+    assert!(Config::from_json(r#"{"prune_empty": ["zeros"]}"#).is_err());
+}
+
+#[test]
+fn test_post_process() {
+    let conf = Config::new_with_defaults()
+        .with_prune_empty(PruneEmpty::ALL)
+        .with_post_process(|mut json| {
+            if let Some(map) = json.as_object_mut() {
+                map.insert("count".to_owned(), json!(map.len()));
+            }
+            json
+        });
+
+    // applied after the other options
+    let xml = "<feed><item><a>1</a><b/></item><item><a>2</a></item></feed>";
+    assert_eq!(
+        json!({"feed": {"item": [{"a": 1}, {"a": 2}]}, "count": 1}),
+        xml_str_to_json(xml, &conf).unwrap()
+    );
+
+    // and to every record of the streaming conversions
+    let records: Vec<Value> = XmlRecords::new(xml.as_bytes(), "/feed/item", &conf)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        vec![
+            json!({"a": 1, "b": {}, "count": 2}),
+            json!({"a": 2, "count": 1})
+        ],
+        records
+    );
+    assert_eq!(
+        records,
+        xml_records_to_json(xml, "/feed/item", &conf).unwrap()
+    );
+}
+
+#[test]
+fn test_idrefs() {
+    let xml = r#"<shop>
+        <product xml:id="p1" name="pen"><part ref="p2"/></product>
+        <product xml:id="p2" name="ink"/>
+        <loop xml:id="l1" next="l2"/><loop xml:id="l2" next="l1"/>
+        <order items="p1 p2 p9" main="p2" id="7"/>
+    </shop>"#;
+    let conf = Config::new_with_defaults()
+        .add_idref_path("/shop/order/@items", IdRef::InlineList)
+        .add_idref_path("/shop/order/@main", IdRef::Pointer)
+        .add_idref_path("/shop/product/part/@ref", IdRef::Inline)
+        .add_idref_path("/shop/loop/@next", IdRef::Inline);
+    let mut diagnostics = Diagnostics::new();
+    let json = xml_str_to_json_with_diagnostics(xml, &conf, &mut diagnostics).unwrap();
+    let ink = json!({"@id": "p2", "@name": "ink"});
+    let pen = json!({"@id": "p1", "@name": "pen", "part": {"@ref": ink}});
+    assert_eq!(pen, json["shop"]["product"][0]);
+    assert_eq!(
+        json!({"@items": [pen, ink, "p9"], "@main": "/shop/product/1", "@id": 7}),
+        json["shop"]["order"]
+    );
+    // a copy never contains itself
+    assert_eq!(
+        json!({"@id": "l2", "@next": {"@id": "l1", "@next": "l2"}}),
+        json["shop"]["loop"][0]["@next"]
+    );
+    assert_eq!(
+        vec![Diagnostic {
+            path: "/shop/order/@items".to_owned(),
+            kind: DiagnosticKind::UnresolvedReference("p9".to_owned())
+        }],
+        diagnostics
+            .iter()
+            .filter(|d| matches!(d.kind, DiagnosticKind::UnresolvedReference(_)))
+            .cloned()
+            .collect::<Vec<_>>()
+    );
+
+    let conf = Config::from_json(
+        r#"{"idref_paths": {"/a/c/@refs": "pointer_list", "/a/c/@ref": "inline"}}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        json!({"a": {"b": [{"@id": "x"}, {"@id": "y"}], "c": {"@refs": ["/a/b/0", "/a/b/1"], "@ref": "z"}}}),
+        xml_str_to_json(
+            r#"<a><b xml:id="x"/><b xml:id="y"/><c refs=" x  y " ref="z"/></a>"#,
+            &conf
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn test_entity_resolver() {
+    let xml = r#"<?xml version="1.0"?>
+<!-- a book -->
+<!DOCTYPE book PUBLIC "-//Example//DTD Book//EN" "http://example.com/book.dtd" [
+    <!ENTITY title "Internal title">
+    <!ENTITY chapter SYSTEM 'chapter.xml'>
+]>
+<book><title>&title;</title>&chapter;<legal>&legal;</legal></book>"#;
+    let conf = Config::new_with_defaults().with_entity_resolver(|public_id, system_id| {
+        match (public_id, system_id) {
+            (Some("-//Example//DTD Book//EN"), _) => Some(
+                r#"<?xml version="1.0" encoding="UTF-8"?><!ENTITY title "External title"><!ENTITY legal SYSTEM "legal.xml">"#
+                    .to_owned(),
+            ),
+            (None, "chapter.xml") => Some(
+                "<?xml version=\"1.0\"?><chapter n=\"1\" by='the \"team\"' it=\"it's\">100% &#60;\"quoted\"&#62; &amp; 'single'<![CDATA[ 'raw']]></chapter>"
+                    .to_owned(),
+            ),
+            (None, "legal.xml") => Some("\u{feff}All rights reserved.".to_owned()),
+            _ => None,
+        }
+    });
+    assert_eq!(
+        json!({"book": {
+            "title": "Internal title",
+            "chapter": {"@n": 1, "@by": "the \"team\"", "@it": "it's", "#text": "100% <\"quoted\"> & 'single' 'raw'"},
+            "legal": "All rights reserved."
+        }}),
+        xml_str_to_json(xml, &conf).unwrap()
+    );
+    let doc_xml = prepare_input(xml, &conf).unwrap();
+    let doc = roxmltree::Document::parse_with_options(
+        &doc_xml,
+        roxmltree::ParsingOptions {
+            allow_dtd: true,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        Some("All rights reserved."),
+        doc.root_element().last_child().and_then(|n| n.text())
+    );
+
+    // single quotes in comments can't be declared next to double quotes
+    let conf = Config::new_with_defaults()
+        .with_entity_resolver(|_, _| Some(r#"<!-- it's --><a b="1"/>"#.to_owned()));
+    assert!(matches!(
+        xml_str_to_json(
+            r#"<!DOCTYPE a [<!ENTITY e SYSTEM "e.xml">]><a>&e;</a>"#,
+            &conf
+        ),
+        Err(Error::ExternalEntity(id)) if id == "e.xml"
+    ));
+
+    // refused entities fail the conversion, and DTDs are rejected without a resolver
+    let conf = Config::new_with_defaults().with_entity_resolver(|_, _| None);
+    assert!(matches!(
+        xml_str_to_json(xml, &conf),
+        Err(Error::ExternalEntity(id)) if id == "http://example.com/book.dtd"
+    ));
+    assert!(matches!(
+        xml_str_to_json(xml, &Config::new_with_defaults()),
+        Err(Error::Xml(roxmltree::Error::DtdDetected))
+    ));
+}
+
+#[test]
+fn test_entity_catalog() {
+    let dir = std::env::temp_dir().join(format!("rxts-catalog-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("dtd")).unwrap();
+    std::fs::write(dir.join("dtd/note.dtd"), r#"<!ENTITY sig "-- the team">"#).unwrap();
+    std::fs::write(
+        dir.join("catalog.xml"),
+        r#"<catalog xmlns="urn:oasis:names:tc:entity:xmlns:xml:catalog">
+            <public publicId="-//Example//DTD Note//EN" uri="dtd/note.dtd"/>
+            <system systemId="http://example.com/remote.dtd" uri="http://mirror.example.com/remote.dtd"/>
+        </catalog>"#,
+    )
+    .unwrap();
+    let catalog = EntityCatalog::from_file(dir.join("catalog.xml")).unwrap();
+    let conf = Config::new_with_defaults().with_entity_catalog(catalog);
+    assert_eq!(
+        json!({"note": "-- the team"}),
+        xml_str_to_json(
+            r#"<!DOCTYPE note PUBLIC "-//Example//DTD Note//EN" "http://example.com/note.dtd"><note>&sig;</note>"#,
+            &conf
+        )
+        .unwrap()
+    );
+    // remote URIs in the catalog are never fetched
+    assert!(matches!(
+        xml_str_to_json(
+            r#"<!DOCTYPE note SYSTEM "http://example.com/remote.dtd"><note/>"#,
+            &conf
+        ),
+        Err(Error::ExternalEntity(_))
+    ));
+
+    // catalogs are parsed with the same depth limit as documents
+    std::fs::write(
+        dir.join("deep.xml"),
+        "<catalog>".repeat(10_000) + &"</catalog>".repeat(10_000),
+    )
+    .unwrap();
+    assert!(matches!(
+        EntityCatalog::from_file(dir.join("deep.xml")),
+        Err(Error::DepthLimitExceeded(_))
+    ));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(feature = "encoding")]
+#[test]
+fn test_decode_xml() {
+    let conf = Config::new_with_defaults();
+    assert_eq!(
+        json!({"a": {"@name": "Müller", "#text": "café"}}),
+        xml_bytes_to_json(
+            b"<?xml version='1.0' encoding = 'ISO-8859-1' ?><a name=\"M\xfcller\">caf\xe9</a>",
+            &conf
+        )
+        .unwrap()
+    );
+    // Shift_JIS
+    assert_eq!(
+        json!({"a": "日本"}),
+        xml_bytes_to_json(
+            b"<?xml version=\"1.0\" encoding=\"Shift_JIS\"?><a>\x93\xfa\x96\x7b</a>",
+            &conf
+        )
+        .unwrap()
+    );
+    // UTF-16 with and without a byte order mark
+    let utf16: Vec<u8> = "<?xml version=\"1.0\" encoding=\"UTF-16\"?><a>ü</a>"
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    assert_eq!(json!({"a": "ü"}), xml_bytes_to_json(&utf16, &conf).unwrap());
+    let with_bom = [&[0xff, 0xfe][..], &utf16].concat();
+    assert_eq!(
+        json!({"a": "ü"}),
+        xml_bytes_to_json(&with_bom, &conf).unwrap()
+    );
+    // UTF-8 is borrowed without the byte order mark
+    assert!(matches!(
+        decode_xml(b"\xef\xbb\xbf<a/>").unwrap(),
+        std::borrow::Cow::Borrowed("<a/>")
+    ));
+
+    assert!(matches!(
+        decode_xml(b"<?xml version=\"1.0\" encoding=\"EBCDIC-42\"?><a/>"),
+        Err(Error::Encoding(e)) if e == "unsupported encoding EBCDIC-42"
+    ));
+    assert!(matches!(
+        decode_xml(b"<a>\xff</a>"),
+        Err(Error::Encoding(_))
+    ));
+    assert!(matches!(
+        decode_xml(b"<?xml version=\"1.0\" encoding=\"Shift_JIS\"?><a>\x82</a>"),
+        Err(Error::Encoding(e)) if e == "invalid Shift_JIS input"
+    ));
+}
+
+#[test]
+fn test_skip_leading_junk() {
+    let conf = Config::new_with_defaults().with_skip_leading_junk();
+    for xml in [
+        "\u{feff}<a>1</a>",
+        "\n\n<?xml version=\"1.0\"?><a>1</a>",
+        "\u{feff}\u{feff}<?xml version=\"1.0\"?><a>1</a>",
+        "HTTP/1.1 200 OK\r\n\r\n\u{feff}<?xml version='1.0'?>\n<a>1</a>",
+        "tail of the previous feed</b><?xml version=\"1.0\"?><a>1</a>",
+        "garbage 1 < 2 <!-- c --><a>1</a>",
+    ] {
+        assert_eq!(
+            json!({"a": 1}),
+            xml_str_to_json(xml, &conf).unwrap(),
+            "{:?}",
+            xml
+        );
+    }
+    assert!(xml_str_to_json(
+        "\n<?xml version=\"1.0\"?><a>1</a>",
+        &Config::new_with_defaults()
+    )
+    .is_err());
+
+    // only junk other than byte order marks and whitespace is reported
+    let mut diagnostics = Diagnostics::new();
+    xml_str_to_json_with_diagnostics(
+        "\u{feff}\n <?xml version=\"1.0\"?><a/>",
+        &conf,
+        &mut diagnostics,
+    )
+    .unwrap();
+    assert!(diagnostics.is_empty());
+    xml_str_to_json_with_diagnostics("HTTP/1.1 200 OK\n\u{feff}<a/>", &conf, &mut diagnostics)
+        .unwrap();
+    assert_eq!(
+        vec![DiagnosticKind::SkippedPrefix("HTTP/1.1 200 OK".to_owned())],
+        diagnostics
+            .iter()
+            .map(|d| d.kind.clone())
+            .collect::<Vec<_>>()
+    );
+    let mut strict = Config::from_json(r#"{"skip_leading_junk": true}"#).unwrap();
+    strict.strict = true;
+    assert!(matches!(
+        xml_str_to_json_with_diagnostics("junk<a/>", &strict, &mut Diagnostics::new()),
+        Err(Error::Lossy(_))
+    ));
+}
+
+#[test]
+fn test_utf8_policy() {
+    let xml = b"<feed><r>caf\xe9</r><r>ok</r><r>\xff\xfe</r></feed>";
+    let config = Config::new_with_defaults();
+    assert!(matches!(
+        xml_bytes_to_json(xml, &config),
+        Err(Error::Encoding(_))
+    ));
+    assert_eq!(
+        xml_bytes_to_str(b"<a>\xc3\xa9</a>", &config).unwrap(),
+        "<a>é</a>"
+    );
+
+    let config = Config::new_with_defaults().with_utf8_policy(Utf8Policy::Lossy);
+    assert_eq!(
+        xml_bytes_to_json(xml, &config).unwrap(),
+        json!({"feed": {"r": ["caf\u{fffd}", "ok", "\u{fffd}\u{fffd}"]}})
+    );
+    // valid input is borrowed
+    assert!(matches!(
+        xml_bytes_to_str(b"<a/>", &config).unwrap(),
+        Cow::Borrowed(_)
+    ));
+
+    let config = Config::from_json(r#"{"utf8_policy": "lossy"}"#).unwrap();
+    assert_eq!(config.utf8_policy, Utf8Policy::Lossy);
+}
+
+#[test]
+fn test_xml_records_reader() {
+    /// Returns the document one byte at a time, to split it at every position.
+    struct ByteReader<'a>(&'a [u8]);
+    impl std::io::Read for ByteReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.0.split_first() {
+                Some((&b, rest)) if !buf.is_empty() => {
+                    buf[0] = b;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    let config = Config::new_with_defaults();
+    let xml = r#"<?xml version="1.0"?>
+<!-- <feed> -->
+<f:feed xmlns:f="urn:feed" xmlns="urn:items" updated="a > b">
+  <meta><item>not a record</item><item/></meta>
+  <item id="1"><![CDATA[</item>]]></item>
+  <item id="2"><item>nested</item></item>
+  <?pi <item>?>
+  <item/>
+  <other:item xmlns:other="urn:other">wrong namespace</other:item>
+</f:feed>
+trailing"#;
+    let records: Vec<Value> = XmlRecords::new(ByteReader(xml.as_bytes()), "/feed/item", &config)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        records,
+        [
+            json!({"@id": 1, "#text": "</item>"}),
+            json!({"@id": 2, "item": "nested"}),
+            json!({}),
+            json!("wrong namespace"),
+        ]
+    );
+    assert_eq!(
+        Value::Array(records),
+        Value::Array(
+            xml_records_to_json(xml.trim_end_matches("trailing"), "/feed/item", &config).unwrap()
+        )
+    );
+
+    // the namespaces of the ancestors are in scope
+    let config = Config::new_with_defaults().with_namespace_prefix("urn:other", "o");
+    let records: Vec<Value> = XmlRecords::new(xml.as_bytes(), "/feed/o:item", &config)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(records, [json!("wrong namespace")]);
+
+    // a malformed record doesn't end the iteration, a truncated document does
+    let config = Config::new_with_defaults();
+    let xml = "<a><b>1</b><b>2 & 3</b><b>3</b><b>4";
+    let records: Vec<_> = XmlRecords::new(xml.as_bytes(), "/a/b", &config).collect();
+    assert_eq!(records.len(), 4);
+    assert_eq!(records[0].as_ref().unwrap(), &json!(1));
+    assert!(matches!(records[1], Err(Error::Xml(_))));
+    assert_eq!(records[2].as_ref().unwrap(), &json!(3));
+    assert!(matches!(records[3], Err(Error::Xml(_))));
+
+    assert_eq!(
+        XmlRecords::new(&b"<a><c/></a>"[..], "/b/c", &config).count(),
+        0
+    );
+    assert_eq!(
+        XmlRecords::new(&b"<a>1</a>"[..], "/a", &config)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>(),
+        [json!(1)]
+    );
+}
+
+#[test]
+fn test_xml_split_records() {
+    let config = Config::new_with_defaults();
+    let xml =
+        "<export><row><id>1</id></row><skip/><row><id>2</id></row><row><id>3</id></row></export>";
+
+    // the callback stops the split with its error
+    let mut seen = Vec::new();
+    let result = xml_split_records(xml.as_bytes(), "/export/row", &config, |n, row| {
+        seen.push(row["id"].clone());
+        if n == 1 {
+            return Err(Error::Cancelled);
+        }
+        Ok(())
+    });
+    assert!(matches!(result, Err(Error::Cancelled)));
+    assert_eq!(seen, [json!(1), json!(2)]);
+
+    let dir = std::env::temp_dir().join(format!("roxmltree_to_serde_split_{}", std::process::id()));
+    let split =
+        SplitConfig::new(dir.join("rows/row-{n:3}.json").to_str().unwrap()).with_first_index(1);
+    assert_eq!(
+        xml_split_records_to_files(xml.as_bytes(), "/export/row", &config, &split).unwrap(),
+        3
+    );
+    let row = std::fs::read_to_string(dir.join("rows/row-003.json")).unwrap();
+    assert_eq!(row, "{\"id\":3}\n");
+    assert!(!dir.join("rows/row-004.json").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let split = SplitConfig::new("row.json");
+    assert!(matches!(
+        xml_split_records_to_files(xml.as_bytes(), "/export/row", &config, &split),
+        Err(Error::InvalidConfig(_))
+    ));
+}
+
+#[test]
+fn test_for_each_match() {
+    use std::ops::ControlFlow;
+
+    let config = Config::new_with_defaults();
+    let xml = r#"<shop><order><id>1</id><item>a</item></order><archive><order><id>2</id></order></archive></shop>"#;
+    let mut matches = Vec::new();
+    let count = for_each_match(
+        xml.as_bytes(),
+        "/shop/archive/order",
+        &config,
+        |path, value| {
+            matches.push((path.to_owned(), value));
+            ControlFlow::Continue(())
+        },
+    );
+    assert_eq!(count.unwrap(), 1);
+    assert_eq!(
+        matches,
+        [("/shop/archive/order".to_owned(), json!({"id": 2}))]
+    );
+
+    // the paths of elements in a namespace are built like in the output
+    let xml = r#"<a xmlns:n="urn:n"><n:b>1</n:b><c><n:b>2</n:b></c></a>"#;
+    let config = Config::new_with_defaults().with_namespace_prefix("urn:n", "x");
+    let mut values = Vec::new();
+    for_each_match(xml.as_bytes(), "/a/c/x:b", &config, |_, value| {
+        values.push(value);
+        ControlFlow::Continue(())
+    })
+    .unwrap();
+    assert_eq!(values, [json!(2)]);
+
+    // a malformed element stops the conversion
+    let xml = "<a><b>1</b><b>&</b></a>";
+    let result = for_each_match(xml.as_bytes(), "/a/b", &config, |_, _| {
+        ControlFlow::Continue(())
+    });
+    assert!(matches!(result, Err(Error::Xml(_))));
+}
+
+#[cfg(feature = "regex_path")]
+#[test]
+fn test_for_each_match_regex() {
+    use regex::Regex;
+    use std::ops::ControlFlow;
+
+    let config = Config::new_with_defaults();
+    let xml = "<shop><order><id>1</id></order><archive><order><id>2</id><order>nested</order></order></archive></shop>";
+    let mut matches = Vec::new();
+    let count = for_each_match(
+        xml.as_bytes(),
+        Regex::new("/order$").unwrap(),
+        &config,
+        |path, value| {
+            matches.push((path.to_owned(), value));
+            ControlFlow::Break(())
+        },
+    );
+    assert_eq!(count.unwrap(), 1);
+    assert_eq!(matches, [("/shop/order".to_owned(), json!({"id": 1}))]);
+
+    let mut paths = Vec::new();
+    for_each_match(
+        xml.as_bytes(),
+        Regex::new("/order$").unwrap(),
+        &config,
+        |path, _| {
+            paths.push(path.to_owned());
+            ControlFlow::Continue(())
+        },
+    )
+    .unwrap();
+    assert_eq!(paths, ["/shop/order", "/shop/archive/order"]);
+}
+
+#[test]
+fn test_xml_records_channel() {
+    let xml: String = std::iter::once("<feed>".to_owned())
+        .chain((0..100).map(|i| format!("<item>{}</item>", i)))
+        .chain(std::iter::once("</feed>".to_owned()))
+        .collect();
+    let config = std::sync::Arc::new(Config::new_with_defaults());
+    let records = xml_records_channel(
+        std::io::Cursor::new(xml.clone()),
+        "/feed/item",
+        config.clone(),
+        1,
+    );
+    let values: Vec<Value> = records.iter().map(Result::unwrap).collect();
+    assert_eq!(values.len(), 100);
+    assert_eq!(values[42], json!(42));
+
+    // dropping the receiver stops the producer
+    let records = xml_records_channel(std::io::Cursor::new(xml), "/feed/item", config, 1);
+    assert_eq!(records.recv().unwrap().unwrap(), json!(0));
+    drop(records);
+
+    let records = xml_records_channel(
+        &b"<feed><item>1</item>"[..],
+        "/feed/item",
+        Config::new_with_defaults(),
+        4,
+    );
+    let results: Vec<_> = records.iter().collect();
+    assert_eq!(results.len(), 2);
+    assert!(results[1].is_err());
+}
+
+#[test]
+fn test_xml_json_iter() {
+    let config = Config::new_with_defaults().with_clark_notation();
+    let xml = r#"<export xmlns:u="http://example.com/u">
+  text
+  <u:user id="1"><name>Ann</name></u:user>
+  <!-- comment -->
+  <group/>
+  <u:user id="&"/>
+  <group><user>nested</user></group>
+</export>"#;
+    let children: Vec<_> = XmlJsonIter::new(xml, &config).collect();
+    assert_eq!(children.len(), 4);
+    assert_eq!(
+        children[0].as_ref().unwrap(),
+        &(
+            "{http://example.com/u}user".to_owned(),
+            json!({"@id": 1, "name": "Ann"})
+        )
+    );
+    assert_eq!(
+        children[1].as_ref().unwrap(),
+        &("group".to_owned(), json!({}))
+    );
+    assert!(matches!(children[2], Err(Error::Xml(_))));
+    assert_eq!(
+        children[3].as_ref().unwrap(),
+        &("group".to_owned(), json!({"user": "nested"}))
+    );
+
+    assert!(XmlJsonIter::new("<a/>", &config).next().is_none());
+    assert!(matches!(
+        XmlJsonIter::new("", &config).next(),
+        Some(Err(Error::Xml(_)))
+    ));
+}
+
+#[test]
+fn test_xml_records_deserialize() {
+    #[derive(serde_derive::Deserialize, Debug, PartialEq)]
+    enum Status {
+        Active,
+        Locked { reason: String },
+    }
+
+    #[derive(serde_derive::Deserialize, Debug, PartialEq)]
+    struct User {
+        #[serde(rename = "@id")]
+        id: u32,
+        name: String,
+        zip: String,
+        email: Option<String>,
+        role: Vec<String>,
+        status: Status,
+        admin: Option<()>,
+    }
+
+    let xml = r#"<users>
+  <user id="1"><name>Ann</name><zip>01234</zip><role>a</role><role>b</role><status>Active</status></user>
+  <user id="x"><name>Bob</name></user>
+  <user id="3"><name>Cid</name><zip>12345</zip><email>cid@example.com</email><role>a</role><role>c</role>
+    <status><Locked><reason>too many attempts</reason></Locked></status><admin/></user>
+</users>"#;
+    let config = Config {
+        leading_zero_as_string: true,
+        ..Config::new_with_defaults()
+    };
+    let users: Vec<Result<User, Error>> = XmlRecords::new(xml.as_bytes(), "/users/user", &config)
+        .deserialize()
+        .collect();
+    assert_eq!(users.len(), 3);
+    assert_eq!(
+        users[0].as_ref().unwrap(),
+        &User {
+            id: 1,
+            name: "Ann".to_owned(),
+            zip: "01234".to_owned(),
+            email: None,
+            role: vec!["a".to_owned(), "b".to_owned()],
+            status: Status::Active,
+            admin: None,
+        }
+    );
+    assert!(matches!(users[1], Err(Error::Deserialize(_))));
+    let cid = users[2].as_ref().unwrap();
+    assert_eq!(cid.zip, "12345");
+    assert_eq!(cid.email.as_deref(), Some("cid@example.com"));
+    assert_eq!(
+        cid.status,
+        Status::Locked {
+            reason: "too many attempts".to_owned()
+        }
+    );
+    assert_eq!(cid.admin, Some(()));
+
+    // the records are the same as with `serde_json::from_value`
+    let values: Vec<Value> = XmlRecords::new(xml.as_bytes(), "/users/user", &config)
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let expected: Vec<Value> = XmlRecords::new(xml.as_bytes(), "/users/user", &config)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(values, expected);
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn test_xml_attribute_macro() {
+    #[xml]
+    #[derive(serde_derive::Deserialize, Debug, PartialEq)]
+    struct Price {
+        #[xml(attr = "currency")]
+        currency: String,
+        #[xml(text)]
+        value: f64,
+    }
+
+    #[xml]
+    #[derive(serde_derive::Deserialize, Debug, PartialEq)]
+    struct Item {
+        #[xml(attr = "id")]
+        id: u32,
+        price: Price,
+        #[xml(path = "meta/author/@name")]
+        author: String,
+        #[xml(path = "stock/warehouse/count")]
+        stock: Option<u32>,
+        #[xml(path = "tag")]
+        tags: Option<Vec<String>>,
+    }
+
+    let xml = r#"<items>
+  <item id="1"><price currency="EUR">9.99</price><meta><author name="Ann"/></meta>
+    <stock><warehouse><count>7</count></warehouse></stock><tag>a</tag><tag>b</tag></item>
+  <item id="2"><price currency="USD">5</price><meta><author name="Bob"/></meta></item>
+  <item id="3"><price currency="USD">5</price></item>
+</items>"#;
+    let config = Config::new_with_defaults();
+    let items: Vec<Result<Item, Error>> = XmlRecords::new(xml.as_bytes(), "/items/item", &config)
+        .deserialize()
+        .collect();
+    assert_eq!(
+        items[0].as_ref().unwrap(),
+        &Item {
+            id: 1,
+            price: Price {
+                currency: "EUR".to_owned(),
+                value: 9.99
+            },
+            author: "Ann".to_owned(),
+            stock: Some(7),
+            tags: Some(vec!["a".to_owned(), "b".to_owned()]),
+        }
+    );
+    let bob = items[1].as_ref().unwrap();
+    assert_eq!(
+        (bob.author.as_str(), bob.stock, &bob.tags),
+        ("Bob", None, &None)
+    );
+    // a missing element of a required path fails like a missing field
+    assert!(matches!(&items[2], Err(Error::Deserialize(e)) if e.contains("meta")));
+
+    #[xml(attr_prefix = "", text = "value")]
+    #[derive(serde_derive::Deserialize, Debug, PartialEq)]
+    struct Amount {
+        #[xml(attr = "currency")]
+        unit: String,
+        #[xml(text)]
+        amount: f64,
+    }
+
+    let config = Config::new_with_custom_values(false, "", "value", NullValue::EmptyObject);
+    let xml = r#"<a><amount currency="EUR">1.5</amount></a>"#;
+    let amount = XmlRecords::new(xml.as_bytes(), "/a/amount", &config)
+        .deserialize::<Amount>()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        amount,
+        Amount {
+            unit: "EUR".to_owned(),
+            amount: 1.5
+        }
+    );
+}
+
+#[test]
+fn test_xml_records_deserialize_renamed_keys() {
+    #[derive(serde_derive::Deserialize, Debug, PartialEq)]
+    struct Price {
+        #[serde(rename = "@currency")]
+        currency: Option<String>,
+        #[serde(rename = "#text")]
+        value: f64,
+    }
+
+    #[derive(serde_derive::Deserialize, Debug, PartialEq)]
+    struct Note {
+        #[serde(rename = "@lang")]
+        lang: Option<String>,
+        #[serde(rename = "#text")]
+        text: Option<String>,
+    }
+
+    #[derive(serde_derive::Deserialize, Debug, PartialEq)]
+    #[serde(tag = "@type")]
+    enum Shipping {
+        #[serde(rename = "pickup")]
+        Pickup,
+        #[serde(rename = "parcel")]
+        Parcel {
+            #[serde(rename = "@carrier")]
+            carrier: String,
+            #[serde(rename = "#text")]
+            tracking: String,
+        },
+    }
+
+    #[derive(serde_derive::Deserialize, Debug, PartialEq)]
+    enum Discount {
+        Percent(Price),
+        Fixed { amount: Price },
+    }
+
+    #[derive(serde_derive::Deserialize, Debug, PartialEq)]
+    struct Order {
+        #[serde(rename = "@id")]
+        id: u32,
+        price: Price,
+        note: Option<Note>,
+        shipping: Shipping,
+        discount: Option<Discount>,
+    }
+
+    let xml = r#"<orders>
+  <order id="1"><price currency="EUR">9.99</price><note/><shipping type="pickup"/></order>
+  <order id="2"><price>5</price><note lang="en">fragile</note>
+    <shipping type="parcel" carrier="dhl">JD123</shipping><discount><Percent>10</Percent></discount></order>
+  <order id="3"><price>5</price><shipping type="pickup"/>
+    <discount><Fixed><amount currency="USD">2.5</amount></Fixed></discount></order>
+</orders>"#;
+    let expected = [
+        Order {
+            id: 1,
+            price: Price {
+                currency: Some("EUR".to_owned()),
+                value: 9.99,
+            },
+            note: Some(Note {
+                lang: None,
+                text: None,
+            }),
+            shipping: Shipping::Pickup,
+            discount: None,
+        },
+        Order {
+            id: 2,
+            price: Price {
+                currency: None,
+                value: 5.0,
+            },
+            note: Some(Note {
+                lang: Some("en".to_owned()),
+                text: Some("fragile".to_owned()),
+            }),
+            shipping: Shipping::Parcel {
+                carrier: "dhl".to_owned(),
+                tracking: "JD123".to_owned(),
+            },
+            discount: Some(Discount::Percent(Price {
+                currency: None,
+                value: 10.0,
+            })),
+        },
+        Order {
+            id: 3,
+            price: Price {
+                currency: None,
+                value: 5.0,
+            },
+            note: None,
+            shipping: Shipping::Pickup,
+            discount: Some(Discount::Fixed {
+                amount: Price {
+                    currency: Some("USD".to_owned()),
+                    value: 2.5,
+                },
+            }),
+        },
+    ];
+    let config = Config::new_with_defaults();
+    let orders: Vec<Order> = XmlRecords::new(xml.as_bytes(), "/orders/order", &config)
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(orders, expected);
+
+    // an empty element is `null` with `NullValue::Null`
+    let config = Config::new_with_custom_values(false, "@", "#text", NullValue::Null);
+    let orders: Vec<Order> = XmlRecords::new(xml.as_bytes(), "/orders/order", &config)
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(orders[0].note, None);
+
+    // the text property follows the config
+    #[derive(serde_derive::Deserialize, Debug, PartialEq)]
+    struct Amount {
+        value: f64,
+        currency: Option<String>,
+    }
+    let config = Config::new_with_custom_values(false, "", "value", NullValue::EmptyObject);
+    let prices: Vec<Amount> = XmlRecords::new(xml.as_bytes(), "/orders/order/price", &config)
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(prices[0].currency.as_deref(), Some("EUR"));
+    assert_eq!(prices[1].value, 5.0);
+}
+
+#[test]
+fn test_xml_records_deserialize_adjacently_tagged() {
+    #[derive(serde_derive::Deserialize, Debug, PartialEq)]
+    #[serde(tag = "@type", content = "#text")]
+    enum Code {
+        #[serde(rename = "ean")]
+        Ean(String),
+        #[serde(rename = "sku")]
+        Sku(String),
+    }
+
+    #[derive(serde_derive::Deserialize, Debug, PartialEq)]
+    #[serde(tag = "@kind", content = "details")]
+    enum Payment {
+        #[serde(rename = "card")]
+        Card { number: String },
+        #[serde(rename = "cash")]
+        Cash,
+    }
+
+    let xml = r#"<a>
+  <item><code type="ean">4006381333931</code><payment kind="card"><details><number>1234</number></details></payment></item>
+  <item><code type="sku">AB-1</code><payment kind="cash"/></item>
+</a>"#;
+    let config = Config::new_with_defaults();
+    let codes: Vec<Code> = XmlRecords::new(xml.as_bytes(), "/a/item/code", &config)
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        codes,
+        [
+            Code::Ean("4006381333931".to_owned()),
+            Code::Sku("AB-1".to_owned())
+        ]
+    );
+    let payments: Vec<Payment> = XmlRecords::new(xml.as_bytes(), "/a/item/payment", &config)
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        payments,
+        [
+            Payment::Card {
+                number: "1234".to_owned()
+            },
+            Payment::Cash
+        ]
+    );
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn test_xml_attribute_macro_untagged() {
+    #[xml]
+    #[derive(serde_derive::Deserialize, Debug, PartialEq)]
+    struct Iban {
+        #[xml(attr = "bic")]
+        bic: Option<String>,
+        #[xml(text)]
+        iban: String,
+    }
+
+    #[xml]
+    #[derive(serde_derive::Deserialize, Debug, PartialEq)]
+    #[serde(untagged)]
+    enum Payment {
+        Card {
+            #[xml(path = "card/number")]
+            number: String,
+        },
+        Iban {
+            iban: Iban,
+        },
+        Voucher(u32),
+        Unknown,
+    }
+
+    let xml = r#"<payments>
+  <payment><card><number>4111111111111111</number></card></payment>
+  <payment><iban>DE0012</iban></payment>
+  <payment><iban bic="MARKDEF1">DE0034</iban></payment>
+  <payment>42</payment>
+  <payment/>
+  <payment>cash</payment>
+</payments>"#;
+    let config = Config::new_with_defaults();
+    let payments: Vec<Result<Payment, Error>> =
+        XmlRecords::new(xml.as_bytes(), "/payments/payment", &config)
+            .deserialize()
+            .collect();
+    assert_eq!(
+        payments[..5]
+            .iter()
+            .map(|p| p.as_ref().unwrap())
+            .collect::<Vec<_>>(),
+        [
+            &Payment::Card {
+                number: "4111111111111111".to_owned()
+            },
+            &Payment::Iban {
+                iban: Iban {
+                    bic: None,
+                    iban: "DE0012".to_owned()
+                }
+            },
+            &Payment::Iban {
+                iban: Iban {
+                    bic: Some("MARKDEF1".to_owned()),
+                    iban: "DE0034".to_owned()
+                }
+            },
+            &Payment::Voucher(42),
+            &Payment::Unknown,
+        ]
+    );
+    assert!(
+        matches!(&payments[5], Err(Error::Deserialize(e)) if e.contains("untagged enum Payment"))
+    );
+}
+
+#[test]
+fn test_xml_records_deserialize_singleton_sequences() {
+    #[derive(serde_derive::Deserialize, Debug, PartialEq)]
+    struct Line {
+        #[serde(rename = "@sku")]
+        sku: String,
+    }
+
+    #[derive(serde_derive::Deserialize, Debug, PartialEq)]
+    struct Order {
+        tag: Vec<String>,
+        line: Option<Vec<Line>>,
+        #[serde(default)]
+        note: Vec<String>,
+    }
+
+    let xml = r#"<orders>
+  <order><tag>a</tag><tag>b</tag><line sku="1"/><line sku="2"/><note>x</note></order>
+  <order><tag>a</tag><line sku="1"/></order>
+  <order><tag/></order>
+</orders>"#;
+    let config = Config::new_with_custom_values(false, "@", "#text", NullValue::Null);
+    let orders: Vec<Order> = XmlRecords::new(xml.as_bytes(), "/orders/order", &config)
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let line = |sku: &str| Line {
+        sku: sku.to_owned(),
+    };
+    assert_eq!(
+        orders,
+        [
+            Order {
+                tag: vec!["a".to_owned(), "b".to_owned()],
+                line: Some(vec![line("1"), line("2")]),
+                note: vec!["x".to_owned()],
+            },
+            Order {
+                tag: vec!["a".to_owned()],
+                line: Some(vec![line("1")]),
+                note: vec![],
+            },
+            Order {
+                tag: vec![],
+                line: None,
+                note: vec![],
+            },
+        ]
+    );
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn test_xml_attribute_macro_sequences() {
+    #[xml]
+    #[derive(serde_derive::Deserialize, Debug, PartialEq)]
+    struct Order {
+        tag: Vec<String>,
+        #[xml(path = "lines/sku")]
+        skus: Vec<u32>,
+    }
+
+    let xml = r#"<orders>
+  <order><tag>a</tag><lines><sku>1</sku><sku>2</sku></lines></order>
+  <order><lines><sku>3</sku></lines></order>
+  <order/>
+</orders>"#;
+    let config = Config::new_with_defaults();
+    let orders: Vec<Order> = XmlRecords::new(xml.as_bytes(), "/orders/order", &config)
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        orders,
+        [
+            Order {
+                tag: vec!["a".to_owned()],
+                skus: vec![1, 2],
+            },
+            Order {
+                tag: vec![],
+                skus: vec![3],
+            },
+            Order {
+                tag: vec![],
+                skus: vec![],
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_xml_records_deserialize_flatten() {
+    #[derive(serde_derive::Deserialize, Debug, PartialEq)]
+    struct Currency {
+        #[serde(rename = "@currency")]
+        currency: Option<String>,
+    }
+
+    #[derive(serde_derive::Deserialize, Debug, PartialEq)]
+    struct Price {
+        #[serde(flatten)]
+        attributes: Currency,
+        #[serde(rename = "#text")]
+        value: f64,
+    }
+
+    #[derive(serde_derive::Deserialize, Debug, PartialEq)]
+    struct Tagged {
+        #[serde(flatten)]
+        attributes: std::collections::BTreeMap<String, String>,
+        #[serde(rename = "#text")]
+        text: Option<String>,
+    }
+
+    let xml = r#"<a>
+  <price currency="EUR">9.99</price>
+  <price>5</price>
+  <tagged lang="en" dir="ltr">Hello</tagged>
+  <tagged>Bye</tagged>
+  <tagged lang="de"/>
+</a>"#;
+    let config = Config::new_with_defaults();
+    let prices: Vec<Price> = XmlRecords::new(xml.as_bytes(), "/a/price", &config)
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        prices,
+        [
+            Price {
+                attributes: Currency {
+                    currency: Some("EUR".to_owned())
+                },
+                value: 9.99
+            },
+            Price {
+                attributes: Currency { currency: None },
+                value: 5.0
+            },
+        ]
+    );
+    let tagged: Vec<Tagged> = XmlRecords::new(xml.as_bytes(), "/a/tagged", &config)
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let attributes = |pairs: &[(&str, &str)]| {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    };
+    assert_eq!(
+        tagged,
+        [
+            Tagged {
+                attributes: attributes(&[("@dir", "ltr"), ("@lang", "en")]),
+                text: Some("Hello".to_owned()),
+            },
+            Tagged {
+                attributes: attributes(&[]),
+                text: Some("Bye".to_owned()),
+            },
+            Tagged {
+                attributes: attributes(&[("@lang", "de")]),
+                text: None,
+            },
+        ]
+    );
+
+    // a text-only element is a map with the text property
+    let texts: Vec<std::collections::HashMap<String, String>> =
+        XmlRecords::new(xml.as_bytes(), "/a/tagged", &config)
+            .deserialize()
+            .collect::<Result<_, _>>()
+            .unwrap();
+    assert_eq!(texts[1]["#text"], "Bye");
+}
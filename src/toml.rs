@@ -0,0 +1,152 @@
+//! Conversion of XML into TOML, available with the `toml` feature.
+
+use serde_json::Value;
+use toml::{Table, Value as TomlValue};
+
+use crate::{xml_str_to_json, Config, Error};
+
+/// Defines how JSON `null` values are written, because TOML has no null.
+/// * `Omit` - leave out the key or the array item. This is the default.
+/// * `EmptyString` - write an empty string instead.
+/// * `Error` - fail with `Error::Toml`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TomlNull {
+    Omit,
+    EmptyString,
+    Error,
+}
+
+/// Defines how arrays with items of different types are written, e.g. `[1, "x"]`.
+/// TOML 1.0 allows them, but parsers for older versions of the spec reject them.
+/// * `Keep` - write the array as it is. This is the default.
+/// * `Stringify` - convert all items into strings, tables and arrays into their JSON text.
+/// * `Error` - fail with `Error::Toml`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TomlMixedArray {
+    Keep,
+    Stringify,
+    Error,
+}
+
+/// Defines how structures that TOML can't express are converted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TomlConfig {
+    /// Defaults to `TomlNull::Omit`.
+    pub null_values: TomlNull,
+    /// Defaults to `TomlMixedArray::Keep`.
+    pub mixed_arrays: TomlMixedArray,
+}
+
+impl TomlConfig {
+    pub fn new_with_defaults() -> Self {
+        TomlConfig {
+            null_values: TomlNull::Omit,
+            mixed_arrays: TomlMixedArray::Keep,
+        }
+    }
+}
+
+impl Default for TomlConfig {
+    fn default() -> Self {
+        TomlConfig::new_with_defaults()
+    }
+}
+
+/// Converts an XML document into a TOML table with the same rules as `xml_str_to_json`.
+/// Integers that don't fit into the 64-bit signed integers of TOML are converted into strings.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_str_to_toml, Config, TomlConfig};
+///
+/// let table = xml_str_to_toml("<a><b>1</b><c/></a>", &Config::new_with_defaults(), &TomlConfig::default()).unwrap();
+/// assert_eq!(table["a"]["b"].as_integer(), Some(1));
+/// ```
+pub fn xml_str_to_toml(
+    xml: &str,
+    config: &Config,
+    toml_config: &TomlConfig,
+) -> Result<Table, Error> {
+    let json = xml_str_to_json(xml, config)?;
+    // the root is always an object with the root element
+    match json_to_toml(json, toml_config, "")? {
+        Some(TomlValue::Table(table)) => Ok(table),
+        _ => Ok(Table::new()),
+    }
+}
+
+/// Converts an XML document into a TOML string. See `xml_str_to_toml` for details.
+pub fn xml_str_to_toml_string(
+    xml: &str,
+    config: &Config,
+    toml_config: &TomlConfig,
+) -> Result<String, Error> {
+    let table = xml_str_to_toml(xml, config, toml_config)?;
+    toml::to_string(&table).map_err(|e| Error::Toml(e.to_string()))
+}
+
+/// Converts the JSON value at the dotted `path` into a TOML value, `None` if it's omitted.
+fn json_to_toml(
+    value: Value,
+    toml_config: &TomlConfig,
+    path: &str,
+) -> Result<Option<TomlValue>, Error> {
+    Ok(Some(match value {
+        Value::Null => match toml_config.null_values {
+            TomlNull::Omit => return Ok(None),
+            TomlNull::EmptyString => TomlValue::String(String::new()),
+            TomlNull::Error => return Err(Error::Toml(format!("{}: null value", path))),
+        },
+        Value::Bool(b) => TomlValue::Boolean(b),
+        Value::Number(n) => match (n.as_i64(), n.as_f64()) {
+            (Some(i), _) => TomlValue::Integer(i),
+            (None, Some(f)) if !n.is_u64() => TomlValue::Float(f),
+            _ => TomlValue::String(n.to_string()),
+        },
+        Value::String(s) => TomlValue::String(s),
+        Value::Array(items) => {
+            let mut types = items.iter().filter(|item| !item.is_null());
+            let mixed = match types.next() {
+                Some(first) => types.any(|item| !same_type(item, first)),
+                None => false,
+            };
+            let stringify = mixed && toml_config.mixed_arrays == TomlMixedArray::Stringify;
+            if mixed && toml_config.mixed_arrays == TomlMixedArray::Error {
+                return Err(Error::Toml(format!("{}: array of mixed types", path)));
+            }
+            let mut array = Vec::with_capacity(items.len());
+            for item in items {
+                let item = match item {
+                    Value::Null | Value::String(_) => item,
+                    item if stringify => Value::String(item.to_string()),
+                    item => item,
+                };
+                if let Some(item) = json_to_toml(item, toml_config, path)? {
+                    array.push(item);
+                }
+            }
+            TomlValue::Array(array)
+        }
+        Value::Object(map) => {
+            let mut table = Table::new();
+            for (key, value) in map {
+                let key_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    [path, ".", &key].concat()
+                };
+                if let Some(value) = json_to_toml(value, toml_config, &key_path)? {
+                    table.insert(key, value);
+                }
+            }
+            TomlValue::Table(table)
+        }
+    }))
+}
+
+/// Returns `true` if both values are converted into the same TOML type.
+fn same_type(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.is_f64() == b.is_f64(),
+        _ => std::mem::discriminant(a) == std::mem::discriminant(b),
+    }
+}
@@ -0,0 +1,76 @@
+//! A record of the path and the JSON type of every node, to debug JSON type overrides.
+
+use serde_json::Value;
+
+use crate::{parse_document, prepare_input, xml_to_map, Config, Context, Error, JsonType};
+
+/// The override of a `Config` that applied to a node.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppliedOverride {
+    /// The path of `Config.json_type_overrides`.
+    Path(String),
+    /// The pattern of the regex of `Config.json_regex_type_overrides`.
+    Regex(String),
+}
+
+/// The path of an element or attribute as the converter computed it, and how its JSON type was determined.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceEntry {
+    /// The absolute path that overrides are matched against, e.g. `/a/b/@c`.
+    pub path: String,
+    /// The override that applied, or `None` if the JSON type was inferred.
+    pub applied: Option<AppliedOverride>,
+    /// The JSON type of the value of the node.
+    pub json_type: JsonType,
+    /// `true` if the element is always converted into an array.
+    pub always_array: bool,
+}
+
+/// Converts the given XML string into `serde::Value` just like `xml_str_to_json` and records the path
+/// of every element and attribute in document order, together with the override that applied to it.
+/// Use it to find out why an override doesn't apply, e.g. because the path has a namespace prefix from
+/// `Config.namespace_prefixes` or because a regex override takes precedence.
+/// The children of the root element are converted sequentially, even with `Config.parallel_children`.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_str_to_json_with_trace, Config, JsonType};
+///
+/// let (json, trace) = xml_str_to_json_with_trace(r#"<a><b c="1"/></a>"#, &Config::new_with_defaults()).unwrap();
+/// let paths: Vec<_> = trace.iter().map(|entry| entry.path.as_str()).collect();
+/// assert_eq!(paths, vec!["/a", "/a/b", "/a/b/@c"]);
+/// assert_eq!(trace[2].applied, None);
+/// assert_eq!(trace[2].json_type, JsonType::Infer);
+/// ```
+pub fn xml_str_to_json_with_trace(
+    xml: &str,
+    config: &Config,
+) -> Result<(Value, Vec<TraceEntry>), Error> {
+    let xml = prepare_input(xml, config)?;
+    let doc = parse_document(&xml, config)?;
+    let mut ctx = Context::new(config, None);
+    ctx.trace = Some(Vec::new());
+    let json = xml_to_map(&mut ctx, &doc)?;
+    Ok((json, ctx.trace.unwrap_or_default()))
+}
+
+/// Returns the override that `get_json_type` applies to the path, if any.
+#[cfg(feature = "json_types")]
+pub(crate) fn applied_override(config: &Config, path: &str) -> Option<AppliedOverride> {
+    #[cfg(feature = "regex_path")]
+    if let Some((regex, _)) = config
+        .json_regex_type_overrides
+        .iter()
+        .find(|(regex, _)| regex.is_match(path))
+    {
+        return Some(AppliedOverride::Regex(regex.as_str().to_owned()));
+    }
+    config
+        .json_type_overrides
+        .contains_key(path)
+        .then(|| AppliedOverride::Path(path.to_owned()))
+}
+
+#[cfg(not(feature = "json_types"))]
+pub(crate) fn applied_override(_config: &Config, _path: &str) -> Option<AppliedOverride> {
+    None
+}
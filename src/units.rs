@@ -0,0 +1,147 @@
+//! Splitting of values with a unit, like `10.5kg`, into their magnitude and unit, see `Config.unit_paths`.
+
+use std::collections::HashMap;
+
+use serde_json::{Map, Number, Value};
+
+use crate::Config;
+
+/// The units of `Config.unit_paths` that are converted into another unit, e.g. seconds into milliseconds.
+/// Units that aren't in the table are kept as they are.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UnitTable {
+    units: HashMap<String, (String, f64)>,
+}
+
+impl UnitTable {
+    /// A table without conversions, which only splits the values.
+    pub fn new() -> Self {
+        UnitTable::default()
+    }
+
+    /// Converts values in `unit` into `base_unit` by multiplying them with `factor`, e.g. `"s"`, `"ms"`
+    /// and `1000.0` for seconds into milliseconds. The base unit itself needs a factor of `1.0` to be
+    /// recognized, too. Converted values are rounded to 15 significant digits, the precision of an `f64`,
+    /// so `4.35min` becomes `261000` milliseconds instead of `260999.99999999997`.
+    pub fn with_unit(self, unit: &str, base_unit: &str, factor: f64) -> Self {
+        let mut table = self;
+        table
+            .units
+            .insert(unit.to_owned(), (base_unit.to_owned(), factor));
+        table
+    }
+
+    /// Converts durations in `ns`, `us`, `µs`, `ms`, `s`, `min`, `h` and `d` into milliseconds.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{xml_str_to_json, Config, UnitTable};
+    /// use serde_json::json;
+    ///
+    /// let conf = Config::new_with_defaults().add_unit_path("/job/@timeout", UnitTable::durations());
+    /// assert_eq!(
+    ///     xml_str_to_json(r#"<job timeout="1.5s"/>"#, &conf).unwrap(),
+    ///     json!({"job": {"@timeout": {"value": 1500, "unit": "ms"}}})
+    /// );
+    /// ```
+    pub fn durations() -> Self {
+        UnitTable::new()
+            .with_unit("ns", "ms", 1e-6)
+            .with_unit("us", "ms", 1e-3)
+            .with_unit("µs", "ms", 1e-3)
+            .with_unit("ms", "ms", 1.0)
+            .with_unit("s", "ms", 1e3)
+            .with_unit("min", "ms", 60e3)
+            .with_unit("h", "ms", 3600e3)
+            .with_unit("d", "ms", 86400e3)
+    }
+
+    /// Returns the magnitude and unit in the base unit of `unit`, if it has one.
+    fn convert<'u>(&'u self, magnitude: f64, unit: &'u str) -> (f64, &'u str) {
+        match self.units.get(unit) {
+            Some((base_unit, factor)) => (round_significant(magnitude * factor), base_unit),
+            None => (magnitude, unit),
+        }
+    }
+}
+
+/// Rounds `value` to 15 significant digits, which drops the binary rounding errors of the multiplication
+/// with a decimal factor like `0.001`.
+fn round_significant(value: f64) -> f64 {
+    format!("{value:.14e}").parse().unwrap_or(value)
+}
+
+/// Returns `true` if the value at `path` is split into magnitude and unit, see `Config.unit_paths`.
+#[inline]
+pub(crate) fn is_unit_path(config: &Config, path: &str) -> bool {
+    !config.unit_paths.is_empty() && config.unit_paths.contains_key(path)
+}
+
+/// Returns the object with the magnitude and unit of `text` if `path` is in `Config.unit_paths` and the text
+/// is a number followed by an optional unit without whitespace, e.g. `10.5kg`, `120 ms` or `-3`.
+pub(crate) fn split_unit(config: &Config, path: &str, text: &str) -> Option<Value> {
+    let table = config.unit_paths.get(path)?;
+    let (number, unit) = split_number(text.trim())?;
+    if unit.contains(char::is_whitespace) {
+        return None;
+    }
+    let magnitude: f64 = number.parse().ok()?;
+    let (value, unit) = match unit {
+        "" => (magnitude, None),
+        unit => {
+            let (value, unit) = table.convert(magnitude, unit);
+            (value, Some(unit))
+        }
+    };
+
+    let (value_name, unit_name) = &config.unit_prop_names;
+    let mut data = Map::with_capacity(2);
+    data.insert(value_name.clone(), magnitude_value(value));
+    data.insert(
+        unit_name.clone(),
+        unit.map_or(Value::Null, |unit| Value::String(unit.to_owned())),
+    );
+    Some(Value::Object(data))
+}
+
+/// Splits `text` into the leading number, with an optional sign, fraction and exponent, and the rest
+/// without leading whitespace. The `e` of units like `em` isn't taken for an exponent.
+fn split_number(text: &str) -> Option<(&str, &str)> {
+    let bytes = text.as_bytes();
+    let digits = |from: usize| {
+        from + bytes[from.min(bytes.len())..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count()
+    };
+    let start = usize::from(matches!(bytes.first(), Some(b'+' | b'-')));
+    let int_end = digits(start);
+    let mut end = int_end;
+    if bytes.get(end) == Some(&b'.') {
+        // `5.` and `.5` are numbers, `.` isn't
+        let fraction_end = digits(end + 1);
+        if int_end > start || fraction_end > end + 1 {
+            end = fraction_end;
+        }
+    }
+    if end == start {
+        return None;
+    }
+    if matches!(bytes.get(end), Some(b'e' | b'E')) {
+        let exponent = end + 1 + usize::from(matches!(bytes.get(end + 1), Some(b'+' | b'-')));
+        let exponent_end = digits(exponent);
+        if exponent_end > exponent {
+            end = exponent_end;
+        }
+    }
+    Some((&text[..end], text[end..].trim_start()))
+}
+
+/// Returns the magnitude as an integer if it has no fraction, like `1500` for `1.5s` in milliseconds.
+fn magnitude_value(value: f64) -> Value {
+    // integers up to 2^53 are exact in an f64
+    if value.fract() == 0.0 && value.abs() < 9_007_199_254_740_992.0 {
+        Value::from(value as i64)
+    } else {
+        Number::from_f64(value).map_or(Value::Null, Value::Number)
+    }
+}
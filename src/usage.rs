@@ -0,0 +1,87 @@
+//! Tracking of the JSON type overrides that are applied during conversions.
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use crate::Config;
+
+/// Records which JSON type overrides of a `Config` are applied, across any number of conversions.
+/// Set it with `Config::with_override_usage` and call `report` after converting a representative
+/// sample of documents to find the rules that never matched.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_str_to_json, Config, JsonArray, JsonType, OverrideUsage};
+/// use std::sync::Arc;
+///
+/// let usage = Arc::new(OverrideUsage::default());
+/// let conf = Config::new_with_defaults()
+///     .add_json_type_override("/a/b", JsonArray::Always(JsonType::Infer))
+///     .add_json_type_override("/a/c", JsonArray::Infer(JsonType::AlwaysString))
+///     .with_override_usage(usage.clone());
+/// xml_str_to_json("<a><b>1</b></a>", &conf).unwrap();
+///
+/// let report = usage.report(&conf);
+/// assert_eq!(report.matched, vec!["/a/b"]);
+/// assert_eq!(report.unmatched, vec!["/a/c"]);
+/// ```
+#[derive(Debug, Default)]
+pub struct OverrideUsage {
+    paths: Mutex<HashSet<String>>,
+    #[cfg(feature = "regex_path")]
+    regexes: Mutex<HashSet<usize>>,
+}
+
+/// The result of `OverrideUsage::report`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverrideReport {
+    /// The paths of `Config.json_type_overrides` and the patterns of `Config.json_regex_type_overrides`
+    /// that were applied at least once, in sorted order followed by the patterns in configured order.
+    pub matched: Vec<String>,
+    /// The paths and patterns that were never applied, in the same order.
+    pub unmatched: Vec<String>,
+}
+
+impl OverrideUsage {
+    /// Records that the override of the absolute path was applied.
+    pub(crate) fn record_path(&self, path: &str) {
+        let mut paths = self.paths.lock().unwrap();
+        if !paths.contains(path) {
+            paths.insert(path.to_owned());
+        }
+    }
+
+    /// Records that the regex override at `index` of `Config.json_regex_type_overrides` was applied.
+    #[cfg(feature = "regex_path")]
+    pub(crate) fn record_regex(&self, index: usize) {
+        self.regexes.lock().unwrap().insert(index);
+    }
+
+    /// Splits the overrides of `config` into the ones that were applied since the usage was created
+    /// and the ones that weren't. An absolute path that is only ever shadowed by a regex override
+    /// counts as not applied. Use the same `config` the usage was set on.
+    pub fn report(&self, config: &Config) -> OverrideReport {
+        let paths = self.paths.lock().unwrap();
+        let mut overrides: Vec<&String> = config.json_type_overrides.keys().collect();
+        overrides.sort();
+        let rules = overrides
+            .into_iter()
+            .map(|path| (path.clone(), paths.contains(path)));
+
+        #[cfg(feature = "regex_path")]
+        let regexes = self.regexes.lock().unwrap();
+        #[cfg(feature = "regex_path")]
+        let rules = rules.chain(
+            config
+                .json_regex_type_overrides
+                .iter()
+                .enumerate()
+                .map(|(i, (regex, _))| (regex.as_str().to_owned(), regexes.contains(&i))),
+        );
+
+        let (matched, unmatched): (Vec<_>, Vec<_>) = rules.partition(|(_, applied)| *applied);
+        OverrideReport {
+            matched: matched.into_iter().map(|(rule, _)| rule).collect(),
+            unmatched: unmatched.into_iter().map(|(rule, _)| rule).collect(),
+        }
+    }
+}
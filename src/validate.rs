@@ -0,0 +1,242 @@
+//! Checks of the paths in a `Config` for mistakes that would make them silently match nothing.
+
+use std::fmt;
+
+use crate::{Config, RawValues};
+
+/// Describes what is wrong with a path of a `Config`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigWarningKind {
+    /// The path doesn't start with `/`.
+    NotAbsolute,
+    /// The path has an empty step, e.g. `/a//b` or `/a/b/`.
+    EmptyStep,
+    /// A step is not an element or attribute name, e.g. `b[1]` or `*`. Paths don't support XPath
+    /// predicates, axes or wildcards.
+    InvalidName(String),
+    /// A step contains an `@` after its start, e.g. `/a/b@id` instead of `/a/b/@id`.
+    MisplacedAt(String),
+    /// An attribute is followed by more steps, e.g. `/a/@b/c`.
+    AttributeNotLast,
+    /// The option only applies to attributes, but the path ends with an element name, e.g. `/a/b/id`
+    /// instead of `/a/b/@id` in `Config.idref_paths`.
+    NotAnAttribute,
+    /// The option only applies to elements, but the path ends with an attribute.
+    NotAnElement,
+    /// The override never applies, because the regex override with this pattern matches the path
+    /// and takes precedence.
+    ShadowedByRegex(String),
+}
+
+/// A problem with a path of a `Config`, found by `Config::validate`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigWarning {
+    /// The name of the `Config` field with the path, e.g. `json_type_overrides`.
+    pub option: &'static str,
+    /// The path as it is configured.
+    pub path: String,
+    /// What is wrong with it.
+    pub kind: ConfigWarningKind,
+}
+
+impl fmt::Display for ConfigWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {:?}: ", self.option, self.path)?;
+        match &self.kind {
+            ConfigWarningKind::NotAbsolute => write!(f, "path doesn't start with `/`"),
+            ConfigWarningKind::EmptyStep => write!(f, "path has an empty step"),
+            ConfigWarningKind::InvalidName(step) => write!(f, "{:?} is not a name", step),
+            ConfigWarningKind::MisplacedAt(step) => {
+                write!(f, "{:?} has an `@` that is not at its start", step)
+            }
+            ConfigWarningKind::AttributeNotLast => write!(f, "attribute is not the last step"),
+            ConfigWarningKind::NotAnAttribute => write!(f, "path doesn't end with an attribute"),
+            ConfigWarningKind::NotAnElement => write!(f, "path doesn't end with an element"),
+            ConfigWarningKind::ShadowedByRegex(regex) => {
+                write!(f, "never applies, the regex {:?} takes precedence", regex)
+            }
+        }
+    }
+}
+
+/// The kinds of nodes the paths of an option apply to.
+#[derive(Clone, Copy, PartialEq)]
+enum Target {
+    Any,
+    Element,
+    Attribute,
+}
+
+impl Config {
+    /// Checks the paths of the JSON type overrides, `nillable_paths`, `raw_values`, `xml_base_paths`,
+    /// `idref_paths`, `inner_text_paths`, `text_prop_names`, `unit_paths`, `array_limits` and `pseudonymized_paths` for mistakes that make them silently match nothing: malformed paths, XPath
+    /// syntax that isn't supported, attributes without `@` where only attributes apply, and overrides
+    /// that are shadowed by a regex override. Returns an empty list if there are none.
+    /// # Example
+    /// ```
+    /// use roxmltree_to_serde::{Config, ConfigWarningKind, IdRef};
+    ///
+    /// let conf = Config::new_with_defaults()
+    ///     .add_xml_base_path("/feed//link/@href")
+    ///     .add_idref_path("/order/item/product", IdRef::Inline);
+    /// let warnings = conf.validate();
+    /// assert_eq!(warnings[0].kind, ConfigWarningKind::EmptyStep);
+    /// assert_eq!(warnings[1].kind, ConfigWarningKind::NotAnAttribute);
+    /// assert_eq!(
+    ///     warnings[1].to_string(),
+    ///     r#"idref_paths "/order/item/product": path doesn't end with an attribute"#
+    /// );
+    /// ```
+    pub fn validate(&self) -> Vec<ConfigWarning> {
+        let mut warnings = Vec::new();
+
+        #[cfg(feature = "json_types")]
+        {
+            let mut paths: Vec<&String> = self.json_type_overrides.keys().collect();
+            paths.sort();
+            for path in paths {
+                check_path(&mut warnings, "json_type_overrides", path, Target::Any);
+                #[cfg(feature = "regex_path")]
+                if let Some((regex, _)) = self
+                    .json_regex_type_overrides
+                    .iter()
+                    .find(|(regex, _)| regex.is_match(path))
+                {
+                    warnings.push(ConfigWarning {
+                        option: "json_type_overrides",
+                        path: path.clone(),
+                        kind: ConfigWarningKind::ShadowedByRegex(regex.as_str().to_owned()),
+                    });
+                }
+            }
+            let mut paths: Vec<&String> = self.nillable_paths.iter().collect();
+            paths.sort();
+            for path in paths {
+                check_path(&mut warnings, "nillable_paths", path, Target::Element);
+            }
+        }
+
+        if let RawValues::Paths(paths) = &self.raw_values {
+            let mut paths: Vec<&String> = paths.iter().collect();
+            paths.sort();
+            for path in paths {
+                check_path(&mut warnings, "raw_values", path, Target::Any);
+            }
+        }
+        let mut paths: Vec<&String> = self.xml_base_paths.iter().collect();
+        paths.sort();
+        for path in paths {
+            check_path(&mut warnings, "xml_base_paths", path, Target::Attribute);
+        }
+        let mut paths: Vec<&String> = self.idref_paths.keys().collect();
+        paths.sort();
+        for path in paths {
+            check_path(&mut warnings, "idref_paths", path, Target::Attribute);
+        }
+        let mut paths: Vec<&String> = self.inner_text_paths.iter().collect();
+        paths.sort();
+        for path in paths {
+            check_path(&mut warnings, "inner_text_paths", path, Target::Element);
+        }
+        let mut paths: Vec<&String> = self.text_prop_names.keys().collect();
+        paths.sort();
+        for path in paths {
+            check_path(&mut warnings, "text_prop_names", path, Target::Element);
+        }
+        let mut paths: Vec<&String> = self.unit_paths.keys().collect();
+        paths.sort();
+        for path in paths {
+            check_path(&mut warnings, "unit_paths", path, Target::Any);
+        }
+        let mut paths: Vec<&String> = self.array_limits.keys().collect();
+        paths.sort();
+        for path in paths {
+            check_path(&mut warnings, "array_limits", path, Target::Element);
+        }
+        #[cfg(feature = "pseudonymize")]
+        {
+            let mut paths: Vec<&String> = self.pseudonymized_paths.iter().collect();
+            paths.sort();
+            for path in paths {
+                check_path(&mut warnings, "pseudonymized_paths", path, Target::Any);
+            }
+        }
+        warnings
+    }
+}
+
+/// Adds a warning for the first problem of the path, if there is one.
+fn check_path(warnings: &mut Vec<ConfigWarning>, option: &'static str, path: &str, target: Target) {
+    if let Some(kind) = path_problem(path, target) {
+        warnings.push(ConfigWarning {
+            option,
+            path: path.to_owned(),
+            kind,
+        });
+    }
+}
+
+fn path_problem(path: &str, target: Target) -> Option<ConfigWarningKind> {
+    let Some(path) = path.strip_prefix('/') else {
+        return Some(ConfigWarningKind::NotAbsolute);
+    };
+    let steps = steps(path);
+    for (i, step) in steps.iter().enumerate() {
+        let name = step.strip_prefix('@').unwrap_or(step);
+        // the namespace URI of a name in Clark notation may contain anything
+        let local_name = match name.strip_prefix('{').and_then(|n| n.split_once('}')) {
+            Some((_, local_name)) => local_name,
+            None => name,
+        };
+        if local_name.is_empty() {
+            return Some(ConfigWarningKind::EmptyStep);
+        }
+        if local_name.contains('@') {
+            return Some(ConfigWarningKind::MisplacedAt((*step).to_owned()));
+        }
+        if !is_name(local_name) {
+            return Some(ConfigWarningKind::InvalidName((*step).to_owned()));
+        }
+        if step.starts_with('@') && i + 1 < steps.len() {
+            return Some(ConfigWarningKind::AttributeNotLast);
+        }
+    }
+    let is_attribute = steps.last().is_some_and(|step| step.starts_with('@'));
+    match target {
+        Target::Attribute if !is_attribute => Some(ConfigWarningKind::NotAnAttribute),
+        Target::Element if is_attribute => Some(ConfigWarningKind::NotAnElement),
+        _ => None,
+    }
+}
+
+/// Splits a path without the leading `/` into its steps, keeping the namespace URIs of names in
+/// Clark notation together.
+fn steps(path: &str) -> Vec<&str> {
+    let mut steps = Vec::new();
+    let (mut start, mut in_uri) = (0, false);
+    for (i, c) in path.char_indices() {
+        match c {
+            '{' => in_uri = true,
+            '}' => in_uri = false,
+            '/' if !in_uri => {
+                steps.push(&path[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    steps.push(&path[start..]);
+    steps
+}
+
+/// Returns `true` if the text is a plausible XML name, optionally with a namespace prefix.
+fn is_name(name: &str) -> bool {
+    let starts_like_name = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_' || c == ':');
+    starts_like_name
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | ':' | '-' | '.') || !c.is_ascii())
+}
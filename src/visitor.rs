@@ -0,0 +1,292 @@
+//! Conversion into custom outputs with a visitor that receives the converted nodes as events.
+
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+#[cfg(feature = "json_types")]
+use crate::is_nil;
+use crate::xml_base;
+use crate::{
+    collapses_to_attribute, convert_attributes, convert_value, element_key, finish_conversion,
+    get_json_type, is_inner_text, keep_element, keep_text_attribute, key_name, parse_document,
+    prepare_input, start_conversion, text_content, Config, Context, Error, NullValue,
+};
+
+/// Receives the events of `xml_str_to_visitor` in document order, with the keys and values converted
+/// by the same rules as in `xml_str_to_json`. All methods do nothing by default.
+///
+/// Repeated elements are not grouped into arrays, every one of them has its own events. Elements that
+/// become arrays regardless of their siblings are flagged in `on_element_start`.
+pub trait XmlVisitor {
+    /// Called when an element starts, with its key and absolute path. `always_array` is set if the element
+    /// is an array in the output even without siblings of the same name, because of a JSON type override
+    /// or `ArrayInference::DocumentWide`.
+    fn on_element_start(&mut self, _key: &str, _path: &str, _always_array: bool) {}
+
+    /// Called for every attribute of the current element, with its key including
+    /// `Config.xml_attr_prefix`, its path, e.g. `/a/@id`, and its converted value.
+    fn on_attribute(&mut self, _key: &str, _path: &str, _value: Value) {}
+
+    /// Called with the converted text of the current element after its attributes, e.g. `42` for
+    /// `<n>42</n>`. Elements that start with text have no child elements in the output, and `xsi:nil`
+    /// elements, `Config.inner_text_paths` and `Config.collapse_single_attributes` are reported as text, too.
+    fn on_text(&mut self, _path: &str, _value: Value) {}
+
+    /// Called when an element ends, after its attributes, text and child elements.
+    fn on_element_end(&mut self, _key: &str, _path: &str) {}
+}
+
+/// The default visitor, which builds the same `serde_json::Value` as `xml_str_to_json`. Options that
+/// change the finished output, like `Config.prune_empty`, `Config.idref_paths` or `Config.post_process`,
+/// as well as `Config.array_limits`, source locations and namespace declarations are not applied.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_str_to_visitor, Config, JsonBuilder};
+/// use serde_json::json;
+///
+/// let config = Config::new_with_defaults();
+/// let mut builder = JsonBuilder::new(&config);
+/// xml_str_to_visitor(r#"<a id="1"><b>x</b><b>y</b></a>"#, &config, &mut builder).unwrap();
+/// assert_eq!(builder.into_value(), json!({"a": {"@id": 1, "b": ["x", "y"]}}));
+/// ```
+#[derive(Debug)]
+pub struct JsonBuilder {
+    text_prop_name: String,
+    text_prop_names: HashMap<String, String>,
+    empty_element_handling: NullValue,
+    open: Vec<OpenElement>,
+    value: Option<Value>,
+}
+
+/// An element of `JsonBuilder` that has started but not ended yet.
+#[derive(Debug)]
+struct OpenElement {
+    always_array: bool,
+    data: Map<String, Value>,
+    text: Option<Value>,
+}
+
+impl JsonBuilder {
+    pub fn new(config: &Config) -> Self {
+        JsonBuilder {
+            text_prop_name: config.xml_text_node_prop_name.clone(),
+            text_prop_names: config.text_prop_names.clone(),
+            empty_element_handling: config.empty_element_handling,
+            open: Vec::new(),
+            value: None,
+        }
+    }
+
+    /// Returns the built value, or `null` if no document was visited.
+    pub fn into_value(self) -> Value {
+        self.value.unwrap_or(Value::Null)
+    }
+}
+
+impl XmlVisitor for JsonBuilder {
+    fn on_element_start(&mut self, _key: &str, _path: &str, always_array: bool) {
+        self.open.push(OpenElement {
+            always_array,
+            data: Map::new(),
+            text: None,
+        });
+    }
+
+    fn on_attribute(&mut self, key: &str, _path: &str, value: Value) {
+        if let Some(element) = self.open.last_mut() {
+            element.data.insert(key.to_owned(), value);
+        }
+    }
+
+    fn on_text(&mut self, _path: &str, value: Value) {
+        if let Some(element) = self.open.last_mut() {
+            element.text = Some(value);
+        }
+    }
+
+    fn on_element_end(&mut self, key: &str, path: &str) {
+        let Some(element) = self.open.pop() else {
+            return;
+        };
+        let mut data = element.data;
+        let value = match element.text {
+            Some(text) if data.is_empty() => Some(text),
+            Some(text) => {
+                let name = self.text_prop_names.get(path);
+                data.insert(name.unwrap_or(&self.text_prop_name).clone(), text);
+                Some(Value::Object(data))
+            }
+            None if !data.is_empty() => Some(Value::Object(data)),
+            None => match self.empty_element_handling {
+                NullValue::Null => Some(Value::Null),
+                NullValue::EmptyObject => Some(Value::Object(data)),
+                NullValue::Ignore => None,
+            },
+        };
+
+        let Some(parent) = self.open.last_mut() else {
+            let mut root = Map::new();
+            root.insert(key.to_owned(), value.unwrap_or(Value::Null));
+            self.value = Some(Value::Object(root));
+            return;
+        };
+        let Some(value) = value else {
+            return;
+        };
+        // the same grouping of repeated elements as in `convert_no_text`
+        match parent.data.get_mut(key) {
+            Some(Value::Array(existing)) => existing.push(value),
+            Some(existing) => {
+                let first = existing.take();
+                *existing = Value::Array(vec![first, value]);
+            }
+            None if element.always_array => {
+                parent
+                    .data
+                    .insert(key.to_owned(), Value::Array(vec![value]));
+            }
+            None => {
+                parent.data.insert(key.to_owned(), value);
+            }
+        }
+    }
+}
+
+/// Converts the given XML string with the same rules as `xml_str_to_json`, but instead of building a
+/// `serde_json::Value`, the elements, attributes and texts are passed to `visitor` as they are converted.
+/// Use it for custom outputs like database rows without another traversal of the document.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_str_to_visitor, Config, XmlVisitor};
+/// use serde_json::Value;
+///
+/// // collects the `price` of every `item` as a row
+/// struct Prices(Vec<(String, Value)>);
+///
+/// impl XmlVisitor for Prices {
+///     fn on_text(&mut self, path: &str, value: Value) {
+///         if path.ends_with("/item/price") {
+///             self.0.push((path.to_owned(), value));
+///         }
+///     }
+/// }
+///
+/// let xml = "<order><item><price>9.5</price></item><item><price>3</price></item></order>";
+/// let mut prices = Prices(Vec::new());
+/// xml_str_to_visitor(xml, &Config::new_with_defaults(), &mut prices).unwrap();
+/// assert_eq!(prices.0.len(), 2);
+/// assert_eq!(prices.0[1].1, 3);
+/// ```
+pub fn xml_str_to_visitor<V: XmlVisitor>(
+    xml: &str,
+    config: &Config,
+    visitor: &mut V,
+) -> Result<(), Error> {
+    let xml = prepare_input(xml, config)?;
+    let doc = parse_document(&xml, config)?;
+    let mut ctx = Context::new(config, None);
+    start_conversion(&mut ctx, &doc)?;
+    visit_element(&mut ctx, &doc.root_element(), &mut String::new(), visitor)?;
+    finish_conversion(&ctx);
+    Ok(())
+}
+
+/// Sends the events of `el` and its descendants to `visitor`, with the path of its parent in `path`,
+/// following `convert_element` and `convert_element_at_path`.
+fn visit_element<V: XmlVisitor>(
+    ctx: &mut Context,
+    el: &roxmltree::Node,
+    path: &mut String,
+    visitor: &mut V,
+) -> Result<(), Error> {
+    if !keep_element(ctx.config, el) {
+        return Ok(());
+    }
+    let key = element_key(ctx.config, el);
+    let path_len = path.len();
+    path.push('/');
+    path.push_str(&key);
+    ctx.report_element_namespace(path, el)?;
+    ctx.tick()?;
+    ctx.charge(el)?;
+    if ctx.depth >= ctx.config.max_depth {
+        return Err(Error::DepthLimitExceeded(ctx.config.max_depth));
+    }
+
+    let (json_type_array, json_type_value) = get_json_type(ctx.config, path);
+    let always_array = json_type_array || ctx.repeated_names.contains(key.as_ref());
+    visitor.on_element_start(&key, path, always_array);
+
+    #[cfg(feature = "json_types")]
+    let nil = is_nil(ctx.config, el, path);
+    #[cfg(not(feature = "json_types"))]
+    let nil = false;
+
+    if is_inner_text(ctx.config, path) {
+        visitor.on_text(path, Value::String(text_content(ctx.config, el, path)?));
+    } else if nil {
+        visitor.on_text(path, Value::Null);
+    } else {
+        ctx.depth += 1;
+        let text = el.text().filter(|text| !text.trim().is_empty());
+        if text.is_none() && collapses_to_attribute(ctx.config, el) {
+            // `<flag value="3"/>` is reported as the text `3`
+            let mut data = Map::new();
+            convert_attributes(ctx, el, path, &mut data, false)?;
+            visitor.on_text(path, data.into_values().next().unwrap_or(Value::Null));
+        } else {
+            visit_attributes(ctx, el, path, visitor, text.is_some())?;
+        }
+        match text {
+            Some(text) => {
+                for child in el.children().skip(1) {
+                    ctx.report_dropped(path, &child)?;
+                }
+                let value = convert_value(ctx, path, text.trim(), json_type_value)?;
+                visitor.on_text(path, value);
+            }
+            None => {
+                for child in el.children() {
+                    if child.is_element() {
+                        visit_element(ctx, &child, path, visitor)?;
+                    } else {
+                        ctx.report_dropped(path, &child)?;
+                    }
+                }
+            }
+        }
+        ctx.depth -= 1;
+    }
+
+    visitor.on_element_end(&key, path);
+    path.truncate(path_len);
+    Ok(())
+}
+
+/// Sends the converted attributes of `el` to `visitor`, following `convert_attributes`.
+fn visit_attributes<V: XmlVisitor>(
+    ctx: &mut Context,
+    el: &roxmltree::Node,
+    path: &mut String,
+    visitor: &mut V,
+    with_text: bool,
+) -> Result<(), Error> {
+    let path_len = path.len();
+    for attr in el.attributes() {
+        let name = key_name(ctx.config, attr.namespace(), attr.name());
+        if with_text && !keep_text_attribute(ctx.config, &name) {
+            continue;
+        }
+        path.push_str("/@");
+        path.push_str(&name);
+        ctx.report_namespace(path, attr.namespace())?;
+        let (_, json_type_value) = get_json_type(ctx.config, path);
+        let text = xml_base::resolve_attribute(ctx.config, el, path, attr.value());
+        let value = convert_value(ctx, path, &text, json_type_value)?;
+        let key = [ctx.config.xml_attr_prefix.as_str(), &name].concat();
+        visitor.on_attribute(&key, path, value);
+        path.truncate(path_len);
+    }
+    Ok(())
+}
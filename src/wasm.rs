@@ -0,0 +1,22 @@
+//! Bindings for JavaScript with wasm-bindgen, available with the `wasm` feature.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{xml_str_to_json, Config, Error};
+
+/// Converts an XML document into a JSON string with the same rules as `xml_str_to_json` and a `Config`
+/// in the JSON format of `Config::from_json`, or the defaults for an empty string. In JavaScript it's
+/// `xml_to_json(xml, configJson)`, which throws an `Error` with the message of the failure.
+#[wasm_bindgen]
+pub fn xml_to_json(xml: &str, config_json: &str) -> Result<String, JsError> {
+    convert(xml, config_json).map_err(|e| JsError::new(&e.to_string()))
+}
+
+pub(crate) fn convert(xml: &str, config_json: &str) -> Result<String, Error> {
+    let config = if config_json.trim().is_empty() {
+        Config::new_with_defaults()
+    } else {
+        Config::from_json(config_json)?
+    };
+    Ok(xml_str_to_json(xml, &config)?.to_string())
+}
@@ -0,0 +1,275 @@
+//! Conversion of JSON back into XML, following the same conventions as the XML to JSON conversion.
+
+use serde_json::{Map, Value};
+use std::io::Write;
+
+use crate::{text_prop_name, Config, Error};
+
+/// Formatting options for `json_to_xml_writer`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct XmlWriterConfig {
+    /// Indentation for each nesting level, e.g. two spaces. Elements are written on a single line if it's `None`.
+    /// Defaults to `None`.
+    pub indent: Option<String>,
+    /// Write an `<?xml version="1.0" encoding="UTF-8"?>` declaration before the root element.
+    /// Defaults to `false`.
+    pub declaration: bool,
+}
+
+impl XmlWriterConfig {
+    pub fn new_with_defaults() -> Self {
+        XmlWriterConfig {
+            indent: None,
+            declaration: false,
+        }
+    }
+
+    /// Indents nested elements with the given string.
+    pub fn with_indent(self, indent: &str) -> Self {
+        XmlWriterConfig {
+            indent: Some(indent.to_owned()),
+            ..self
+        }
+    }
+
+    /// Writes an XML declaration before the root element.
+    pub fn with_declaration(self) -> Self {
+        XmlWriterConfig {
+            declaration: true,
+            ..self
+        }
+    }
+}
+
+impl Default for XmlWriterConfig {
+    fn default() -> Self {
+        XmlWriterConfig::new_with_defaults()
+    }
+}
+
+/// Converts JSON into XML and writes it incrementally to `writer`, so the document never has to be held
+/// in memory as a whole. This is the reverse of `xml_str_to_json` with the same `Config`:
+/// properties starting with `Config.xml_attr_prefix` become attributes, `Config.xml_text_node_prop_name`
+/// or the name of `Config.text_prop_names` becomes the text of the element, arrays become repeated elements and `null` becomes an empty element.
+///
+/// The value must be an object with a single property for the root element. Anything that can't be
+/// represented in XML fails with `Error::UnexpectedJson`, write errors with `Error::Io`.
+/// Use a `BufWriter` for files and sockets, because the XML is written in many small pieces.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{json_to_xml_writer, Config, XmlWriterConfig};
+/// use serde_json::json;
+///
+/// let json = json!({"a": {"@x": 1, "b": ["one", "two"]}});
+/// let mut xml = Vec::new();
+/// json_to_xml_writer(&json, &Config::new_with_defaults(), &XmlWriterConfig::new_with_defaults(), &mut xml).unwrap();
+/// assert_eq!(String::from_utf8(xml).unwrap(), r#"<a x="1"><b>one</b><b>two</b></a>"#);
+/// ```
+pub fn json_to_xml_writer<W: Write>(
+    json: &Value,
+    config: &Config,
+    writer_config: &XmlWriterConfig,
+    writer: W,
+) -> Result<(), Error> {
+    let root = match json {
+        Value::Object(map) if map.len() == 1 => map.iter().next(),
+        _ => None,
+    };
+    let Some((name, value)) = root else {
+        return Err(Error::UnexpectedJson(
+            "the root must be an object with a single property".to_owned(),
+        ));
+    };
+    if value.is_array() {
+        return Err(Error::UnexpectedJson(format!(
+            "multiple root elements `{}`",
+            name
+        )));
+    }
+
+    let mut xml = XmlWriter {
+        config,
+        writer_config,
+        writer,
+        depth: 0,
+        path: String::new(),
+    };
+    if writer_config.declaration {
+        xml.write(r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        xml.newline()?;
+    }
+    xml.element(name, value)?;
+    xml.writer.flush()?;
+    Ok(())
+}
+
+/// Converts JSON into an XML string. See `json_to_xml_writer` for details.
+pub fn json_to_xml_string(
+    json: &Value,
+    config: &Config,
+    writer_config: &XmlWriterConfig,
+) -> Result<String, Error> {
+    let mut xml = Vec::new();
+    json_to_xml_writer(json, config, writer_config, &mut xml)?;
+    // only valid UTF-8 is written
+    Ok(String::from_utf8(xml).unwrap_or_default())
+}
+
+struct XmlWriter<'a, W: Write> {
+    config: &'a Config,
+    writer_config: &'a XmlWriterConfig,
+    writer: W,
+    depth: usize,
+    /// The path of the current element, for `Config.text_prop_names`.
+    path: String,
+}
+
+impl<W: Write> XmlWriter<'_, W> {
+    fn write(&mut self, s: &str) -> Result<(), Error> {
+        Ok(self.writer.write_all(s.as_bytes())?)
+    }
+
+    /// Starts a new line with the indentation of the current depth, if indentation is enabled.
+    fn newline(&mut self) -> Result<(), Error> {
+        if let Some(indent) = &self.writer_config.indent {
+            self.writer.write_all(b"\n")?;
+            for _ in 0..self.depth {
+                self.writer.write_all(indent.as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes `value` as one element, or as one element per item for arrays.
+    fn element(&mut self, name: &str, value: &Value) -> Result<(), Error> {
+        check_name(name)?;
+        match value {
+            Value::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        self.newline()?;
+                    }
+                    if item.is_array() {
+                        return Err(Error::UnexpectedJson(format!(
+                            "nested arrays can't be represented in XML `{}`",
+                            name
+                        )));
+                    }
+                    self.element(name, item)?;
+                }
+                Ok(())
+            }
+            Value::Object(map) => self.object(name, map),
+            Value::Null => {
+                self.write("<")?;
+                self.write(name)?;
+                self.write("/>")
+            }
+            scalar => {
+                self.write("<")?;
+                self.write(name)?;
+                self.write(">")?;
+                self.write(&escape(&scalar_to_string(name, scalar)?, false))?;
+                self.write("</")?;
+                self.write(name)?;
+                self.write(">")
+            }
+        }
+    }
+
+    fn object(&mut self, name: &str, map: &Map<String, Value>) -> Result<(), Error> {
+        let prefix = self.config.xml_attr_prefix.as_str();
+        let path_len = self.path.len();
+        self.path.push('/');
+        self.path.push_str(name);
+        let text_name = text_prop_name(self.config, &self.path);
+
+        self.write("<")?;
+        self.write(name)?;
+        let mut text = None;
+        let mut has_children = false;
+        for (key, value) in map {
+            if key == text_name {
+                text = Some(value);
+            } else if let Some(attr) = key.strip_prefix(prefix).filter(|_| !prefix.is_empty()) {
+                check_name(attr)?;
+                self.write(" ")?;
+                self.write(attr)?;
+                self.write("=\"")?;
+                self.write(&escape(&scalar_to_string(key, value)?, true))?;
+                self.write("\"")?;
+            } else {
+                has_children = true;
+            }
+        }
+
+        if text.is_none() && !has_children {
+            self.path.truncate(path_len);
+            return self.write("/>");
+        }
+        self.write(">")?;
+        if let Some(text) = text {
+            self.write(&escape(&scalar_to_string(text_name, text)?, false))?;
+        }
+        if has_children {
+            self.depth += 1;
+            for (key, value) in map {
+                if key == text_name || (!prefix.is_empty() && key.starts_with(prefix)) {
+                    continue;
+                }
+                self.newline()?;
+                self.element(key, value)?;
+            }
+            self.depth -= 1;
+            self.newline()?;
+        }
+        self.path.truncate(path_len);
+        self.write("</")?;
+        self.write(name)?;
+        self.write(">")
+    }
+}
+
+/// Returns the text of a value that is written as text or attribute value.
+fn scalar_to_string(name: &str, value: &Value) -> Result<String, Error> {
+    match value {
+        Value::Null => Ok(String::new()),
+        Value::String(s) => Ok(s.clone()),
+        Value::Bool(_) | Value::Number(_) => Ok(value.to_string()),
+        Value::Array(_) | Value::Object(_) => Err(Error::UnexpectedJson(format!(
+            "`{}` must be a scalar value",
+            name
+        ))),
+    }
+}
+
+/// Checks that the JSON key is a valid XML name, e.g. not `#loc` or `1st`.
+fn check_name(name: &str) -> Result<(), Error> {
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_' || c == ':')
+        && chars.all(|c| c.is_alphanumeric() || matches!(c, '_' | ':' | '-' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::UnexpectedJson(format!(
+            "`{}` is not a valid XML name",
+            name
+        )))
+    }
+}
+
+fn escape(text: &str, attribute: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if attribute => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,169 @@
+//! Resolution of relative URIs against `xml:base`, see `Config.xml_base_paths`.
+
+use std::borrow::Cow;
+
+use crate::Config;
+
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Returns the value of the attribute at `path` of `el`, resolved against the base URI in scope
+/// if `path` is in `Config.xml_base_paths`.
+#[inline]
+pub(crate) fn resolve_attribute<'v>(
+    config: &Config,
+    el: &roxmltree::Node,
+    path: &str,
+    value: &'v str,
+) -> Cow<'v, str> {
+    if config.xml_base_paths.is_empty() || !config.xml_base_paths.contains(path) {
+        return Cow::Borrowed(value);
+    }
+    match base_uri(config, el) {
+        Some(base) => Cow::Owned(resolve(&base, value.trim())),
+        None => Cow::Borrowed(value),
+    }
+}
+
+/// Returns the base URI in scope of `el`: the `xml:base` attributes of the element and its ancestors,
+/// each resolved against the outer one, starting from `Config.base_uri`.
+fn base_uri(config: &Config, el: &roxmltree::Node) -> Option<String> {
+    let bases: Vec<&str> = el
+        .ancestors()
+        .filter_map(|n| n.attribute((XML_NAMESPACE, "base")))
+        .collect();
+    bases
+        .iter()
+        .rev()
+        .fold(config.base_uri.clone(), |base, reference| {
+            Some(match base {
+                Some(base) => resolve(&base, reference.trim()),
+                None => reference.trim().to_owned(),
+            })
+        })
+}
+
+/// The components of a URI reference, see RFC 3986 section 3.
+struct Components<'u> {
+    scheme: Option<&'u str>,
+    authority: Option<&'u str>,
+    path: &'u str,
+    query: Option<&'u str>,
+    fragment: Option<&'u str>,
+}
+
+fn parse(uri: &str) -> Components<'_> {
+    let (rest, fragment) = match uri.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (uri, None),
+    };
+    let (rest, query) = match rest.split_once('?') {
+        Some((rest, query)) => (rest, Some(query)),
+        None => (rest, None),
+    };
+    let (scheme, rest) = match rest.split_once(':') {
+        Some((scheme, rest)) if is_scheme(scheme) => (Some(scheme), rest),
+        _ => (None, rest),
+    };
+    let (authority, path) = match rest.strip_prefix("//") {
+        Some(rest) => {
+            let end = rest.find('/').unwrap_or(rest.len());
+            (Some(&rest[..end]), &rest[end..])
+        }
+        None => (None, rest),
+    };
+    Components {
+        scheme,
+        authority,
+        path,
+        query,
+        fragment,
+    }
+}
+
+fn is_scheme(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic())
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+/// Resolves the URI `reference` against `base`, see RFC 3986 section 5.2. A relative `base` gives
+/// a relative result, e.g. `../img/a.png` against `docs/ch1/` becomes `docs/img/a.png`.
+pub(crate) fn resolve(base: &str, reference: &str) -> String {
+    let r = parse(reference);
+    let b = parse(base);
+    let (scheme, authority, path, query) = if r.scheme.is_some() {
+        (r.scheme, r.authority, remove_dot_segments(r.path), r.query)
+    } else if r.authority.is_some() {
+        (b.scheme, r.authority, remove_dot_segments(r.path), r.query)
+    } else if r.path.is_empty() {
+        (
+            b.scheme,
+            b.authority,
+            b.path.to_owned(),
+            r.query.or(b.query),
+        )
+    } else if r.path.starts_with('/') {
+        (b.scheme, b.authority, remove_dot_segments(r.path), r.query)
+    } else {
+        let merged = if b.authority.is_some() && b.path.is_empty() {
+            ["/", r.path].concat()
+        } else {
+            let dir = b.path.rfind('/').map_or("", |i| &b.path[..=i]);
+            [dir, r.path].concat()
+        };
+        (b.scheme, b.authority, remove_dot_segments(&merged), r.query)
+    };
+
+    let mut uri = String::with_capacity(base.len() + reference.len());
+    if let Some(scheme) = scheme {
+        uri.push_str(scheme);
+        uri.push(':');
+    }
+    if let Some(authority) = authority {
+        uri.push_str("//");
+        uri.push_str(authority);
+    }
+    uri.push_str(&path);
+    if let Some(query) = query {
+        uri.push('?');
+        uri.push_str(query);
+    }
+    if let Some(fragment) = r.fragment {
+        uri.push('#');
+        uri.push_str(fragment);
+    }
+    uri
+}
+
+/// Removes the `.` and `..` segments of a path, see RFC 3986 section 5.2.4.
+fn remove_dot_segments(path: &str) -> String {
+    let mut output: Vec<&str> = Vec::new();
+    let mut input = path;
+    while !input.is_empty() {
+        if let Some(rest) = input
+            .strip_prefix("../")
+            .or_else(|| input.strip_prefix("./"))
+        {
+            input = rest;
+        } else if input.starts_with("/./") {
+            input = &input[2..];
+        } else if input == "/." {
+            input = "/";
+        } else if input.starts_with("/../") {
+            input = &input[3..];
+            output.pop();
+        } else if input == "/.." {
+            input = "/";
+            output.pop();
+        } else if input == "." || input == ".." {
+            input = "";
+        } else {
+            // move the first segment with its leading `/` to the output
+            let start = usize::from(input.starts_with('/'));
+            let end = input[start..].find('/').map_or(input.len(), |i| i + start);
+            output.push(&input[..end]);
+            input = &input[end..];
+        }
+    }
+    output.concat()
+}
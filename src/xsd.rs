@@ -0,0 +1,747 @@
+//! Conversion rules derived from an XML Schema (XSD).
+//!
+//! The schema decides the JSON types of elements and attributes, which elements are arrays
+//! (`maxOccurs` greater than 1) and which elements may be `xsi:nil`, so there is nothing left to guess.
+//! Only a single schema document is supported: `xs:import` and `xs:include` are not followed
+//! and type names are matched by their local names.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::{
+    parse_document, Config, Diagnostic, DiagnosticKind, Error, JsonArray, JsonType, XSI_NAMESPACE,
+};
+
+const XSD_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema";
+
+/// Limits the resolution of chained simple types, groups and attribute groups,
+/// which may refer to each other in a loop in a broken schema.
+const MAX_REFERENCE_DEPTH: usize = 32;
+
+/// The JSON-relevant category of an XSD simple type.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SimpleType {
+    String,
+    Number,
+    Boolean,
+    /// `xs:anySimpleType` or a type that could not be resolved.
+    Any,
+}
+
+impl SimpleType {
+    /// Maps a built-in XSD type to its category. Dates, times and binary types are kept as strings.
+    fn from_builtin(name: &str) -> Self {
+        match name {
+            "decimal" | "float" | "double" | "integer" | "int" | "long" | "short" | "byte"
+            | "nonNegativeInteger" | "positiveInteger" | "nonPositiveInteger"
+            | "negativeInteger" | "unsignedLong" | "unsignedInt" | "unsignedShort"
+            | "unsignedByte" => SimpleType::Number,
+            "boolean" => SimpleType::Boolean,
+            "anySimpleType" | "anyType" => SimpleType::Any,
+            _ => SimpleType::String,
+        }
+    }
+
+    pub(crate) fn json_type(self) -> JsonType {
+        match self {
+            SimpleType::String => JsonType::AlwaysString,
+            SimpleType::Number => JsonType::AlwaysNumber,
+            SimpleType::Boolean => JsonType::Bool(vec!["true", "1"]),
+            SimpleType::Any => JsonType::Infer,
+        }
+    }
+}
+
+/// The type of an element declaration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum TypeRef {
+    Simple(SimpleType),
+    /// Index of the complex type in `XsdSchema.types`.
+    Complex(usize),
+    /// `xs:anyType` or an element without a type, anything goes.
+    Any,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ElementDecl {
+    pub(crate) name: String,
+    pub(crate) min_occurs: u64,
+    /// `None` stands for `unbounded`.
+    pub(crate) max_occurs: Option<u64>,
+    pub(crate) nillable: bool,
+    pub(crate) ty: TypeRef,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct AttributeDecl {
+    pub(crate) name: String,
+    pub(crate) ty: SimpleType,
+    pub(crate) required: bool,
+}
+
+/// The content model of a complex type. Model groups are flattened into a list of child elements
+/// with the occurrence limits of the groups applied to them.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ComplexType {
+    pub(crate) attributes: Vec<AttributeDecl>,
+    pub(crate) children: Vec<ElementDecl>,
+    /// The type of the text node for simple content.
+    pub(crate) text: Option<SimpleType>,
+    pub(crate) mixed: bool,
+    /// `xs:any` allows elements that are not declared.
+    pub(crate) any_children: bool,
+    /// `xs:anyAttribute` allows attributes that are not declared.
+    pub(crate) any_attributes: bool,
+}
+
+/// A parsed XML Schema. Use it with `Config::add_xsd_schema` to derive conversion rules.
+#[derive(Debug, Clone)]
+pub struct XsdSchema {
+    /// Global element declarations, i.e. the possible root elements.
+    pub(crate) roots: Vec<ElementDecl>,
+    /// All complex types, named and anonymous. Recursive types refer to each other by index.
+    pub(crate) types: Vec<ComplexType>,
+}
+
+impl XsdSchema {
+    /// Parses an XSD document. Fails on malformed XML or if the root element is not `xs:schema`.
+    pub fn parse(xsd: &str) -> Result<Self, Error> {
+        let doc = parse_document(xsd, &Config::new_with_defaults())?;
+        let root = doc.root_element();
+        if !is_xsd(&root, "schema") {
+            return Err(Error::Xsd("the root element is not xs:schema".to_owned()));
+        }
+
+        let mut parser = Parser::default();
+        for node in root.children().filter(|n| n.is_element()) {
+            if let Some(name) = node.attribute("name") {
+                let map = match node.tag_name().name() {
+                    "element" => &mut parser.elements,
+                    "complexType" => &mut parser.complex_types,
+                    "simpleType" => &mut parser.simple_types,
+                    "group" => &mut parser.groups,
+                    "attributeGroup" => &mut parser.attribute_groups,
+                    "attribute" => &mut parser.attributes,
+                    _ => continue,
+                };
+                map.insert(name, node);
+            }
+        }
+
+        let roots = root
+            .children()
+            .filter(|n| is_xsd(n, "element"))
+            .map(|n| parser.element(&n, (1, Some(1)), false))
+            .collect();
+
+        Ok(XsdSchema {
+            roots,
+            types: parser.types,
+        })
+    }
+}
+
+fn is_xsd(node: &roxmltree::Node, name: &str) -> bool {
+    node.is_element()
+        && node.tag_name().namespace() == Some(XSD_NAMESPACE)
+        && node.tag_name().name() == name
+}
+
+/// Strips the prefix from a QName like `xs:string`.
+fn local_name(qname: &str) -> &str {
+    qname.rsplit(':').next().unwrap_or(qname)
+}
+
+/// Returns `true` if the QName refers to a built-in XSD type.
+fn is_builtin(node: &roxmltree::Node, qname: &str) -> bool {
+    let prefix = qname.rsplit_once(':').map(|(prefix, _)| prefix);
+    node.lookup_namespace_uri(prefix) == Some(XSD_NAMESPACE)
+}
+
+/// Parses `minOccurs` and `maxOccurs` of a particle, both default to 1.
+fn occurs(node: &roxmltree::Node) -> (u64, Option<u64>) {
+    let min = node
+        .attribute("minOccurs")
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(1);
+    let max = match node.attribute("maxOccurs").map(str::trim) {
+        Some("unbounded") => None,
+        Some(v) => Some(v.parse().unwrap_or(1)),
+        None => Some(1),
+    };
+    (min, max)
+}
+
+/// Applies the occurrence limits of a model group to a particle inside of it.
+fn multiply(outer: (u64, Option<u64>), inner: (u64, Option<u64>)) -> (u64, Option<u64>) {
+    let min = outer.0.saturating_mul(inner.0);
+    let max = match (outer.1, inner.1) {
+        (Some(a), Some(b)) => Some(a.saturating_mul(b)),
+        _ => None,
+    };
+    (min, max)
+}
+
+#[derive(Default)]
+struct Parser<'a, 'input> {
+    elements: HashMap<&'a str, roxmltree::Node<'a, 'input>>,
+    complex_types: HashMap<&'a str, roxmltree::Node<'a, 'input>>,
+    simple_types: HashMap<&'a str, roxmltree::Node<'a, 'input>>,
+    groups: HashMap<&'a str, roxmltree::Node<'a, 'input>>,
+    attribute_groups: HashMap<&'a str, roxmltree::Node<'a, 'input>>,
+    attributes: HashMap<&'a str, roxmltree::Node<'a, 'input>>,
+    /// Named complex types that were already resolved, or are being resolved right now.
+    resolved: HashMap<&'a str, usize>,
+    types: Vec<ComplexType>,
+}
+
+impl<'a, 'input> Parser<'a, 'input> {
+    /// Parses an `xs:element` declaration or reference. `occurs` are the effective occurrence
+    /// limits of the enclosing model groups.
+    fn element(
+        &mut self,
+        node: &roxmltree::Node<'a, 'input>,
+        occurs: (u64, Option<u64>),
+        in_choice: bool,
+    ) -> ElementDecl {
+        let (min_occurs, max_occurs) = multiply(occurs, self::occurs(node));
+        let min_occurs = if in_choice { 0 } else { min_occurs };
+
+        // a reference takes everything but the occurrence limits from the global declaration
+        let decl = match node.attribute("ref") {
+            Some(r) => self.elements.get(local_name(r)).copied().unwrap_or(*node),
+            None => *node,
+        };
+        let name = decl
+            .attribute("name")
+            .or_else(|| node.attribute("ref").map(local_name))
+            .unwrap_or_default();
+
+        let ty = if let Some(ty) = decl.attribute("type") {
+            self.type_ref(&decl, ty)
+        } else if let Some(ct) = decl.children().find(|n| is_xsd(n, "complexType")) {
+            let ct = self.complex_type(&ct);
+            self.types.push(ct);
+            TypeRef::Complex(self.types.len() - 1)
+        } else if let Some(st) = decl.children().find(|n| is_xsd(n, "simpleType")) {
+            TypeRef::Simple(self.simple_type(&st, 0))
+        } else {
+            TypeRef::Any
+        };
+
+        ElementDecl {
+            name: name.to_owned(),
+            min_occurs,
+            max_occurs,
+            nillable: matches!(decl.attribute("nillable"), Some("true") | Some("1")),
+            ty,
+        }
+    }
+
+    /// Resolves a `type` attribute of an element.
+    fn type_ref(&mut self, context: &roxmltree::Node, qname: &str) -> TypeRef {
+        let name = local_name(qname);
+        if is_builtin(context, qname) {
+            return match name {
+                "anyType" => TypeRef::Any,
+                _ => TypeRef::Simple(SimpleType::from_builtin(name)),
+            };
+        }
+        match self.named_complex_type(name) {
+            Some(index) => TypeRef::Complex(index),
+            None => TypeRef::Simple(self.named_simple_type(context, qname, 0)),
+        }
+    }
+
+    /// Returns the index of a named complex type, resolving it on first use.
+    fn named_complex_type(&mut self, name: &str) -> Option<usize> {
+        if let Some(index) = self.resolved.get(name) {
+            return Some(*index);
+        }
+        let (&name, &node) = self.complex_types.get_key_value(name)?;
+
+        // reserve the index first, so recursive references to this type can be resolved
+        let index = self.types.len();
+        self.types.push(ComplexType::default());
+        self.resolved.insert(name, index);
+        self.types[index] = self.complex_type(&node);
+        Some(index)
+    }
+
+    /// Resolves a simple type by its QName. Complex types with simple content are accepted
+    /// as well, because they may be used as the base of another simple content.
+    fn named_simple_type(
+        &mut self,
+        context: &roxmltree::Node,
+        qname: &str,
+        depth: usize,
+    ) -> SimpleType {
+        let name = local_name(qname);
+        if is_builtin(context, qname) {
+            return SimpleType::from_builtin(name);
+        }
+        if depth >= MAX_REFERENCE_DEPTH {
+            return SimpleType::Any;
+        }
+        if let Some(node) = self.simple_types.get(name).copied() {
+            return self.simple_type(&node, depth + 1);
+        }
+        match self.named_complex_type(name) {
+            Some(index) => self.types[index].text.unwrap_or(SimpleType::Any),
+            None => SimpleType::Any,
+        }
+    }
+
+    /// Resolves an `xs:simpleType` definition. Lists and unions are kept as strings.
+    fn simple_type(&mut self, node: &roxmltree::Node, depth: usize) -> SimpleType {
+        for child in node.children().filter(|n| n.is_element()) {
+            if is_xsd(&child, "restriction") {
+                if let Some(base) = child.attribute("base") {
+                    return self.named_simple_type(&child, base, depth + 1);
+                }
+                if let Some(st) = child.children().find(|n| is_xsd(n, "simpleType")) {
+                    return self.simple_type(&st, depth + 1);
+                }
+            } else if is_xsd(&child, "list") || is_xsd(&child, "union") {
+                return SimpleType::String;
+            }
+        }
+        SimpleType::Any
+    }
+
+    /// Parses an `xs:complexType` definition.
+    fn complex_type(&mut self, node: &roxmltree::Node<'a, 'input>) -> ComplexType {
+        let mut ct = ComplexType {
+            mixed: node.attribute("mixed") == Some("true"),
+            ..Default::default()
+        };
+
+        for child in node.children().filter(|n| n.is_element()) {
+            match child.tag_name().name() {
+                "simpleContent" | "complexContent" => {
+                    if child.attribute("mixed") == Some("true") {
+                        ct.mixed = true;
+                    }
+                    let Some(derivation) = child
+                        .children()
+                        .find(|n| is_xsd(n, "extension") || is_xsd(n, "restriction"))
+                    else {
+                        continue;
+                    };
+                    let base = derivation.attribute("base").unwrap_or_default();
+                    let simple = child.tag_name().name() == "simpleContent";
+                    self.derived(&mut ct, &derivation, base, simple);
+                }
+                _ => self.content(&mut ct, &child, (1, Some(1)), false, 0),
+            }
+        }
+        ct
+    }
+
+    /// Applies `xs:extension` or `xs:restriction` of simple or complex content.
+    fn derived(
+        &mut self,
+        ct: &mut ComplexType,
+        derivation: &roxmltree::Node<'a, 'input>,
+        base: &str,
+        simple: bool,
+    ) {
+        let extension = derivation.tag_name().name() == "extension";
+        let base_type = if is_builtin(derivation, base) {
+            None
+        } else {
+            self.named_complex_type(local_name(base))
+        };
+
+        if simple {
+            ct.text = Some(match base_type {
+                Some(index) => self.types[index].text.unwrap_or(SimpleType::Any),
+                None => self.named_simple_type(derivation, base, 0),
+            });
+        }
+        if let Some(index) = base_type {
+            let base = self.types[index].clone();
+            // a restriction of complex content repeats the content model it keeps
+            if extension || simple {
+                ct.attributes = base.attributes;
+                ct.any_attributes = base.any_attributes;
+            }
+            if extension {
+                ct.children = base.children;
+                ct.any_children = base.any_children;
+                ct.mixed |= base.mixed;
+            }
+        }
+        for child in derivation.children().filter(|n| n.is_element()) {
+            self.content(ct, &child, (1, Some(1)), false, 0);
+        }
+    }
+
+    /// Parses a particle or attribute declaration of a content model.
+    fn content(
+        &mut self,
+        ct: &mut ComplexType,
+        node: &roxmltree::Node<'a, 'input>,
+        occurs: (u64, Option<u64>),
+        in_choice: bool,
+        depth: usize,
+    ) {
+        if depth >= MAX_REFERENCE_DEPTH || node.tag_name().namespace() != Some(XSD_NAMESPACE) {
+            return;
+        }
+        match node.tag_name().name() {
+            "element" => {
+                let decl = self.element(node, occurs, in_choice);
+                // the same element may appear several times in a sequence
+                match ct.children.iter_mut().find(|c| c.name == decl.name) {
+                    Some(existing) => {
+                        existing.min_occurs = existing.min_occurs.saturating_add(decl.min_occurs);
+                        existing.max_occurs = match (existing.max_occurs, decl.max_occurs) {
+                            (Some(a), Some(b)) => Some(a.saturating_add(b)),
+                            _ => None,
+                        };
+                    }
+                    None => ct.children.push(decl),
+                }
+            }
+            "sequence" | "choice" | "all" => {
+                let (min, max) = multiply(occurs, self::occurs(node));
+                let min = if in_choice { 0 } else { min };
+                let choice = node.tag_name().name() == "choice";
+                for child in node.children().filter(|n| n.is_element()) {
+                    self.content(ct, &child, (min, max), choice, depth + 1);
+                }
+            }
+            "group" => {
+                let group_occurs = multiply(occurs, self::occurs(node));
+                let group = node
+                    .attribute("ref")
+                    .and_then(|r| self.groups.get(local_name(r)).copied());
+                if let Some(group) = group {
+                    for child in group.children().filter(|n| n.is_element()) {
+                        self.content(ct, &child, group_occurs, in_choice, depth + 1);
+                    }
+                }
+            }
+            "any" => ct.any_children = true,
+            "attribute" => {
+                if node.attribute("use") == Some("prohibited") {
+                    return;
+                }
+                let decl = match node.attribute("ref") {
+                    Some(r) => self.attributes.get(local_name(r)).copied().unwrap_or(*node),
+                    None => *node,
+                };
+                let name = decl
+                    .attribute("name")
+                    .or_else(|| node.attribute("ref").map(local_name))
+                    .unwrap_or_default();
+                let ty = match decl.attribute("type") {
+                    Some(ty) => self.named_simple_type(&decl, ty, 0),
+                    None => match decl.children().find(|n| is_xsd(n, "simpleType")) {
+                        Some(st) => self.simple_type(&st, 0),
+                        None => SimpleType::Any,
+                    },
+                };
+                ct.attributes.push(AttributeDecl {
+                    name: name.to_owned(),
+                    ty,
+                    required: node.attribute("use") == Some("required"),
+                });
+            }
+            "attributeGroup" => {
+                let group = node
+                    .attribute("ref")
+                    .and_then(|r| self.attribute_groups.get(local_name(r)).copied());
+                if let Some(group) = group {
+                    for child in group.children().filter(|n| n.is_element()) {
+                        self.content(ct, &child, occurs, in_choice, depth + 1);
+                    }
+                }
+            }
+            "anyAttribute" => ct.any_attributes = true,
+            _ => (),
+        }
+    }
+}
+
+impl SimpleType {
+    /// Returns `true` if the text is a valid lexical representation of the type.
+    /// Only the JSON-relevant category is checked, not facets like patterns or enumerations.
+    fn accepts(self, text: &str) -> bool {
+        let text = text.trim();
+        match self {
+            SimpleType::Number => text.parse::<f64>().is_ok(),
+            SimpleType::Boolean => matches!(text, "true" | "false" | "1" | "0"),
+            SimpleType::String | SimpleType::Any => true,
+        }
+    }
+}
+
+/// Defines what happens if the document doesn't conform to the schema set with `Config::with_xsd_validation`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum XsdValidation {
+    /// Fail the conversion with `Error::Invalid` listing all violations.
+    Fail,
+    /// Convert the document anyway and add `DiagnosticKind::SchemaViolation` for every violation
+    /// to the diagnostics.
+    Report,
+}
+
+/// Validates the document against the schema and returns a diagnostic for every violation.
+///
+/// This is a simplified validation of the structure the converter relies on: declared elements and
+/// attributes, occurrence limits of child elements (but not their order), required attributes,
+/// `xsi:nil` and the lexical form of numbers and booleans. Facets like patterns are not checked.
+pub(crate) fn validate(schema: &XsdSchema, doc: &roxmltree::Document) -> Vec<Diagnostic> {
+    let mut findings = Vec::new();
+    let root = doc.root_element();
+    let name = root.tag_name().name();
+    match schema.roots.iter().find(|decl| decl.name == name) {
+        Some(decl) => validate_element(schema, decl, &root, "", &mut findings),
+        None => violation(
+            &mut findings,
+            &["/", name].concat(),
+            "undeclared root element",
+        ),
+    }
+    findings
+}
+
+fn violation(findings: &mut Vec<Diagnostic>, path: &str, message: &str) {
+    findings.push(Diagnostic {
+        path: path.to_owned(),
+        kind: DiagnosticKind::SchemaViolation(message.to_owned()),
+    });
+}
+
+/// Returns the text nodes directly inside of the element, without comments and child elements.
+fn own_text(el: &roxmltree::Node) -> String {
+    el.children()
+        .filter(|n| n.is_text())
+        .filter_map(|n| n.text())
+        .collect()
+}
+
+fn validate_element(
+    schema: &XsdSchema,
+    decl: &ElementDecl,
+    el: &roxmltree::Node,
+    parent_path: &str,
+    findings: &mut Vec<Diagnostic>,
+) {
+    let path = [parent_path, "/", el.tag_name().name()].concat();
+
+    if let Some(nil) = el.attribute((XSI_NAMESPACE, "nil")) {
+        if matches!(nil, "true" | "1") {
+            if !decl.nillable {
+                violation(
+                    findings,
+                    &path,
+                    "xsi:nil on an element that is not nillable",
+                );
+            }
+            if el.has_children() {
+                violation(findings, &path, "an element with xsi:nil must be empty");
+            }
+            return;
+        }
+    }
+
+    match decl.ty {
+        TypeRef::Any => (),
+        TypeRef::Simple(ty) => {
+            if el.children().any(|n| n.is_element()) {
+                violation(
+                    findings,
+                    &path,
+                    "child elements in an element of a simple type",
+                );
+            }
+            if el
+                .attributes()
+                .any(|a| a.namespace() != Some(XSI_NAMESPACE))
+            {
+                violation(findings, &path, "attributes in an element of a simple type");
+            }
+            if !ty.accepts(&own_text(el)) {
+                violation(findings, &path, "invalid value");
+            }
+        }
+        TypeRef::Complex(index) => {
+            let ct = &schema.types[index];
+            validate_attributes(ct, el, &path, findings);
+
+            match ct.text {
+                Some(ty) if !ty.accepts(&own_text(el)) => {
+                    violation(findings, &path, "invalid value")
+                }
+                None if !ct.mixed && !own_text(el).trim().is_empty() => violation(
+                    findings,
+                    &path,
+                    "text in an element with element-only content",
+                ),
+                _ => (),
+            }
+
+            for child in &ct.children {
+                let count = el
+                    .children()
+                    .filter(|n| n.is_element() && n.tag_name().name() == child.name)
+                    .count() as u64;
+                if count < child.min_occurs {
+                    violation(
+                        findings,
+                        &[path.as_str(), "/", &child.name].concat(),
+                        "missing element",
+                    );
+                }
+                if child.max_occurs.is_some_and(|max| count > max) {
+                    violation(
+                        findings,
+                        &[path.as_str(), "/", &child.name].concat(),
+                        "too many elements",
+                    );
+                }
+            }
+
+            for child in el.children().filter(|n| n.is_element()) {
+                match ct
+                    .children
+                    .iter()
+                    .find(|c| c.name == child.tag_name().name())
+                {
+                    Some(child_decl) => {
+                        validate_element(schema, child_decl, &child, &path, findings)
+                    }
+                    None if ct.any_children => (),
+                    None => violation(
+                        findings,
+                        &[path.as_str(), "/", child.tag_name().name()].concat(),
+                        "undeclared element",
+                    ),
+                }
+            }
+        }
+    }
+}
+
+fn validate_attributes(
+    ct: &ComplexType,
+    el: &roxmltree::Node,
+    path: &str,
+    findings: &mut Vec<Diagnostic>,
+) {
+    for attr in el.attributes() {
+        // `xsi:type`, `xml:lang` and other attributes of the standard namespaces are always allowed
+        if matches!(
+            attr.namespace(),
+            Some(XSI_NAMESPACE) | Some("http://www.w3.org/XML/1998/namespace")
+        ) {
+            continue;
+        }
+        let attr_path = [path, "/@", attr.name()].concat();
+        match ct.attributes.iter().find(|a| a.name == attr.name()) {
+            Some(decl) if !decl.ty.accepts(attr.value()) => {
+                violation(findings, &attr_path, "invalid value")
+            }
+            Some(_) => (),
+            None if ct.any_attributes => (),
+            None => violation(findings, &attr_path, "undeclared attribute"),
+        }
+    }
+    for decl in ct.attributes.iter().filter(|a| a.required) {
+        if !el.attributes().any(|a| a.name() == decl.name) {
+            violation(
+                findings,
+                &[path, "/@", &decl.name].concat(),
+                "missing attribute",
+            );
+        }
+    }
+}
+
+impl Config {
+    /// Validates every document against the schema before converting it, see `XsdValidation`
+    /// for the available options and `validate` for the scope of the validation.
+    pub fn with_xsd_validation(self, schema: Arc<XsdSchema>, validation: XsdValidation) -> Self {
+        Config {
+            xsd_validation: Some((schema, validation)),
+            ..self
+        }
+    }
+
+    /// Derives JSON type overrides from the schema, see `Config::add_xsd_schema`.
+    pub fn add_xsd(self, xsd: &str) -> Result<Self, Error> {
+        Ok(self.add_xsd_schema(&XsdSchema::parse(xsd)?))
+    }
+
+    /// Adds JSON type overrides for every element and attribute declared in the schema:
+    /// - elements with `maxOccurs` greater than 1 are always arrays
+    /// - numeric types become JSON numbers, `xs:boolean` becomes JSON bool and all other
+    ///   simple types, including dates, are kept as JSON strings
+    /// - `nillable` elements with `xsi:nil="true"` become JSON `null`
+    ///
+    /// Overrides that were added explicitly take precedence over the rules from the schema.
+    /// Recursive types only produce rules for their first level of nesting.
+    pub fn add_xsd_schema(self, schema: &XsdSchema) -> Self {
+        let mut conf = self;
+        let mut stack = Vec::new();
+        for root in &schema.roots {
+            add_element_rules(&mut conf, schema, root, "", &mut stack);
+        }
+        conf
+    }
+}
+
+/// Adds the rules for a declared element and all its descendants. `stack` holds the complex types
+/// of the ancestors to stop at recursive types.
+fn add_element_rules(
+    conf: &mut Config,
+    schema: &XsdSchema,
+    decl: &ElementDecl,
+    parent_path: &str,
+    stack: &mut Vec<usize>,
+) {
+    let path = [parent_path, "/", decl.name.as_str()].concat();
+
+    let json_type = match decl.ty {
+        TypeRef::Simple(ty) => ty.json_type(),
+        TypeRef::Complex(index) => schema.types[index]
+            .text
+            .map_or(JsonType::Infer, SimpleType::json_type),
+        TypeRef::Any => JsonType::Infer,
+    };
+    let array = decl.max_occurs.is_none_or(|max| max > 1);
+    if array || json_type != JsonType::Infer {
+        let rule = if array {
+            JsonArray::Always(json_type)
+        } else {
+            JsonArray::Infer(json_type)
+        };
+        conf.json_type_overrides.entry(path.clone()).or_insert(rule);
+    }
+    if decl.nillable {
+        conf.nillable_paths.insert(path.clone());
+    }
+
+    if let TypeRef::Complex(index) = decl.ty {
+        if stack.contains(&index) || stack.len() >= conf.max_depth {
+            return;
+        }
+        stack.push(index);
+        let ct = &schema.types[index];
+        for attr in &ct.attributes {
+            let json_type = attr.ty.json_type();
+            if json_type != JsonType::Infer {
+                conf.json_type_overrides
+                    .entry([path.as_str(), "/@", attr.name.as_str()].concat())
+                    .or_insert(JsonArray::Infer(json_type));
+            }
+        }
+        for child in &ct.children {
+            add_element_rules(conf, schema, child, &path, stack);
+        }
+        stack.pop();
+    }
+}
@@ -0,0 +1,51 @@
+//! Conversion of XML into YAML, available with the `yaml` feature.
+
+use serde_json::Value;
+use serde_yaml::{Mapping, Number, Value as YamlValue};
+
+use crate::{xml_str_to_json, Config, Error};
+
+/// Converts an XML document into a `serde_yaml::Value` with the same rules as `xml_str_to_json`.
+/// # Example
+/// ```
+/// use roxmltree_to_serde::{xml_str_to_yaml, Config};
+///
+/// let yaml = xml_str_to_yaml("<a><b>1</b></a>", &Config::new_with_defaults()).unwrap();
+/// assert_eq!(yaml["a"]["b"], serde_yaml::Value::from(1));
+/// ```
+pub fn xml_str_to_yaml(xml: &str, config: &Config) -> Result<YamlValue, Error> {
+    Ok(json_to_yaml(xml_str_to_json(xml, config)?))
+}
+
+/// Converts an XML document into a YAML string with the same rules as `xml_str_to_json`.
+pub fn xml_str_to_yaml_string(xml: &str, config: &Config) -> Result<String, Error> {
+    let yaml = xml_str_to_yaml(xml, config)?;
+    serde_yaml::to_string(&yaml).map_err(Error::Yaml)
+}
+
+/// Moves the JSON value into the equivalent YAML value without serializing it.
+fn json_to_yaml(value: Value) -> YamlValue {
+    match value {
+        Value::Null => YamlValue::Null,
+        Value::Bool(b) => YamlValue::Bool(b),
+        Value::Number(n) => {
+            let number = if let Some(u) = n.as_u64() {
+                Number::from(u)
+            } else if let Some(i) = n.as_i64() {
+                Number::from(i)
+            } else {
+                Number::from(n.as_f64().unwrap_or(f64::NAN))
+            };
+            YamlValue::Number(number)
+        }
+        Value::String(s) => YamlValue::String(s),
+        Value::Array(items) => YamlValue::Sequence(items.into_iter().map(json_to_yaml).collect()),
+        Value::Object(map) => {
+            let mut mapping = Mapping::with_capacity(map.len());
+            for (key, value) in map {
+                mapping.insert(YamlValue::String(key), json_to_yaml(value));
+            }
+            YamlValue::Mapping(mapping)
+        }
+    }
+}